@@ -6,10 +6,11 @@ pub struct BufReader<R> {
     buf: Vec<u8>,
     pos: usize,
     cap: usize,
+    max_cap: usize,
 }
 
 const INIT_BUFFER_SIZE: usize = 4096;
-const MAX_BUFFER_SIZE: usize = 8192 + 4096 * 100;
+pub const MAX_BUFFER_SIZE: usize = 8192 + 4096 * 100;
 
 impl<R: Read> BufReader<R> {
     #[inline]
@@ -28,9 +29,17 @@ impl<R: Read> BufReader<R> {
             buf: buf,
             pos: 0,
             cap: 0,
+            max_cap: MAX_BUFFER_SIZE,
         }
     }
 
+    /// Let the buffer grow to at least `max` bytes, rather than the default
+    /// `MAX_BUFFER_SIZE`.
+    #[inline]
+    pub fn set_max_capacity(&mut self, max: usize) {
+        self.max_cap = cmp::max(max, MAX_BUFFER_SIZE);
+    }
+
     #[inline]
     pub fn get_ref(&self) -> &R { &self.inner }
 
@@ -68,8 +77,8 @@ impl<R: Read> BufReader<R> {
     #[inline]
     fn maybe_reserve(&mut self) {
         let cap = self.buf.capacity();
-        if self.cap == cap && cap < MAX_BUFFER_SIZE {
-            self.buf.reserve(cmp::min(cap * 4, MAX_BUFFER_SIZE) - cap);
+        if self.cap == cap && cap < self.max_cap {
+            self.buf.reserve(cmp::min(cap * 4, self.max_cap) - cap);
             let new = self.buf.capacity() - self.buf.len();
             trace!("reserved {}", new);
             unsafe { grow_zerofill(&mut self.buf, new) }
@@ -82,7 +91,8 @@ unsafe fn grow_zerofill(buf: &mut Vec<u8>, additional: usize) {
     use std::ptr;
     let len = buf.len();
     buf.set_len(len + additional);
-    ptr::write_bytes(buf.as_mut_ptr(), 0, buf.len());
+    // only the new bytes, keeping those already read
+    ptr::write_bytes(buf.as_mut_ptr().offset(len as isize), 0, additional);
 }
 
 impl<R: Read> Read for BufReader<R> {
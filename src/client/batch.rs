@@ -0,0 +1,186 @@
+//! Sending many requests at once.
+//!
+//! `Client::send_all` sends a set of prepared requests over a few threads
+//! at a time, and returns the responses in the order of the requests.
+//! `Client::send_each` hands each response to a callback as soon as it
+//! arrives instead.
+//!
+//! ```no_run
+//! use hyper::Client;
+//! use hyper::client::batch::BatchRequest;
+//!
+//! let client = Client::new();
+//! let requests = vec![
+//!     BatchRequest::get("http://example.domain/a").unwrap(),
+//!     BatchRequest::get("http://example.domain/b").unwrap(),
+//! ];
+//! for res in client.send_all(requests, 4) {
+//!     println!("{:?}", res.map(|res| res.status));
+//! }
+//! ```
+use std::cmp;
+use std::sync::Mutex;
+use std::sync::mpsc;
+use std::thread;
+
+use header::{Header, HeaderFormat, Headers};
+use method::Method;
+use url::Url;
+
+use super::{Client, IntoUrl, Response};
+
+/// A request prepared to be sent later, by `Client::send_all` or
+/// `Client::send_each`.
+///
+/// Unlike a `RequestBuilder`, it owns its parts, so it can be handed to
+/// another thread.
+#[derive(Clone, Debug)]
+pub struct BatchRequest {
+    /// The method of the request.
+    pub method: Method,
+    /// The URL to send the request to.
+    pub url: Url,
+    /// The headers to send, besides those the client adds itself.
+    pub headers: Headers,
+    /// The body to send, if any.
+    pub body: Option<Vec<u8>>,
+}
+
+impl BatchRequest {
+    /// Prepare a request without headers or a body.
+    pub fn new<U: IntoUrl>(method: Method, url: U) -> ::Result<BatchRequest> {
+        Ok(BatchRequest {
+            method: method,
+            url: try!(url.into_url()),
+            headers: Headers::new(),
+            body: None,
+        })
+    }
+
+    /// Prepare a `GET` request.
+    pub fn get<U: IntoUrl>(url: U) -> ::Result<BatchRequest> {
+        BatchRequest::new(Method::Get, url)
+    }
+
+    /// Add a header to the request.
+    pub fn header<H: Header + HeaderFormat>(mut self, header: H) -> BatchRequest {
+        self.headers.set(header);
+        self
+    }
+
+    /// Set the body of the request.
+    pub fn body<B: Into<Vec<u8>>>(mut self, body: B) -> BatchRequest {
+        self.body = Some(body.into());
+        self
+    }
+}
+
+impl Client {
+    /// Send every request, at most `parallelism` at a time, and wait for
+    /// all the responses.
+    ///
+    /// The results are in the same order as the requests.
+    pub fn send_all(&self, requests: Vec<BatchRequest>, parallelism: usize)
+                    -> Vec<::Result<Response>> {
+        let mut results: Vec<Option<::Result<Response>>> =
+            requests.iter().map(|_| None).collect();
+        self.send_each(requests, parallelism, |index, result| results[index] = Some(result));
+        results.into_iter().map(|result| result.expect("every request has a result")).collect()
+    }
+
+    /// Send every request, at most `parallelism` at a time, calling
+    /// `on_response` with the index of each request and its result as soon
+    /// as it completes.
+    ///
+    /// `on_response` is called on the calling thread, and this returns once
+    /// every request has completed.
+    pub fn send_each<F>(&self, requests: Vec<BatchRequest>, parallelism: usize, mut on_response: F)
+    where F: FnMut(usize, ::Result<Response>) {
+        let threads = cmp::min(cmp::max(parallelism, 1), requests.len());
+        let queue = Mutex::new(requests.into_iter().enumerate());
+        let (tx, rx) = mpsc::channel();
+
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                let tx = tx.clone();
+                let queue = &queue;
+                scope.spawn(move || loop {
+                    let next = queue.lock().unwrap().next();
+                    let (index, req) = match next {
+                        Some(next) => next,
+                        None => return,
+                    };
+                    if tx.send((index, self.send_batch_request(req))).is_err() {
+                        return;
+                    }
+                });
+            }
+            drop(tx);
+
+            for (index, result) in rx {
+                on_response(index, result);
+            }
+        });
+    }
+
+    fn send_batch_request(&self, req: BatchRequest) -> ::Result<Response> {
+        let BatchRequest { method, url, headers, body } = req;
+        let builder = self.request(method, url).headers(headers);
+        match body {
+            Some(ref body) => builder.body(&body[..]).send(),
+            None => builder.send(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use client::Client;
+    use mock::ScriptedConnector;
+    use super::BatchRequest;
+
+    fn ok(body: &str) -> String {
+        format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body)
+    }
+
+    #[test]
+    fn test_send_all_in_order() {
+        let connector = ScriptedConnector::new();
+        connector.respond("http://127.0.0.1", ok("a").as_bytes());
+        connector.respond("http://127.0.0.2", ok("b").as_bytes());
+        connector.respond("http://127.0.0.3", ok("c").as_bytes());
+        let client = Client::with_connector(connector);
+
+        let requests = vec![
+            BatchRequest::get("http://127.0.0.1").unwrap(),
+            BatchRequest::get("http://127.0.0.2").unwrap(),
+            BatchRequest::get("http://127.0.0.4").unwrap(),
+            BatchRequest::get("http://127.0.0.3").unwrap().body("posted"),
+        ];
+        let bodies: Vec<Option<String>> = client.send_all(requests, 2).into_iter()
+            .map(|res| res.ok().map(|mut res| {
+                let mut body = String::new();
+                res.read_to_string(&mut body).unwrap();
+                body
+            }))
+            .collect();
+        assert_eq!(bodies, vec![Some("a".to_owned()), Some("b".to_owned()), None,
+                                Some("c".to_owned())]);
+    }
+
+    #[test]
+    fn test_send_each() {
+        let connector = ScriptedConnector::new();
+        connector.respond("http://127.0.0.1", ok("a").as_bytes());
+        let client = Client::with_connector(connector);
+
+        let mut seen = vec![];
+        client.send_each(vec![BatchRequest::get("http://127.0.0.1").unwrap()], 0,
+                         |index, res| seen.push((index, res.is_ok())));
+        assert_eq!(seen, vec![(0, true)]);
+
+        client.send_each(vec![], 4, |_, _| panic!("no requests were given"));
+    }
+}
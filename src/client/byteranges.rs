@@ -0,0 +1,315 @@
+//! Reading `multipart/byteranges` responses.
+//!
+//! A server answering a request for several ranges sends a
+//! `206 Partial Content` with a `multipart/byteranges` body, holding each
+//! range as a part with its own `Content-Range`, as
+//! [RFC7233 Appendix A](https://tools.ietf.org/html/rfc7233#appendix-A)
+//! describes. `ByteRanges` reads the parts one after another.
+//!
+//! ```no_run
+//! use std::io::Read;
+//! use hyper::Client;
+//! use hyper::header::{ContentRangeSpec, Range};
+//! use hyper::client::byteranges::ByteRanges;
+//!
+//! let client = Client::new();
+//! let res = client.get("http://example.domain/big")
+//!     .header(Range::bytes_multi(vec![(0, 99), (1000, 1099)]))
+//!     .send().unwrap();
+//!
+//! let mut parts = ByteRanges::from_response(res).unwrap();
+//! while let Some(mut part) = parts.next_part().unwrap() {
+//!     let range = match part.content_range() {
+//!         Some(&ContentRangeSpec::Bytes { range, .. }) => range,
+//!         _ => None,
+//!     };
+//!     let mut body = Vec::new();
+//!     part.read_to_end(&mut body).unwrap();
+//!     println!("{:?}: {} bytes", range, body.len());
+//! }
+//! ```
+use std::cmp;
+use std::io::{self, Read};
+
+use httparse;
+
+use Error;
+use client::Response;
+use header::{Headers, ContentType, ContentRange, ContentRangeSpec};
+use mime::{Mime, TopLevel, SubLevel, Attr};
+use status::StatusCode;
+
+/// The most bytes of headers a single part may have.
+const MAX_PART_HEAD: usize = 8192;
+const MAX_PART_HEADERS: usize = 16;
+
+/// Reads the parts of a `multipart/byteranges` body.
+#[derive(Debug)]
+pub struct ByteRanges<R> {
+    inner: R,
+    delimiter: Vec<u8>,
+    buf: Vec<u8>,
+    done: bool,
+}
+
+impl ByteRanges<Response> {
+    /// Read the parts of a response.
+    ///
+    /// Fails with `Error::Status` unless the response is a
+    /// `206 Partial Content`, and with `Error::Header` unless it has a
+    /// `multipart/byteranges` `Content-Type` with a boundary.
+    pub fn from_response(res: Response) -> ::Result<ByteRanges<Response>> {
+        if res.status != StatusCode::PartialContent {
+            return Err(Error::Status);
+        }
+        let boundary = match res.headers.get::<ContentType>() {
+            Some(&ContentType(ref mime)) => try!(boundary(mime)),
+            None => return Err(Error::Header),
+        };
+        Ok(ByteRanges::new(res, &boundary))
+    }
+}
+
+impl<R: Read> ByteRanges<R> {
+    /// Read the parts of a body delimited by `boundary`.
+    pub fn new(inner: R, boundary: &str) -> ByteRanges<R> {
+        let mut delimiter = b"\r\n--".to_vec();
+        delimiter.extend_from_slice(boundary.as_bytes());
+        ByteRanges {
+            inner: inner,
+            delimiter: delimiter,
+            // the first delimiter needn't follow a line break, so start on one
+            buf: b"\r\n".to_vec(),
+            done: false,
+        }
+    }
+
+    /// Move to the next part, skipping whatever remains of the current one.
+    ///
+    /// Returns `None` once the closing delimiter has been read.
+    pub fn next_part(&mut self) -> ::Result<Option<Part<R>>> {
+        if self.done {
+            return Ok(None);
+        }
+        let mut skip = [0; 4096];
+        while try!(self.read_body(&mut skip)) > 0 {}
+        self.buf.drain(..self.delimiter.len());
+
+        while self.buf.len() < 2 {
+            try!(self.fill());
+        }
+        if self.buf.starts_with(b"--") {
+            self.done = true;
+            return Ok(None);
+        }
+
+        // skip any transport padding after the delimiter
+        loop {
+            if let Some(pos) = find(&self.buf, b"\r\n") {
+                self.buf.drain(..pos + 2);
+                break;
+            }
+            if self.buf.len() > MAX_PART_HEAD {
+                return Err(Error::TooLarge);
+            }
+            try!(self.fill());
+        }
+
+        loop {
+            let parsed = {
+                let mut raw = [httparse::EMPTY_HEADER; MAX_PART_HEADERS];
+                match try!(httparse::parse_headers(&self.buf, &mut raw)) {
+                    httparse::Status::Complete((len, raw)) => {
+                        Some((len, try!(Headers::from_raw(raw))))
+                    },
+                    httparse::Status::Partial => None,
+                }
+            };
+            match parsed {
+                Some((len, headers)) => {
+                    self.buf.drain(..len);
+                    return Ok(Some(Part {
+                        headers: headers,
+                        parts: self,
+                    }));
+                },
+                None => {
+                    if self.buf.len() > MAX_PART_HEAD {
+                        return Err(Error::TooLarge);
+                    }
+                    try!(self.fill());
+                }
+            }
+        }
+    }
+
+    /// Get the underlying reader back.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    // Reads body bytes up to the next delimiter, which is left in the buffer.
+    fn read_body(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let available = match find(&self.buf, &self.delimiter) {
+                Some(pos) => pos,
+                // hold back anything that could be the start of a delimiter
+                None => self.buf.len().saturating_sub(self.delimiter.len() - 1),
+            };
+            if available > 0 || out.is_empty() {
+                let n = cmp::min(available, out.len());
+                out[..n].copy_from_slice(&self.buf[..n]);
+                self.buf.drain(..n);
+                return Ok(n);
+            }
+            if find(&self.buf, &self.delimiter) == Some(0) {
+                return Ok(0);
+            }
+            try!(self.fill());
+        }
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        let mut chunk = [0; 4096];
+        let n = try!(self.inner.read(&mut chunk));
+        if n == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof,
+                                      "multipart body ended before its closing delimiter"));
+        }
+        self.buf.extend_from_slice(&chunk[..n]);
+        Ok(())
+    }
+}
+
+/// A single part of a `multipart/byteranges` body, which reads as the
+/// bytes of its range.
+#[derive(Debug)]
+pub struct Part<'a, R: 'a> {
+    /// The headers of this part.
+    pub headers: Headers,
+    parts: &'a mut ByteRanges<R>,
+}
+
+impl<'a, R> Part<'a, R> {
+    /// The range of the representation this part holds.
+    pub fn content_range(&self) -> Option<&ContentRangeSpec> {
+        self.headers.get::<ContentRange>().map(|range| &range.0)
+    }
+}
+
+impl<'a, R: Read> Read for Part<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.parts.read_body(buf)
+    }
+}
+
+fn boundary(mime: &Mime) -> ::Result<String> {
+    match *mime {
+        Mime(TopLevel::Multipart, SubLevel::Ext(ref sub), ref params)
+                if sub.eq_ignore_ascii_case("byteranges") => {
+            params.iter()
+                .find(|&&(ref attr, _)| *attr == Attr::Boundary)
+                .map(|&(_, ref value)| value.to_string().trim_matches('"').to_owned())
+                .ok_or(Error::Header)
+        },
+        _ => Err(Error::Header),
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use url::Url;
+
+    use Error;
+    use client::Response;
+    use header::ContentRangeSpec;
+    use mock::MockStream;
+    use super::ByteRanges;
+
+    const BODY: &'static [u8] = b"\
+        preamble\r\n\
+        --THIS_STRING_SEPARATES\r\n\
+        Content-Type: text/plain\r\n\
+        Content-Range: bytes 0-4/20\r\n\
+        \r\n\
+        hello\r\n\
+        --THIS_STRING_SEPARATES  \r\n\
+        Content-Range: bytes 10-19/20\r\n\
+        \r\n\
+        line\r\n--THIS_\r\nend\r\n\
+        --THIS_STRING_SEPARATES--\r\n";
+
+    fn read_part(parts: &mut ByteRanges<Cursor<&[u8]>>) -> Option<(ContentRangeSpec, Vec<u8>)> {
+        parts.next_part().unwrap().map(|mut part| {
+            let mut body = Vec::new();
+            part.read_to_end(&mut body).unwrap();
+            (part.content_range().unwrap().clone(), body)
+        })
+    }
+
+    #[test]
+    fn test_parts() {
+        let mut parts = ByteRanges::new(Cursor::new(BODY), "THIS_STRING_SEPARATES");
+        let (range, body) = read_part(&mut parts).unwrap();
+        assert_eq!(range, ContentRangeSpec::Bytes {
+            range: Some((0, 4)),
+            instance_length: Some(20),
+        });
+        assert_eq!(body, b"hello");
+        let (range, body) = read_part(&mut parts).unwrap();
+        assert_eq!(range, ContentRangeSpec::Bytes {
+            range: Some((10, 19)),
+            instance_length: Some(20),
+        });
+        assert_eq!(body, b"line\r\n--THIS_\r\nend");
+        assert!(read_part(&mut parts).is_none());
+        assert!(read_part(&mut parts).is_none());
+    }
+
+    #[test]
+    fn test_skip_unread_part() {
+        let mut parts = ByteRanges::new(Cursor::new(BODY), "THIS_STRING_SEPARATES");
+        parts.next_part().unwrap().unwrap();
+        let (range, _) = read_part(&mut parts).unwrap();
+        assert_eq!(range, ContentRangeSpec::Bytes {
+            range: Some((10, 19)),
+            instance_length: Some(20),
+        });
+    }
+
+    #[test]
+    fn test_truncated() {
+        let mut parts = ByteRanges::new(Cursor::new(&BODY[..BODY.len() - 35]),
+                                        "THIS_STRING_SEPARATES");
+        read_part(&mut parts).unwrap();
+        let mut part = parts.next_part().unwrap().unwrap();
+        assert!(part.read_to_end(&mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_from_response() {
+        let mut input = b"\
+            HTTP/1.1 206 Partial Content\r\n\
+            Content-Type: multipart/byteranges; boundary=THIS_STRING_SEPARATES\r\n\
+            \r\n".to_vec();
+        input.extend_from_slice(BODY);
+        let url = Url::parse("http://hyper.rs").unwrap();
+        let res = Response::new(url, Box::new(MockStream::with_input(&input))).unwrap();
+        let mut parts = ByteRanges::from_response(res).unwrap();
+        assert!(parts.next_part().unwrap().is_some());
+
+        let input = b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n";
+        let url = Url::parse("http://hyper.rs").unwrap();
+        let res = Response::new(url, Box::new(MockStream::with_input(input))).unwrap();
+        match ByteRanges::from_response(res) {
+            Err(Error::Status) => (),
+            other => panic!("expected Error::Status, got {:?}", other),
+        }
+    }
+}
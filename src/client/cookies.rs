@@ -0,0 +1,789 @@
+//! Keeping the cookies that servers set, and sending them back.
+//!
+//! A `CookieJar` stores the cookies of `Set-Cookie` response headers, and
+//! picks those to send in the `Cookie` header of a later request, following
+//! the storage model of [RFC6265](https://tools.ietf.org/html/rfc6265#section-5.3):
+//! a cookie is only sent to the hosts and paths it was set for, over HTTPS
+//! if it is `Secure`, and until it expires.
+//!
+//! A `Client` given a jar with `Client::set_cookie_jar` does this for every
+//! request it sends, including the requests following a redirect.
+//!
+//! ```
+//! use hyper::client::cookies::CookieJar;
+//! use hyper::header::{Headers, HeaderFormatter};
+//! use hyper::Url;
+//!
+//! let jar = CookieJar::new();
+//! let mut headers = Headers::new();
+//! headers.set_raw("Set-Cookie", vec![b"session=1234; Path=/app; HttpOnly".to_vec()]);
+//! jar.store(&Url::parse("https://example.com/login").unwrap(), &headers);
+//!
+//! let cookie = jar.cookie_header(&Url::parse("https://example.com/app/home").unwrap());
+//! assert_eq!(HeaderFormatter(&cookie.unwrap()).to_string(), "session=1234");
+//! assert!(jar.cookie_header(&Url::parse("https://example.com/").unwrap()).is_none());
+//! ```
+//!
+//! A host can't set a cookie for a domain of a single label, such as `com`,
+//! which would be sent to every host under it. The jar doesn't know the
+//! other public suffixes, such as `co.uk`, unless told them with
+//! `CookieJar::set_public_suffixes`, and accepts cookies set for them
+//! otherwise.
+//!
+//! A jar keeps its cookies in a `CookieStore`, in memory unless given
+//! another with `CookieJar::with_store`. A `FileCookieStore` keeps them in a
+//! file, so that sessions outlive the process, and other stores can back
+//! them with a database.
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::str::from_utf8;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use cookie::Cookie as CookiePair;
+use time::{Duration, Timespec};
+use url::Host;
+
+use clock::{Clock, SystemClock};
+use header::{Cookie, Headers};
+use Url;
+
+/// The cookies set by the servers a client has talked to.
+pub struct CookieJar {
+    store: Box<CookieStore>,
+    clock: Box<Clock>,
+    public_suffix: Option<Box<Fn(&str) -> bool + Send + Sync>>,
+}
+
+/// A cookie as a jar keeps it, once its domain, path and expiry are known.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StoredCookie {
+    /// The name of the cookie.
+    pub name: String,
+    /// The value of the cookie.
+    pub value: String,
+    /// The domain the cookie is sent to, lowercased and without a leading
+    /// dot.
+    pub domain: String,
+    /// Whether the cookie is only sent to `domain` itself, and not to its
+    /// subdomains, as when it was set without a `Domain`.
+    pub host_only: bool,
+    /// The path the cookie is sent to, and below.
+    pub path: String,
+    /// Whether the cookie is only sent over HTTPS.
+    pub secure: bool,
+    /// Whether the cookie was set `HttpOnly`.
+    pub http_only: bool,
+    /// When the cookie expires, or `None` for a cookie lasting the session.
+    pub expires: Option<Timespec>,
+    /// When the cookie was first set, which orders cookies with paths of
+    /// the same length.
+    pub created: Timespec,
+}
+
+impl StoredCookie {
+    /// Whether the cookie has expired at `now`.
+    pub fn is_expired(&self, now: Timespec) -> bool {
+        self.expires.map_or(false, |expires| expires <= now)
+    }
+
+    /// Whether this cookie replaces `other`, having the same name, domain
+    /// and path.
+    pub fn replaces(&self, other: &StoredCookie) -> bool {
+        self.name == other.name && self.domain == other.domain && self.path == other.path
+    }
+
+    fn matches(&self, host: &str, path: &str, secure: bool) -> bool {
+        let host_matches = if self.host_only {
+            host == self.domain
+        } else {
+            domain_match(host, &self.domain)
+        };
+        host_matches && path_match(path, &self.path) && (secure || !self.secure)
+    }
+}
+
+/// Where a `CookieJar` keeps its cookies.
+///
+/// The jar decides which cookies to keep and which to send, so a store only
+/// has to hold them. Errors, such as failing to save, are the store's to
+/// handle.
+pub trait CookieStore: Send + Sync {
+    /// Every cookie stored, in the order they were first set.
+    fn get(&self) -> Vec<StoredCookie>;
+
+    /// Store `cookie`, in the place of the one it replaces, if any.
+    fn set(&self, cookie: StoredCookie);
+
+    /// Remove the cookie that `cookie` replaces, which has expired.
+    fn expire(&self, cookie: &StoredCookie);
+
+    /// Remove every cookie.
+    fn clear(&self);
+
+    /// Called once the jar is done with a batch of changes, such as the
+    /// cookies of a response, for a store to persist them all at once
+    /// rather than after each. Does nothing by default.
+    fn flush(&self) {}
+}
+
+impl<S: CookieStore + ?Sized> CookieStore for Arc<S> {
+    fn get(&self) -> Vec<StoredCookie> {
+        (**self).get()
+    }
+
+    fn set(&self, cookie: StoredCookie) {
+        (**self).set(cookie)
+    }
+
+    fn expire(&self, cookie: &StoredCookie) {
+        (**self).expire(cookie)
+    }
+
+    fn clear(&self) {
+        (**self).clear()
+    }
+
+    fn flush(&self) {
+        (**self).flush()
+    }
+}
+
+/// A `CookieStore` in memory, lasting as long as the process.
+#[derive(Debug, Default)]
+pub struct MemoryCookieStore {
+    cookies: Mutex<Vec<StoredCookie>>,
+}
+
+impl MemoryCookieStore {
+    /// An empty store.
+    pub fn new() -> MemoryCookieStore {
+        MemoryCookieStore::default()
+    }
+}
+
+impl CookieStore for MemoryCookieStore {
+    fn get(&self) -> Vec<StoredCookie> {
+        self.cookies.lock().unwrap().clone()
+    }
+
+    fn set(&self, cookie: StoredCookie) {
+        let mut cookies = self.cookies.lock().unwrap();
+        match cookies.iter().position(|c| cookie.replaces(c)) {
+            Some(i) => cookies[i] = cookie,
+            None => cookies.push(cookie),
+        }
+    }
+
+    fn expire(&self, cookie: &StoredCookie) {
+        self.cookies.lock().unwrap().retain(|c| !cookie.replaces(c));
+    }
+
+    fn clear(&self) {
+        self.cookies.lock().unwrap().clear();
+    }
+}
+
+/// A `CookieStore` saved to a file, in the Netscape `cookies.txt` format
+/// that curl and wget read and write too.
+///
+/// Changes are saved on `flush`, which a `CookieJar` calls after each batch
+/// of them, and when the store is dropped. On Unix, the file is only
+/// readable by its owner.
+///
+/// Session cookies are saved as well, so that a session outlives the
+/// process. When cookies were created isn't saved, so those read from the
+/// file count as created before any set since, in the order they're read.
+#[derive(Debug)]
+pub struct FileCookieStore {
+    path: PathBuf,
+    memory: MemoryCookieStore,
+    // whether there are changes left to save
+    dirty: AtomicBool,
+}
+
+impl FileCookieStore {
+    /// A store saved to `path`, starting with the cookies in it, if it
+    /// exists.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<FileCookieStore> {
+        let path = path.as_ref().to_path_buf();
+        let memory = MemoryCookieStore::new();
+        match File::open(&path) {
+            Ok(file) => {
+                for line in BufReader::new(file).lines() {
+                    if let Some(cookie) = parse_line(&try!(line)) {
+                        memory.set(cookie);
+                    }
+                }
+            },
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => (),
+            Err(e) => return Err(e),
+        }
+        Ok(FileCookieStore {
+            path: path,
+            memory: memory,
+            dirty: AtomicBool::new(false),
+        })
+    }
+
+    // Writes a temporary file first, so that a failed save leaves the last
+    // one intact.
+    fn save(&self) -> io::Result<()> {
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        // one left by a failed save may have been created with another mode
+        let _ = fs::remove_file(&tmp);
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        owner_only(&mut options);
+        let mut file = try!(options.open(&tmp));
+        try!(file.write_all(b"# Netscape HTTP Cookie File\n"));
+        for cookie in self.memory.get() {
+            try!(file.write_all(format_line(&cookie).as_bytes()));
+        }
+        try!(file.sync_all());
+        fs::rename(&tmp, &self.path)
+    }
+}
+
+#[cfg(unix)]
+fn owner_only(options: &mut OpenOptions) {
+    use std::os::unix::fs::OpenOptionsExt;
+    options.mode(0o600);
+}
+
+#[cfg(not(unix))]
+fn owner_only(_: &mut OpenOptions) {}
+
+impl CookieStore for FileCookieStore {
+    fn get(&self) -> Vec<StoredCookie> {
+        self.memory.get()
+    }
+
+    fn set(&self, cookie: StoredCookie) {
+        self.memory.set(cookie);
+        self.dirty.store(true, Ordering::SeqCst);
+    }
+
+    fn expire(&self, cookie: &StoredCookie) {
+        self.memory.expire(cookie);
+        self.dirty.store(true, Ordering::SeqCst);
+    }
+
+    fn clear(&self) {
+        self.memory.clear();
+        self.dirty.store(true, Ordering::SeqCst);
+    }
+
+    fn flush(&self) {
+        if self.dirty.swap(false, Ordering::SeqCst) {
+            if let Err(e) = self.save() {
+                error!("error saving cookies to {:?}: {}", self.path, e);
+                self.dirty.store(true, Ordering::SeqCst);
+            }
+        }
+    }
+}
+
+impl Drop for FileCookieStore {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+// A line is `domain subdomains path secure expires name value`, separated by
+// tabs. A domain sending to subdomains starts with a dot, and an `HttpOnly`
+// cookie's line with `#HttpOnly_`. An expiry of 0 is a session cookie.
+fn format_line(cookie: &StoredCookie) -> String {
+    format!("{}{}{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            if cookie.http_only { "#HttpOnly_" } else { "" },
+            if cookie.host_only { "" } else { "." },
+            cookie.domain,
+            if cookie.host_only { "FALSE" } else { "TRUE" },
+            cookie.path,
+            if cookie.secure { "TRUE" } else { "FALSE" },
+            cookie.expires.map_or(0, |expires| expires.sec),
+            cookie.name,
+            cookie.value)
+}
+
+fn parse_line(line: &str) -> Option<StoredCookie> {
+    let (line, http_only) = if line.starts_with("#HttpOnly_") {
+        (&line["#HttpOnly_".len()..], true)
+    } else if line.starts_with('#') {
+        return None;
+    } else {
+        (line, false)
+    };
+    let fields: Vec<&str> = line.trim_right_matches(&['\r', '\n'][..]).split('\t').collect();
+    if fields.len() != 7 {
+        return None;
+    }
+    let expires = match fields[4].parse() {
+        Ok(0) => None,
+        Ok(sec) => Some(Timespec::new(sec, 0)),
+        Err(_) => return None,
+    };
+    Some(StoredCookie {
+        name: fields[5].to_owned(),
+        value: fields[6].to_owned(),
+        domain: fields[0].trim_left_matches('.').to_lowercase(),
+        host_only: fields[1] != "TRUE",
+        path: fields[2].to_owned(),
+        secure: fields[3] == "TRUE",
+        http_only: http_only,
+        expires: expires,
+        created: Timespec::new(0, 0),
+    })
+}
+
+impl CookieJar {
+    /// An empty jar, using the system time to expire cookies.
+    pub fn new() -> CookieJar {
+        CookieJar::with_clock(SystemClock)
+    }
+
+    /// An empty jar, using `clock` to expire cookies.
+    pub fn with_clock<C: Clock + 'static>(clock: C) -> CookieJar {
+        CookieJar {
+            store: Box::new(MemoryCookieStore::new()),
+            clock: Box::new(clock),
+            public_suffix: None,
+        }
+    }
+
+    /// A jar keeping its cookies in `store`, starting with those already
+    /// in it.
+    ///
+    /// ```no_run
+    /// use std::sync::Arc;
+    /// use hyper::Client;
+    /// use hyper::client::cookies::{CookieJar, FileCookieStore};
+    ///
+    /// let store = FileCookieStore::open("cookies.txt").unwrap();
+    /// let mut client = Client::new();
+    /// client.set_cookie_jar(Arc::new(CookieJar::with_store(store)));
+    /// ```
+    pub fn with_store<S: CookieStore + 'static>(store: S) -> CookieJar {
+        CookieJar {
+            store: Box::new(store),
+            clock: Box::new(SystemClock),
+            public_suffix: None,
+        }
+    }
+
+    /// Refuse cookies set for the domains that `is_public` says are public
+    /// suffixes, such as `co.uk`, under which anyone can register a domain,
+    /// except from a host that is the suffix itself, for which they are
+    /// kept as host-only cookies.
+    ///
+    /// ```
+    /// use hyper::client::cookies::CookieJar;
+    ///
+    /// let mut jar = CookieJar::new();
+    /// jar.set_public_suffixes(|domain: &str| domain == "co.uk" || domain == "github.io");
+    /// ```
+    pub fn set_public_suffixes<F>(&mut self, is_public: F)
+    where F: Fn(&str) -> bool + Send + Sync + 'static {
+        self.public_suffix = Some(Box::new(is_public));
+    }
+
+    /// Store the cookies set by the `headers` of a response to `url`.
+    ///
+    /// A cookie replaces the one with the same name, domain and path. A
+    /// cookie that has already expired removes it instead. Cookies that
+    /// `url` isn't allowed to set, such as one for another domain, are
+    /// ignored.
+    pub fn store(&self, url: &Url, headers: &Headers) {
+        let lines = match headers.get_raw("Set-Cookie") {
+            Some(lines) => lines,
+            None => return,
+        };
+        for line in lines {
+            if let Ok(line) = from_utf8(line) {
+                if let Ok(pair) = CookiePair::parse(line) {
+                    self.insert(url, pair, has_path(line));
+                }
+            }
+        }
+        self.store.flush();
+    }
+
+    /// Store a single cookie, as if set by a response to `url`.
+    pub fn set(&self, url: &Url, pair: CookiePair) {
+        let has_path = pair.path.is_some();
+        self.insert(url, pair, has_path);
+        self.store.flush();
+    }
+
+    fn insert(&self, url: &Url, pair: CookiePair, has_path: bool) {
+        let host = match request_host(url) {
+            Some(host) => host,
+            None => return,
+        };
+        let now = self.clock.now().to_timespec();
+
+        let (domain, host_only) = match pair.domain {
+            Some(ref domain) if !domain.is_empty() => {
+                let domain = domain.trim_left_matches('.').to_lowercase();
+                // an IP address only sets cookies for itself
+                if !domain_match(&host, &domain) || (is_ip(url) && domain != host) {
+                    debug!("ignoring cookie {:?} for domain {:?} from {:?}",
+                           pair.name, domain, host);
+                    return;
+                }
+                // a public suffix only sets cookies for itself
+                let public = !domain.contains('.') ||
+                    self.public_suffix.as_ref().map_or(false, |is_public| is_public(&domain));
+                if public && domain != host {
+                    debug!("ignoring cookie {:?} for public suffix {:?} from {:?}",
+                           pair.name, domain, host);
+                    return;
+                }
+                (domain, public)
+            },
+            _ => (host, true),
+        };
+        let path = match pair.path {
+            Some(ref path) if has_path && path.starts_with('/') => path.clone(),
+            _ => default_path(url),
+        };
+        let expires = match (pair.max_age, pair.expires) {
+            (Some(max_age), _) => {
+                let max_age = if max_age > i64::max_value() as u64 / 1000 {
+                    i64::max_value() / 1000
+                } else {
+                    max_age as i64
+                };
+                Some(now + Duration::seconds(max_age))
+            },
+            (None, Some(expires)) => Some(expires.to_timespec()),
+            (None, None) => None,
+        };
+
+        let mut cookie = StoredCookie {
+            name: pair.name,
+            value: pair.value,
+            domain: domain,
+            host_only: host_only,
+            path: path,
+            secure: pair.secure,
+            http_only: pair.httponly,
+            expires: expires,
+            created: now,
+        };
+        if cookie.is_expired(now) {
+            self.store.expire(&cookie);
+            return;
+        }
+        if let Some(old) = self.store.get().into_iter().find(|c| cookie.replaces(c)) {
+            cookie.created = old.created;
+        }
+        self.store.set(cookie);
+    }
+
+    /// The cookies to send with a request to `url`, those with longer paths
+    /// first, then those created earlier.
+    pub fn cookies(&self, url: &Url) -> Vec<CookiePair> {
+        let host = match request_host(url) {
+            Some(host) => host,
+            None => return Vec::new(),
+        };
+        let path = url.serialize_path().unwrap_or_else(|| "/".to_owned());
+        let secure = url.scheme == "https" || url.scheme == "wss";
+        let now = self.clock.now().to_timespec();
+
+        let mut matching = Vec::new();
+        let mut expired = false;
+        for cookie in self.store.get() {
+            if cookie.is_expired(now) {
+                self.store.expire(&cookie);
+                expired = true;
+            } else if cookie.matches(&host, &path, secure) {
+                matching.push(cookie);
+            }
+        }
+        if expired {
+            self.store.flush();
+        }
+        // a stable sort, keeping the store's order for the same creation time
+        matching.sort_by(|a, b| b.path.len().cmp(&a.path.len()).then(a.created.cmp(&b.created)));
+        matching.into_iter()
+            .map(|c| CookiePair::new(c.name, c.value))
+            .collect()
+    }
+
+    /// The `Cookie` header to send with a request to `url`, unless there
+    /// are no cookies for it.
+    pub fn cookie_header(&self, url: &Url) -> Option<Cookie> {
+        let cookies = self.cookies(url);
+        if cookies.is_empty() {
+            None
+        } else {
+            Some(Cookie(cookies))
+        }
+    }
+
+    /// Remove every cookie.
+    pub fn clear(&self) {
+        self.store.clear();
+        self.store.flush();
+    }
+}
+
+impl Default for CookieJar {
+    fn default() -> CookieJar {
+        CookieJar::new()
+    }
+}
+
+impl fmt::Debug for CookieJar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CookieJar")
+            .field("cookies", &self.store.get())
+            .finish()
+    }
+}
+
+fn request_host(url: &Url) -> Option<String> {
+    url.serialize_host().map(|host| host.to_lowercase())
+}
+
+fn is_ip(url: &Url) -> bool {
+    match url.host() {
+        Some(&Host::Ipv6(_)) => true,
+        Some(&Host::Domain(ref domain)) => domain.parse::<::std::net::Ipv4Addr>().is_ok(),
+        None => false,
+    }
+}
+
+// `Cookie::parse` fills in a `Path` of `/` when there is none, but a missing
+// path means the directory of the request instead.
+fn has_path(line: &str) -> bool {
+    line.split(';').skip(1).any(|attr| {
+        let name = attr.split('=').next().unwrap_or("").trim();
+        name.eq_ignore_ascii_case("path")
+    })
+}
+
+/// Whether `host` is `domain` or one of its subdomains.
+fn domain_match(host: &str, domain: &str) -> bool {
+    host == domain ||
+        (host.ends_with(domain) && host[..host.len() - domain.len()].ends_with('.'))
+}
+
+/// Whether `path` is `cookie_path` or below it.
+fn path_match(path: &str, cookie_path: &str) -> bool {
+    path == cookie_path ||
+        (path.starts_with(cookie_path) &&
+         (cookie_path.ends_with('/') || path[cookie_path.len()..].starts_with('/')))
+}
+
+/// The directory of the request path, for a cookie set without a `Path`.
+fn default_path(url: &Url) -> String {
+    let path = url.serialize_path().unwrap_or_else(|| "/".to_owned());
+    match path.rfind('/') {
+        Some(0) | None => "/".to_owned(),
+        Some(i) => path[..i].to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use cookie::Cookie as CookiePair;
+    use time::{self, Duration, Timespec};
+
+    use clock::ManualClock;
+    use header::{Headers, HeaderFormatter};
+    use Url;
+    use super::{CookieJar, default_path, domain_match, path_match};
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    fn store(jar: &CookieJar, from: &str, set_cookie: &str) {
+        let mut headers = Headers::new();
+        headers.set_raw("Set-Cookie", vec![set_cookie.as_bytes().to_vec()]);
+        jar.store(&url(from), &headers);
+    }
+
+    fn sent(jar: &CookieJar, to: &str) -> String {
+        jar.cookie_header(&url(to)).map_or(String::new(), |cookie| HeaderFormatter(&cookie).to_string())
+    }
+
+    #[test]
+    fn test_domain() {
+        let jar = CookieJar::new();
+        store(&jar, "http://www.example.com/", "host=1");
+        store(&jar, "http://www.example.com/", "domain=2; Domain=.Example.com");
+        store(&jar, "http://www.example.com/", "other=3; Domain=example.org");
+        store(&jar, "http://www.example.com/", "sub=4; Domain=a.www.example.com");
+
+        assert_eq!(sent(&jar, "http://www.example.com/"), "host=1; domain=2");
+        assert_eq!(sent(&jar, "http://example.com/"), "domain=2");
+        assert_eq!(sent(&jar, "http://a.www.example.com/"), "domain=2");
+        assert_eq!(sent(&jar, "http://badexample.com/"), "");
+        assert_eq!(sent(&jar, "http://example.org/"), "");
+
+        store(&jar, "http://127.0.0.1/", "ip=5; Domain=0.0.1");
+        assert_eq!(sent(&jar, "http://127.0.0.1/"), "");
+    }
+
+    #[test]
+    fn test_public_suffix() {
+        let mut jar = CookieJar::new();
+        jar.set_public_suffixes(|domain: &str| domain == "co.uk");
+        store(&jar, "http://www.example.com/", "tld=1; Domain=com");
+        store(&jar, "http://www.example.co.uk/", "suffix=2; Domain=.co.uk");
+        store(&jar, "http://www.example.co.uk/", "site=3; Domain=example.co.uk");
+        assert_eq!(sent(&jar, "http://other.com/"), "");
+        assert_eq!(sent(&jar, "http://other.co.uk/"), "");
+        assert_eq!(sent(&jar, "http://a.example.co.uk/"), "site=3");
+
+        // the suffix itself still sets cookies, but only for itself
+        store(&jar, "http://localhost/", "local=4; Domain=localhost");
+        assert_eq!(sent(&jar, "http://localhost/"), "local=4");
+        assert_eq!(sent(&jar, "http://a.localhost/"), "");
+    }
+
+    #[test]
+    fn test_path() {
+        let jar = CookieJar::new();
+        store(&jar, "http://example.com/app/login", "dir=1");
+        store(&jar, "http://example.com/", "root=2; Path=/");
+        store(&jar, "http://example.com/", "deep=3; Path=/app/admin");
+
+        assert_eq!(sent(&jar, "http://example.com/app/admin/users?page=2"),
+                   "deep=3; dir=1; root=2");
+        assert_eq!(sent(&jar, "http://example.com/app"), "dir=1; root=2");
+        assert_eq!(sent(&jar, "http://example.com/application"), "root=2");
+    }
+
+    #[test]
+    fn test_secure() {
+        let jar = CookieJar::new();
+        store(&jar, "https://example.com/", "token=1; Secure");
+        assert_eq!(sent(&jar, "https://example.com/"), "token=1");
+        assert_eq!(sent(&jar, "http://example.com/"), "");
+    }
+
+    #[test]
+    fn test_replace_and_expire() {
+        let clock = Arc::new(ManualClock::new(time::at_utc(Timespec::new(784111777, 0))));
+        let jar = CookieJar::with_clock(clock.clone());
+        store(&jar, "http://example.com/", "a=1");
+        store(&jar, "http://example.com/", "b=2; Max-Age=60");
+        store(&jar, "http://example.com/", "c=3; Expires=Sun, 06 Nov 1994 09:49:37 GMT");
+        store(&jar, "http://example.com/", "a=4");
+        assert_eq!(sent(&jar, "http://example.com/"), "a=4; b=2; c=3");
+
+        clock.advance(Duration::seconds(60));
+        assert_eq!(sent(&jar, "http://example.com/"), "a=4; c=3");
+
+        store(&jar, "http://example.com/", "a=; Max-Age=0");
+        assert_eq!(sent(&jar, "http://example.com/"), "c=3");
+
+        clock.advance(Duration::hours(1));
+        assert_eq!(sent(&jar, "http://example.com/"), "");
+    }
+
+    #[test]
+    fn test_set() {
+        let jar = CookieJar::new();
+        jar.set(&url("http://example.com/a/b"), CookiePair::new("a".to_owned(), "1".to_owned()));
+        assert_eq!(sent(&jar, "http://example.com/"), "a=1");
+        jar.clear();
+        assert_eq!(sent(&jar, "http://example.com/"), "");
+    }
+
+    #[test]
+    fn test_file_store() {
+        use std::{env, fs, process};
+        use super::{CookieStore, FileCookieStore};
+
+        let path = env::temp_dir().join(format!("hyper-cookies-{}.txt", process::id()));
+        let _ = fs::remove_file(&path);
+        {
+            let jar = CookieJar::with_store(FileCookieStore::open(&path).unwrap());
+            store(&jar, "https://www.example.com/app/login", "session=abc; HttpOnly; Secure");
+            store(&jar, "https://www.example.com/", "theme=dark; Domain=example.com; Max-Age=3600");
+            store(&jar, "https://www.example.com/", "gone=1");
+            store(&jar, "https://www.example.com/", "gone=; Max-Age=0");
+        }
+
+        let saved = fs::read_to_string(&path).unwrap();
+        let lines: Vec<_> = saved.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "# Netscape HTTP Cookie File");
+        assert_eq!(lines[1], "#HttpOnly_www.example.com\tFALSE\t/app\tTRUE\t0\tsession\tabc");
+        assert!(lines[2].starts_with(".example.com\tTRUE\t/\tFALSE\t"));
+        assert!(lines[2].ends_with("\ttheme\tdark"));
+
+        let store = FileCookieStore::open(&path).unwrap();
+        let cookies = store.get();
+        assert_eq!(cookies.len(), 2);
+        assert!(cookies[0].http_only && cookies[0].host_only && cookies[0].expires.is_none());
+        assert!(!cookies[1].host_only && cookies[1].expires.is_some());
+        let jar = CookieJar::with_store(store);
+        assert_eq!(sent(&jar, "https://www.example.com/app/home"), "session=abc; theme=dark");
+        assert_eq!(sent(&jar, "http://example.com/"), "theme=dark");
+
+        jar.clear();
+        assert!(FileCookieStore::open(&path).unwrap().get().is_empty());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_file_store_flush() {
+        use std::{env, fs, process};
+        use time::Timespec;
+        use super::{CookieStore, FileCookieStore, StoredCookie};
+
+        let path = env::temp_dir().join(format!("hyper-cookies-flush-{}.txt", process::id()));
+        let _ = fs::remove_file(&path);
+        let store = FileCookieStore::open(&path).unwrap();
+        store.set(StoredCookie {
+            name: "a".to_owned(),
+            value: "1".to_owned(),
+            domain: "example.com".to_owned(),
+            host_only: true,
+            path: "/".to_owned(),
+            secure: false,
+            http_only: false,
+            expires: None,
+            created: Timespec::new(0, 0),
+        });
+        assert!(!path.exists());
+        store.flush();
+        assert_eq!(FileCookieStore::open(&path).unwrap().get().len(), 1);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+
+        store.clear();
+        drop(store);
+        assert!(FileCookieStore::open(&path).unwrap().get().is_empty());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_matching() {
+        assert!(domain_match("example.com", "example.com"));
+        assert!(domain_match("a.example.com", "example.com"));
+        assert!(!domain_match("aexample.com", "example.com"));
+        assert!(path_match("/a/b", "/a"));
+        assert!(path_match("/a/b", "/a/"));
+        assert!(!path_match("/ab", "/a"));
+        assert_eq!(default_path(&url("http://example.com")), "/");
+        assert_eq!(default_path(&url("http://example.com/a")), "/");
+        assert_eq!(default_path(&url("http://example.com/a/b?c/d")), "/a");
+    }
+}
@@ -0,0 +1,286 @@
+//! Downloading a resource, resuming where an interrupted download stopped.
+//!
+//! A `Download` writes the body of a `GET` to a `Target`, keeping track of
+//! how much of it has arrived, and of the `ETag` or `Last-Modified` it was
+//! sent with. When the connection fails part way, it asks for the rest
+//! with `Range: bytes=N-` and an `If-Range` of that validator, so that a
+//! server whose resource has changed since sends all of the new one
+//! instead, which the download starts over with.
+//!
+//! ```no_run
+//! use std::fs::File;
+//! use hyper::Client;
+//! use hyper::client::download::Download;
+//!
+//! let client = Client::new();
+//! let file = File::create("big.iso").unwrap();
+//! let mut download = Download::new(&client, "http://example.domain/big.iso", file).unwrap();
+//! let len = download.run().unwrap();
+//! println!("downloaded {} bytes", len);
+//! ```
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use header::{ByteRangeSpec, ContentLength, ContentRange, ContentRangeSpec, ETag, Headers, IfRange};
+use header::{LastModified, Range};
+use status::StatusCode;
+use url::Url;
+use Error;
+
+use super::{Client, IntoUrl};
+
+/// Where a `Download` writes the body, which must be able to start over.
+pub trait Target: Write {
+    /// Discards everything written so far, for the body to be written
+    /// again from its start.
+    fn restart(&mut self) -> io::Result<()>;
+}
+
+impl Target for File {
+    fn restart(&mut self) -> io::Result<()> {
+        try!(self.set_len(0));
+        try!(self.seek(SeekFrom::Start(0)));
+        Ok(())
+    }
+}
+
+impl Target for Vec<u8> {
+    fn restart(&mut self) -> io::Result<()> {
+        self.clear();
+        Ok(())
+    }
+}
+
+impl<'a, T: Target + ?Sized> Target for &'a mut T {
+    fn restart(&mut self) -> io::Result<()> {
+        (**self).restart()
+    }
+}
+
+/// How far a `Download` got, which can be kept to resume it later, even
+/// in another run of the program.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Progress {
+    /// The bytes of the body written to the target.
+    pub received: u64,
+    /// The length of the whole body, if the server told it.
+    pub length: Option<u64>,
+    /// What tells the body apart from later versions of the resource: its
+    /// `ETag`, if a strong one, or else its `Last-Modified` date. Without
+    /// one, a download can't resume, and starts over instead.
+    pub validator: Option<IfRange>,
+}
+
+/// Downloads a resource to a `Target`, resuming after interruptions.
+pub struct Download<'c, W> {
+    client: &'c Client,
+    url: Url,
+    target: W,
+    progress: Progress,
+    max_resumes: u32,
+}
+
+impl<'c, W: Target> Download<'c, W> {
+    /// Download `url` with `client`, writing it to `target`.
+    pub fn new<U: IntoUrl>(client: &'c Client, url: U, target: W) -> ::Result<Download<'c, W>> {
+        Download::resume(client, url, target, Progress::default())
+    }
+
+    /// Go on with a download that got as far as `progress`, such as in an
+    /// earlier run of the program, with `target` already holding the bytes
+    /// received then, and positioned after them.
+    pub fn resume<U: IntoUrl>(client: &'c Client, url: U, target: W, progress: Progress)
+                              -> ::Result<Download<'c, W>> {
+        Ok(Download {
+            client: client,
+            url: try!(url.into_url()),
+            target: target,
+            progress: progress,
+            max_resumes: 3,
+        })
+    }
+
+    /// Set how many times `run` resumes after the connection fails, which
+    /// is 3 by default.
+    pub fn set_max_resumes(&mut self, max: u32) {
+        self.max_resumes = max;
+    }
+
+    /// How far the download got.
+    pub fn progress(&self) -> &Progress {
+        &self.progress
+    }
+
+    /// Consumes the `Download`, returning its target.
+    pub fn into_inner(self) -> W {
+        self.target
+    }
+
+    /// Downloads the rest of the body, and returns its whole length.
+    ///
+    /// A failure to connect or to read the response is resumed from, as
+    /// often as `set_max_resumes` allows. Errors writing to the target end
+    /// the download, as does a response that is neither a `200 OK` nor the
+    /// `206 Partial Content` asked for, with `Error::Status`.
+    pub fn run(&mut self) -> ::Result<u64> {
+        let mut resumes = 0;
+        loop {
+            match self.attempt() {
+                Ok(()) => return Ok(self.progress.received),
+                Err(Stop::Interrupted(e)) if resumes < self.max_resumes => {
+                    debug!("download of {} interrupted after {} bytes: {:?}",
+                           self.url, self.progress.received, e);
+                    resumes += 1;
+                },
+                Err(Stop::Interrupted(e)) | Err(Stop::Failed(e)) => return Err(e),
+            }
+        }
+    }
+
+    fn attempt(&mut self) -> Result<(), Stop> {
+        let resuming = self.progress.received > 0 && self.progress.validator.is_some();
+        let mut req = self.client.get(self.url.clone());
+        if let (true, Some(validator)) = (resuming, self.progress.validator.clone()) {
+            req = req.header(Range::Bytes(vec![ByteRangeSpec::AllFrom(self.progress.received)]))
+                .header(validator);
+        }
+        let mut res = try!(req.send().map_err(|e| match e {
+            Error::Io(..) => Stop::Interrupted(e),
+            e => Stop::Failed(e),
+        }));
+
+        match res.status {
+            StatusCode::PartialContent if resuming => match res.headers.get() {
+                Some(&ContentRange(ContentRangeSpec::Bytes { range: Some((first, _)), instance_length }))
+                        if first == self.progress.received => {
+                    self.progress.length = instance_length.or(self.progress.length);
+                },
+                _ => return Err(Stop::Failed(Error::Header)),
+            },
+            StatusCode::RangeNotSatisfiable if resuming => match res.headers.get() {
+                // all of it had been received
+                Some(&ContentRange(ContentRangeSpec::Bytes { range: None, instance_length: Some(len) }))
+                        if len == self.progress.received => {
+                    self.progress.length = Some(len);
+                    return Ok(());
+                },
+                _ => return Err(Stop::Failed(Error::Status)),
+            },
+            StatusCode::Ok => {
+                if self.progress.received > 0 {
+                    debug!("restarting the download of {}", self.url);
+                    try!(self.target.restart().map_err(|e| Stop::Failed(Error::Io(e))));
+                    self.progress.received = 0;
+                }
+                self.progress.validator = validator(&res.headers);
+                self.progress.length = res.headers.get::<ContentLength>().map(|len| **len);
+            },
+            _ => return Err(Stop::Failed(Error::Status)),
+        }
+
+        let mut buf = [0; 8192];
+        loop {
+            let n = match res.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(Stop::Interrupted(Error::Io(e))),
+            };
+            try!(self.target.write_all(&buf[..n]).map_err(|e| Stop::Failed(Error::Io(e))));
+            self.progress.received += n as u64;
+        }
+        try!(self.target.flush().map_err(|e| Stop::Failed(Error::Io(e))));
+        match self.progress.length {
+            Some(len) if self.progress.received < len => {
+                let e = io::Error::new(io::ErrorKind::UnexpectedEof, "the body ended early");
+                Err(Stop::Interrupted(Error::Io(e)))
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<'c, W> fmt::Debug for Download<'c, W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Download")
+            .field("url", &self.url)
+            .field("progress", &self.progress)
+            .field("max_resumes", &self.max_resumes)
+            .finish()
+    }
+}
+
+// Why an attempt at a download ended.
+enum Stop {
+    // the connection failed, and the download can resume
+    Interrupted(Error),
+    // the download can't go on
+    Failed(Error),
+}
+
+fn validator(headers: &Headers) -> Option<IfRange> {
+    match headers.get::<ETag>() {
+        Some(&ETag(ref tag)) if !tag.weak => Some(IfRange::EntityTag(tag.clone())),
+        _ => headers.get::<LastModified>().map(|&LastModified(date)| IfRange::Date(date)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+    use client::Client;
+    use header::{EntityTag, IfRange};
+    use mock::ScriptedConnector;
+    use super::Download;
+
+    #[test]
+    fn test_resume() {
+        let connector = ScriptedConnector::new();
+        connector.respond_with_error("http://127.0.0.1", b"HTTP/1.1 200 OK\r\nETag: \"v1\"\r\n\
+                                     Content-Length: 10\r\n\r\n0123456789", 55, ErrorKind::ConnectionReset);
+        connector.respond("http://127.0.0.1", b"HTTP/1.1 206 Partial Content\r\n\
+                                                Content-Range: bytes 4-9/10\r\n\
+                                                Content-Length: 6\r\n\r\n456789");
+        let client = Client::with_connector(connector.clone());
+        let mut download = Download::new(&client, "http://127.0.0.1/file", Vec::new()).unwrap();
+        assert_eq!(download.run().unwrap(), 10);
+        assert_eq!(download.progress().validator,
+                   Some(IfRange::EntityTag(EntityTag::strong("v1".to_owned()))));
+        assert_eq!(download.into_inner(), b"0123456789");
+        let resumed = String::from_utf8(connector.written().pop().unwrap()).unwrap();
+        assert!(resumed.contains("Range: bytes=4-\r\n"));
+        assert!(resumed.contains("If-Range: \"v1\"\r\n"));
+        connector.assert_done();
+    }
+
+    #[test]
+    fn test_restart() {
+        let connector = ScriptedConnector::new();
+        connector.respond_with_error("http://127.0.0.1", b"HTTP/1.1 200 OK\r\n\
+                                     Last-Modified: Sun, 06 Nov 1994 08:49:37 GMT\r\n\
+                                     Content-Length: 4\r\n\r\nold!", 86, ErrorKind::ConnectionReset);
+        // the resource changed, and is sent whole
+        connector.respond("http://127.0.0.1", b"HTTP/1.1 200 OK\r\nETag: W/\"v2\"\r\n\
+                                                Content-Length: 3\r\n\r\nnew");
+        let client = Client::with_connector(connector.clone());
+        let mut download = Download::new(&client, "http://127.0.0.1/file", Vec::new()).unwrap();
+        assert_eq!(download.run().unwrap(), 3);
+        assert_eq!(download.progress().validator, None);
+        assert_eq!(download.into_inner(), b"new");
+        let resumed = String::from_utf8(connector.written().pop().unwrap()).unwrap();
+        assert!(resumed.contains("If-Range: Sun, 06 Nov 1994 08:49:37 GMT\r\n"));
+
+        // without a validator, a download starts over instead of resuming
+        let connector = ScriptedConnector::new();
+        connector.respond("http://127.0.0.1", b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nab");
+        connector.respond("http://127.0.0.1", b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nabcd");
+        let client = Client::with_connector(connector.clone());
+        let mut download = Download::new(&client, "http://127.0.0.1/file", Vec::new()).unwrap();
+        download.set_max_resumes(1);
+        assert_eq!(download.run().unwrap(), 4);
+        assert_eq!(download.into_inner(), b"abcd");
+        assert!(!String::from_utf8(connector.written().pop().unwrap()).unwrap().contains("Range"));
+        connector.assert_done();
+    }
+}
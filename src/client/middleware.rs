@@ -0,0 +1,143 @@
+//! Running code around each request a `Client` sends.
+//!
+//! `Middleware` added with `Client::add_middleware` is handed every request
+//! before it is sent, including each retry and each redirect followed, and
+//! the `Next` to pass it on to. It can change the request on its way, look
+//! at the response, or answer without sending anything, which makes it the
+//! place for concerns that every request shares, such as authentication,
+//! signing or tracing.
+//!
+//! ```
+//! use hyper::Client;
+//! use hyper::client::{Response, Middleware};
+//! use hyper::client::middleware::{Next, Outgoing};
+//! use hyper::header::{Authorization, Bearer};
+//!
+//! struct Token(String);
+//!
+//! impl Middleware for Token {
+//!     fn handle(&self, mut req: Outgoing, next: Next) -> hyper::Result<Response> {
+//!         if req.url.domain() == Some("api.example.domain") {
+//!             req.headers.set(Authorization(Bearer { token: self.0.clone() }));
+//!         }
+//!         next.run(req)
+//!     }
+//! }
+//!
+//! let mut client = Client::new();
+//! client.add_middleware(Token("secret".to_owned()));
+//! ```
+use std::fmt;
+
+use extensions::Extensions;
+use header::Headers;
+use method::Method;
+use url::Url;
+
+use client::{Body, Client, Response, send_once};
+
+/// Code run around each request a `Client` sends.
+pub trait Middleware: Send + Sync {
+    /// Handles `req`, usually by passing it to `next.run`, and returns the
+    /// response.
+    fn handle(&self, req: Outgoing, next: Next) -> ::Result<Response>;
+}
+
+impl<'a> fmt::Debug for Middleware + 'a {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad("Middleware")
+    }
+}
+
+/// A request on its way through the middleware of a `Client`.
+///
+/// The headers are those the request was built with. `Host`,
+/// `Content-Length`, cookies from the `CookieJar`, a proxy's credentials and
+/// the `Accept-Encoding` of `Client::set_decompression` are added after the
+/// middleware has run.
+pub struct Outgoing<'r, 'a: 'r> {
+    /// The method of the request.
+    pub method: Method,
+    /// The URL the request is sent to.
+    pub url: Url,
+    /// The headers of the request.
+    pub headers: Headers,
+    /// The body of the request, if any.
+    pub body: Option<Body<'a>>,
+    /// The extensions passed to the connector.
+    pub extensions: &'r mut Extensions,
+    // where a body that can be sent again is left, for a retry or redirect
+    resend: &'r mut Option<Body<'a>>,
+}
+
+impl<'r, 'a> Outgoing<'r, 'a> {
+    #[doc(hidden)]
+    pub fn new(method: Method, url: Url, headers: Headers, extensions: &'r mut Extensions,
+                      body: &'r mut Option<Body<'a>>) -> Outgoing<'r, 'a> {
+        Outgoing {
+            method: method,
+            url: url,
+            headers: headers,
+            body: body.take(),
+            extensions: extensions,
+            resend: body,
+        }
+    }
+
+    // Moves the body to where it is left for another attempt, and returns
+    // that place.
+    #[doc(hidden)]
+    pub fn into_parts(self) -> (Method, Url, Headers, &'r mut Extensions,
+                                       &'r mut Option<Body<'a>>) {
+        let Outgoing { method, url, headers, body, extensions, resend } = self;
+        *resend = body;
+        (method, url, headers, extensions, resend)
+    }
+}
+
+impl<'r, 'a> fmt::Debug for Outgoing<'r, 'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Outgoing")
+            .field("method", &self.method)
+            .field("url", &self.url)
+            .field("headers", &self.headers)
+            .field("body", &self.body.is_some())
+            .finish()
+    }
+}
+
+/// The rest of the middleware, and then sending the request.
+pub struct Next<'r> {
+    client: &'r Client,
+    middleware: &'r [Box<Middleware>],
+}
+
+impl<'r> Next<'r> {
+    #[doc(hidden)]
+    pub fn new(client: &'r Client) -> Next<'r> {
+        Next {
+            client: client,
+            middleware: &client.middleware,
+        }
+    }
+
+    /// Passes `req` to the next middleware, or sends it if there is none
+    /// left.
+    pub fn run(self, req: Outgoing) -> ::Result<Response> {
+        match self.middleware.split_first() {
+            Some((first, rest)) => first.handle(req, Next {
+                client: self.client,
+                middleware: rest,
+            }),
+            None => send_once(self.client, req),
+        }
+    }
+}
+
+impl<'r> fmt::Debug for Next<'r> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Next")
+            .field("middleware", &self.middleware.len())
+            .finish()
+    }
+}
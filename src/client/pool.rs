@@ -4,10 +4,28 @@ use std::collections::HashMap;
 use std::io::{self, Read, Write};
 use std::net::{SocketAddr, Shutdown};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use net::{NetworkConnector, NetworkStream, DefaultConnector};
+use extensions::Extensions;
+use metrics::{self, Tag};
+use net::{NetworkConnector, NetworkStream, DefaultConnector, Resolve, ResolveOverrides};
+use net::{ServerName, TlsInfo, Tunnel};
 
 /// The `NetworkConnector` that behaves as a connection pool used by hyper's `Client`.
+///
+/// The extensions of a request only reach the inner connector when a new
+/// connection is made. An idle connection is reused for a request to the
+/// same host, port and scheme, and with the same:
+///
+/// - `ServerName`,
+/// - `Tunnel`,
+/// - address for the host in its `ResolveOverrides`, and
+/// - `Arc<Resolve>`, by identity, unless the address is overridden.
+///
+/// `Timeouts` are set again on every request by `Http11Protocol`, so a
+/// reused connection honors them as well, except for the connect timeout.
+/// Other extensions, such as ones a custom connector reads, don't keep
+/// connections apart; such a connector shouldn't be wrapped in a `Pool`.
 pub struct Pool<C: NetworkConnector> {
     connector: C,
     inner: Arc<Mutex<PoolImpl<<C as NetworkConnector>::Stream>>>
@@ -16,7 +34,8 @@ pub struct Pool<C: NetworkConnector> {
 /// Config options for the `Pool`.
 #[derive(Debug)]
 pub struct Config {
-    /// The maximum idle connections *per host*.
+    /// The maximum idle connections *per host*. When a host has this many,
+    /// the one idle the longest is closed to make room for another.
     pub max_idle: usize,
 }
 
@@ -31,14 +50,59 @@ impl Default for Config {
 
 #[derive(Debug)]
 struct PoolImpl<S> {
-    conns: HashMap<Key, Vec<S>>,
+    conns: HashMap<Key, Vec<Idle<S>>>,
     config: Config,
+    idle_timeout: Option<Duration>,
 }
 
-type Key = (String, u16, Scheme);
+// An idle connection, with its identifier, how many requests it has
+// been used for, and when it was last returned to the pool.
+#[derive(Debug)]
+struct Idle<S> {
+    conn: S,
+    id: usize,
+    uses: usize,
+    since: Instant,
+}
+
+type Key = (String, u16, Scheme, Route);
 
-fn key<T: Into<Scheme>>(host: &str, port: u16, scheme: T) -> Key {
-    (host.to_owned(), port, scheme.into())
+fn key<T: Into<Scheme>>(host: &str, port: u16, scheme: T, extensions: &Extensions) -> Key {
+    (host.to_owned(), port, scheme.into(), Route::new(host, port, extensions))
+}
+
+// What a connection was made with, from the extensions of the request that
+// made it, that makes it unfit for requests made with something else.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+struct Route {
+    // the name a TLS connection was verified against, if not the host
+    server_name: Option<String>,
+    // the proxy tunneled through, and the credentials given to it
+    tunnel: Option<(String, u16, Option<String>)>,
+    // the address connected to in place of resolving the host
+    addr: Option<SocketAddr>,
+    // the identity of the resolver the host was resolved with
+    resolver: Option<usize>,
+}
+
+impl Route {
+    fn new(host: &str, port: u16, extensions: &Extensions) -> Route {
+        let tunnel = extensions.get::<Tunnel>();
+        // with a tunnel, it is the proxy that is overridden or resolved
+        let (host, port) = tunnel.map_or((host, port), |tunnel| (&tunnel.host[..], tunnel.port));
+        let addr = extensions.get::<ResolveOverrides>().and_then(|o| o.get(host, port));
+        let resolver = match addr {
+            Some(_) => None,
+            None => extensions.get::<Arc<Resolve>>()
+                .map(|resolver| &**resolver as *const Resolve as *const () as usize),
+        };
+        Route {
+            server_name: extensions.get::<ServerName>().map(|name| name.0.clone()),
+            tunnel: tunnel.map(|t| (t.host.clone(), t.port, t.authorization.clone())),
+            addr: addr,
+            resolver: resolver,
+        }
+    }
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
@@ -75,46 +139,130 @@ impl<C: NetworkConnector> Pool<C> {
             inner: Arc::new(Mutex::new(PoolImpl {
                 conns: HashMap::new(),
                 config: config,
+                idle_timeout: Some(Duration::from_secs(90)),
             }))
         }
     }
 
+    /// Set how long a connection may stay idle before it is closed instead
+    /// of reused, or `None` to keep it for as long as the pool lives. The
+    /// default is 90 seconds.
+    ///
+    /// Servers close connections idle for too long themselves, and reusing
+    /// one they are about to close fails the request sent on it.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use hyper::client::Client;
+    /// use hyper::client::pool::{Config, Pool};
+    ///
+    /// let mut pool = Pool::new(Config { max_idle: 20 });
+    /// pool.set_idle_timeout(Some(Duration::from_secs(30)));
+    /// let client = Client::with_connector(pool);
+    /// # drop(client);
+    /// ```
+    pub fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+        self.inner.lock().unwrap().idle_timeout = timeout;
+    }
+
     /// Clear all idle connections from the Pool, closing them.
     #[inline]
     pub fn clear_idle(&mut self) {
         self.inner.lock().unwrap().conns.clear();
     }
+
+    /// Close the idle connections that have outlived the idle timeout.
+    ///
+    /// The pool does this itself whenever a connection is taken from or
+    /// returned to it, so this is only needed to release connections of a
+    /// client that sits unused.
+    pub fn evict_expired(&self) {
+        self.inner.lock().unwrap().evict_expired(Instant::now());
+    }
+
+    /// The number of idle connections in the pool, over all hosts.
+    pub fn idle_count(&self) -> usize {
+        self.inner.lock().unwrap().conns.values().map(Vec::len).sum()
+    }
 }
 
 impl<S> PoolImpl<S> {
-    fn reuse(&mut self, key: Key, conn: S) {
-        trace!("reuse {:?}", key);
+    fn reuse(&mut self, key: Key, idle: Idle<S>) {
+        trace!("reuse {:?} connection {}", key, idle.id);
+        self.evict_expired(idle.since);
+        let max_idle = self.config.max_idle;
+        if max_idle == 0 {
+            return;
+        }
         let conns = self.conns.entry(key).or_insert(vec![]);
-        if conns.len() < self.config.max_idle {
-            conns.push(conn);
+        if conns.len() >= max_idle {
+            let evicted = conns.remove(0);
+            trace!("evicting connection {}, pool is full", evicted.id);
+        }
+        conns.push(idle);
+    }
+
+    // Takes the most recently used connection to `key` that hasn't expired.
+    fn take(&mut self, key: &Key, now: Instant) -> Option<Idle<S>> {
+        self.evict_expired(now);
+        let idle = match self.conns.get_mut(key) {
+            Some(conns) => conns.pop(),
+            None => None,
+        };
+        if self.conns.get(key).map_or(false, Vec::is_empty) {
+            self.conns.remove(key);
         }
+        idle
+    }
+
+    fn evict_expired(&mut self, now: Instant) {
+        let timeout = match self.idle_timeout {
+            Some(timeout) => timeout,
+            None => return,
+        };
+        for conns in self.conns.values_mut() {
+            conns.retain(|idle| {
+                let expired = now.duration_since(idle.since) >= timeout;
+                if expired {
+                    trace!("evicting connection {}, idle for too long", idle.id);
+                }
+                !expired
+            });
+        }
+        self.conns.retain(|_, conns| !conns.is_empty());
     }
 }
 
 impl<C: NetworkConnector<Stream=S>, S: NetworkStream + Send> NetworkConnector for Pool<C> {
     type Stream = PooledStream<S>;
     fn connect(&self, host: &str, port: u16, scheme: &str) -> ::Result<PooledStream<S>> {
-        let key = key(host, port, scheme);
-        let mut locked = self.inner.lock().unwrap();
-        let mut should_remove = false;
-        let conn = match locked.conns.get_mut(&key) {
-            Some(ref mut vec) => {
+        self.connect_with(host, port, scheme, &Extensions::new())
+    }
+
+    // The extensions only reach the inner connector when a new connection
+    // is made; an idle one is reused for requests whose extensions lead to
+    // the same kind of connection, as the docs of `Pool` list.
+    fn connect_with(&self, host: &str, port: u16, scheme: &str, extensions: &Extensions)
+                    -> ::Result<PooledStream<S>> {
+        let key = key(host, port, scheme, extensions);
+        let idle = self.inner.lock().unwrap().take(&key, Instant::now());
+        let idle = match idle {
+            Some(idle) => {
                 trace!("Pool had connection, using");
-                should_remove = vec.len() == 1;
-                vec.pop().unwrap()
+                idle
+            }
+            None => Idle {
+                conn: try!(self.connector.connect_with(host, port, scheme, extensions)),
+                id: metrics::next_connection_id(),
+                uses: 0,
+                since: Instant::now(),
             }
-            _ => try!(self.connector.connect(host, port, scheme))
         };
-        if should_remove {
-            locked.conns.remove(&key);
-        }
+        debug!("connection {} to {:?}, use {}", idle.id, key, idle.uses + 1);
         Ok(PooledStream {
-            inner: Some((key, conn)),
+            inner: Some((key, idle.conn)),
+            id: idle.id,
+            uses: idle.uses + 1,
             is_closed: false,
             pool: self.inner.clone()
         })
@@ -124,6 +272,8 @@ impl<C: NetworkConnector<Stream=S>, S: NetworkStream + Send> NetworkConnector fo
 /// A Stream that will try to be returned to the Pool when dropped.
 pub struct PooledStream<S> {
     inner: Option<(Key, S)>,
+    id: usize,
+    uses: usize,
     is_closed: bool,
     pool: Arc<Mutex<PoolImpl<S>>>
 }
@@ -158,15 +308,59 @@ impl<S: NetworkStream> NetworkStream for PooledStream<S> {
         self.is_closed = true;
         self.inner.as_mut().unwrap().1.close(how)
     }
+
+    #[inline]
+    fn local_addr(&mut self) -> Option<SocketAddr> {
+        self.inner.as_mut().unwrap().1.local_addr()
+    }
+
+    #[inline]
+    fn tls_info(&mut self) -> Option<TlsInfo> {
+        self.inner.as_mut().unwrap().1.tls_info()
+    }
+
+    #[inline]
+    fn peer_certificate(&mut self) -> Option<Vec<u8>> {
+        self.inner.as_mut().unwrap().1.peer_certificate()
+    }
+
+    #[inline]
+    fn set_read_timeout(&mut self, dur: Option<Duration>) -> io::Result<()> {
+        self.inner.as_mut().unwrap().1.set_read_timeout(dur)
+    }
+
+    #[inline]
+    fn set_write_timeout(&mut self, dur: Option<Duration>) -> io::Result<()> {
+        self.inner.as_mut().unwrap().1.set_write_timeout(dur)
+    }
+
+    #[inline]
+    fn set_read_deadline(&mut self, deadline: Option<Instant>) -> io::Result<()> {
+        self.inner.as_mut().unwrap().1.set_read_deadline(deadline)
+    }
+
+    #[inline]
+    fn set_write_deadline(&mut self, deadline: Option<Instant>) -> io::Result<()> {
+        self.inner.as_mut().unwrap().1.set_write_deadline(deadline)
+    }
+
+    #[inline]
+    fn tag(&mut self) -> Option<Tag> {
+        Some(Tag {
+            connection: self.id,
+            request: Some(self.uses),
+        })
+    }
 }
 
 impl<S> Drop for PooledStream<S> {
     fn drop(&mut self) {
         trace!("PooledStream.drop, is_closed={}", self.is_closed);
         if !self.is_closed {
+            let (id, uses) = (self.id, self.uses);
             self.inner.take().map(|(key, conn)| {
                 if let Ok(mut pool) = self.pool.lock() {
-                    pool.reuse(key, conn);
+                    pool.reuse(key, Idle { conn: conn, id: id, uses: uses, since: Instant::now() });
                 }
                 // else poisoned, give up
             });
@@ -177,10 +371,13 @@ impl<S> Drop for PooledStream<S> {
 #[cfg(test)]
 mod tests {
     use std::net::Shutdown;
+    use std::thread;
+    use std::time::Duration;
+    use extensions::Extensions;
     use mock::{MockConnector};
-    use net::{NetworkConnector, NetworkStream};
+    use net::{NetworkConnector, NetworkStream, ServerName};
 
-    use super::{Pool, key};
+    use super::{Config, Pool, key};
 
     macro_rules! mocked {
         () => ({
@@ -191,7 +388,7 @@ mod tests {
     #[test]
     fn test_connect_and_drop() {
         let pool = mocked!();
-        let key = key("127.0.0.1", 3000, "http");
+        let key = key("127.0.0.1", 3000, "http", &Extensions::new());
         pool.connect("127.0.0.1", 3000, "http").unwrap();
         {
             let locked = pool.inner.lock().unwrap();
@@ -215,4 +412,119 @@ mod tests {
         let locked = pool.inner.lock().unwrap();
         assert_eq!(locked.conns.len(), 0);
     }
+
+    #[test]
+    fn test_tag_reuse() {
+        let pool = mocked!();
+        let first = pool.connect("127.0.0.1", 3000, "http").unwrap().tag().unwrap();
+        let second = pool.connect("127.0.0.1", 3000, "http").unwrap().tag().unwrap();
+        assert_eq!(first.request, Some(1));
+        assert_eq!(second.connection, first.connection);
+        assert_eq!(second.request, Some(2));
+
+        let other = pool.connect("127.0.0.1", 3001, "http").unwrap().tag().unwrap();
+        assert!(other.connection != first.connection);
+        assert_eq!(other.request, Some(1));
+    }
+
+    #[test]
+    fn test_server_name() {
+        let pool = mocked!();
+        let mut named = Extensions::new();
+        named.insert(ServerName("api.example.domain".to_owned()));
+        let first = pool.connect_with("127.0.0.1", 3000, "https", &named).unwrap().id;
+
+        // a connection verified against one name isn't used for another
+        let unnamed = pool.connect("127.0.0.1", 3000, "https").unwrap().id;
+        assert!(unnamed != first);
+        named.insert(ServerName("www.example.domain".to_owned()));
+        assert!(pool.connect_with("127.0.0.1", 3000, "https", &named).unwrap().id != first);
+        named.insert(ServerName("api.example.domain".to_owned()));
+        assert_eq!(pool.connect_with("127.0.0.1", 3000, "https", &named).unwrap().id, first);
+        assert_eq!(pool.idle_count(), 3);
+    }
+
+    #[test]
+    fn test_route() {
+        use std::io;
+        use std::net::SocketAddr;
+        use std::sync::Arc;
+        use net::{Resolve, ResolveOverrides, SystemResolver, Tunnel};
+
+        let pool = mocked!();
+        let first = pool.connect("example.domain", 443, "https").unwrap().id;
+
+        let mut tunneled = Extensions::new();
+        tunneled.insert(Tunnel { host: "proxy".to_owned(), port: 3128, authorization: None });
+        let through_proxy = pool.connect_with("example.domain", 443, "https", &tunneled).unwrap().id;
+        assert!(through_proxy != first);
+        assert_eq!(pool.connect_with("example.domain", 443, "https", &tunneled).unwrap().id,
+                   through_proxy);
+
+        let mut overridden = Extensions::new();
+        let mut overrides = ResolveOverrides::new();
+        overrides.insert("example.domain", 443, "127.0.0.1:8443".parse().unwrap());
+        overridden.insert(overrides);
+        let to_override = pool.connect_with("example.domain", 443, "https", &overridden).unwrap().id;
+        assert!(to_override != first);
+        // overrides of other hosts don't matter
+        let mut overrides = ResolveOverrides::new();
+        overrides.insert("other.domain", 443, "127.0.0.1:8443".parse().unwrap());
+        overridden.insert(overrides);
+        assert_eq!(pool.connect_with("example.domain", 443, "https", &overridden).unwrap().id, first);
+
+        struct Fixed;
+
+        impl Resolve for Fixed {
+            fn resolve(&self, _: &str, _: u16) -> io::Result<Vec<SocketAddr>> {
+                Ok(vec!["127.0.0.1:443".parse().unwrap()])
+            }
+        }
+
+        let fixed = Arc::new(Fixed) as Arc<Resolve>;
+        let mut resolved = Extensions::new();
+        resolved.insert(fixed.clone());
+        let with_resolver = pool.connect_with("example.domain", 443, "https", &resolved).unwrap().id;
+        assert!(with_resolver != first);
+        assert_eq!(pool.connect_with("example.domain", 443, "https", &resolved).unwrap().id,
+                   with_resolver);
+        resolved.insert(Arc::new(SystemResolver) as Arc<Resolve>);
+        assert!(pool.connect_with("example.domain", 443, "https", &resolved).unwrap().id
+                != with_resolver);
+    }
+
+    #[test]
+    fn test_max_idle() {
+        let mut pool = Pool::with_connector(Config { max_idle: 2 }, MockConnector);
+        pool.set_idle_timeout(None);
+        let streams = (0..3).map(|_| pool.connect("127.0.0.1", 3000, "http").unwrap())
+                            .collect::<Vec<_>>();
+        let mut ids = streams.iter().map(|stream| stream.id).collect::<Vec<_>>();
+        drop(streams);
+        assert_eq!(pool.idle_count(), 2);
+
+        // the connection idle the longest made room for the others
+        ids.remove(0);
+        let mut reused = vec![
+            pool.connect("127.0.0.1", 3000, "http").unwrap().id,
+            pool.connect("127.0.0.1", 3000, "http").unwrap().id,
+        ];
+        reused.sort();
+        assert_eq!(reused, ids);
+    }
+
+    #[test]
+    fn test_idle_timeout() {
+        let mut pool = Pool::with_connector(Config { max_idle: 5 }, MockConnector);
+        pool.set_idle_timeout(Some(Duration::from_millis(50)));
+        let first = pool.connect("127.0.0.1", 3000, "http").unwrap().id;
+        assert_eq!(pool.connect("127.0.0.1", 3000, "http").unwrap().id, first);
+        drop(pool.connect("127.0.0.1", 3001, "http").unwrap());
+        assert_eq!(pool.idle_count(), 2);
+
+        thread::sleep(Duration::from_millis(100));
+        pool.evict_expired();
+        assert_eq!(pool.idle_count(), 0);
+        assert!(pool.connect("127.0.0.1", 3000, "http").unwrap().id != first);
+    }
 }
@@ -0,0 +1,79 @@
+//! Following how much of a body has been written or read, for
+//! `RequestBuilder::on_upload_progress` and
+//! `RequestBuilder::on_download_progress`.
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::Arc;
+
+/// Told how many bytes of a body have been transferred, and how many the
+/// whole body has, if that is known.
+pub type Observer = Arc<Fn(u64, Option<u64>) + Send + Sync>;
+
+/// The observer of writing a request's body, kept in its extensions.
+#[derive(Clone)]
+pub struct UploadProgress(pub Observer);
+
+/// The observer of reading a response's body, kept in the extensions of
+/// its request.
+#[derive(Clone)]
+pub struct DownloadProgress(pub Observer);
+
+/// Counts the bytes of a body, telling an observer as they add up.
+pub struct Counter {
+    observer: Observer,
+    transferred: u64,
+    total: Option<u64>,
+}
+
+impl Counter {
+    pub fn new(observer: Observer, total: Option<u64>) -> Counter {
+        Counter {
+            observer: observer,
+            transferred: 0,
+            total: total,
+        }
+    }
+
+    pub fn add(&mut self, n: usize) {
+        if n > 0 {
+            self.transferred += n as u64;
+            (self.observer)(self.transferred, self.total);
+        }
+    }
+}
+
+impl fmt::Debug for Counter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Counter")
+            .field("transferred", &self.transferred)
+            .field("total", &self.total)
+            .finish()
+    }
+}
+
+/// A writer counting the bytes written through it.
+pub struct Counted<'a, W: 'a> {
+    inner: &'a mut W,
+    counter: Counter,
+}
+
+impl<'a, W: Write> Counted<'a, W> {
+    pub fn new(inner: &'a mut W, counter: Counter) -> Counted<'a, W> {
+        Counted {
+            inner: inner,
+            counter: counter,
+        }
+    }
+}
+
+impl<'a, W: Write> Write for Counted<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = try!(self.inner.write(buf));
+        self.counter.add(n);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
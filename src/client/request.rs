@@ -1,10 +1,14 @@
 //! Client Requests
 use std::marker::PhantomData;
 use std::io::{self, Write};
+use std::sync::Arc;
+use std::time::Duration;
 
 use url::Url;
 
+use extensions::Extensions;
 use method::{self, Method};
+use metrics::{self, ClientEvents, Tag};
 use header::Headers;
 use header::Host;
 use net::{NetworkStream, NetworkConnector, DefaultConnector, Fresh, Streaming};
@@ -27,6 +31,8 @@ pub struct Request<W> {
     message: Box<HttpMessage>,
     headers: Headers,
     method: method::Method,
+    extensions: Extensions,
+    tag: Tag,
 
     _marker: PhantomData<W>,
 }
@@ -39,15 +45,41 @@ impl<W> Request<W> {
     /// Read the Request method.
     #[inline]
     pub fn method(&self) -> method::Method { self.method.clone() }
+
+    /// Read the values attached to this Request.
+    #[inline]
+    pub fn extensions(&self) -> &Extensions { &self.extensions }
+
+    /// Get a mutable reference to the values attached to this Request.
+    #[inline]
+    pub fn extensions_mut(&mut self) -> &mut Extensions { &mut self.extensions }
+
+    /// Identifies the connection this Request is sent on, and how many
+    /// requests, this one included, have been started on it.
+    ///
+    /// A connection that isn't pooled is only used once, and so gets an
+    /// identifier of its own for each request.
+    #[inline]
+    pub fn tag(&self) -> Tag { self.tag }
+
+    /// Reports the progress of the request to `events`.
+    #[doc(hidden)]
+    pub fn set_events(&mut self, events: Arc<ClientEvents>) {
+        self.message.set_events(events, self.tag);
+    }
 }
 
 impl Request<Fresh> {
     /// Create a new `Request<Fresh>` that will use the given `HttpMessage` for its communication
     /// with the server. This implies that the given `HttpMessage` instance has already been
     /// properly initialized by the caller (e.g. a TCP connection's already established).
-    pub fn with_message(method: method::Method, url: Url, message: Box<HttpMessage>)
+    pub fn with_message(method: method::Method, url: Url, mut message: Box<HttpMessage>)
             -> ::Result<Request<Fresh>> {
         let (host, port) = try!(get_host_and_port(&url));
+        let tag = message.tag().unwrap_or_else(|| Tag {
+            connection: metrics::next_connection_id(),
+            request: Some(1),
+        });
         let mut headers = Headers::new();
         headers.set(Host {
             hostname: host,
@@ -60,6 +92,8 @@ impl Request<Fresh> {
             url: url,
             version: version::HttpVersion::Http11,
             message: message,
+            extensions: Extensions::new(),
+            tag: tag,
             _marker: PhantomData,
         })
     }
@@ -96,6 +130,8 @@ impl Request<Fresh> {
             url: head.url,
             version: self.version,
             message: self.message,
+            extensions: self.extensions,
+            tag: self.tag,
             _marker: PhantomData,
         })
     }
@@ -106,11 +142,30 @@ impl Request<Fresh> {
 }
 
 impl Request<Streaming> {
+    /// Waits up to `timeout` for the server to agree to a request sent with
+    /// `Expect: 100-continue`, before writing its body.
+    ///
+    /// Returns whether to write the body. It isn't when the server already
+    /// sent its final response, which `send` then returns. Not hearing back
+    /// in time means the body should be written anyway.
+    pub fn wait_for_continue(&mut self, timeout: Duration) -> ::Result<bool> {
+        self.message.wait_for_continue(timeout)
+    }
+
+    /// Closes the connection of a request that can't be completed, such as
+    /// when its body fails to be read, so that it isn't reused.
+    #[doc(hidden)]
+    pub fn abort(mut self) -> ::Result<()> {
+        self.message.close_connection()
+    }
+
     /// Completes writing the request, and returns a response to read from.
     ///
     /// Consumes the Request.
     pub fn send(self) -> ::Result<Response> {
-        Response::with_message(self.url, self.message)
+        let mut res = try!(Response::with_message(self.url, self.message));
+        res.set_tag(self.tag);
+        Ok(res)
     }
 }
 
@@ -130,7 +185,7 @@ impl Write for Request<Streaming> {
 mod tests {
     use std::str::from_utf8;
     use url::Url;
-    use method::Method::{Get, Head, Post};
+    use method::Method::{Connect, Get, Head, Post};
     use mock::{MockStream, MockConnector};
     use net::Fresh;
     use header::{ContentLength,TransferEncoding,Encoding};
@@ -220,4 +275,16 @@ mod tests {
         assert!(!s.contains("Content-Length:"));
         assert!(s.contains("Transfer-Encoding:"));
     }
+
+    #[test]
+    fn test_connect_authority_form() {
+        let url = Url::parse("https://example.dom").unwrap();
+        let req = Request::with_connector(
+            Connect, url, &mut MockConnector
+        ).unwrap();
+        let bytes = run_request(req);
+        let s = from_utf8(&bytes[..]).unwrap();
+        assert!(s.starts_with("CONNECT example.dom:443 HTTP/1.1\r\n"), "{}", s);
+        assert_no_body(s);
+    }
 }
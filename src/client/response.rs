@@ -1,10 +1,22 @@
 //! Client Responses
-use std::io::{self, Read};
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::mem;
+use std::net::Shutdown;
+use std::ptr;
 
 use url::Url;
 
-use header;
+#[cfg(feature = "flate2")]
+use compress::{DeflateDecoder, GzipDecoder};
+#[cfg(feature = "brotli")]
+use compress::BrotliDecoder;
+use header::{self, ContentEncoding, ContentLength, ContentType, Encoding};
+use mime::{Attr, Mime};
+use metrics::{self, Tag};
 use net::NetworkStream;
+use super::progress::Counter;
+use Error;
 use http::{self, RawStatus, ResponseHead, HttpMessage};
 use http::h1::Http11Message;
 use status;
@@ -22,8 +34,10 @@ pub struct Response {
     /// The final URL of this response.
     pub url: Url,
     status_raw: RawStatus,
-    message: Box<HttpMessage>,
+    body: Body,
     is_drained: bool,
+    tag: Tag,
+    progress: Option<Counter>,
 }
 
 impl Response {
@@ -41,15 +55,21 @@ impl Response {
         let status = status::StatusCode::from_u16(raw_status.0);
         debug!("version={:?}, status={:?}", version, status);
         debug!("headers={:?}", headers);
+        let tag = message.tag().unwrap_or_else(|| Tag {
+            connection: metrics::next_connection_id(),
+            request: Some(1),
+        });
 
         Ok(Response {
             status: status,
             version: version,
             headers: headers,
             url: url,
-            message: message,
+            body: Body::Plain(message),
             status_raw: raw_status,
             is_drained: false,
+            tag: tag,
+            progress: None,
         })
     }
 
@@ -58,21 +78,320 @@ impl Response {
         &self.status_raw
     }
 
+    /// The trailer fields sent after a chunked body, such as a checksum of
+    /// it, once the body has been read to its end.
+    pub fn trailers(&self) -> Option<&header::Headers> {
+        self.body.message().trailers()
+    }
+
+    /// Identifies the connection this response came on, and the position
+    /// of its request on it, as `Request::tag` does.
+    #[inline]
+    pub fn tag(&self) -> Tag {
+        self.tag
+    }
+
+    #[doc(hidden)]
+    pub fn set_tag(&mut self, tag: Tag) {
+        self.tag = tag;
+    }
+
+    #[doc(hidden)]
+    pub fn set_progress(&mut self, counter: Counter) {
+        self.progress = Some(counter);
+    }
+
+    // Reads the body decompressed, if its `Content-Encoding` is one of the
+    // codings in `compress`, and then leaves out that header and the
+    // `Content-Length`, which were of the compressed body.
+    #[doc(hidden)]
+    pub fn decompress(&mut self) {
+        let coding = match self.headers.get::<ContentEncoding>() {
+            Some(&ContentEncoding(ref codings)) if codings.len() == 1 => codings[0].clone(),
+            _ => return,
+        };
+        match coding {
+            #[cfg(feature = "flate2")]
+            Encoding::Gzip | Encoding::Deflate => (),
+            #[cfg(feature = "brotli")]
+            Encoding::Brotli => (),
+            _ => return,
+        }
+        debug!("decompressing {} body", coding);
+        // `decode` only moves the message, so nothing can unwind between
+        // the read and the write
+        unsafe {
+            let body = ptr::read(&self.body);
+            ptr::write(&mut self.body, body.decode(&coding));
+        }
+        self.headers.remove::<ContentEncoding>();
+        self.headers.remove::<ContentLength>();
+    }
+
+    /// Take the connection this response came on, to speak the protocol it
+    /// switched to with a `101 Switching Protocols`, or to tunnel through
+    /// after a `CONNECT` was answered with a `2xx`.
+    ///
+    /// Any other response is an `Error::Status`, as is a response received
+    /// over HTTP/2.
+    pub fn into_upgraded(self) -> ::Result<Upgraded> {
+        let upgraded = self.status == status::StatusCode::SwitchingProtocols ||
+            self.status.is_success();
+        if !upgraded || !self.body.message().is::<Http11Message>() {
+            return Err(Error::Status);
+        }
+
+        // there's nothing left of the response to drain, nor a connection to
+        // close, so take the message apart without dropping the response
+        let message = unsafe {
+            let message = ptr::read(&self.body).into_message();
+            drop(ptr::read(&self.headers));
+            drop(ptr::read(&self.url));
+            drop(ptr::read(&self.status_raw));
+            drop(ptr::read(&self.progress));
+            mem::forget(self);
+            message
+        };
+        let message = message.downcast::<Http11Message>().ok().unwrap();
+        let (stream, buffered) = message.into_upgraded();
+        Ok(Upgraded {
+            stream: stream,
+            buffered: io::Cursor::new(buffered),
+        })
+    }
+
+    /// Read the body, of at most `limit` bytes, as text in the charset named
+    /// by its `Content-Type`.
+    ///
+    /// UTF-8, US-ASCII, ISO-8859-1 and UTF-16 are understood, and any other
+    /// charset is an error. Without a charset, the body is read as UTF-8 if it
+    /// is valid UTF-8, and as ISO-8859-1 otherwise. Invalid UTF-8 and UTF-16
+    /// is replaced with `U+FFFD`.
+    ///
+    /// A body longer than `limit` is a `TooLarge` error.
+    pub fn text(&mut self, limit: u64) -> ::Result<String> {
+        let mut body = Vec::new();
+        try!(self.by_ref().take(limit.saturating_add(1)).read_to_end(&mut body));
+        if body.len() as u64 > limit {
+            return Err(::Error::TooLarge);
+        }
+        let charset = match self.headers.get::<ContentType>() {
+            Some(&ContentType(Mime(_, _, ref params))) => {
+                params.iter().find(|&&(ref attr, _)| *attr == Attr::Charset)
+                    .map(|&(_, ref value)| value.to_string().to_ascii_lowercase())
+            },
+            None => None,
+        };
+        decode(body, charset.as_ref().map(|charset| &charset[..])).map_err(::Error::Io)
+    }
+
+}
+
+fn decode(body: Vec<u8>, charset: Option<&str>) -> io::Result<String> {
+    fn latin1(body: &[u8]) -> String {
+        body.iter().map(|&b| b as char).collect()
+    }
+
+    fn utf16(body: &[u8], big_endian: bool) -> String {
+        let units = body.chunks(2).map(|pair| {
+            let (a, b) = (pair[0] as u16, *pair.get(1).unwrap_or(&0) as u16);
+            if big_endian { a << 8 | b } else { b << 8 | a }
+        }).collect::<Vec<_>>();
+        String::from_utf16_lossy(&units)
+    }
+
+    Ok(match charset {
+        None => match String::from_utf8(body) {
+            Ok(text) => text,
+            Err(e) => latin1(e.as_bytes()),
+        },
+        Some("utf-8") | Some("utf8") => match String::from_utf8(body) {
+            Ok(text) => text,
+            Err(e) => String::from_utf8_lossy(e.as_bytes()).into_owned(),
+        },
+        Some("us-ascii") | Some("iso-8859-1") | Some("latin1") => latin1(&body),
+        Some("utf-16be") => utf16(&body, true),
+        Some("utf-16le") => utf16(&body, false),
+        // without a byte order mark, UTF-16 is big endian
+        Some("utf-16") => match body.get(..2) {
+            Some(&[0xFF, 0xFE]) => utf16(&body[2..], false),
+            Some(&[0xFE, 0xFF]) => utf16(&body[2..], true),
+            _ => utf16(&body, true),
+        },
+        Some(charset) => return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                                   format!("unsupported charset: {}", charset))),
+    })
+
+}
+
+// The body of a response, as it came or decompressed.
+#[derive(Debug)]
+enum Body {
+    Plain(Box<HttpMessage>),
+    #[cfg(feature = "flate2")]
+    Gzip(GzipDecoder<Box<HttpMessage>>),
+    #[cfg(feature = "flate2")]
+    Deflate(DeflateDecoder<Box<HttpMessage>>),
+    #[cfg(feature = "brotli")]
+    Brotli(BrotliDecoder<Box<HttpMessage>>),
+}
+
+impl Body {
+    fn decode(self, coding: &Encoding) -> Body {
+        match (self, coding) {
+            #[cfg(feature = "flate2")]
+            (Body::Plain(message), &Encoding::Gzip) => Body::Gzip(GzipDecoder::new(message)),
+            #[cfg(feature = "flate2")]
+            (Body::Plain(message), &Encoding::Deflate) => Body::Deflate(DeflateDecoder::new(message)),
+            #[cfg(feature = "brotli")]
+            (Body::Plain(message), &Encoding::Brotli) => Body::Brotli(BrotliDecoder::new(message)),
+            (body, _) => body,
+        }
+    }
+
+    fn message(&self) -> &HttpMessage {
+        match *self {
+            Body::Plain(ref message) => &**message,
+            #[cfg(feature = "flate2")]
+            Body::Gzip(ref decoder) => &**decoder.get_ref(),
+            #[cfg(feature = "flate2")]
+            Body::Deflate(ref decoder) => &**decoder.get_ref(),
+            #[cfg(feature = "brotli")]
+            Body::Brotli(ref decoder) => &**decoder.get_ref(),
+        }
+    }
+
+    fn message_mut(&mut self) -> &mut Box<HttpMessage> {
+        match *self {
+            Body::Plain(ref mut message) => message,
+            #[cfg(feature = "flate2")]
+            Body::Gzip(ref mut decoder) => decoder.get_mut(),
+            #[cfg(feature = "flate2")]
+            Body::Deflate(ref mut decoder) => decoder.get_mut(),
+            #[cfg(feature = "brotli")]
+            Body::Brotli(ref mut decoder) => decoder.get_mut(),
+        }
+    }
+
+    fn into_message(self) -> Box<HttpMessage> {
+        match self {
+            Body::Plain(message) => message,
+            #[cfg(feature = "flate2")]
+            Body::Gzip(decoder) => decoder.into_inner(),
+            #[cfg(feature = "flate2")]
+            Body::Deflate(decoder) => decoder.into_inner(),
+            #[cfg(feature = "brotli")]
+            Body::Brotli(decoder) => decoder.into_inner(),
+        }
+    }
+}
+
+impl Read for Body {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match *self {
+            Body::Plain(ref mut message) => message.read(buf),
+            #[cfg(feature = "flate2")]
+            Body::Gzip(ref mut decoder) => decoder.read(buf),
+            #[cfg(feature = "flate2")]
+            Body::Deflate(ref mut decoder) => decoder.read(buf),
+            #[cfg(feature = "brotli")]
+            Body::Brotli(ref mut decoder) => decoder.read(buf),
+        }
+    }
 }
 
 impl Read for Response {
     #[inline]
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        match self.message.read(buf) {
+        match self.body.read(buf) {
             Ok(0) => {
+                // the compressed data can end before the message does, which
+                // has to be read to its end for the connection to be reused
+                match self.body {
+                    Body::Plain(..) => (),
+                    #[cfg(any(feature = "flate2", feature = "brotli"))]
+                    ref mut body => { try!(io::copy(body.message_mut(), &mut io::sink())); },
+                }
                 self.is_drained = true;
                 Ok(0)
             },
+            Ok(n) => {
+                if let Some(ref mut progress) = self.progress {
+                    progress.add(n);
+                }
+                Ok(n)
+            },
             r => r
         }
     }
 }
 
+/// A connection that has stopped speaking HTTP, after a protocol switch or
+/// a `CONNECT`.
+///
+/// Reading first gives back any bytes that had already arrived after the
+/// response head, then reads from the connection. The connection is closed
+/// when this is dropped, rather than returned to a `Pool` to be reused.
+pub struct Upgraded {
+    stream: Box<NetworkStream + Send>,
+    buffered: io::Cursor<Vec<u8>>,
+}
+
+impl Upgraded {
+    /// Get a reference to the underlying stream.
+    pub fn get_ref(&self) -> &Box<NetworkStream + Send> {
+        &self.stream
+    }
+
+    /// Get a mutable reference to the underlying stream.
+    ///
+    /// Reading from it directly skips any buffered bytes.
+    pub fn get_mut(&mut self) -> &mut Box<NetworkStream + Send> {
+        &mut self.stream
+    }
+
+    /// Shut down the reading, writing, or both halves of the connection.
+    pub fn close(&mut self, how: Shutdown) -> io::Result<()> {
+        self.stream.close(how)
+    }
+}
+
+impl Read for Upgraded {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if (self.buffered.position() as usize) < self.buffered.get_ref().len() {
+            self.buffered.read(buf)
+        } else {
+            self.stream.read(buf)
+        }
+    }
+}
+
+impl Write for Upgraded {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl fmt::Debug for Upgraded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Upgraded")
+            .field("buffered", &(self.buffered.get_ref().len() - self.buffered.position() as usize))
+            .finish()
+    }
+}
+
+impl Drop for Upgraded {
+    fn drop(&mut self) {
+        if let Err(e) = self.stream.close(Shutdown::Both) {
+            debug!("error closing upgraded connection: {}", e);
+        }
+    }
+}
+
 impl Drop for Response {
     fn drop(&mut self) {
         // if not drained, theres old bits in the Reader. we can't reuse this,
@@ -83,7 +402,7 @@ impl Drop for Response {
         trace!("Response.is_drained = {:?}", self.is_drained);
         if !(self.is_drained && http::should_keep_alive(self.version, &self.headers)) {
             trace!("closing connection");
-            if let Err(e) = self.message.close_connection() {
+            if let Err(e) = self.body.message_mut().close_connection() {
                 error!("error closing connection: {}", e);
             }
         }
@@ -215,4 +534,75 @@ mod tests {
 
         assert_eq!(read_to_string(res).unwrap(), "1".to_owned());
     }
+
+    #[test]
+    fn test_text_charset() {
+        fn text(content_type: &str, body: &[u8]) -> ::Result<String> {
+            let mut input = format!("HTTP/1.1 200 OK\r\n{}Content-Length: {}\r\n\r\n",
+                                    content_type, body.len()).into_bytes();
+            input.extend_from_slice(body);
+            let url = Url::parse("http://hyper.rs").unwrap();
+            let mut res = Response::new(url, Box::new(MockStream::with_input(&input))).unwrap();
+            res.text(64)
+        }
+
+        assert_eq!(text("Content-Type: text/plain; charset=utf-8\r\n", "caf\u{e9}".as_bytes())
+                   .unwrap(), "caf\u{e9}");
+        assert_eq!(text("Content-Type: text/plain; charset=ISO-8859-1\r\n", b"caf\xe9")
+                   .unwrap(), "caf\u{e9}");
+        assert_eq!(text("Content-Type: text/plain; charset=utf-16\r\n", b"\xff\xfec\0a\0f\0\xe9\0")
+                   .unwrap(), "caf\u{e9}");
+        assert_eq!(text("Content-Type: text/plain; charset=utf-8\r\n", b"caf\xe9").unwrap(),
+                   "caf\u{fffd}");
+        // without a charset, what isn't UTF-8 is Latin-1
+        assert_eq!(text("", "caf\u{e9}".as_bytes()).unwrap(), "caf\u{e9}");
+        assert_eq!(text("", b"caf\xe9").unwrap(), "caf\u{e9}");
+
+        match text("Content-Type: text/plain; charset=koi8-r\r\n", b"caf") {
+            Err(::Error::Io(ref e)) if e.kind() == io::ErrorKind::InvalidData => (),
+            other => panic!("expected unsupported charset, got {:?}", other),
+        }
+        match text("", &[b'a'; 65]) {
+            Err(::Error::TooLarge) => (),
+            other => panic!("expected TooLarge, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_into_upgraded() {
+        use std::io::Write;
+        use super::Upgraded;
+
+        let stream = MockStream::with_input(b"\
+            HTTP/1.1 101 Switching Protocols\r\n\
+            Upgrade: websocket\r\n\
+            Connection: Upgrade\r\n\
+            \r\n\
+            \x81\x02hi"
+        );
+        let url = Url::parse("http://hyper.rs").unwrap();
+        let res = Response::new(url, Box::new(stream)).unwrap();
+        let mut upgraded: Upgraded = res.into_upgraded().unwrap();
+
+        let mut frame = Vec::new();
+        upgraded.read_to_end(&mut frame).unwrap();
+        assert_eq!(frame, b"\x81\x02hi");
+        upgraded.write_all(b"\x88\x00").unwrap();
+        assert_eq!(upgraded.get_ref().downcast_ref::<MockStream>().unwrap().write, b"\x88\x00");
+    }
+
+    #[test]
+    fn test_into_upgraded_not_switching() {
+        let stream = MockStream::with_input(b"\
+            HTTP/1.1 404 Not Found\r\n\
+            Content-Length: 0\r\n\
+            \r\n"
+        );
+        let url = Url::parse("http://hyper.rs").unwrap();
+        let res = Response::new(url, Box::new(stream)).unwrap();
+        match res.into_upgraded() {
+            Err(::Error::Status) => (),
+            other => panic!("expected Error::Status, got {:?}", other),
+        }
+    }
 }
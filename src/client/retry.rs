@@ -0,0 +1,333 @@
+//! Deciding whether to retry a request, and how long to wait before it.
+//!
+//! A server that is overloaded or limiting its clients' rate answers
+//! `503 Service Unavailable` or `429 Too Many Requests`, often with a
+//! `Retry-After` saying when to come back. `Backoff` waits exponentially
+//! longer after each attempt, except that it honors such a `Retry-After`,
+//! up to a limit.
+//!
+//! A `Client` given a `RetryPolicy` with `Client::set_retry_policy` retries
+//! requests itself, after such responses, connection errors and timeouts.
+//!
+//! ```
+//! use std::time::Duration;
+//! use hyper::client::retry::Backoff;
+//! use hyper::clock::SystemClock;
+//! use hyper::header::{Headers, RetryAfter};
+//! use hyper::status::StatusCode;
+//!
+//! let backoff = Backoff::default();
+//! let mut headers = Headers::new();
+//! headers.set(RetryAfter::Delay(Duration::from_secs(5)));
+//!
+//! let delay = backoff.delay_after(0, StatusCode::ServiceUnavailable, &headers, &SystemClock);
+//! assert_eq!(delay, Duration::from_secs(5));
+//! ```
+use std::cmp;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io::ErrorKind;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use clock::Clock;
+use header::{Headers, RetryAfter};
+use status::StatusCode;
+use Error;
+
+/// How long to wait between attempts at a request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Backoff {
+    /// The delay before the first retry, doubled for each one after it.
+    ///
+    /// Defaults to 100 milliseconds.
+    pub initial: Duration,
+    /// The longest to wait between attempts, however many there have been.
+    ///
+    /// Defaults to 10 seconds.
+    pub max: Duration,
+    /// The longest to wait when a response's `Retry-After` asks for longer.
+    ///
+    /// Defaults to 60 seconds.
+    pub max_retry_after: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Backoff {
+        Backoff {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(10),
+            max_retry_after: Duration::from_secs(60),
+        }
+    }
+}
+
+impl Backoff {
+    /// The delay before retrying, after `attempt` retries so far.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 1u32.checked_shl(attempt).unwrap_or(u32::max_value());
+        self.initial.checked_mul(factor).map_or(self.max, |delay| cmp::min(delay, self.max))
+    }
+
+    /// The delay before retrying a request that was answered with `status`
+    /// and `headers`, after `attempt` retries so far.
+    ///
+    /// For a `429 Too Many Requests` or `503 Service Unavailable` with a
+    /// `Retry-After`, this is the delay the server asked for, up to
+    /// `max_retry_after`. A `Retry-After` date is measured against `clock`.
+    /// Otherwise, it is the same as `delay(attempt)`.
+    pub fn delay_after(&self, attempt: u32, status: StatusCode, headers: &Headers,
+                       clock: &Clock) -> Duration {
+        self.asked(status, headers, clock).unwrap_or_else(|| self.delay(attempt))
+    }
+
+    fn asked(&self, status: StatusCode, headers: &Headers, clock: &Clock) -> Option<Duration> {
+        let asked = match status {
+            StatusCode::TooManyRequests | StatusCode::ServiceUnavailable => {
+                headers.get::<RetryAfter>().map(|retry_after| retry_delay(retry_after, clock))
+            },
+            _ => None,
+        };
+        asked.map(|asked| cmp::min(asked, self.max_retry_after))
+    }
+}
+
+/// When a `Client` retries a request, set with `Client::set_retry_policy`.
+///
+/// Only requests with an idempotent method, such as `GET`, `PUT` or
+/// `DELETE`, are retried, and only while their body can still be sent:
+/// one from a buffer can be sent again, but one read from a `Read` can't
+/// once it has been, so a `POST`, or an upload from a file, is never sent
+/// twice.
+///
+/// ```
+/// use std::time::Duration;
+/// use hyper::Client;
+/// use hyper::client::retry::RetryPolicy;
+/// use hyper::status::StatusCode;
+///
+/// let mut policy = RetryPolicy::default();
+/// policy.max_retries = 5;
+/// policy.statuses.push(StatusCode::BadGateway);
+/// policy.backoff.max = Duration::from_secs(30);
+///
+/// let mut client = Client::new();
+/// client.set_retry_policy(policy);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// The most times to retry a request, after its first attempt.
+    ///
+    /// Defaults to 3.
+    pub max_retries: u32,
+    /// The statuses of the responses to retry after.
+    ///
+    /// Defaults to `429 Too Many Requests` and `503 Service Unavailable`.
+    pub statuses: Vec<StatusCode>,
+    /// How long to wait before each retry.
+    pub backoff: Backoff,
+    /// Whether to wait a random time between half the backoff's delay and
+    /// all of it, so that clients failing together don't retry together.
+    /// The delay a `Retry-After` asks for is kept as it is.
+    ///
+    /// Defaults to true.
+    pub jitter: bool,
+    /// How many retries all of the client's requests may make together.
+    pub budget: RetryBudget,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 3,
+            statuses: vec![StatusCode::TooManyRequests, StatusCode::ServiceUnavailable],
+            backoff: Backoff::default(),
+            jitter: true,
+            budget: RetryBudget::default(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Whether an attempt failing with `error` is retried, which it is for
+    /// connection errors and timeouts.
+    pub fn retries_error(&self, error: &Error) -> bool {
+        match *error {
+            Error::Io(ref e) => match e.kind() {
+                ErrorKind::ConnectionRefused | ErrorKind::ConnectionReset |
+                ErrorKind::ConnectionAborted | ErrorKind::NotConnected |
+                ErrorKind::BrokenPipe | ErrorKind::UnexpectedEof |
+                ErrorKind::TimedOut | ErrorKind::WouldBlock => true,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// The delay before retrying, after `attempt` retries so far, and a
+    /// response with `status` and `headers`, if there was one.
+    pub fn delay(&self, attempt: u32, response: Option<(StatusCode, &Headers)>,
+                 clock: &Clock) -> Duration {
+        if let Some((status, headers)) = response {
+            if let Some(asked) = self.backoff.asked(status, headers, clock) {
+                return asked;
+            }
+        }
+        let delay = self.backoff.delay(attempt);
+        if self.jitter {
+            delay / 2 + random_fraction(delay / 2)
+        } else {
+            delay
+        }
+    }
+}
+
+// Some time up to `max`. The randomly keyed std hasher is random enough to
+// spread retries out.
+fn random_fraction(max: Duration) -> Duration {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u8(0);
+    let nanos = max.as_secs() * 1_000_000_000 + max.subsec_nanos() as u64;
+    Duration::from_nanos(if nanos == 0 { 0 } else { hasher.finish() % nanos })
+}
+
+/// Limits the retries of all of a client's requests to a share of them, so
+/// that a struggling server isn't sent even more requests.
+///
+/// Each request adds `ratio` to the retries available, up to `max`, and
+/// each retry takes one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RetryBudget {
+    /// The retries each request makes available, such as `0.2` for one
+    /// retry for every five requests.
+    ///
+    /// Defaults to 0.2.
+    pub ratio: f32,
+    /// The most retries available at once, which is also how many are
+    /// available at first.
+    ///
+    /// Defaults to 10.
+    pub max: u32,
+}
+
+impl Default for RetryBudget {
+    fn default() -> RetryBudget {
+        RetryBudget {
+            ratio: 0.2,
+            max: 10,
+        }
+    }
+}
+
+// The retries a client has available under its `RetryBudget`.
+#[derive(Debug)]
+#[doc(hidden)]
+pub struct Tokens {
+    budget: RetryBudget,
+    available: Mutex<f32>,
+}
+
+impl Tokens {
+    pub fn new(budget: RetryBudget) -> Tokens {
+        Tokens {
+            budget: budget,
+            available: Mutex::new(budget.max as f32),
+        }
+    }
+
+    pub fn deposit(&self) {
+        let mut available = self.available.lock().unwrap();
+        *available = (*available + self.budget.ratio).min(self.budget.max as f32);
+    }
+
+    pub fn withdraw(&self) -> bool {
+        let mut available = self.available.lock().unwrap();
+        if *available < 1.0 {
+            return false;
+        }
+        *available -= 1.0;
+        true
+    }
+}
+
+fn retry_delay(retry_after: &RetryAfter, clock: &Clock) -> Duration {
+    match *retry_after {
+        RetryAfter::Delay(delay) => delay,
+        RetryAfter::DateTime(ref date) => {
+            let secs = (date.0.to_timespec() - clock.now().to_timespec()).num_seconds();
+            Duration::from_secs(cmp::max(secs, 0) as u64)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use time::{self, Timespec};
+
+    use clock::ManualClock;
+    use header::{Headers, HttpDate, RetryAfter};
+    use status::StatusCode;
+    use super::{Backoff, RetryPolicy};
+
+    #[test]
+    fn test_delay() {
+        let backoff = Backoff::default();
+        assert_eq!(backoff.delay(0), Duration::from_millis(100));
+        assert_eq!(backoff.delay(3), Duration::from_millis(800));
+        assert_eq!(backoff.delay(7), Duration::from_secs(10));
+        assert_eq!(backoff.delay(40), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_delay_after_retry_after() {
+        let backoff = Backoff::default();
+        let clock = ManualClock::new(time::at_utc(Timespec::new(1000, 0)));
+        let mut headers = Headers::new();
+        assert_eq!(backoff.delay_after(1, StatusCode::ServiceUnavailable, &headers, &clock),
+                   Duration::from_millis(200));
+
+        headers.set(RetryAfter::Delay(Duration::from_secs(3)));
+        assert_eq!(backoff.delay_after(1, StatusCode::TooManyRequests, &headers, &clock),
+                   Duration::from_secs(3));
+        // only an overloaded server's Retry-After is a backoff
+        assert_eq!(backoff.delay_after(1, StatusCode::MovedPermanently, &headers, &clock),
+                   Duration::from_millis(200));
+
+        headers.set(RetryAfter::Delay(Duration::from_secs(3600)));
+        assert_eq!(backoff.delay_after(1, StatusCode::ServiceUnavailable, &headers, &clock),
+                   Duration::from_secs(60));
+
+        headers.set(RetryAfter::DateTime(HttpDate(time::at_utc(Timespec::new(1030, 0)))));
+        assert_eq!(backoff.delay_after(1, StatusCode::ServiceUnavailable, &headers, &clock),
+                   Duration::from_secs(30));
+        headers.set(RetryAfter::DateTime(HttpDate(time::at_utc(Timespec::new(900, 0)))));
+        assert_eq!(backoff.delay_after(1, StatusCode::ServiceUnavailable, &headers, &clock),
+                   Duration::from_secs(0));
+    }
+
+    #[test]
+    fn test_policy_delay() {
+        use std::io::{Error, ErrorKind};
+
+        let policy = RetryPolicy::default();
+        let clock = ManualClock::new(time::at_utc(Timespec::new(1000, 0)));
+        for _ in 0..20 {
+            let delay = policy.delay(2, None, &clock);
+            assert!(delay >= Duration::from_millis(200) && delay <= Duration::from_millis(400));
+        }
+        let mut headers = Headers::new();
+        headers.set(RetryAfter::Delay(Duration::from_secs(3)));
+        assert_eq!(policy.delay(2, Some((StatusCode::ServiceUnavailable, &headers)), &clock),
+                   Duration::from_secs(3));
+        let policy = RetryPolicy { jitter: false, ..RetryPolicy::default() };
+        assert_eq!(policy.delay(2, Some((StatusCode::BadGateway, &headers)), &clock),
+                   Duration::from_millis(400));
+
+        assert!(policy.retries_error(&Error::new(ErrorKind::ConnectionRefused, "refused").into()));
+        assert!(policy.retries_error(&Error::new(ErrorKind::TimedOut, "timed out").into()));
+        assert!(!policy.retries_error(&Error::new(ErrorKind::InvalidData, "invalid").into()));
+        assert!(!policy.retries_error(&::Error::Status));
+    }
+}
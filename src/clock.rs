@@ -0,0 +1,115 @@
+//! Sources of the current time.
+//!
+//! Wherever hyper needs to know "now", such as when a `Server` adds a `Date`
+//! header to a response, it asks a `Clock`. Production code uses the
+//! `SystemClock`, while tests can use a `ManualClock` to freeze time, or
+//! advance it on demand.
+//!
+//! ```
+//! # extern crate hyper;
+//! # extern crate time;
+//! # fn main() {
+//! use std::sync::Arc;
+//! use hyper::clock::{Clock, ManualClock};
+//!
+//! let clock = Arc::new(ManualClock::new(time::at_utc(time::Timespec::new(784111777, 0))));
+//! assert_eq!(clock.http_date().to_string(), "Sun, 06 Nov 1994 08:49:37 GMT");
+//!
+//! clock.advance(time::Duration::days(1));
+//! assert_eq!(clock.http_date().to_string(), "Mon, 07 Nov 1994 08:49:37 GMT");
+//! # }
+//! ```
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use time::{self, Duration, Tm};
+
+use header::HttpDate;
+
+/// A source of the current time.
+pub trait Clock: Send + Sync {
+    /// The current time, in UTC.
+    fn now(&self) -> Tm;
+
+    /// The current time, as an `HttpDate` suitable for `Date` or `Expires`.
+    fn http_date(&self) -> HttpDate {
+        HttpDate(self.now())
+    }
+}
+
+impl<'a> fmt::Debug for Clock + 'a {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad("Clock")
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> Tm {
+        (**self).now()
+    }
+}
+
+/// A `Clock` reading the system time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Tm {
+        time::now_utc()
+    }
+}
+
+/// A `Clock` that only moves when told to.
+#[derive(Debug)]
+pub struct ManualClock {
+    now: Mutex<Tm>,
+}
+
+impl ManualClock {
+    /// Create a clock stopped at `now`.
+    pub fn new(now: Tm) -> ManualClock {
+        ManualClock {
+            now: Mutex::new(now.to_utc()),
+        }
+    }
+
+    /// Move the clock to `now`.
+    pub fn set(&self, now: Tm) {
+        *self.now.lock().unwrap() = now.to_utc();
+    }
+
+    /// Move the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self.now.lock().unwrap();
+        *now = *now + duration;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Tm {
+        *self.now.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{self, Duration, Timespec};
+    use super::{Clock, ManualClock, SystemClock};
+
+    #[test]
+    fn test_manual_clock() {
+        let clock = ManualClock::new(time::at_utc(Timespec::new(0, 0)));
+        assert_eq!(clock.now().to_timespec(), Timespec::new(0, 0));
+        clock.advance(Duration::seconds(90));
+        assert_eq!(clock.now().to_timespec(), Timespec::new(90, 0));
+        clock.set(time::at_utc(Timespec::new(10, 0)));
+        assert_eq!(clock.http_date().to_string(), "Thu, 01 Jan 1970 00:00:10 GMT");
+    }
+
+    #[test]
+    fn test_system_clock() {
+        let before = time::now_utc().to_timespec();
+        let now = SystemClock.now().to_timespec();
+        assert!(now >= before);
+    }
+}
@@ -0,0 +1,601 @@
+//! Streaming gzip, deflate and Brotli compression.
+//!
+//! These are the codecs for the `gzip`, `deflate` and `br` content-codings,
+//! as plain `Read` and `Write` adapters, so they can be put around any body:
+//! wrap a `Response` in a `GzipDecoder` to read it decompressed, or a
+//! streaming `Response` in a `GzipEncoder` to compress what is written.
+//!
+//! The gzip and deflate codecs use the `flate2` crate, and need the optional
+//! `flate2` feature. The `deflate` coding is, despite its name, the zlib
+//! format (RFC 1950), so that is what `DeflateEncoder` writes. Since some
+//! servers send raw DEFLATE data instead, `DeflateDecoder` reads either.
+//!
+//! `BrotliDecoder` and `BrotliEncoder` read and write Brotli (RFC 7932),
+//! using the `brotli` crate. They need the optional `brotli` feature.
+//!
+//! ```
+//! # #[cfg(feature = "flate2")]
+//! # fn main() {
+//! use std::io::{Read, Write};
+//! use hyper::compress::{GzipDecoder, GzipEncoder};
+//!
+//! let mut encoder = GzipEncoder::new(Vec::new());
+//! encoder.write_all(b"Hello, World!").unwrap();
+//! let compressed = encoder.finish().unwrap();
+//!
+//! let mut body = String::new();
+//! GzipDecoder::new(&compressed[..]).read_to_string(&mut body).unwrap();
+//! assert_eq!(body, "Hello, World!");
+//! # }
+//! # #[cfg(not(feature = "flate2"))]
+//! # fn main() {}
+//! ```
+use std::fmt;
+use std::io::{self, Read, Write};
+
+#[cfg(feature = "brotli")]
+use brotli;
+#[cfg(feature = "flate2")]
+use flate2::{self, Compression};
+
+// A middling quality, since bodies are compressed as they are sent, with
+// the default 4MB window.
+#[cfg(feature = "brotli")]
+const BROTLI_QUALITY: u32 = 5;
+#[cfg(feature = "brotli")]
+const BROTLI_WINDOW_BITS: u32 = 22;
+#[cfg(feature = "brotli")]
+const BROTLI_BUFFER_SIZE: usize = 4096;
+
+/// A writer that compresses what is written to it into gzip.
+///
+/// `finish` must be called once everything has been written, to write the
+/// end of the stream.
+#[cfg(feature = "flate2")]
+pub struct GzipEncoder<W: Write> {
+    inner: flate2::write::GzEncoder<W>,
+}
+
+#[cfg(feature = "flate2")]
+impl<W: Write> GzipEncoder<W> {
+    /// Creates an encoder writing into `inner`.
+    pub fn new(inner: W) -> GzipEncoder<W> {
+        GzipEncoder {
+            inner: flate2::write::GzEncoder::new(inner, Compression::default()),
+        }
+    }
+
+    /// Get a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        self.inner.get_ref()
+    }
+
+    /// Get a mutable reference to the underlying writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        self.inner.get_mut()
+    }
+
+    /// Writes the rest of the stream, and returns the underlying writer.
+    pub fn finish(self) -> io::Result<W> {
+        let mut inner = try!(self.inner.finish());
+        try!(inner.flush());
+        Ok(inner)
+    }
+}
+
+#[cfg(feature = "flate2")]
+impl<W: Write> Write for GzipEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    /// Writes out everything written so far, in a form that can already be
+    /// decompressed.
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(feature = "flate2")]
+impl<W: Write> fmt::Debug for GzipEncoder<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GzipEncoder").finish()
+    }
+}
+
+/// A writer that compresses what is written to it into the `deflate`
+/// content-coding, which is the zlib format.
+///
+/// `finish` must be called once everything has been written, to write the
+/// end of the stream.
+#[cfg(feature = "flate2")]
+pub struct DeflateEncoder<W: Write> {
+    inner: flate2::write::ZlibEncoder<W>,
+}
+
+#[cfg(feature = "flate2")]
+impl<W: Write> DeflateEncoder<W> {
+    /// Creates an encoder writing into `inner`.
+    pub fn new(inner: W) -> DeflateEncoder<W> {
+        DeflateEncoder {
+            inner: flate2::write::ZlibEncoder::new(inner, Compression::default()),
+        }
+    }
+
+    /// Get a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        self.inner.get_ref()
+    }
+
+    /// Get a mutable reference to the underlying writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        self.inner.get_mut()
+    }
+
+    /// Writes the rest of the stream, and returns the underlying writer.
+    pub fn finish(self) -> io::Result<W> {
+        let mut inner = try!(self.inner.finish());
+        try!(inner.flush());
+        Ok(inner)
+    }
+}
+
+#[cfg(feature = "flate2")]
+impl<W: Write> Write for DeflateEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    /// Writes out everything written so far, in a form that can already be
+    /// decompressed.
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(feature = "flate2")]
+impl<W: Write> fmt::Debug for DeflateEncoder<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DeflateEncoder").finish()
+    }
+}
+
+/// A writer that compresses what is written to it into the `br`
+/// content-coding, Brotli.
+///
+/// `finish` must be called once everything has been written, to write the
+/// end of the stream.
+#[cfg(feature = "brotli")]
+pub struct BrotliEncoder<W: Write> {
+    inner: brotli::CompressorWriter<W>,
+}
+
+#[cfg(feature = "brotli")]
+impl<W: Write> BrotliEncoder<W> {
+    /// Creates an encoder writing into `inner`.
+    pub fn new(inner: W) -> BrotliEncoder<W> {
+        BrotliEncoder {
+            inner: brotli::CompressorWriter::new(inner, BROTLI_BUFFER_SIZE, BROTLI_QUALITY,
+                                                 BROTLI_WINDOW_BITS),
+        }
+    }
+
+    /// Get a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        self.inner.get_ref()
+    }
+
+    /// Get a mutable reference to the underlying writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        self.inner.get_mut()
+    }
+
+    /// Writes the rest of the stream, and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        // the end of the stream is written without reporting errors, so at
+        // least find those of the writer before it
+        try!(self.inner.flush());
+        let mut inner = self.inner.into_inner();
+        try!(inner.flush());
+        Ok(inner)
+    }
+}
+
+#[cfg(feature = "brotli")]
+impl<W: Write> Write for BrotliEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    /// Writes out everything written so far, in a form that can already be
+    /// decompressed.
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(feature = "brotli")]
+impl<W: Write> fmt::Debug for BrotliEncoder<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BrotliEncoder").finish()
+    }
+}
+
+/// A reader that decompresses gzip read from another reader.
+///
+/// Concatenated gzip members are read as one stream, as `gunzip` does.
+#[cfg(feature = "flate2")]
+pub struct GzipDecoder<R: Read> {
+    inner: flate2::read::MultiGzDecoder<R>,
+}
+
+#[cfg(feature = "flate2")]
+impl<R: Read> GzipDecoder<R> {
+    /// Creates a decoder reading from `inner`.
+    pub fn new(inner: R) -> GzipDecoder<R> {
+        GzipDecoder {
+            inner: flate2::read::MultiGzDecoder::new(inner),
+        }
+    }
+
+    /// Get a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref()
+    }
+
+    /// Get a mutable reference to the underlying reader.
+    ///
+    /// Reading from it directly will corrupt the decompressed stream.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut()
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+}
+
+#[cfg(feature = "flate2")]
+impl<R: Read> Read for GzipDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+#[cfg(feature = "flate2")]
+impl<R: Read> fmt::Debug for GzipDecoder<R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GzipDecoder").finish()
+    }
+}
+
+/// A reader that decompresses the `deflate` content-coding read from
+/// another reader.
+///
+/// This is the zlib format, but raw DEFLATE data is read as well.
+#[cfg(feature = "flate2")]
+pub struct DeflateDecoder<R: Read> {
+    // only None while the first read swaps `Start` for a decoder
+    inner: Option<Inflater<R>>,
+}
+
+// The first 2 bytes tell a zlib stream from raw DEFLATE, and are then read
+// again by the decoder for it, ahead of the rest.
+#[cfg(feature = "flate2")]
+type Prefixed<R> = io::Chain<io::Cursor<Vec<u8>>, R>;
+
+#[cfg(feature = "flate2")]
+enum Inflater<R: Read> {
+    Start(R),
+    Zlib(flate2::read::ZlibDecoder<Prefixed<R>>),
+    Raw(flate2::read::DeflateDecoder<Prefixed<R>>),
+}
+
+#[cfg(feature = "flate2")]
+impl<R: Read> DeflateDecoder<R> {
+    /// Creates a decoder reading from `inner`.
+    pub fn new(inner: R) -> DeflateDecoder<R> {
+        DeflateDecoder {
+            inner: Some(Inflater::Start(inner)),
+        }
+    }
+
+    /// Get a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        match self.inner {
+            Some(Inflater::Start(ref inner)) => inner,
+            Some(Inflater::Zlib(ref decoder)) => decoder.get_ref().get_ref().1,
+            Some(Inflater::Raw(ref decoder)) => decoder.get_ref().get_ref().1,
+            None => unreachable!("DeflateDecoder without a reader"),
+        }
+    }
+
+    /// Get a mutable reference to the underlying reader.
+    ///
+    /// Reading from it directly will corrupt the decompressed stream.
+    pub fn get_mut(&mut self) -> &mut R {
+        match self.inner {
+            Some(Inflater::Start(ref mut inner)) => inner,
+            Some(Inflater::Zlib(ref mut decoder)) => decoder.get_mut().get_mut().1,
+            Some(Inflater::Raw(ref mut decoder)) => decoder.get_mut().get_mut().1,
+            None => unreachable!("DeflateDecoder without a reader"),
+        }
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        match self.inner {
+            Some(Inflater::Start(inner)) => inner,
+            Some(Inflater::Zlib(decoder)) => decoder.into_inner().into_inner().1,
+            Some(Inflater::Raw(decoder)) => decoder.into_inner().into_inner().1,
+            None => unreachable!("DeflateDecoder without a reader"),
+        }
+    }
+}
+
+#[cfg(feature = "flate2")]
+impl<R: Read> Read for DeflateDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let header = match self.inner {
+            Some(Inflater::Start(ref mut inner)) => {
+                let mut header = Vec::with_capacity(2);
+                try!(inner.by_ref().take(2).read_to_end(&mut header));
+                Some(header)
+            },
+            _ => None,
+        };
+        if let Some(header) = header {
+            let inner = match self.inner.take() {
+                Some(Inflater::Start(inner)) => inner,
+                _ => unreachable!("DeflateDecoder without a reader"),
+            };
+            // A zlib header is 2 bytes, the first naming DEFLATE with at most
+            // a 32KB window, and together a multiple of 31. Otherwise, the
+            // stream starts right away with a DEFLATE block.
+            let zlib = match header[..] {
+                [cmf, flg] => cmf & 0x0F == 8 && cmf >> 4 <= 7 &&
+                    (u16::from(cmf) << 8 | u16::from(flg)) % 31 == 0,
+                _ => false,
+            };
+            let prefixed = io::Cursor::new(header).chain(inner);
+            self.inner = Some(if zlib {
+                Inflater::Zlib(flate2::read::ZlibDecoder::new(prefixed))
+            } else {
+                Inflater::Raw(flate2::read::DeflateDecoder::new(prefixed))
+            });
+        }
+        match self.inner {
+            Some(Inflater::Zlib(ref mut decoder)) => decoder.read(buf),
+            Some(Inflater::Raw(ref mut decoder)) => decoder.read(buf),
+            _ => unreachable!("DeflateDecoder without a reader"),
+        }
+    }
+}
+
+#[cfg(feature = "flate2")]
+impl<R: Read> fmt::Debug for DeflateDecoder<R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let zlib = match self.inner {
+            Some(Inflater::Zlib(..)) => Some(true),
+            Some(Inflater::Raw(..)) => Some(false),
+            _ => None,
+        };
+        f.debug_struct("DeflateDecoder").field("zlib", &zlib).finish()
+    }
+}
+
+/// A reader that decompresses the `br` content-coding, Brotli, read from
+/// another reader.
+#[cfg(feature = "brotli")]
+pub struct BrotliDecoder<R: Read> {
+    inner: brotli::Decompressor<R>,
+}
+
+#[cfg(feature = "brotli")]
+impl<R: Read> BrotliDecoder<R> {
+    /// Creates a decoder reading from `inner`.
+    pub fn new(inner: R) -> BrotliDecoder<R> {
+        BrotliDecoder {
+            inner: brotli::Decompressor::new(inner, BROTLI_BUFFER_SIZE),
+        }
+    }
+
+    /// Get a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref()
+    }
+
+    /// Get a mutable reference to the underlying reader.
+    ///
+    /// Reading from it directly will corrupt the decompressed stream.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut()
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+}
+
+#[cfg(feature = "brotli")]
+impl<R: Read> Read for BrotliDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+#[cfg(feature = "brotli")]
+impl<R: Read> fmt::Debug for BrotliDecoder<R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BrotliDecoder").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read, Write};
+
+    #[cfg(feature = "flate2")]
+    use super::{DeflateDecoder, DeflateEncoder, GzipDecoder, GzipEncoder};
+    #[cfg(feature = "brotli")]
+    use super::{BrotliDecoder, BrotliEncoder};
+
+    fn bottles() -> String {
+        (90..100).rev().map(|n| format!("{} bottles of beer on the wall, {} bottles of beer.\n", n, n))
+            .collect()
+    }
+
+    fn read_all<R: Read>(mut r: R) -> io::Result<Vec<u8>> {
+        let mut v = Vec::new();
+        try!(r.read_to_end(&mut v));
+        Ok(v)
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn test_gzip_decode() {
+        // from `gzip`, using dynamic Huffman codes
+        let gz = b"\x1f\x8b\x08\x00\x00\x00\x00\x00\x02\x03\x85\xcb\xcb\t\x800\x10\x05\xc0\xbbUl\
+                   \x01\"\x89\xf9\x97c`\xc5C0\xa0\x01\xdb\xb7\x80<x\xe7aJ\x91\xda\xc7h\xfaJ?\xa5\
+                   \xaa>\xd2o\x19\x97\xcaw\xb4\xb6J\x99|[J&'\x83\x93\xc8I\xe0Dr\"8\x81\x9c\x00\x8e\
+                   '\xc7\x83\xe3\xc8q\xe0\xec\xe4\xec\xe0Xr,8\x86\x1c3\x9f\x1f\xf4\xea\xcd;\x08\x02\
+                   \x00\x00";
+        assert_eq!(read_all(GzipDecoder::new(&gz[..])).unwrap(), bottles().as_bytes());
+
+        let mut concatenated = gz.to_vec();
+        concatenated.extend_from_slice(gz);
+        assert_eq!(read_all(GzipDecoder::new(&concatenated[..])).unwrap().len(), 2 * 520);
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn test_deflate_decode_zlib_and_raw() {
+        let zlib = [120, 156, 243, 72, 205, 201, 201, 215, 81, 8, 207, 47, 202, 73, 81, 84, 240,
+                    192, 198, 203, 168, 44, 72, 45, 82, 4, 0, 29, 252, 13, 156];
+        let expected = b"Hello, World! Hello, World! Hello, hyper!";
+        assert_eq!(read_all(DeflateDecoder::new(&zlib[..])).unwrap(), &expected[..]);
+        assert_eq!(read_all(DeflateDecoder::new(&zlib[2..zlib.len() - 4])).unwrap(),
+                   &expected[..]);
+    }
+
+    #[cfg(feature = "brotli")]
+    #[test]
+    fn test_brotli_decode() {
+        // from `brotli`, with context modeling
+        let br = b"\x1b\x07\x02\xf8\x1d\x07vL\xec\xe9\xb0\xc5H\xe4\x06c\xf3R\x06\x90[K\xf5\xe8b\x0d\xc2\
+                   L\xe2\xe0\xdfP\xc0R\x0e\x00\x9a\x03\xa7\x07\x1e\xca\xf2\x85!V\xfa\xb5\x94\xefF\x1f7%=\xbc_\
+                   r\x04.\xd9O_NWV[FS\xa2\x14\xcbER\xa1X\x00\x00";
+        assert_eq!(read_all(BrotliDecoder::new(&br[..])).unwrap(), bottles().as_bytes());
+
+        // and made mostly of words from the static dictionary
+        let br = b"\x1bM\x00\x00$}x^\xba\x90F(\x19\x06x\x0ck\xcf\x83G\xf9\xacT5\xd9$\x19p)\xd4\xc1\xd2\x00";
+        assert_eq!(read_all(BrotliDecoder::new(&br[..])).unwrap(),
+                   &b"<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Welcome</title></head>"[..]);
+
+        assert!(read_all(BrotliDecoder::new(&br[..br.len() - 3])).is_err());
+        // a window size that is reserved
+        let reserved = read_all(BrotliDecoder::new(&b"\x11\x00"[..])).unwrap_err();
+        assert_eq!(reserved.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn test_decode_errors() {
+        let mut gz = GzipEncoder::new(Vec::new());
+        gz.write_all(bottles().as_bytes()).unwrap();
+        let mut gz = gz.finish().unwrap();
+
+        let truncated = read_all(GzipDecoder::new(&gz[..gz.len() - 6])).unwrap_err();
+        assert_eq!(truncated.kind(), io::ErrorKind::UnexpectedEof);
+
+        let len = gz.len();
+        gz[len - 8] ^= 1;
+        assert!(read_all(GzipDecoder::new(&gz[..])).is_err());
+
+        assert!(read_all(GzipDecoder::new(&b"not gzip at all"[..])).is_err());
+    }
+
+    // long enough to span several blocks, repetitive enough to match
+    fn lines() -> Vec<u8> {
+        let mut body = Vec::new();
+        for i in 0..20000u32 {
+            body.extend_from_slice(format!("line {} of {}\n", i % 977, i / 7).as_bytes());
+        }
+        body
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn test_round_trip() {
+        let body = lines();
+        let mut gz = GzipEncoder::new(Vec::new());
+        for chunk in body.chunks(5000) {
+            gz.write_all(chunk).unwrap();
+        }
+        let gz = gz.finish().unwrap();
+        assert!(gz.len() < body.len() / 3);
+        assert_eq!(read_all(GzipDecoder::new(&gz[..])).unwrap(), body);
+
+        let mut deflate = DeflateEncoder::new(Vec::new());
+        deflate.write_all(&body).unwrap();
+        let deflate = deflate.finish().unwrap();
+        assert_eq!(read_all(DeflateDecoder::new(&deflate[..])).unwrap(), body);
+
+        let empty = GzipEncoder::new(Vec::new()).finish().unwrap();
+        assert_eq!(read_all(GzipDecoder::new(&empty[..])).unwrap(), b"");
+    }
+
+    #[cfg(feature = "brotli")]
+    #[test]
+    fn test_brotli_round_trip() {
+        let body = lines();
+        let mut br = BrotliEncoder::new(Vec::new());
+        for chunk in body.chunks(5000) {
+            br.write_all(chunk).unwrap();
+        }
+        let br = br.finish().unwrap();
+        assert!(br.len() < body.len() / 3);
+        assert_eq!(read_all(BrotliDecoder::new(&br[..])).unwrap(), body);
+
+        let empty = BrotliEncoder::new(Vec::new()).finish().unwrap();
+        assert_eq!(read_all(BrotliDecoder::new(&empty[..])).unwrap(), b"");
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn test_flush_makes_output_decodable() {
+        let mut deflate = DeflateEncoder::new(Vec::new());
+        deflate.write_all(b"event: one\n\n").unwrap();
+        deflate.flush().unwrap();
+
+        let mut decoder = DeflateDecoder::new(&deflate.get_ref()[..]);
+        let mut buf = [0; 64];
+        let n = decoder.read(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"event: one\n\n");
+
+        deflate.write_all(b"event: two\n\n").unwrap();
+        let deflate = deflate.finish().unwrap();
+        assert_eq!(read_all(DeflateDecoder::new(&deflate[..])).unwrap(),
+                   &b"event: one\n\nevent: two\n\n"[..]);
+    }
+
+    #[cfg(feature = "brotli")]
+    #[test]
+    fn test_brotli_flush() {
+        let mut br = BrotliEncoder::new(Vec::new());
+        br.write_all(b"event: one\n\n").unwrap();
+        br.flush().unwrap();
+
+        let mut decoder = BrotliDecoder::new(&br.get_ref()[..]);
+        let mut buf = [0; 64];
+        let n = decoder.read(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"event: one\n\n");
+
+        br.write_all(b"event: two\n\n").unwrap();
+        let br = br.finish().unwrap();
+        assert_eq!(read_all(BrotliDecoder::new(&br[..])).unwrap(),
+                   &b"event: one\n\nevent: two\n\n"[..]);
+    }
+}
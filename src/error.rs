@@ -40,7 +40,7 @@ pub enum Error {
     Version,
     /// An invalid `Header`.
     Header,
-    /// A message head is too large to be reasonable.
+    /// A message head, or a body read whole, is too large to be reasonable.
     TooLarge,
     /// An invalid `Status`, such as `1337 ELITE`.
     Status,
@@ -78,7 +78,7 @@ impl StdError for Error {
             Method => "Invalid Method specified",
             Version => "Invalid HTTP version specified",
             Header => "Invalid Header provided",
-            TooLarge => "Message head is too large",
+            TooLarge => "Message is too large",
             Status => "Invalid Status provided",
             Uri(ref e) => e.description(),
             Io(ref e) => e.description(),
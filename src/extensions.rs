@@ -0,0 +1,111 @@
+//! A map of values of any type, attached to a message.
+//!
+//! Middleware often needs to hand data it worked out, such as an
+//! authenticated identity or route parameters, on to the handlers after it.
+//! `Extensions` holds at most one value of each type, so each piece of code
+//! can keep its data under a type of its own.
+//!
+//! ```
+//! use hyper::extensions::Extensions;
+//!
+//! struct UserId(u32);
+//!
+//! let mut extensions = Extensions::new();
+//! extensions.insert(UserId(7));
+//! assert_eq!(extensions.get::<UserId>().map(|id| id.0), Some(7));
+//! ```
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+
+/// A map holding at most one value of each type.
+#[derive(Default)]
+pub struct Extensions {
+    map: HashMap<TypeId, Box<Any + Send + Sync>>,
+}
+
+impl Extensions {
+    /// Creates a new, empty map.
+    pub fn new() -> Extensions {
+        Extensions { map: HashMap::new() }
+    }
+
+    /// Insert a value, returning the value of the same type it replaced.
+    pub fn insert<T: Any + Send + Sync>(&mut self, value: T) -> Option<T> {
+        self.map.insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|old| old.downcast().ok().map(|old| *old))
+    }
+
+    /// Get a reference to the value of type `T`, if there is one.
+    pub fn get<T: Any + Send + Sync>(&self) -> Option<&T> {
+        self.map.get(&TypeId::of::<T>()).and_then(|value| value.downcast_ref())
+    }
+
+    /// Get a mutable reference to the value of type `T`, if there is one.
+    pub fn get_mut<T: Any + Send + Sync>(&mut self) -> Option<&mut T> {
+        self.map.get_mut(&TypeId::of::<T>()).and_then(|value| value.downcast_mut())
+    }
+
+    /// Returns true if there is a value of type `T`.
+    pub fn contains<T: Any + Send + Sync>(&self) -> bool {
+        self.map.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Remove the value of type `T`, returning it if there was one.
+    pub fn remove<T: Any + Send + Sync>(&mut self) -> Option<T> {
+        self.map.remove(&TypeId::of::<T>())
+            .and_then(|value| value.downcast().ok().map(|value| *value))
+    }
+
+    /// Returns the number of values in the map.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns true if the map holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Remove every value from the map.
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+}
+
+impl fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Extensions {{ len: {} }}", self.map.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Extensions;
+
+    #[derive(Debug, PartialEq)]
+    struct Id(u32);
+
+    #[test]
+    fn test_insert_get() {
+        let mut extensions = Extensions::new();
+        assert_eq!(extensions.insert(Id(1)), None);
+        assert_eq!(extensions.insert(Id(2)), Some(Id(1)));
+        extensions.insert("route");
+        assert_eq!(extensions.get::<Id>(), Some(&Id(2)));
+        assert_eq!(extensions.get::<&'static str>(), Some(&"route"));
+        assert_eq!(extensions.get::<u32>(), None);
+        assert_eq!(extensions.len(), 2);
+    }
+
+    #[test]
+    fn test_get_mut_remove() {
+        let mut extensions = Extensions::new();
+        extensions.insert(Id(1));
+        extensions.get_mut::<Id>().unwrap().0 = 5;
+        assert!(extensions.contains::<Id>());
+        assert_eq!(extensions.remove::<Id>(), Some(Id(5)));
+        assert_eq!(extensions.remove::<Id>(), None);
+        assert!(extensions.is_empty());
+    }
+}
@@ -1,4 +1,4 @@
-use header::{Encoding, QualityItem};
+use header::{Encoding, Quality, QualityItem, parsing};
 
 header! {
     #[doc="`Accept-Encoding` header, defined in"]
@@ -67,5 +67,63 @@ header! {
         test_header!(test4, vec![b"compress;q=0.5, gzip"]);
         // Note: Removed quality 1 from gzip
         test_header!(test5, vec![b"gzip, identity; q=0.5, *;q=0"]);
+
+        #[test]
+        fn test_negotiate() {
+            let supported = [Encoding::Gzip, Encoding::Deflate, Encoding::Identity];
+            let accept = |s: &str| AcceptEncoding::parse_header(&[s.as_bytes().to_vec()]).unwrap();
+
+            assert_eq!(accept("deflate, gzip").negotiate(&supported), Some(Encoding::Gzip));
+            assert_eq!(accept("gzip;q=0.5, deflate").negotiate(&supported), Some(Encoding::Deflate));
+            assert_eq!(accept("br").negotiate(&supported), Some(Encoding::Identity));
+            assert_eq!(accept("").negotiate(&supported), Some(Encoding::Identity));
+            assert_eq!(accept("*").negotiate(&supported), Some(Encoding::Gzip));
+            assert_eq!(accept("gzip;q=0, *;q=0.5").negotiate(&supported), Some(Encoding::Deflate));
+            assert_eq!(accept("br, identity;q=0").negotiate(&supported), None);
+            assert_eq!(accept("br, *;q=0").negotiate(&supported), None);
+            assert_eq!(accept("gzip;q=0, identity;q=0.1").negotiate(&supported),
+                       Some(Encoding::Identity));
+        }
+    }
+}
+
+impl AcceptEncoding {
+    /// The quality of `encoding`, which may match exactly or by `*`.
+    ///
+    /// `identity` is acceptable unless excluded, as
+    /// [RFC7231](https://tools.ietf.org/html/rfc7231#section-5.3.4) says.
+    pub fn quality(&self, encoding: &Encoding) -> Quality {
+        let quality = parsing::match_quality(self, encoding, |coding, encoding| match *coding {
+            Encoding::EncodingExt(ref star) if star == "*" => Some(0),
+            ref coding if coding == encoding => Some(1),
+            _ => None,
+        });
+        match quality {
+            Some(quality) => quality,
+            None if *encoding == Encoding::Identity => Quality(1000),
+            None => Quality(0),
+        }
+    }
+
+    /// Picks the `supported` encoding with the highest quality, preferring
+    /// earlier ones on a tie.
+    ///
+    /// `None` means that none is acceptable, which includes `identity` if
+    /// excluded with `identity;q=0` or `*;q=0`, and the server may respond
+    /// with 406 Not Acceptable. A server that can always send the body
+    /// unencoded should include `Encoding::Identity` last.
+    ///
+    /// ```
+    /// use hyper::header::{AcceptEncoding, Encoding, Quality, QualityItem, qitem};
+    ///
+    /// let accept = AcceptEncoding(vec![
+    ///     QualityItem::new(Encoding::Gzip, Quality(500)),
+    ///     qitem(Encoding::Deflate),
+    /// ]);
+    /// let supported = [Encoding::Gzip, Encoding::Deflate, Encoding::Identity];
+    /// assert_eq!(accept.negotiate(&supported), Some(Encoding::Deflate));
+    /// ```
+    pub fn negotiate(&self, supported: &[Encoding]) -> Option<Encoding> {
+        parsing::negotiate(supported, |encoding| self.quality(encoding)).cloned()
     }
 }
@@ -0,0 +1,76 @@
+use std::fmt::{self, Display};
+use std::str;
+
+use unicase::UniCase;
+
+use header::{Header, HeaderFormat};
+
+/// `Access-Control-Allow-Credentials` header, part of
+/// [CORS](http://www.w3.org/TR/cors/#access-control-allow-credentials-response-header)
+///
+/// The `Access-Control-Allow-Credentials` header indicates whether the
+/// response to a request with credentials, such as cookies, can be exposed.
+/// As part of the response to a preflight request, it indicates that the
+/// actual request can be made with credentials.
+///
+/// Its only valid value is `true`, so the header is left out rather than
+/// sent as `false`. Browsers don't allow credentials with an
+/// `AccessControlAllowOrigin::Any`.
+///
+/// # ABNF
+/// ```plain
+/// Access-Control-Allow-Credentials: "Access-Control-Allow-Credentials" ":" "true"
+/// ```
+///
+/// # Example values
+/// * `true`
+///
+/// # Examples
+/// ```
+/// use hyper::header::{Headers, AccessControlAllowCredentials};
+///
+/// let mut headers = Headers::new();
+/// headers.set(AccessControlAllowCredentials);
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct AccessControlAllowCredentials;
+
+impl Header for AccessControlAllowCredentials {
+    fn header_name() -> &'static str {
+        "Access-Control-Allow-Credentials"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> ::Result<AccessControlAllowCredentials> {
+        if raw.len() == 1 {
+            let text = try!(str::from_utf8(&raw[0]));
+            if UniCase(text.trim()) == UniCase("true") {
+                return Ok(AccessControlAllowCredentials);
+            }
+        }
+        Err(::Error::Header)
+    }
+}
+
+impl HeaderFormat for AccessControlAllowCredentials {
+    fn fmt_header(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("true")
+    }
+}
+
+impl Display for AccessControlAllowCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_header(f)
+    }
+}
+
+#[cfg(test)]
+mod test_access_control_allow_credentials {
+    use std::str;
+    use header::*;
+    use super::AccessControlAllowCredentials as HeaderField;
+    test_header!(works,        vec![b"true"], Some(HeaderField));
+    test_header!(ignores_case, vec![b"True"]);
+    test_header!(not_bool,     vec![b"false"], None::<HeaderField>);
+    test_header!(only_single,  vec![b"true", b"true"], None::<HeaderField>);
+    test_header!(no_gibberish, vec!["\u{645}\u{631}\u{62d}\u{628}\u{627}".as_bytes()], None::<HeaderField>);
+}
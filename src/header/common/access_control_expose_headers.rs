@@ -0,0 +1,42 @@
+use unicase::UniCase;
+
+header! {
+    #[doc="`Access-Control-Expose-Headers` header, part of"]
+    #[doc="[CORS](http://www.w3.org/TR/cors/#access-control-expose-headers-response-header)"]
+    #[doc=""]
+    #[doc="The `Access-Control-Expose-Headers` header indicates which headers of"]
+    #[doc="the response, besides the simple ones, scripts may read."]
+    #[doc=""]
+    #[doc="# ABNF"]
+    #[doc="```plain"]
+    #[doc="Access-Control-Expose-Headers = \"Access-Control-Expose-Headers\" \":\" #field-name"]
+    #[doc="```"]
+    #[doc=""]
+    #[doc="# Example values"]
+    #[doc="* `ETag, Content-Length`"]
+    #[doc=""]
+    #[doc="# Examples"]
+    #[doc="```"]
+    #[doc="# extern crate hyper;"]
+    #[doc="# extern crate unicase;"]
+    #[doc="# fn main() {"]
+    #[doc="// extern crate unicase;"]
+    #[doc=""]
+    #[doc="use hyper::header::{Headers, AccessControlExposeHeaders};"]
+    #[doc="use unicase::UniCase;"]
+    #[doc=""]
+    #[doc="let mut headers = Headers::new();"]
+    #[doc="headers.set("]
+    #[doc="    AccessControlExposeHeaders(vec!["]
+    #[doc="        UniCase(\"etag\".to_owned()),"]
+    #[doc="        UniCase(\"content-length\".to_owned())"]
+    #[doc="    ])"]
+    #[doc=");"]
+    #[doc="# }"]
+    #[doc="```"]
+    (AccessControlExposeHeaders, "Access-Control-Expose-Headers") => (UniCase<String>)*
+
+    test_access_control_expose_headers {
+        test_header!(test1, vec![b"etag, content-length"]);
+    }
+}
@@ -0,0 +1,222 @@
+use std::fmt;
+use std::str::{self, FromStr};
+
+use header::{Header, HeaderFormat, parsing};
+
+/// `Alt-Svc` header, defined in [RFC7838](https://tools.ietf.org/html/rfc7838#section-3)
+///
+/// The `Alt-Svc` header field is sent by a server to advertise alternative
+/// services: other protocols, hosts or ports through which the same
+/// resources can be reached, such as `h2` on another port. The `clear`
+/// value invalidates all alternatives advertised before.
+///
+/// # ABNF
+/// ```plain
+/// Alt-Svc       = clear / 1#alt-value
+/// clear         = %s"clear"
+/// alt-value     = alternative *( OWS ";" OWS parameter )
+/// alternative   = protocol-id "=" alt-authority
+/// protocol-id   = token
+/// alt-authority = quoted-string ; containing [ uri-host ] ":" port
+/// parameter     = token "=" ( token / quoted-string )
+/// ```
+///
+/// # Example values
+/// * `h2=":8000"`
+/// * `h2="new.example.org:80"; ma=3600; persist=1`
+/// * `clear`
+///
+/// # Example
+/// ```
+/// use hyper::header::{Headers, AltSvc, AltService};
+///
+/// let mut h2 = AltService::new("h2", None, 8000);
+/// h2.max_age = Some(3600);
+///
+/// let mut headers = Headers::new();
+/// headers.set(AltSvc::Alternatives(vec![h2]));
+/// assert_eq!(headers.to_string(), "Alt-Svc: h2=\":8000\"; ma=3600\r\n");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum AltSvc {
+    /// `clear`, invalidating all alternatives of the origin.
+    Clear,
+    /// The alternatives of the origin, in order of preference.
+    Alternatives(Vec<AltService>),
+}
+
+/// A single alternative service within an `Alt-Svc` header.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AltService {
+    /// The protocol of the alternative, as its ALPN protocol name, such as
+    /// `h2`.
+    pub protocol_id: String,
+    /// The host of the alternative, or `None` for the origin's own host.
+    pub host: Option<String>,
+    /// The port of the alternative.
+    pub port: u16,
+    /// `ma`, how many seconds the alternative may be used for, which is a
+    /// day if not given.
+    pub max_age: Option<u32>,
+    /// `persist=1`, whether the alternative should be kept despite changes
+    /// to the client's network.
+    pub persist: bool,
+    /// Any other parameters.
+    pub params: Vec<(String, String)>,
+}
+
+impl AltService {
+    /// An alternative with no parameters.
+    pub fn new<P: Into<String>>(protocol_id: P, host: Option<String>, port: u16) -> AltService {
+        AltService {
+            protocol_id: protocol_id.into(),
+            host: host,
+            port: port,
+            max_age: None,
+            persist: false,
+            params: Vec::new(),
+        }
+    }
+}
+
+impl FromStr for AltService {
+    type Err = ::Error;
+
+    fn from_str(s: &str) -> ::Result<AltService> {
+        let mut parts = parsing::split_delimited(s, b';');
+        let (protocol_id, authority) = try!(parse_pair(parts.next().unwrap_or("")));
+        let colon = try!(authority.rfind(':').ok_or(::Error::Header));
+        let port = try!(authority[colon + 1..].parse().map_err(|_| ::Error::Header));
+        let host = match &authority[..colon] {
+            "" => None,
+            host => Some(host.to_owned()),
+        };
+
+        let mut service = AltService::new(protocol_id, host, port);
+        for param in parts {
+            if param.trim().is_empty() {
+                continue;
+            }
+            let (name, value) = try!(parse_pair(param));
+            match &name[..] {
+                "ma" => service.max_age = Some(try!(value.parse().map_err(|_| ::Error::Header))),
+                // other values of persist are reserved, and ignored
+                "persist" => service.persist = value == "1",
+                _ => service.params.push((name, value)),
+            }
+        }
+        Ok(service)
+    }
+}
+
+// Parses `token "=" ( token / quoted-string )`.
+fn parse_pair(s: &str) -> ::Result<(String, String)> {
+    let mut pair = s.splitn(2, '=');
+    let name = pair.next().unwrap_or("").trim();
+    if parsing::needs_quoting(name) {
+        return Err(::Error::Header);
+    }
+    let value = try!(parsing::parse_token_or_quoted(try!(pair.next().ok_or(::Error::Header))));
+    Ok((name.to_owned(), value))
+}
+
+impl fmt::Display for AltService {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let authority = format!("{}:{}", self.host.as_ref().map_or("", |host| &host[..]), self.port);
+        try!(write!(f, "{}=", self.protocol_id));
+        try!(parsing::write_quoted(f, &authority));
+        if let Some(max_age) = self.max_age {
+            try!(write!(f, "; ma={}", max_age));
+        }
+        if self.persist {
+            try!(f.write_str("; persist=1"));
+        }
+        for &(ref name, ref value) in &self.params {
+            try!(write!(f, "; {}=", name));
+            try!(parsing::write_token_or_quoted(f, value));
+        }
+        Ok(())
+    }
+}
+
+impl Header for AltSvc {
+    fn header_name() -> &'static str {
+        "Alt-Svc"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> ::Result<AltSvc> {
+        if raw.len() == 1 && raw[0] == b"clear" {
+            return Ok(AltSvc::Clear);
+        }
+        let mut services = Vec::new();
+        for line in raw {
+            for service in parsing::split_comma_delimited(try!(str::from_utf8(line))) {
+                if !service.trim().is_empty() {
+                    services.push(try!(service.parse()));
+                }
+            }
+        }
+        if services.is_empty() {
+            return Err(::Error::Header);
+        }
+        Ok(AltSvc::Alternatives(services))
+    }
+}
+
+impl HeaderFormat for AltSvc {
+    fn fmt_header(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AltSvc::Clear => f.write_str("clear"),
+            AltSvc::Alternatives(ref services) => parsing::fmt_comma_delimited(f, services),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, Headers};
+    use super::{AltSvc, AltService};
+
+    #[test]
+    fn test_parse() {
+        let alt_svc: AltSvc = Header::parse_header(&[
+            b"h2=\"new.example.org:80\"; ma=3600; persist=1, h2=\":443\"".to_vec(),
+            b"h3-29=\"[::1]:8443\";foo=\"a, b\"".to_vec(),
+        ]).unwrap();
+        let mut new = AltService::new("h2", Some("new.example.org".to_owned()), 80);
+        new.max_age = Some(3600);
+        new.persist = true;
+        let mut h3 = AltService::new("h3-29", Some("[::1]".to_owned()), 8443);
+        h3.params.push(("foo".to_owned(), "a, b".to_owned()));
+        assert_eq!(alt_svc, AltSvc::Alternatives(vec![new, AltService::new("h2", None, 443), h3]));
+
+        assert_eq!(AltSvc::parse_header(&[b"clear".to_vec()]).unwrap(), AltSvc::Clear);
+
+        let parse = |s: &str| AltSvc::parse_header(&[s.as_bytes().to_vec()]);
+        assert!(parse("").is_err());
+        assert!(parse("h2").is_err());
+        assert!(parse("h2=\"example.org\"").is_err());
+        assert!(parse("h2=\":https\"").is_err());
+        assert!(parse("h2=\":443\"; ma=soon").is_err());
+    }
+
+    #[test]
+    fn test_format() {
+        let mut h2 = AltService::new("h2", Some("alt.example.com".to_owned()), 443);
+        h2.persist = true;
+        h2.params.push(("foo".to_owned(), "a b".to_owned()));
+        let alt_svc = AltSvc::Alternatives(vec![h2, AltService::new("h2", None, 8000)]);
+
+        let mut headers = Headers::new();
+        headers.set(alt_svc.clone());
+        assert_eq!(headers.to_string(), "Alt-Svc: h2=\"alt.example.com:443\"; persist=1; \
+                                         foo=\"a b\", h2=\":8000\"\r\n");
+        let raw = headers.get_raw("Alt-Svc").unwrap().to_vec();
+        assert_eq!(AltSvc::parse_header(&raw).unwrap(), alt_svc);
+
+        headers.set(AltSvc::Clear);
+        assert_eq!(headers.to_string(), "Alt-Svc: clear\r\n");
+    }
+}
+
+bench_header!(bench, AltSvc, { vec![b"h2=\"new.example.org:80\"; ma=3600, h2=\":443\"".to_vec()] });
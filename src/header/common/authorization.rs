@@ -77,37 +77,41 @@ impl<S: Scheme + Any> Header for Authorization<S> where <S as FromStr>::Err: 'st
     }
 
     fn parse_header(raw: &[Vec<u8>]) -> ::Result<Authorization<S>> {
-        if raw.len() != 1 {
-            return Err(::Error::Header);
-        }
-        let header = try!(from_utf8(unsafe { &raw.get_unchecked(0)[..] }));
-        return if let Some(scheme) = <S as Scheme>::scheme() {
-            if header.starts_with(scheme) && header.len() > scheme.len() + 1 {
-                match header[scheme.len() + 1..].parse::<S>().map(Authorization) {
-                    Ok(h) => Ok(h),
-                    Err(_) => Err(::Error::Header)
-                }
-            } else {
-                Err(::Error::Header)
-            }
-        } else {
-            match header.parse::<S>().map(Authorization) {
-                Ok(h) => Ok(h),
-                Err(_) => Err(::Error::Header)
-            }
-        }
+        parse_credentials(raw).map(Authorization)
     }
 }
 
 impl<S: Scheme + Any> HeaderFormat for Authorization<S> where <S as FromStr>::Err: 'static {
     fn fmt_header(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if let Some(scheme) = <S as Scheme>::scheme() {
-            try!(write!(f, "{} ", scheme))
-        };
-        self.0.fmt_scheme(f)
+        fmt_credentials(&self.0, f)
+    }
+}
+
+// Parses `credentials`, as in both `Authorization` and `Proxy-Authorization`.
+pub fn parse_credentials<S: Scheme>(raw: &[Vec<u8>]) -> ::Result<S> {
+    if raw.len() != 1 {
+        return Err(::Error::Header);
+    }
+    let header = try!(from_utf8(unsafe { &raw.get_unchecked(0)[..] }));
+    if let Some(scheme) = <S as Scheme>::scheme() {
+        if header.starts_with(scheme) && header.len() > scheme.len() + 1 {
+            header[scheme.len() + 1..].parse::<S>().map_err(|_| ::Error::Header)
+        } else {
+            Err(::Error::Header)
+        }
+    } else {
+        header.parse::<S>().map_err(|_| ::Error::Header)
     }
 }
 
+// Formats `credentials`, with the name of their scheme.
+pub fn fmt_credentials<S: Scheme>(credentials: &S, f: &mut fmt::Formatter) -> fmt::Result {
+    if let Some(scheme) = <S as Scheme>::scheme() {
+        try!(write!(f, "{} ", scheme))
+    };
+    credentials.fmt_scheme(f)
+}
+
 /// An Authorization scheme to be used in the header.
 pub trait Scheme: FromStr + fmt::Debug + Clone + Send + Sync {
     /// An optional Scheme name.
@@ -211,10 +215,33 @@ impl Scheme for Bearer {
 	}
 }
 
+impl Bearer {
+	/// A Bearer token, if `token` is a valid `b64token`, as defined in
+	/// [RFC6750](https://tools.ietf.org/html/rfc6750#section-2.1).
+	pub fn new<S: Into<String>>(token: S) -> ::Result<Bearer> {
+		let token = token.into();
+		if is_b64token(&token) {
+			Ok(Bearer { token: token })
+		} else {
+			Err(::Error::Header)
+		}
+	}
+}
+
+// b64token = 1*( ALPHA / DIGIT / "-" / "." / "_" / "~" / "+" / "/" ) *"="
+fn is_b64token(s: &str) -> bool {
+	let token = s.trim_right_matches('=');
+	!token.is_empty() && token.bytes().all(|b| match b {
+		b'a'...b'z' | b'A'...b'Z' | b'0'...b'9' |
+		b'-' | b'.' | b'_' | b'~' | b'+' | b'/' => true,
+		_ => false
+	})
+}
+
 impl FromStr for Bearer {
 	type Err = ::Error;
 	fn from_str(s: &str) -> ::Result<Bearer> {
-		Ok(Bearer { token: s.to_owned()})
+		Bearer::new(s)
 	}
 }
 
@@ -286,6 +313,19 @@ mod tests {
             &[b"Bearer fpKL54jvWmEGVoRdCNjG".to_vec()]).unwrap();
         assert_eq!(auth.0.token, "fpKL54jvWmEGVoRdCNjG");
     }
+
+    #[test]
+    fn test_bearer_b64token() {
+        assert_eq!(Bearer::new("mF_9.B5f-4.1JqM/a+b~==").unwrap().token, "mF_9.B5f-4.1JqM/a+b~==");
+        assert!(Bearer::new("").is_err());
+        assert!(Bearer::new("==").is_err());
+        assert!(Bearer::new("a=b").is_err());
+        assert!(Bearer::new("two tokens").is_err());
+
+        let auth: ::Result<Authorization<Bearer>> = Header::parse_header(
+            &[b"Bearer fpKL54jvWmEG\"VoRdCNjG".to_vec()]);
+        assert!(auth.is_err());
+    }
 }
 
 bench_header!(raw, Authorization<String>, { vec![b"foo bar baz".to_vec()] });
@@ -1,7 +1,7 @@
 use std::fmt;
 use std::str::FromStr;
 use header::{Header, HeaderFormat};
-use header::parsing::{from_one_comma_delimited, fmt_comma_delimited};
+use header::parsing::{from_one_comma_delimited, fmt_comma_delimited, write_token_or_quoted};
 
 /// `Cache-Control` header, defined in [RFC7234](https://tools.ietf.org/html/rfc7234#section-5.2)
 ///
@@ -104,6 +104,14 @@ pub enum CacheDirective {
     ProxyRevalidate,
     /// "s-maxage=delta"
     SMaxAge(u32),
+    /// "immutable", from [RFC8246](https://tools.ietf.org/html/rfc8246)
+    Immutable,
+
+    // directives from [RFC5861](https://tools.ietf.org/html/rfc5861)
+    /// "stale-while-revalidate=delta"
+    StaleWhileRevalidate(u32),
+    /// "stale-if-error=delta"
+    StaleIfError(u32),
 
     /// Extension directives. Optionally include an argument.
     Extension(String, Option<String>)
@@ -127,9 +135,16 @@ impl fmt::Display for CacheDirective {
             Private => "private",
             ProxyRevalidate => "proxy-revalidate",
             SMaxAge(secs) => return write!(f, "s-maxage={}", secs),
+            Immutable => "immutable",
+
+            StaleWhileRevalidate(secs) => return write!(f, "stale-while-revalidate={}", secs),
+            StaleIfError(secs) => return write!(f, "stale-if-error={}", secs),
 
             Extension(ref name, None) => &name[..],
-            Extension(ref name, Some(ref arg)) => return write!(f, "{}={}", name, arg),
+            Extension(ref name, Some(ref arg)) => {
+                try!(write!(f, "{}=", name));
+                return write_token_or_quoted(f, arg);
+            },
 
         }, f)
     }
@@ -148,6 +163,7 @@ impl FromStr for CacheDirective {
             "public" => Ok(Public),
             "private" => Ok(Private),
             "proxy-revalidate" => Ok(ProxyRevalidate),
+            "immutable" => Ok(Immutable),
             "" => Err(None),
             _ => match s.find('=') {
                 Some(idx) if idx+1 < s.len() => match (&s[..idx], (&s[idx+1..]).trim_matches('"')) {
@@ -155,6 +171,10 @@ impl FromStr for CacheDirective {
                     ("max-stale", secs) => secs.parse().map(MaxStale).map_err(Some),
                     ("min-fresh", secs) => secs.parse().map(MinFresh).map_err(Some),
                     ("s-maxage", secs) => secs.parse().map(SMaxAge).map_err(Some),
+                    ("stale-while-revalidate", secs) => {
+                        secs.parse().map(StaleWhileRevalidate).map_err(Some)
+                    },
+                    ("stale-if-error", secs) => secs.parse().map(StaleIfError).map_err(Some),
                     (left, right) => Ok(Extension(left.to_owned(), Some(right.to_owned())))
                 },
                 Some(_) => Err(None),
@@ -166,7 +186,7 @@ impl FromStr for CacheDirective {
 
 #[cfg(test)]
 mod tests {
-    use header::Header;
+    use header::{Header, Headers};
     use super::*;
 
     #[test]
@@ -183,6 +203,22 @@ mod tests {
                                                  CacheDirective::Private])))
     }
 
+    #[test]
+    fn test_stale_directives() {
+        let raw = b"max-age=60, stale-while-revalidate=30, stale-if-error=600, immutable";
+        let cache = Header::parse_header(&[raw.to_vec()]);
+        let expected = CacheControl(vec![CacheDirective::MaxAge(60),
+                                         CacheDirective::StaleWhileRevalidate(30),
+                                         CacheDirective::StaleIfError(600),
+                                         CacheDirective::Immutable]);
+        assert_eq!(cache.ok(), Some(expected.clone()));
+
+        let mut headers = Headers::new();
+        headers.set(expected);
+        assert_eq!(headers.to_string(), format!("Cache-Control: {}\r\n",
+                                                ::std::str::from_utf8(raw).unwrap()));
+    }
+
     #[test]
     fn test_parse_quote_form() {
         let cache = Header::parse_header(&[b"max-age=\"200\"".to_vec()]);
@@ -197,6 +233,26 @@ mod tests {
             CacheDirective::Extension("bar".to_owned(), Some("baz".to_owned()))])))
     }
 
+    #[test]
+    fn test_parse_quoted_list_argument() {
+        let raw = b"no-cache=\"set-cookie, set-cookie2\", private".to_vec();
+        let cache = Header::parse_header(&[raw]);
+        assert_eq!(cache.ok(), Some(CacheControl(vec![
+            CacheDirective::Extension("no-cache".to_owned(),
+                                      Some("set-cookie, set-cookie2".to_owned())),
+            CacheDirective::Private])))
+    }
+
+    #[test]
+    fn test_fmt_extension() {
+        let cache = CacheControl(vec![
+            CacheDirective::Extension("bar".to_owned(), Some("baz".to_owned())),
+            CacheDirective::Extension("community".to_owned(), Some("UCI ext".to_owned()))]);
+        let mut headers = Headers::new();
+        headers.set(cache);
+        assert_eq!(headers.to_string(), "Cache-Control: bar=baz, community=\"UCI ext\"\r\n");
+    }
+
     #[test]
     fn test_parse_bad_syntax() {
         let cache: ::Result<CacheControl> = Header::parse_header(&[b"foo=".to_vec()]);
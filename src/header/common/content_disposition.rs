@@ -0,0 +1,293 @@
+use std::fmt;
+use std::str::FromStr;
+
+use unicase::UniCase;
+
+use header::{Header, HeaderFormat, Charset};
+use header::parsing::{self, ExtendedValue};
+
+/// `Content-Disposition` header, defined in
+/// [RFC6266](https://tools.ietf.org/html/rfc6266)
+///
+/// The `Content-Disposition` response header field is used to convey
+/// additional information about how to process the response payload, and
+/// also can be used to attach additional metadata, such as the filename to
+/// use when saving the response payload locally.
+///
+/// # ABNF
+/// ```plain
+/// content-disposition = "Content-Disposition" ":"
+///                       disposition-type *( ";" disposition-parm )
+///
+/// disposition-type    = "inline" | "attachment" | disp-ext-type
+///                     ; case-insensitive
+/// disp-ext-type       = token
+///
+/// disposition-parm    = filename-parm | disp-ext-parm
+///
+/// filename-parm       = "filename" "=" value
+///                     | "filename*" "=" ext-value
+///
+/// disp-ext-parm       = token "=" value
+///                     | ext-token "=" ext-value
+/// ext-token           = <the characters in token, followed by "*">
+/// ```
+///
+/// # Example values
+/// * `inline`
+/// * `attachment; filename="report.pdf"`
+/// * `attachment; filename="r_sum_.pdf"; filename*=UTF-8''r%C3%A9sum%C3%A9.pdf`
+///
+/// # Example
+/// ```
+/// use hyper::header::{Headers, ContentDisposition};
+///
+/// let mut headers = Headers::new();
+/// headers.set(ContentDisposition::attachment("résumé.pdf"));
+/// assert_eq!(headers.to_string(), "Content-Disposition: attachment; \
+///     filename=\"r_sum_.pdf\"; filename*=UTF-8''r%C3%A9sum%C3%A9.pdf\r\n");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContentDisposition {
+    /// How the payload should be presented.
+    pub disposition: DispositionType,
+    /// The parameters, in the order they are sent.
+    pub parameters: Vec<DispositionParam>,
+}
+
+/// How a payload with a `Content-Disposition` should be presented.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DispositionType {
+    /// Shown as part of a web page, or as the page itself.
+    Inline,
+    /// Not shown automatically, but saved, usually after asking the user.
+    Attachment,
+    /// An extension type, which recipients treat as `Attachment`.
+    Ext(String),
+}
+
+/// A parameter of a `Content-Disposition`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DispositionParam {
+    /// The `filename` parameter, which can only hold ISO-8859-1 text, and
+    /// should only hold ASCII.
+    Filename(String),
+    /// The `filename*` parameter, encoded as
+    /// [RFC5987](https://tools.ietf.org/html/rfc5987) describes, which can
+    /// hold text in any charset.
+    FilenameExt(ExtendedValue),
+    /// Any other parameter, with its name and its value as sent.
+    Ext(String, String),
+}
+
+impl ContentDisposition {
+    /// An `attachment` to be saved as `filename`.
+    ///
+    /// The name is sent in a `filename` parameter. If it isn't all
+    /// printable ASCII, the `filename` parameter holds an ASCII fallback,
+    /// and the name itself is sent UTF-8 encoded in a `filename*` parameter,
+    /// which recipients prefer if they understand it.
+    pub fn attachment(filename: &str) -> ContentDisposition {
+        let fallback = filename.chars()
+            .map(|c| if c >= ' ' && c < '\x7f' { c } else { '_' })
+            .collect::<String>();
+        let mut parameters = vec![DispositionParam::Filename(fallback.clone())];
+        if fallback != filename {
+            parameters.push(DispositionParam::FilenameExt(ExtendedValue::utf8(filename)));
+        }
+        ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: parameters,
+        }
+    }
+
+    /// The filename to save the payload as, if one was given.
+    ///
+    /// A `filename*` parameter in UTF-8 or ISO-8859-1 is preferred over a
+    /// `filename` parameter. The name is returned as sent, so it must be
+    /// sanitized before being used as a path.
+    pub fn filename(&self) -> Option<String> {
+        let extended = self.parameters.iter().filter_map(|param| match *param {
+            DispositionParam::FilenameExt(ref value) => decode(value),
+            _ => None,
+        }).next();
+        extended.or_else(|| self.parameters.iter().filter_map(|param| match *param {
+            DispositionParam::Filename(ref name) => Some(name.clone()),
+            _ => None,
+        }).next())
+    }
+}
+
+fn decode(value: &ExtendedValue) -> Option<String> {
+    match value.charset {
+        Charset::Ext(ref charset) if UniCase(&charset[..]) == UniCase("UTF-8") => {
+            String::from_utf8(value.value.clone()).ok()
+        },
+        Charset::Iso_8859_1 | Charset::Us_Ascii => {
+            Some(value.value.iter().map(|&b| b as char).collect())
+        },
+        _ => None,
+    }
+}
+
+impl Header for ContentDisposition {
+    fn header_name() -> &'static str {
+        "Content-Disposition"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> ::Result<ContentDisposition> {
+        parsing::from_one_raw_str(raw)
+    }
+}
+
+impl HeaderFormat for ContentDisposition {
+    fn fmt_header(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl FromStr for ContentDisposition {
+    type Err = ::Error;
+
+    fn from_str(s: &str) -> ::Result<ContentDisposition> {
+        let mut parts = parsing::split_delimited(s, b';');
+        let disposition = match parts.next().map(str::trim) {
+            Some(kind) if UniCase(kind) == UniCase("inline") => DispositionType::Inline,
+            Some(kind) if UniCase(kind) == UniCase("attachment") => DispositionType::Attachment,
+            Some(kind) if !parsing::needs_quoting(kind) => DispositionType::Ext(kind.to_owned()),
+            _ => return Err(::Error::Header),
+        };
+
+        let mut parameters = Vec::new();
+        for part in parts {
+            let mut param = part.splitn(2, '=');
+            let (name, value) = match (param.next(), param.next()) {
+                (Some(name), Some(value)) => (name.trim(), value.trim()),
+                _ => return Err(::Error::Header),
+            };
+            if parsing::needs_quoting(name) {
+                return Err(::Error::Header);
+            }
+            parameters.push(if UniCase(name) == UniCase("filename") {
+                DispositionParam::Filename(try!(parsing::parse_token_or_quoted(value)))
+            } else if UniCase(name) == UniCase("filename*") {
+                DispositionParam::FilenameExt(try!(parsing::parse_extended_value(value)))
+            } else if name.ends_with('*') {
+                DispositionParam::Ext(name.to_owned(), value.to_owned())
+            } else {
+                DispositionParam::Ext(name.to_owned(), try!(parsing::parse_token_or_quoted(value)))
+            });
+        }
+
+        Ok(ContentDisposition {
+            disposition: disposition,
+            parameters: parameters,
+        })
+    }
+}
+
+impl fmt::Display for ContentDisposition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(fmt::Display::fmt(&self.disposition, f));
+        for param in &self.parameters {
+            try!(write!(f, "; {}", param));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DispositionType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            DispositionType::Inline => "inline",
+            DispositionType::Attachment => "attachment",
+            DispositionType::Ext(ref kind) => kind,
+        })
+    }
+}
+
+impl fmt::Display for DispositionParam {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DispositionParam::Filename(ref name) => {
+                try!(f.write_str("filename="));
+                parsing::write_quoted(f, name)
+            },
+            DispositionParam::FilenameExt(ref value) => write!(f, "filename*={}", value),
+            DispositionParam::Ext(ref name, ref value) if name.ends_with('*') => {
+                write!(f, "{}={}", name, value)
+            },
+            DispositionParam::Ext(ref name, ref value) => {
+                try!(write!(f, "{}=", name));
+                parsing::write_token_or_quoted(f, value)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, Charset};
+    use header::parsing::ExtendedValue;
+    use super::{ContentDisposition, DispositionType, DispositionParam};
+
+    fn parse(s: &str) -> ::Result<ContentDisposition> {
+        Header::parse_header(&[s.as_bytes().to_vec()])
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("inline").unwrap(), ContentDisposition {
+            disposition: DispositionType::Inline,
+            parameters: vec![],
+        });
+
+        let cd = parse("Attachment; filename=\"a; \\\"b\\\".txt\"; size=10").unwrap();
+        assert_eq!(cd, ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![
+                DispositionParam::Filename("a; \"b\".txt".to_owned()),
+                DispositionParam::Ext("size".to_owned(), "10".to_owned()),
+            ],
+        });
+
+        let cd = parse("form-data; name=field; filename*=iso-8859-1'en'%A3%20rates").unwrap();
+        assert_eq!(cd.disposition, DispositionType::Ext("form-data".to_owned()));
+        assert_eq!(cd.parameters[1], DispositionParam::FilenameExt(ExtendedValue {
+            charset: Charset::Iso_8859_1,
+            language_tag: Some("en".parse().unwrap()),
+            value: b"\xa3 rates".to_vec(),
+        }));
+        assert_eq!(cd.filename().unwrap(), "\u{a3} rates");
+
+        assert!(parse("").is_err());
+        assert!(parse("attachment; filename").is_err());
+        assert!(parse("attachment; filename=two words").is_err());
+        assert!(parse("attachment; filename*=no-quotes").is_err());
+    }
+
+    #[test]
+    fn test_filename_prefers_extended() {
+        let cd = parse("attachment; filename=\"EURO rates\"; \
+                        filename*=utf-8''%e2%82%ac%20rates").unwrap();
+        assert_eq!(cd.filename().unwrap(), "\u{20ac} rates");
+        assert_eq!(parse("attachment; filename=plain").unwrap().filename().unwrap(), "plain");
+        assert_eq!(parse("attachment").unwrap().filename(), None);
+    }
+
+    #[test]
+    fn test_attachment_round_trip() {
+        let ascii = ContentDisposition::attachment("report 2016.pdf");
+        assert_eq!(ascii.to_string(), "attachment; filename=\"report 2016.pdf\"");
+
+        let unicode = ContentDisposition::attachment("\u{20ac} rates.txt");
+        assert_eq!(unicode.filename().unwrap(), "\u{20ac} rates.txt");
+        let reparsed = parse(&unicode.to_string()).unwrap();
+        assert_eq!(reparsed, unicode);
+        assert_eq!(reparsed.filename().unwrap(), "\u{20ac} rates.txt");
+    }
+}
+
+bench_header!(bench, ContentDisposition, {
+    vec![b"attachment; filename=\"r_sum_.pdf\"; filename*=UTF-8''r%C3%A9sum%C3%A9.pdf".to_vec()]
+});
@@ -43,6 +43,7 @@ header! {
     test_content_encoding {
         /// Testcase from the RFC
         test_header!(test1, vec![b"gzip"], Some(ContentEncoding(vec![Encoding::Gzip])));
+        test_header!(test2, vec![b"br"], Some(ContentEncoding(vec![Encoding::Brotli])));
     }
 }
 
@@ -1,6 +1,9 @@
 use std::fmt::{self, Display};
 use std::str::FromStr;
 
+#[cfg(feature = "quickcheck")]
+use quickcheck::{Arbitrary, Gen};
+
 header! {
     #[doc="`Content-Range` header, defined in"]
     #[doc="[RFC7233](http://tools.ietf.org/html/rfc7233#section-4.2)"]
@@ -8,17 +11,17 @@ header! {
 
     test_range {
         test_header!(test1, vec![b"bytes 0-499/500"],
-            Some(ContentRange(ContentRangeSpec {
+            Some(ContentRange(ContentRangeSpec::Bytes {
                 range: Some((0, 499)),
                 instance_length: Some(500)
             })));
         test_header!(test2, vec![b"bytes 0-499/*"],
-            Some(ContentRange(ContentRangeSpec {
+            Some(ContentRange(ContentRangeSpec::Bytes {
                 range: Some((0, 499)),
                 instance_length: None
             })));
         test_header!(test3, vec![b"bytes */500"],
-            Some(ContentRange(ContentRangeSpec {
+            Some(ContentRange(ContentRangeSpec::Bytes {
                 range: None,
                 instance_length: Some(500)
             })));
@@ -26,6 +29,19 @@ header! {
         test_header!(test5, vec![b"bytes"], None::<ContentRange>);
         test_header!(test6, vec![b"bytes 499-0/500"], None::<ContentRange>);
         test_header!(test7, vec![b""], None::<ContentRange>);
+        test_header!(test8, vec![b"items 0-49/200"],
+            Some(ContentRange(ContentRangeSpec::Unregistered {
+                unit: "items".to_owned(),
+                resp: "0-49/200".to_owned()
+            })));
+        test_header!(test9, vec![b"items"], None::<ContentRange>);
+        test_header!(test10, vec![b" 0-49/200"], None::<ContentRange>);
+
+        #[cfg(feature = "quickcheck")]
+        #[test]
+        fn test_round_trip() {
+            ::quickcheck::quickcheck(::header::round_trips as fn(ContentRange) -> bool);
+        }
     }
 }
 
@@ -35,21 +51,56 @@ header! {
 /// # ABNF
 /// ```plain
 /// Range = "Content-Range" ":" content-range-spec
-/// content-range-spec      = byte-content-range-spec
+/// content-range-spec      = byte-content-range-spec / other-content-range
 /// byte-content-range-spec = bytes-unit SP
 ///                           byte-range-resp-spec "/"
 ///                           ( instance-length | "*" )
 /// byte-range-resp-spec = (first-byte-pos "-" last-byte-pos)
 ///                                | "*"
 /// instance-length           = 1*DIGIT
+/// other-content-range       = other-range-unit SP other-range-resp
+/// other-range-resp          = *CHAR
 /// ```
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub struct ContentRangeSpec {
-    /// First and last bytes of the range
-    pub range: Option<(u64, u64)>,
+pub enum ContentRangeSpec {
+    /// Byte range
+    Bytes {
+        /// First and last bytes of the range, omitted if the request could
+        /// not be satisfied
+        range: Option<(u64, u64)>,
+
+        /// Total length of the instance, can be omitted if unknown
+        instance_length: Option<u64>,
+    },
+
+    /// Custom range, with unit not registered at IANA
+    Unregistered {
+        /// other-range-unit
+        unit: String,
+
+        /// other-range-resp
+        resp: String,
+    },
+}
+
+#[cfg(feature = "quickcheck")]
+impl Arbitrary for ContentRangeSpec {
+    fn arbitrary(g: &mut Gen) -> ContentRangeSpec {
+        let range = Option::<(u64, u64)>::arbitrary(g).map(|(a, b)| {
+            if a <= b { (a, b) } else { (b, a) }
+        });
+        ContentRangeSpec::Bytes {
+            range: range,
+            instance_length: Option::arbitrary(g),
+        }
+    }
+}
 
-    /// Total length of the instance, can be omitted if unknown
-    pub instance_length: Option<u64>,
+#[cfg(feature = "quickcheck")]
+impl Arbitrary for ContentRange {
+    fn arbitrary(g: &mut Gen) -> ContentRange {
+        ContentRange(ContentRangeSpec::arbitrary(g))
+    }
 }
 
 macro_rules! try_simple {
@@ -64,13 +115,19 @@ macro_rules! try_simple {
 impl FromStr for ContentRangeSpec {
     type Err = ();
     fn from_str(s: &str) -> Result<Self, ()> {
-        let prefix = "bytes ";
-        if !s.starts_with(prefix) {
-            return Err(());
+        let mut parts = s.splitn(2, ' ');
+        let (unit, resp) = match (parts.next(), parts.next()) {
+            (Some(unit), Some(resp)) if !unit.is_empty() => (unit, resp),
+            _ => return Err(()),
+        };
+        if unit != "bytes" {
+            return Ok(ContentRangeSpec::Unregistered {
+                unit: unit.to_owned(),
+                resp: resp.to_owned(),
+            });
         }
-        let s = &s[prefix.len()..];
 
-        let parts = s.split('/').collect::<Vec<_>>();
+        let parts = resp.split('/').collect::<Vec<_>>();
         if parts.len() != 2 {
             return Err(());
         }
@@ -96,7 +153,7 @@ impl FromStr for ContentRangeSpec {
             Some((first_byte, last_byte))
         };
 
-        Ok(ContentRangeSpec {
+        Ok(ContentRangeSpec::Bytes {
             range: range,
             instance_length: instance_length
         })
@@ -105,20 +162,27 @@ impl FromStr for ContentRangeSpec {
 
 impl Display for ContentRangeSpec {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-	try!(f.write_str("bytes "));
-        match self.range {
-            Some((first_byte, last_byte)) => {
-                try!(f.write_fmt(format_args!("{}-{}", first_byte, last_byte)));
+        match *self {
+            ContentRangeSpec::Bytes { range, instance_length } => {
+                try!(f.write_str("bytes "));
+                match range {
+                    Some((first_byte, last_byte)) => {
+                        try!(write!(f, "{}-{}", first_byte, last_byte));
+                    },
+                    None => {
+                        try!(f.write_str("*"));
+                    }
+                };
+                try!(f.write_str("/"));
+                if let Some(v) = instance_length {
+                    write!(f, "{}", v)
+                } else {
+                    f.write_str("*")
+                }
             },
-            None => {
-                try!(f.write_str("*"));
-            }
-        };
-	try!(f.write_str("/"));
-	if let Some(v) = self.instance_length {
-	    f.write_fmt(format_args!("{}", v))
-	} else {
-	    f.write_str("*")
-	}
+            ContentRangeSpec::Unregistered { ref unit, ref resp } => {
+                write!(f, "{} {}", unit, resp)
+            },
+        }
     }
 }
@@ -0,0 +1,325 @@
+use std::fmt;
+use std::str::FromStr;
+
+use header::{Header, HeaderFormat, parsing};
+
+/// `Content-Security-Policy` header, defined in
+/// [CSP Level 2](https://www.w3.org/TR/CSP2/#content-security-policy-header-field)
+///
+/// The `Content-Security-Policy` header field tells browsers where the
+/// resources of a page, such as its scripts and styles, may be loaded
+/// from, and so limits what an injected script can do.
+///
+/// Browsers ignore the parts of a policy they can't make sense of, so a
+/// misspelled source, such as `self` without its quotes, silently weakens
+/// it. Building the policy from `CspSource`s avoids that.
+///
+/// # ABNF
+/// ```plain
+/// policy-token    = [ directive-token *( ";" [ directive-token ] ) ]
+/// directive-token = *WSP [ directive-name [ WSP directive-value ] ]
+/// directive-name  = 1*( ALPHA / DIGIT / "-" )
+/// directive-value = *( WSP / <VCHAR except ";" and ","> )
+/// ```
+///
+/// # Example values
+/// * `default-src 'self'`
+/// * `default-src 'none'; script-src 'self' 'nonce-2726c7f26c'; frame-ancestors 'none'`
+///
+/// # Example
+/// ```
+/// use hyper::header::{Headers, ContentSecurityPolicy, CspSource};
+///
+/// let mut headers = Headers::new();
+/// headers.set(ContentSecurityPolicy::new()
+///     .default_src(&[CspSource::Self_])
+///     .script_src(&[CspSource::Self_, CspSource::Nonce("2726c7f26c".to_owned())])
+///     .frame_ancestors(&[CspSource::None]));
+/// assert_eq!(headers.to_string(), "Content-Security-Policy: default-src 'self'; \
+///     script-src 'self' 'nonce-2726c7f26c'; frame-ancestors 'none'\r\n");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ContentSecurityPolicy {
+    /// The directives, in the order they are sent.
+    pub directives: Vec<CspDirective>,
+}
+
+/// `Content-Security-Policy-Report-Only` header, defined in
+/// [CSP Level 2](https://www.w3.org/TR/CSP2/#content-security-policy-report-only-header-field)
+///
+/// A policy that browsers don't enforce, but report violations of, to try
+/// out a policy before enforcing it.
+///
+/// # Example
+/// ```
+/// use hyper::header::{Headers, ContentSecurityPolicy, ContentSecurityPolicyReportOnly, CspSource};
+///
+/// let mut headers = Headers::new();
+/// headers.set(ContentSecurityPolicyReportOnly(ContentSecurityPolicy::new()
+///     .default_src(&[CspSource::Self_])
+///     .report_uri("/csp-reports")));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ContentSecurityPolicyReportOnly(pub ContentSecurityPolicy);
+
+/// A directive of a `ContentSecurityPolicy`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CspDirective {
+    /// The name of the directive, in lowercase.
+    pub name: String,
+    /// The values of the directive, such as its source expressions.
+    pub values: Vec<String>,
+}
+
+/// A source expression, which matches where a resource may be loaded from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CspSource {
+    /// `'none'`, which matches nothing.
+    None,
+    /// `'self'`, which matches the origin of the page.
+    Self_,
+    /// `'unsafe-inline'`, which allows inline scripts or styles.
+    UnsafeInline,
+    /// `'unsafe-eval'`, which allows `eval` and the like.
+    UnsafeEval,
+    /// `'strict-dynamic'`, which trusts scripts loaded by trusted scripts.
+    StrictDynamic,
+    /// A `'nonce-...'`, which matches elements with the base64 encoded
+    /// nonce as their `nonce` attribute.
+    Nonce(String),
+    /// A `'sha256-...'`, which matches inline content with the base64
+    /// encoded digest.
+    Sha256(String),
+    /// A `'sha384-...'` digest.
+    Sha384(String),
+    /// A `'sha512-...'` digest.
+    Sha512(String),
+    /// A scheme, such as `https:`, or a host, such as `*.example.com`.
+    Host(String),
+}
+
+impl fmt::Display for CspSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CspSource::None => f.write_str("'none'"),
+            CspSource::Self_ => f.write_str("'self'"),
+            CspSource::UnsafeInline => f.write_str("'unsafe-inline'"),
+            CspSource::UnsafeEval => f.write_str("'unsafe-eval'"),
+            CspSource::StrictDynamic => f.write_str("'strict-dynamic'"),
+            CspSource::Nonce(ref nonce) => write!(f, "'nonce-{}'", nonce),
+            CspSource::Sha256(ref hash) => write!(f, "'sha256-{}'", hash),
+            CspSource::Sha384(ref hash) => write!(f, "'sha384-{}'", hash),
+            CspSource::Sha512(ref hash) => write!(f, "'sha512-{}'", hash),
+            CspSource::Host(ref host) => f.write_str(host),
+        }
+    }
+}
+
+impl ContentSecurityPolicy {
+    /// A policy with no directives, which allows everything.
+    pub fn new() -> ContentSecurityPolicy {
+        ContentSecurityPolicy::default()
+    }
+
+    /// Sets a directive, replacing any with the same name.
+    ///
+    /// Browsers only use the first directive with a given name, so this
+    /// doesn't add another.
+    pub fn directive<I, V>(mut self, name: &str, values: I) -> ContentSecurityPolicy
+    where I: IntoIterator<Item=V>, V: ToString {
+        let directive = CspDirective {
+            name: name.to_lowercase(),
+            values: values.into_iter().map(|v| v.to_string()).collect(),
+        };
+        match self.directives.iter().position(|d| d.name == directive.name) {
+            Some(i) => self.directives[i] = directive,
+            None => self.directives.push(directive),
+        }
+        self
+    }
+
+    /// Sets `default-src`, used for the kinds of resources that have no
+    /// directive of their own.
+    pub fn default_src(self, sources: &[CspSource]) -> ContentSecurityPolicy {
+        self.directive("default-src", sources)
+    }
+
+    /// Sets `script-src`.
+    pub fn script_src(self, sources: &[CspSource]) -> ContentSecurityPolicy {
+        self.directive("script-src", sources)
+    }
+
+    /// Sets `style-src`.
+    pub fn style_src(self, sources: &[CspSource]) -> ContentSecurityPolicy {
+        self.directive("style-src", sources)
+    }
+
+    /// Sets `img-src`.
+    pub fn img_src(self, sources: &[CspSource]) -> ContentSecurityPolicy {
+        self.directive("img-src", sources)
+    }
+
+    /// Sets `connect-src`, for fetches, `XMLHttpRequest`s and websockets.
+    pub fn connect_src(self, sources: &[CspSource]) -> ContentSecurityPolicy {
+        self.directive("connect-src", sources)
+    }
+
+    /// Sets `frame-ancestors`, the pages that may embed this one in a frame.
+    pub fn frame_ancestors(self, sources: &[CspSource]) -> ContentSecurityPolicy {
+        self.directive("frame-ancestors", sources)
+    }
+
+    /// Sets `report-uri`, where browsers send reports of violations.
+    pub fn report_uri(self, uri: &str) -> ContentSecurityPolicy {
+        self.directive("report-uri", Some(uri))
+    }
+
+    /// The values of the directive named `name`, if it is set.
+    pub fn get(&self, name: &str) -> Option<&[String]> {
+        let name = name.to_lowercase();
+        self.directives.iter().find(|d| d.name == name).map(|d| &d.values[..])
+    }
+}
+
+impl FromStr for ContentSecurityPolicy {
+    type Err = ::Error;
+
+    fn from_str(s: &str) -> ::Result<ContentSecurityPolicy> {
+        // a comma separates policies, which can't be merged into one
+        if s.contains(',') {
+            return Err(::Error::Header);
+        }
+        let mut policy = ContentSecurityPolicy::new();
+        for directive in s.split(';') {
+            let mut tokens = directive.split_whitespace();
+            let name = match tokens.next() {
+                Some(name) => name.to_lowercase(),
+                None => continue,
+            };
+            if !name.bytes().all(|b| match b {
+                b'a'...b'z' | b'0'...b'9' | b'-' => true,
+                _ => false,
+            }) {
+                return Err(::Error::Header);
+            }
+            // later directives with the same name are ignored
+            if policy.get(&name).is_none() {
+                policy.directives.push(CspDirective {
+                    name: name,
+                    values: tokens.map(|t| t.to_owned()).collect(),
+                });
+            }
+        }
+        Ok(policy)
+    }
+}
+
+impl fmt::Display for ContentSecurityPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, directive) in self.directives.iter().enumerate() {
+            if i != 0 {
+                try!(f.write_str("; "));
+            }
+            try!(f.write_str(&directive.name));
+            for value in &directive.values {
+                try!(write!(f, " {}", value));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Header for ContentSecurityPolicy {
+    fn header_name() -> &'static str {
+        "Content-Security-Policy"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> ::Result<ContentSecurityPolicy> {
+        parsing::from_one_raw_str(raw)
+    }
+}
+
+impl HeaderFormat for ContentSecurityPolicy {
+    fn fmt_header(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl Header for ContentSecurityPolicyReportOnly {
+    fn header_name() -> &'static str {
+        "Content-Security-Policy-Report-Only"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> ::Result<ContentSecurityPolicyReportOnly> {
+        parsing::from_one_raw_str(raw).map(ContentSecurityPolicyReportOnly)
+    }
+}
+
+impl HeaderFormat for ContentSecurityPolicyReportOnly {
+    fn fmt_header(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, Headers};
+    use super::{ContentSecurityPolicy, ContentSecurityPolicyReportOnly, CspDirective, CspSource};
+
+    #[test]
+    fn test_parse() {
+        let csp: ContentSecurityPolicy = Header::parse_header(&[
+            b"default-src 'none';  Script-Src 'self'  https://cdn.example.com ;; \
+              upgrade-insecure-requests; script-src *".to_vec()
+        ]).unwrap();
+        assert_eq!(csp.directives, vec![
+            CspDirective {
+                name: "default-src".to_owned(),
+                values: vec!["'none'".to_owned()],
+            },
+            CspDirective {
+                name: "script-src".to_owned(),
+                values: vec!["'self'".to_owned(), "https://cdn.example.com".to_owned()],
+            },
+            CspDirective {
+                name: "upgrade-insecure-requests".to_owned(),
+                values: vec![],
+            },
+        ]);
+        assert_eq!(csp.get("SCRIPT-SRC").unwrap().len(), 2);
+
+        assert!(ContentSecurityPolicy::parse_header(&[
+            b"default-src 'self', default-src 'none'".to_vec()
+        ]).is_err());
+    }
+
+    #[test]
+    fn test_builder() {
+        let csp = ContentSecurityPolicy::new()
+            .default_src(&[CspSource::None])
+            .script_src(&[CspSource::UnsafeInline])
+            .style_src(&[CspSource::Self_, CspSource::Sha256("abc+/=".to_owned())])
+            .script_src(&[CspSource::StrictDynamic, CspSource::Host("https:".to_owned())])
+            .directive("upgrade-insecure-requests", Vec::<String>::new())
+            .report_uri("/csp");
+        assert_eq!(csp.to_string(), "default-src 'none'; \
+                                     script-src 'strict-dynamic' https:; \
+                                     style-src 'self' 'sha256-abc+/='; \
+                                     upgrade-insecure-requests; report-uri /csp");
+        assert_eq!(csp.to_string().parse::<ContentSecurityPolicy>().unwrap(), csp);
+    }
+
+    #[test]
+    fn test_report_only() {
+        let mut headers = Headers::new();
+        headers.set(ContentSecurityPolicyReportOnly(ContentSecurityPolicy::new()
+            .img_src(&[CspSource::Self_, CspSource::Host("data:".to_owned())])));
+        assert_eq!(headers.to_string(),
+                   "Content-Security-Policy-Report-Only: img-src 'self' data:\r\n");
+        assert!(headers.get::<ContentSecurityPolicy>().is_none());
+    }
+}
+
+bench_header!(bench, ContentSecurityPolicy, {
+    vec![b"default-src 'none'; script-src 'self' 'nonce-2726c7f26c'; frame-ancestors 'none'".to_vec()]
+});
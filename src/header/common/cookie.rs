@@ -97,6 +97,24 @@ impl Cookie {
     }
 }
 
+impl From<CookiePair> for Cookie {
+    fn from(pair: CookiePair) -> Cookie {
+        Cookie(vec![pair])
+    }
+}
+
+impl From<Vec<CookiePair>> for Cookie {
+    fn from(pairs: Vec<CookiePair>) -> Cookie {
+        Cookie(pairs)
+    }
+}
+
+impl From<Cookie> for Vec<CookiePair> {
+    fn from(cookie: Cookie) -> Vec<CookiePair> {
+        cookie.0
+    }
+}
+
 
 #[test]
 fn test_parse() {
@@ -132,5 +150,15 @@ fn cookie_jar() {
     assert_eq!(cookie_header, new_cookie_header);
 }
 
+#[test]
+fn test_conversions() {
+    let pair = CookiePair::new("foo".to_owned(), "bar".to_owned());
+    let header = Cookie::from(pair.clone());
+    assert_eq!(header, Cookie(vec![pair.clone()]));
+    assert_eq!(Cookie::from(vec![pair.clone()]), header);
+
+    let pairs: Vec<CookiePair> = header.into();
+    assert_eq!(pairs, vec![pair]);
+}
 
 bench_header!(bench, Cookie, { vec![b"foo=bar; baz=quux".to_vec()] });
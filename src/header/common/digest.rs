@@ -0,0 +1,714 @@
+use std::fmt;
+use std::str::FromStr;
+
+use unicase::UniCase;
+
+use header::{Scheme, parsing};
+use method::Method;
+
+/// The hash algorithm of Digest authentication.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DigestAlgorithm {
+    /// `MD5`, the default when no algorithm is given.
+    Md5,
+    /// `MD5-sess`, which also hashes in the nonces.
+    Md5Sess,
+    /// `SHA-256`.
+    Sha256,
+    /// `SHA-256-sess`, which also hashes in the nonces.
+    Sha256Sess,
+}
+
+impl DigestAlgorithm {
+    /// Hashes `data`, returning the lowercase hex encoding of the digest, as
+    /// each step of computing a `response` does.
+    pub fn hash(&self, data: &[u8]) -> String {
+        let digest = match *self {
+            DigestAlgorithm::Md5 | DigestAlgorithm::Md5Sess => md5(data).to_vec(),
+            DigestAlgorithm::Sha256 | DigestAlgorithm::Sha256Sess => sha256(data).to_vec(),
+        };
+        digest.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn is_session(&self) -> bool {
+        match *self {
+            DigestAlgorithm::Md5Sess | DigestAlgorithm::Sha256Sess => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for DigestAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            DigestAlgorithm::Md5 => "MD5",
+            DigestAlgorithm::Md5Sess => "MD5-sess",
+            DigestAlgorithm::Sha256 => "SHA-256",
+            DigestAlgorithm::Sha256Sess => "SHA-256-sess",
+        })
+    }
+}
+
+impl FromStr for DigestAlgorithm {
+    type Err = ::Error;
+
+    fn from_str(s: &str) -> ::Result<DigestAlgorithm> {
+        match UniCase(s) {
+            ref a if *a == UniCase("MD5") => Ok(DigestAlgorithm::Md5),
+            ref a if *a == UniCase("MD5-sess") => Ok(DigestAlgorithm::Md5Sess),
+            ref a if *a == UniCase("SHA-256") => Ok(DigestAlgorithm::Sha256),
+            ref a if *a == UniCase("SHA-256-sess") => Ok(DigestAlgorithm::Sha256Sess),
+            _ => Err(::Error::Header),
+        }
+    }
+}
+
+/// The quality of protection of Digest authentication.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Qop {
+    /// `auth`, which authenticates the request line.
+    Auth,
+    /// `auth-int`, which also protects the integrity of the body.
+    AuthInt,
+}
+
+impl fmt::Display for Qop {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Qop::Auth => "auth",
+            Qop::AuthInt => "auth-int",
+        })
+    }
+}
+
+impl FromStr for Qop {
+    type Err = ::Error;
+
+    fn from_str(s: &str) -> ::Result<Qop> {
+        match UniCase(s.trim()) {
+            ref q if *q == UniCase("auth") => Ok(Qop::Auth),
+            ref q if *q == UniCase("auth-int") => Ok(Qop::AuthInt),
+            _ => Err(::Error::Header),
+        }
+    }
+}
+
+/// A Digest challenge, as sent in a `WwwAuthenticate` header, defined in
+/// [RFC7616](https://tools.ietf.org/html/rfc7616#section-3.3)
+///
+/// # Example
+/// ```
+/// use hyper::header::{Headers, WwwAuthenticate, DigestChallenge, DigestAlgorithm, Qop};
+///
+/// let mut headers = Headers::new();
+/// headers.set(WwwAuthenticate(DigestChallenge {
+///     realm: "http-auth@example.org".to_owned(),
+///     nonce: "7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v".to_owned(),
+///     algorithm: Some(DigestAlgorithm::Sha256),
+///     qop: vec![Qop::Auth],
+///     ..DigestChallenge::default()
+/// }));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DigestChallenge {
+    /// The protection space the credentials are for.
+    pub realm: String,
+    /// The URIs that share the protection space, separated by spaces.
+    pub domain: Option<String>,
+    /// A unique value to hash the response with.
+    pub nonce: String,
+    /// A value the client must send back unchanged.
+    pub opaque: Option<String>,
+    /// Whether the previous request was rejected only because its nonce was
+    /// stale, so that it may be retried with this one.
+    pub stale: bool,
+    /// The hash algorithm, `MD5` if it isn't given.
+    pub algorithm: Option<DigestAlgorithm>,
+    /// The qualities of protection the server supports. If none are given,
+    /// the client responds as [RFC2069](https://tools.ietf.org/html/rfc2069)
+    /// describes.
+    pub qop: Vec<Qop>,
+    /// Whether the server supports hashed usernames.
+    pub userhash: bool,
+}
+
+/// Credentials for Digest authentication, defined in
+/// [RFC7616](https://tools.ietf.org/html/rfc7616#section-3.4)
+///
+/// Clients compute these from a `DigestChallenge` with `Digest::respond`,
+/// and servers check them with `Digest::verify`.
+///
+/// # Example
+/// ```
+/// use hyper::header::{Authorization, Digest, DigestChallenge, Qop};
+/// use hyper::method::Method;
+///
+/// let challenge = DigestChallenge {
+///     realm: "testrealm@host.com".to_owned(),
+///     nonce: "dcd98b7102dd2f0e8b11d0f600bfb0c093".to_owned(),
+///     qop: vec![Qop::Auth],
+///     ..DigestChallenge::default()
+/// };
+/// let digest = Digest::respond(&challenge, "Mufasa", "Circle Of Life",
+///                              &Method::Get, "/dir/index.html", "0a4f113b", 1);
+/// assert!(digest.verify(&Method::Get, "Circle Of Life"));
+/// let header = Authorization(digest);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Digest {
+    /// The username, or its hash if `userhash` is set.
+    pub username: String,
+    /// The realm of the challenge.
+    pub realm: String,
+    /// The nonce of the challenge.
+    pub nonce: String,
+    /// The request target, as in the request line.
+    pub uri: String,
+    /// The hash proving the client knows the password.
+    pub response: String,
+    /// The hash algorithm, `MD5` if it isn't given.
+    pub algorithm: Option<DigestAlgorithm>,
+    /// A nonce chosen by the client, required when `qop` is.
+    pub cnonce: Option<String>,
+    /// The `opaque` of the challenge.
+    pub opaque: Option<String>,
+    /// The quality of protection chosen from the challenge.
+    pub qop: Option<Qop>,
+    /// How many requests have been made with this nonce, required when
+    /// `qop` is.
+    pub nc: Option<u32>,
+    /// Whether `username` is hashed.
+    pub userhash: bool,
+}
+
+impl Digest {
+    /// Computes the credentials answering `challenge`, for a request with
+    /// `method` and the request target `uri`.
+    ///
+    /// `cnonce` must be unpredictable, and `nc` counts the requests made with
+    /// the challenge's nonce, starting at 1.
+    ///
+    /// The body isn't hashed, so `auth` is chosen as the quality of
+    /// protection, and a challenge offering only `auth-int` can't be
+    /// answered.
+    pub fn respond(challenge: &DigestChallenge, username: &str, password: &str,
+                   method: &Method, uri: &str, cnonce: &str, nc: u32) -> Digest {
+        let algorithm = challenge.algorithm.unwrap_or(DigestAlgorithm::Md5);
+        let qop = if challenge.qop.is_empty() { None } else { Some(Qop::Auth) };
+        let mut digest = Digest {
+            username: username.to_owned(),
+            realm: challenge.realm.clone(),
+            nonce: challenge.nonce.clone(),
+            uri: uri.to_owned(),
+            response: String::new(),
+            algorithm: challenge.algorithm,
+            cnonce: qop.map(|_| cnonce.to_owned()),
+            opaque: challenge.opaque.clone(),
+            qop: qop,
+            nc: qop.map(|_| nc),
+            userhash: false,
+        };
+        digest.response = digest.expected_response(method, password);
+        if challenge.userhash {
+            digest.username = algorithm.hash(format!("{}:{}", username, challenge.realm).as_bytes());
+            digest.userhash = true;
+        }
+        digest
+    }
+
+    /// Whether the `response` proves knowledge of `password`, for a request
+    /// with `method`.
+    ///
+    /// The server must still check that the `nonce` is one it issued and
+    /// that `uri` is the request target. Hashed usernames can't be
+    /// verified, since the password can't be looked up without the
+    /// username.
+    pub fn verify(&self, method: &Method, password: &str) -> bool {
+        if self.userhash {
+            return false;
+        }
+        let expected = self.expected_response(method, password);
+        // compare without exiting early, so as not to leak timing
+        expected.len() == self.response.len() &&
+            expected.bytes().zip(self.response.bytes()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+    }
+
+    fn expected_response(&self, method: &Method, password: &str) -> String {
+        let algorithm = self.algorithm.unwrap_or(DigestAlgorithm::Md5);
+        let cnonce = self.cnonce.as_ref().map_or("", |c| &c[..]);
+        let mut ha1 = algorithm.hash(format!("{}:{}:{}", self.username, self.realm, password).as_bytes());
+        if algorithm.is_session() {
+            ha1 = algorithm.hash(format!("{}:{}:{}", ha1, self.nonce, cnonce).as_bytes());
+        }
+        let ha2 = algorithm.hash(format!("{}:{}", method.as_ref(), self.uri).as_bytes());
+        let data = match self.qop {
+            Some(qop) => format!("{}:{}:{:08x}:{}:{}:{}",
+                                 ha1, self.nonce, self.nc.unwrap_or(0), cnonce, qop, ha2),
+            None => format!("{}:{}:{}", ha1, self.nonce, ha2),
+        };
+        algorithm.hash(data.as_bytes())
+    }
+}
+
+// Parses the `name=value` parameters of a challenge or credentials, up to
+// any other challenge following in the same header value.
+fn parse_params(s: &str) -> ::Result<Vec<(UniCase<String>, String)>> {
+    let mut params = Vec::new();
+    for param in parsing::split_comma_delimited(s) {
+        if param.trim().is_empty() {
+            continue;
+        }
+        let mut param = param.splitn(2, '=');
+        let name = param.next().unwrap_or("").trim();
+        let value = match param.next() {
+            Some(value) if !parsing::needs_quoting(name) => try!(parsing::parse_token_or_quoted(value)),
+            // the scheme of the next challenge, such as `Basic realm=...`
+            _ if !params.is_empty() && name.contains(' ') => break,
+            _ => return Err(::Error::Header),
+        };
+        params.push((UniCase(name.to_owned()), value));
+    }
+    Ok(params)
+}
+
+fn take(params: &mut Vec<(UniCase<String>, String)>, name: &str) -> Option<String> {
+    params.iter()
+        .position(|&(ref n, _)| *n == UniCase(name.to_owned()))
+        .map(|i| params.remove(i).1)
+}
+
+fn is_true(value: Option<String>) -> bool {
+    value.map_or(false, |v| UniCase(&v[..]) == UniCase("true"))
+}
+
+impl Scheme for DigestChallenge {
+    fn scheme() -> Option<&'static str> {
+        Some("Digest")
+    }
+
+    fn fmt_scheme(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(f.write_str("realm="));
+        try!(parsing::write_quoted(f, &self.realm));
+        if let Some(ref domain) = self.domain {
+            try!(f.write_str(", domain="));
+            try!(parsing::write_quoted(f, domain));
+        }
+        try!(f.write_str(", nonce="));
+        try!(parsing::write_quoted(f, &self.nonce));
+        if let Some(ref opaque) = self.opaque {
+            try!(f.write_str(", opaque="));
+            try!(parsing::write_quoted(f, opaque));
+        }
+        if self.stale {
+            try!(f.write_str(", stale=true"));
+        }
+        if let Some(algorithm) = self.algorithm {
+            try!(write!(f, ", algorithm={}", algorithm));
+        }
+        if !self.qop.is_empty() {
+            try!(f.write_str(", qop=\""));
+            try!(parsing::fmt_comma_delimited(f, &self.qop));
+            try!(f.write_str("\""));
+        }
+        if self.userhash {
+            try!(f.write_str(", userhash=true"));
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for DigestChallenge {
+    type Err = ::Error;
+
+    fn from_str(s: &str) -> ::Result<DigestChallenge> {
+        let mut params = try!(parse_params(s));
+        let qop = match take(&mut params, "qop") {
+            // qop values this doesn't know of are ignored
+            Some(qop) => qop.split(',').filter_map(|q| q.parse().ok()).collect(),
+            None => Vec::new(),
+        };
+        Ok(DigestChallenge {
+            realm: try!(take(&mut params, "realm").ok_or(::Error::Header)),
+            domain: take(&mut params, "domain"),
+            nonce: try!(take(&mut params, "nonce").ok_or(::Error::Header)),
+            opaque: take(&mut params, "opaque"),
+            stale: is_true(take(&mut params, "stale")),
+            algorithm: match take(&mut params, "algorithm") {
+                Some(algorithm) => Some(try!(algorithm.parse())),
+                None => None,
+            },
+            qop: qop,
+            userhash: is_true(take(&mut params, "userhash")),
+        })
+    }
+}
+
+impl Scheme for Digest {
+    fn scheme() -> Option<&'static str> {
+        Some("Digest")
+    }
+
+    fn fmt_scheme(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &(name, value) in &[("username", &self.username), ("realm", &self.realm),
+                                ("nonce", &self.nonce), ("uri", &self.uri),
+                                ("response", &self.response)] {
+            if name != "username" {
+                try!(f.write_str(", "));
+            }
+            try!(write!(f, "{}=", name));
+            try!(parsing::write_quoted(f, value));
+        }
+        if let Some(algorithm) = self.algorithm {
+            try!(write!(f, ", algorithm={}", algorithm));
+        }
+        if let Some(ref cnonce) = self.cnonce {
+            try!(f.write_str(", cnonce="));
+            try!(parsing::write_quoted(f, cnonce));
+        }
+        if let Some(ref opaque) = self.opaque {
+            try!(f.write_str(", opaque="));
+            try!(parsing::write_quoted(f, opaque));
+        }
+        if let Some(qop) = self.qop {
+            try!(write!(f, ", qop={}", qop));
+        }
+        if let Some(nc) = self.nc {
+            try!(write!(f, ", nc={:08x}", nc));
+        }
+        if self.userhash {
+            try!(f.write_str(", userhash=true"));
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Digest {
+    type Err = ::Error;
+
+    fn from_str(s: &str) -> ::Result<Digest> {
+        let mut params = try!(parse_params(s));
+        let mut required = |name| take(&mut params, name).ok_or(::Error::Header);
+        let username = try!(required("username"));
+        let realm = try!(required("realm"));
+        let nonce = try!(required("nonce"));
+        let uri = try!(required("uri"));
+        let response = try!(required("response"));
+        let digest = Digest {
+            username: username,
+            realm: realm,
+            nonce: nonce,
+            uri: uri,
+            response: response,
+            algorithm: match take(&mut params, "algorithm") {
+                Some(algorithm) => Some(try!(algorithm.parse())),
+                None => None,
+            },
+            cnonce: take(&mut params, "cnonce"),
+            opaque: take(&mut params, "opaque"),
+            qop: match take(&mut params, "qop") {
+                Some(qop) => Some(try!(qop.parse())),
+                None => None,
+            },
+            nc: match take(&mut params, "nc") {
+                Some(nc) => Some(try!(u32::from_str_radix(&nc, 16).map_err(|_| ::Error::Header))),
+                None => None,
+            },
+            userhash: is_true(take(&mut params, "userhash")),
+        };
+        if digest.qop.is_some() && (digest.cnonce.is_none() || digest.nc.is_none()) {
+            return Err(::Error::Header);
+        }
+        Ok(digest)
+    }
+}
+
+// MD5 (RFC 1321) and SHA-256 (FIPS 180-4), the hashes Digest
+// authentication uses, and SHA-1 (FIPS 180-4), which the WebSocket opening
+// handshake uses.
+
+const MD5_SHIFTS: [u32; 16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+
+const MD5_K: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+// Pads a message to a whole number of 64 byte blocks, ending with its
+// length in bits.
+fn pad(data: &[u8], big_endian: bool) -> Vec<u8> {
+    let bits = (data.len() as u64).wrapping_mul(8);
+    let mut msg = data.to_vec();
+    msg.push(0x80);
+    while msg.len() % 64 != 56 {
+        msg.push(0);
+    }
+    for i in 0..8 {
+        let shift = if big_endian { 56 - 8 * i } else { 8 * i };
+        msg.push((bits >> shift) as u8);
+    }
+    msg
+}
+
+fn md5(data: &[u8]) -> [u8; 16] {
+    let mut state = [0x67452301u32, 0xefcdab89, 0x98badcfe, 0x10325476];
+    for block in pad(data, false).chunks(64) {
+        let mut m = [0u32; 16];
+        for (i, word) in block.chunks(4).enumerate() {
+            m[i] = (word[0] as u32) | (word[1] as u32) << 8 | (word[2] as u32) << 16 | (word[3] as u32) << 24;
+        }
+        let (mut a, mut b, mut c, mut d) = (state[0], state[1], state[2], state[3]);
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let rotated = a.wrapping_add(f).wrapping_add(MD5_K[i]).wrapping_add(m[g])
+                .rotate_left(MD5_SHIFTS[(i / 16) * 4 + i % 4]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(rotated);
+        }
+        state[0] = state[0].wrapping_add(a);
+        state[1] = state[1].wrapping_add(b);
+        state[2] = state[2].wrapping_add(c);
+        state[3] = state[3].wrapping_add(d);
+    }
+
+    let mut digest = [0u8; 16];
+    for (i, word) in state.iter().enumerate() {
+        for j in 0..4 {
+            digest[i * 4 + j] = (word >> (8 * j)) as u8;
+        }
+    }
+    digest
+}
+
+pub fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state = [0x67452301u32, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+    for block in pad(data, true).chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = (word[0] as u32) << 24 | (word[1] as u32) << 16 | (word[2] as u32) << 8 | (word[3] as u32);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let mut h = state;
+        for i in 0..80 {
+            let (f, k) = match i / 20 {
+                0 => ((h[1] & h[2]) | (!h[1] & h[3]), 0x5a827999),
+                1 => (h[1] ^ h[2] ^ h[3], 0x6ed9eba1),
+                2 => ((h[1] & h[2]) | (h[1] & h[3]) | (h[2] & h[3]), 0x8f1bbcdc),
+                _ => (h[1] ^ h[2] ^ h[3], 0xca62c1d6),
+            };
+            let t = h[0].rotate_left(5).wrapping_add(f).wrapping_add(h[4]).wrapping_add(k).wrapping_add(w[i]);
+            h = [t, h[0], h[1].rotate_left(30), h[2], h[3]];
+        }
+        for (s, h) in state.iter_mut().zip(h.iter()) {
+            *s = s.wrapping_add(*h);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (i, word) in state.iter().enumerate() {
+        for j in 0..4 {
+            digest[i * 4 + j] = (word >> (24 - 8 * j)) as u8;
+        }
+    }
+    digest
+}
+
+fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut state = [
+        0x6a09e667u32, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a,
+        0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+    ];
+    for block in pad(data, true).chunks(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = (word[0] as u32) << 24 | (word[1] as u32) << 16 | (word[2] as u32) << 8 | (word[3] as u32);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+
+        let mut h = state;
+        for i in 0..64 {
+            let s1 = h[4].rotate_right(6) ^ h[4].rotate_right(11) ^ h[4].rotate_right(25);
+            let ch = (h[4] & h[5]) ^ (!h[4] & h[6]);
+            let t1 = h[7].wrapping_add(s1).wrapping_add(ch).wrapping_add(SHA256_K[i]).wrapping_add(w[i]);
+            let s0 = h[0].rotate_right(2) ^ h[0].rotate_right(13) ^ h[0].rotate_right(22);
+            let maj = (h[0] & h[1]) ^ (h[0] & h[2]) ^ (h[1] & h[2]);
+            let t2 = s0.wrapping_add(maj);
+            h = [t1.wrapping_add(t2), h[0], h[1], h[2], h[3].wrapping_add(t1), h[4], h[5], h[6]];
+        }
+        for (s, h) in state.iter_mut().zip(h.iter()) {
+            *s = s.wrapping_add(*h);
+        }
+    }
+
+    let mut digest = [0u8; 32];
+    for (i, word) in state.iter().enumerate() {
+        for j in 0..4 {
+            digest[i * 4 + j] = (word >> (24 - 8 * j)) as u8;
+        }
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, Headers, Authorization, WwwAuthenticate};
+    use method::Method;
+    use super::{Digest, DigestChallenge, DigestAlgorithm, Qop};
+
+    #[test]
+    fn test_hashes() {
+        let md5 = DigestAlgorithm::Md5;
+        assert_eq!(md5.hash(b""), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(md5.hash(b"The quick brown fox jumps over the lazy dog"),
+                   "9e107d9d372bb6826bd81d3542a419d6");
+        let sha256 = DigestAlgorithm::Sha256;
+        assert_eq!(sha256.hash(b""),
+                   "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert_eq!(sha256.hash(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+                   "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1");
+        // longer than a block
+        assert_eq!(sha256.hash(&[b'a'; 1000][..]),
+                   "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3");
+    }
+
+    // the example of RFC7616 section 3.9.1
+    fn rfc7616_challenge(algorithm: DigestAlgorithm) -> DigestChallenge {
+        DigestChallenge {
+            realm: "http-auth@example.org".to_owned(),
+            nonce: "7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v".to_owned(),
+            opaque: Some("FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS".to_owned()),
+            algorithm: Some(algorithm),
+            qop: vec![Qop::Auth, Qop::AuthInt],
+            ..DigestChallenge::default()
+        }
+    }
+
+    #[test]
+    fn test_respond() {
+        let respond = |algorithm| Digest::respond(&rfc7616_challenge(algorithm), "Mufasa",
+            "Circle of Life", &Method::Get, "/dir/index.html",
+            "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ", 1);
+
+        let md5 = respond(DigestAlgorithm::Md5);
+        assert_eq!(md5.response, "8ca523f5e9506fed4657c9700eebdbec");
+        assert_eq!(md5.qop, Some(Qop::Auth));
+        assert!(md5.verify(&Method::Get, "Circle of Life"));
+        assert!(!md5.verify(&Method::Get, "Circle of Lies"));
+        assert!(!md5.verify(&Method::Post, "Circle of Life"));
+
+        let sha256 = respond(DigestAlgorithm::Sha256);
+        assert_eq!(sha256.response, "753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1");
+        assert!(sha256.verify(&Method::Get, "Circle of Life"));
+
+        let sess = respond(DigestAlgorithm::Md5Sess);
+        assert!(sess.verify(&Method::Get, "Circle of Life"));
+        assert!(sess.response != md5.response);
+    }
+
+    #[test]
+    fn test_respond_without_qop() {
+        let challenge = DigestChallenge {
+            realm: "testrealm@host.com".to_owned(),
+            nonce: "dcd98b7102dd2f0e8b11d0f600bfb0c093".to_owned(),
+            ..DigestChallenge::default()
+        };
+        let digest = Digest::respond(&challenge, "Mufasa", "CircleOfLife",
+                                     &Method::Get, "/dir/index.html", "unused", 1);
+        // the example of RFC2069
+        assert_eq!(digest.response, "1949323746fe6a43ef61f9606e7febea");
+        assert_eq!(digest.cnonce, None);
+        assert_eq!(digest.nc, None);
+    }
+
+    #[test]
+    fn test_challenge_header() {
+        let mut headers = Headers::new();
+        headers.set(WwwAuthenticate(rfc7616_challenge(DigestAlgorithm::Sha256)));
+        assert_eq!(headers.to_string(), "WWW-Authenticate: Digest \
+            realm=\"http-auth@example.org\", \
+            nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\", \
+            opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\", \
+            algorithm=SHA-256, qop=\"auth, auth-int\"\r\n");
+
+        let parsed: WwwAuthenticate<DigestChallenge> = Header::parse_header(&[
+            b"Basic realm=\"simple\"".to_vec(),
+            b"Digest realm=\"http-auth@example.org\", qop=\"auth, auth-int\", \
+              algorithm=SHA-256, nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\", \
+              opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\", \
+              Basic realm=\"http-auth@example.org\"".to_vec(),
+        ]).unwrap();
+        assert_eq!(parsed.0, rfc7616_challenge(DigestAlgorithm::Sha256));
+
+        assert!(WwwAuthenticate::<DigestChallenge>::parse_header(&[
+            b"Digest realm=\"missing nonce\"".to_vec()
+        ]).is_err());
+    }
+
+    #[test]
+    fn test_credentials_header() {
+        let digest = Digest::respond(&rfc7616_challenge(DigestAlgorithm::Md5), "Mufasa",
+            "Circle of Life", &Method::Get, "/dir/index.html",
+            "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ", 1);
+        let mut headers = Headers::new();
+        headers.set(Authorization(digest.clone()));
+        let s = headers.to_string();
+        assert_eq!(s, "Authorization: Digest username=\"Mufasa\", \
+            realm=\"http-auth@example.org\", \
+            nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\", uri=\"/dir/index.html\", \
+            response=\"8ca523f5e9506fed4657c9700eebdbec\", algorithm=MD5, \
+            cnonce=\"f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ\", \
+            opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\", qop=auth, nc=00000001\r\n");
+
+        let parsed: Authorization<Digest> = Header::parse_header(&[
+            s["Authorization: ".len()..s.len() - 2].as_bytes().to_vec()
+        ]).unwrap();
+        assert_eq!(parsed.0, digest);
+        assert!(parsed.verify(&Method::Get, "Circle of Life"));
+
+        assert!(Authorization::<Digest>::parse_header(&[
+            b"Digest username=\"Mufasa\", realm=\"r\", nonce=\"n\", uri=\"/\", \
+              response=\"0\", qop=auth".to_vec()
+        ]).is_err());
+    }
+
+    #[test]
+    fn test_userhash() {
+        let mut challenge = rfc7616_challenge(DigestAlgorithm::Sha256);
+        challenge.userhash = true;
+        let digest = Digest::respond(&challenge, "Mufasa", "Circle of Life", &Method::Get,
+                                     "/dir/index.html", "cnonce", 1);
+        assert!(digest.userhash);
+        assert_eq!(digest.username, DigestAlgorithm::Sha256.hash(b"Mufasa:http-auth@example.org"));
+        assert!(!digest.verify(&Method::Get, "Circle of Life"));
+    }
+}
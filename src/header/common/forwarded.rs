@@ -0,0 +1,403 @@
+use std::fmt;
+use std::net::IpAddr;
+use std::str::{self, FromStr};
+
+use unicase::UniCase;
+
+use header::{Header, HeaderFormat, parsing};
+
+/// `Forwarded` header, defined in [RFC7239](https://tools.ietf.org/html/rfc7239)
+///
+/// The `Forwarded` header field is added by proxies, to disclose
+/// information that is altered or lost when a request passes through them,
+/// such as the address of the client. Each proxy appends an element, so the
+/// first element describes the request as the client sent it.
+///
+/// # ABNF
+/// ```plain
+/// Forwarded   = 1#forwarded-element
+///
+/// forwarded-element =
+///     [ forwarded-pair ] *( ";" [ forwarded-pair ] )
+///
+/// forwarded-pair = token "=" value
+/// value          = token / quoted-string
+///
+/// node     = nodename [ ":" node-port ]
+/// nodename = IPv4address / "[" IPv6address "]" /
+///            "unknown" / obfnode
+/// obfnode  = "_" 1*( ALPHA / DIGIT / "." / "_" / "-")
+/// node-port     = port / obfport
+/// obfport       = "_" 1*(ALPHA / DIGIT / "." / "_" / "-")
+/// ```
+///
+/// # Example values
+/// * `for=192.0.2.60;proto=http;by=203.0.113.43`
+/// * `for="[2001:db8:cafe::17]:4711"`
+/// * `for=192.0.2.43, for=_hidden;by=unknown`
+///
+/// # Example
+/// ```
+/// use hyper::header::{Headers, Forwarded, ForwardedElement, Node, NodeName};
+///
+/// let mut headers = Headers::new();
+/// headers.set(Forwarded(vec![ForwardedElement {
+///     for_: Some(Node::new(NodeName::Ip("2001:db8:cafe::17".parse().unwrap()))),
+///     proto: Some("https".to_owned()),
+///     ..ForwardedElement::default()
+/// }]));
+/// assert_eq!(headers.to_string(), "Forwarded: for=\"[2001:db8:cafe::17]\";proto=https\r\n");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Forwarded(pub Vec<ForwardedElement>);
+
+/// The information one proxy adds to a `Forwarded` header.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ForwardedElement {
+    /// The interface where the request came in to the proxy, from `by=`.
+    pub by: Option<Node>,
+    /// The client that made the request to the proxy, from `for=`.
+    pub for_: Option<Node>,
+    /// The `Host` header the proxy received, from `host=`.
+    pub host: Option<String>,
+    /// The protocol the request was made to the proxy with, such as `http`
+    /// or `https`, from `proto=`.
+    pub proto: Option<String>,
+    /// Any other parameters, with their names and unescaped values.
+    pub extensions: Vec<(String, String)>,
+}
+
+/// A node, such as a client or proxy, in a `Forwarded` element.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    /// The address or identifier of the node.
+    pub name: NodeName,
+    /// The port of the node, if it was disclosed.
+    pub port: Option<NodePort>,
+}
+
+/// The address or identifier of a `Node`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NodeName {
+    /// An IP address.
+    Ip(IpAddr),
+    /// The node is not known, such as when the request came from a process
+    /// on the proxy itself.
+    Unknown,
+    /// An identifier that hides the actual address, including its leading
+    /// `_`.
+    Obfuscated(String),
+}
+
+/// The port of a `Node`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NodePort {
+    /// A real port number.
+    Real(u16),
+    /// An identifier that hides the actual port, including its leading `_`.
+    Obfuscated(String),
+}
+
+impl Forwarded {
+    /// The client that originally made the request, as disclosed by the
+    /// first proxy.
+    ///
+    /// Only proxies that are trusted should be believed, so this must only be
+    /// used when every proxy in front of the server is known to set or
+    /// replace the header.
+    pub fn client(&self) -> Option<&Node> {
+        self.0.first().and_then(|element| element.for_.as_ref())
+    }
+}
+
+impl Node {
+    /// A node with no port.
+    pub fn new(name: NodeName) -> Node {
+        Node {
+            name: name,
+            port: None,
+        }
+    }
+}
+
+fn is_obfuscated(s: &str) -> bool {
+    s.len() > 1 && s.starts_with('_') && s[1..].bytes().all(|b| match b {
+        b'a'...b'z' | b'A'...b'Z' | b'0'...b'9' | b'.' | b'_' | b'-' => true,
+        _ => false,
+    })
+}
+
+impl FromStr for NodeName {
+    type Err = ::Error;
+
+    fn from_str(s: &str) -> ::Result<NodeName> {
+        if UniCase(s) == UniCase("unknown") {
+            Ok(NodeName::Unknown)
+        } else if is_obfuscated(s) {
+            Ok(NodeName::Obfuscated(s.to_owned()))
+        } else if s.starts_with('[') && s.ends_with(']') {
+            match s[1..s.len() - 1].parse() {
+                Ok(IpAddr::V6(addr)) => Ok(NodeName::Ip(IpAddr::V6(addr))),
+                _ => Err(::Error::Header),
+            }
+        } else {
+            match s.parse() {
+                Ok(IpAddr::V4(addr)) => Ok(NodeName::Ip(IpAddr::V4(addr))),
+                _ => Err(::Error::Header),
+            }
+        }
+    }
+}
+
+impl FromStr for NodePort {
+    type Err = ::Error;
+
+    fn from_str(s: &str) -> ::Result<NodePort> {
+        if is_obfuscated(s) {
+            Ok(NodePort::Obfuscated(s.to_owned()))
+        } else if !s.is_empty() && s.bytes().all(|b| b >= b'0' && b <= b'9') {
+            s.parse().map(NodePort::Real).map_err(|_| ::Error::Header)
+        } else {
+            Err(::Error::Header)
+        }
+    }
+}
+
+impl FromStr for Node {
+    type Err = ::Error;
+
+    fn from_str(s: &str) -> ::Result<Node> {
+        // an IPv6 address has colons of its own, but is within brackets
+        let split = match s.rfind(']') {
+            Some(end) => s[end..].find(':').map(|i| end + i),
+            None => s.find(':'),
+        };
+        Ok(match split {
+            Some(i) => Node {
+                name: try!(s[..i].parse()),
+                port: Some(try!(s[i + 1..].parse())),
+            },
+            None => Node::new(try!(s.parse())),
+        })
+    }
+}
+
+impl fmt::Display for NodeName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NodeName::Ip(IpAddr::V4(ref addr)) => fmt::Display::fmt(addr, f),
+            NodeName::Ip(IpAddr::V6(ref addr)) => write!(f, "[{}]", addr),
+            NodeName::Unknown => f.write_str("unknown"),
+            NodeName::Obfuscated(ref name) => f.write_str(name),
+        }
+    }
+}
+
+impl fmt::Display for NodePort {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NodePort::Real(port) => fmt::Display::fmt(&port, f),
+            NodePort::Obfuscated(ref port) => f.write_str(port),
+        }
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.port {
+            Some(ref port) => write!(f, "{}:{}", self.name, port),
+            None => fmt::Display::fmt(&self.name, f),
+        }
+    }
+}
+
+impl Header for Forwarded {
+    fn header_name() -> &'static str {
+        "Forwarded"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> ::Result<Forwarded> {
+        let mut elements = Vec::new();
+        for line in raw {
+            let line = try!(str::from_utf8(line));
+            for element in parsing::split_comma_delimited(line) {
+                if !element.trim().is_empty() {
+                    elements.push(try!(element.parse()));
+                }
+            }
+        }
+        if elements.is_empty() {
+            return Err(::Error::Header);
+        }
+        Ok(Forwarded(elements))
+    }
+}
+
+impl HeaderFormat for Forwarded {
+    fn fmt_header(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl fmt::Display for Forwarded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        parsing::fmt_comma_delimited(f, &self.0)
+    }
+}
+
+impl FromStr for ForwardedElement {
+    type Err = ::Error;
+
+    fn from_str(s: &str) -> ::Result<ForwardedElement> {
+        let mut element = ForwardedElement::default();
+        let mut seen = Vec::new();
+        for pair in parsing::split_delimited(s, b';') {
+            if pair.trim().is_empty() {
+                continue;
+            }
+            let mut pair = pair.splitn(2, '=');
+            let (name, value) = match (pair.next(), pair.next()) {
+                (Some(name), Some(value)) => (name.trim(), try!(parsing::parse_token_or_quoted(value))),
+                _ => return Err(::Error::Header),
+            };
+            let name = UniCase(name.to_owned());
+            // each parameter may occur only once in an element
+            if parsing::needs_quoting(&name) || seen.contains(&name) {
+                return Err(::Error::Header);
+            }
+            if name == UniCase("by".to_owned()) {
+                element.by = Some(try!(value.parse()));
+            } else if name == UniCase("for".to_owned()) {
+                element.for_ = Some(try!(value.parse()));
+            } else if name == UniCase("host".to_owned()) {
+                element.host = Some(value);
+            } else if name == UniCase("proto".to_owned()) {
+                element.proto = Some(value);
+            } else {
+                element.extensions.push((name.to_string(), value));
+            }
+            seen.push(name);
+        }
+        Ok(element)
+    }
+}
+
+impl fmt::Display for ForwardedElement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut pairs = Vec::new();
+        if let Some(ref by) = self.by {
+            pairs.push(("by", by.to_string()));
+        }
+        if let Some(ref for_) = self.for_ {
+            pairs.push(("for", for_.to_string()));
+        }
+        if let Some(ref host) = self.host {
+            pairs.push(("host", host.clone()));
+        }
+        if let Some(ref proto) = self.proto {
+            pairs.push(("proto", proto.clone()));
+        }
+        for &(ref name, ref value) in &self.extensions {
+            pairs.push((name, value.clone()));
+        }
+
+        for (i, &(name, ref value)) in pairs.iter().enumerate() {
+            if i != 0 {
+                try!(f.write_str(";"));
+            }
+            try!(write!(f, "{}=", name));
+            try!(parsing::write_token_or_quoted(f, value));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use header::{Header, Headers};
+    use super::{Forwarded, ForwardedElement, Node, NodeName, NodePort};
+
+    fn parse(s: &str) -> ::Result<Forwarded> {
+        Header::parse_header(&[s.as_bytes().to_vec()])
+    }
+
+    fn ip(s: &str) -> NodeName {
+        NodeName::Ip(s.parse::<IpAddr>().unwrap())
+    }
+
+    #[test]
+    fn test_parse() {
+        let forwarded = parse("for=192.0.2.60;Proto=http;by=203.0.113.43, \
+                               For=\"[2001:db8:cafe::17]:4711\";host=\"example.com:8080\", \
+                               for=\"_hidden:_port\";by=unknown;secret=\"a;b,c\"").unwrap();
+        assert_eq!(forwarded, Forwarded(vec![
+            ForwardedElement {
+                by: Some(Node::new(ip("203.0.113.43"))),
+                for_: Some(Node::new(ip("192.0.2.60"))),
+                proto: Some("http".to_owned()),
+                ..ForwardedElement::default()
+            },
+            ForwardedElement {
+                for_: Some(Node {
+                    name: ip("2001:db8:cafe::17"),
+                    port: Some(NodePort::Real(4711)),
+                }),
+                host: Some("example.com:8080".to_owned()),
+                ..ForwardedElement::default()
+            },
+            ForwardedElement {
+                by: Some(Node::new(NodeName::Unknown)),
+                for_: Some(Node {
+                    name: NodeName::Obfuscated("_hidden".to_owned()),
+                    port: Some(NodePort::Obfuscated("_port".to_owned())),
+                }),
+                extensions: vec![("secret".to_owned(), "a;b,c".to_owned())],
+                ..ForwardedElement::default()
+            },
+        ]));
+        assert_eq!(forwarded.client(), Some(&Node::new(ip("192.0.2.60"))));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse("").is_err());
+        assert!(parse("for").is_err());
+        assert!(parse("for=192.0.2.60;for=192.0.2.61").is_err());
+        // IPv6 addresses must be bracketed, and so quoted
+        assert!(parse("for=\"2001:db8:cafe::17\"").is_err());
+        assert!(parse("for=[2001:db8:cafe::17]").is_err());
+        assert!(parse("for=\"192.0.2.60:http\"").is_err());
+        assert!(parse("for=example.com").is_err());
+    }
+
+    #[test]
+    fn test_format() {
+        let forwarded = Forwarded(vec![
+            ForwardedElement {
+                for_: Some(Node {
+                    name: ip("192.0.2.60"),
+                    port: Some(NodePort::Real(8080)),
+                }),
+                proto: Some("https".to_owned()),
+                ..ForwardedElement::default()
+            },
+            ForwardedElement {
+                by: Some(Node::new(NodeName::Obfuscated("_proxy1".to_owned()))),
+                extensions: vec![("note".to_owned(), "a b".to_owned())],
+                ..ForwardedElement::default()
+            },
+        ]);
+        let mut headers = Headers::new();
+        headers.set(forwarded.clone());
+        assert_eq!(headers.to_string(),
+                   "Forwarded: for=\"192.0.2.60:8080\";proto=https, \
+                    by=_proxy1;note=\"a b\"\r\n");
+        assert_eq!(parse(&forwarded.to_string()).unwrap(), forwarded);
+    }
+}
+
+bench_header!(bench, Forwarded, {
+    vec![b"for=192.0.2.60;proto=http;by=203.0.113.43, for=\"[2001:db8:cafe::17]:4711\"".to_vec()]
+});
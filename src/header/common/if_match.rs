@@ -60,6 +60,29 @@ header! {
                      EntityTag::new(false, "r2d2xxxx".to_owned()),
                      EntityTag::new(false, "c3piozzzz".to_owned())])));
         test_header!(test3, vec![b"*"], Some(IfMatch::Any));
+
+        #[test]
+        fn test_matches() {
+            let if_match = IfMatch::Items(vec![EntityTag::weak("a".to_owned()),
+                                               EntityTag::strong("b".to_owned())]);
+            assert!(!if_match.matches(&EntityTag::strong("a".to_owned())));
+            assert!(if_match.matches(&EntityTag::strong("b".to_owned())));
+            assert!(!if_match.matches(&EntityTag::weak("b".to_owned())));
+            assert!(IfMatch::Any.matches(&EntityTag::weak("c".to_owned())));
+        }
+    }
+}
+
+impl IfMatch {
+    /// Whether `etag`, that of the target resource's current
+    /// representation, matches, using strong comparison.
+    ///
+    /// `*` matches any current representation.
+    pub fn matches(&self, etag: &EntityTag) -> bool {
+        match *self {
+            IfMatch::Any => true,
+            IfMatch::Items(ref tags) => etag.strong_eq_any(tags),
+        }
     }
 }
 
@@ -57,6 +57,19 @@ header! {
     }
 }
 
+impl IfNoneMatch {
+    /// Whether `etag`, that of the target resource's current
+    /// representation, matches, using weak comparison.
+    ///
+    /// `*` matches any current representation.
+    pub fn matches(&self, etag: &EntityTag) -> bool {
+        match *self {
+            IfNoneMatch::Any => true,
+            IfNoneMatch::Items(ref tags) => etag.weak_eq_any(tags),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::IfNoneMatch;
@@ -78,6 +91,16 @@ mod tests {
         entities.push(weak_etag);
         assert_eq!(if_none_match.ok(), Some(IfNoneMatch::Items(entities)));
     }
+
+    #[test]
+    fn test_matches() {
+        let if_none_match = IfNoneMatch::Items(vec![EntityTag::weak("a".to_owned()),
+                                                    EntityTag::strong("b".to_owned())]);
+        assert!(if_none_match.matches(&EntityTag::strong("a".to_owned())));
+        assert!(if_none_match.matches(&EntityTag::weak("b".to_owned())));
+        assert!(!if_none_match.matches(&EntityTag::strong("c".to_owned())));
+        assert!(IfNoneMatch::Any.matches(&EntityTag::weak("c".to_owned())));
+    }
 }
 
 bench_header!(bench, IfNoneMatch, { vec![b"W/\"nonemptytag\"".to_vec()] });
@@ -53,6 +53,33 @@ pub enum IfRange {
     Date(HttpDate),
 }
 
+impl IfRange {
+    /// Whether the representation is unchanged from the one the client has
+    /// part of, given the `ETag` and `Last-Modified` it would be sent with,
+    /// so that the requested `Range` should be served.
+    ///
+    /// An entity-tag must match strongly, and a date must equal the last
+    /// modification date exactly, as RFC 7233 requires. With no validator
+    /// to compare against, the condition fails.
+    ///
+    /// ```
+    /// use hyper::header::{IfRange, EntityTag};
+    ///
+    /// let if_range = IfRange::EntityTag(EntityTag::new(false, "xyzzy".to_owned()));
+    /// assert!(if_range.matches(Some(&EntityTag::new(false, "xyzzy".to_owned())), None));
+    /// assert!(!if_range.matches(Some(&EntityTag::new(true, "xyzzy".to_owned())), None));
+    /// ```
+    pub fn matches(&self, etag: Option<&EntityTag>, last_modified: Option<&HttpDate>) -> bool {
+        match *self {
+            IfRange::EntityTag(ref tag) => etag.map_or(false, |e| tag.strong_eq(e)),
+            // HTTP dates only have a resolution of one second
+            IfRange::Date(ref date) => last_modified.map_or(false, |lm| {
+                lm.0.to_timespec().sec == date.0.to_timespec().sec
+            }),
+        }
+    }
+}
+
 impl Header for IfRange {
     fn header_name() -> &'static str {
         "If-Range"
@@ -93,4 +120,20 @@ mod test_if_range {
     test_header!(test1, vec![b"Sat, 29 Oct 1994 19:43:31 GMT"]);
     test_header!(test2, vec![b"\"xyzzy\""]);
     test_header!(test3, vec![b"this-is-invalid"], None::<IfRange>);
+
+    #[test]
+    fn test_matches() {
+        use time::{self, Timespec};
+
+        let date = |sec| HttpDate(time::at_utc(Timespec::new(sec, 0)));
+        let if_range = HeaderField::Date(date(100));
+        assert!(if_range.matches(None, Some(&date(100))));
+        assert!(!if_range.matches(None, Some(&date(99))));
+        assert!(!if_range.matches(None, None));
+
+        let if_range = HeaderField::EntityTag(EntityTag::new(false, "a".to_owned()));
+        assert!(if_range.matches(Some(&EntityTag::new(false, "a".to_owned())), None));
+        assert!(!if_range.matches(Some(&EntityTag::new(false, "b".to_owned())), None));
+        assert!(!if_range.matches(None, Some(&date(100))));
+    }
 }
@@ -0,0 +1,270 @@
+use std::fmt;
+use std::str::{self, FromStr};
+
+use unicase::UniCase;
+
+use header::{Header, HeaderFormat, parsing};
+
+/// `Link` header, defined in [RFC5988](https://tools.ietf.org/html/rfc5988#section-5)
+///
+/// The `Link` header field provides a means for serialising one or more
+/// links in HTTP headers. Each link has a target URI, and parameters that
+/// say how it relates to the current resource, most importantly `rel`.
+///
+/// # ABNF
+/// ```plain
+/// Link           = "Link" ":" #link-value
+/// link-value     = "<" URI-Reference ">" *( ";" link-param )
+/// link-param     = ( ( "rel" "=" relation-types )
+///                | ( "anchor" "=" <"> URI-Reference <"> )
+///                | ( "rev" "=" relation-types )
+///                | ( "hreflang" "=" Language-Tag )
+///                | ( "media" "=" ( MediaDesc | ( <"> MediaDesc <"> ) ) )
+///                | ( "title" "=" quoted-string )
+///                | ( "title*" "=" ext-value )
+///                | ( "type" "=" ( media-type | quoted-mt ) )
+///                | ( link-extension ) )
+/// ```
+///
+/// # Example values
+/// * `<http://example.com/TheBook/chapter2>; rel="previous"; title="previous chapter"`
+/// * `</items?page=3>; rel="next", </items?page=1>; rel="first"`
+///
+/// # Example
+/// ```
+/// use hyper::header::{Headers, Link, LinkValue};
+///
+/// let mut headers = Headers::new();
+/// headers.set(Link(vec![
+///     LinkValue::new("/items?page=3").with_param("rel", "next"),
+///     LinkValue::new("/items?page=9").with_param("rel", "last"),
+/// ]));
+///
+/// let link = headers.get::<Link>().unwrap();
+/// assert_eq!(link.find_rel("next").unwrap().target, "/items?page=3");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Link(pub Vec<LinkValue>);
+
+/// A single link within a `Link` header.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinkValue {
+    /// The URI reference the link points to, which may be relative.
+    pub target: String,
+    /// The parameters, with their names and unescaped values, in the order
+    /// they are sent.
+    ///
+    /// Values of extended parameters, whose names end with `*`, such as
+    /// `title*`, are kept encoded.
+    pub params: Vec<(String, String)>,
+}
+
+impl Link {
+    /// The first link with the relation type `rel`, such as `next`.
+    pub fn find_rel(&self, rel: &str) -> Option<&LinkValue> {
+        self.0.iter().find(|value| value.has_rel(rel))
+    }
+}
+
+impl LinkValue {
+    /// A link to `target`, with no parameters.
+    pub fn new<T: Into<String>>(target: T) -> LinkValue {
+        LinkValue {
+            target: target.into(),
+            params: Vec::new(),
+        }
+    }
+
+    /// Adds a parameter, such as `rel`, `type`, `title` or `hreflang`.
+    pub fn with_param<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> LinkValue {
+        self.params.push((name.into(), value.into()));
+        self
+    }
+
+    /// The value of the first parameter named `name`, which is matched
+    /// case-insensitively.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter()
+            .find(|&&(ref n, _)| UniCase(&n[..]) == UniCase(name))
+            .map(|&(_, ref v)| &v[..])
+    }
+
+    /// Whether `rel` is one of the link's relation types.
+    ///
+    /// A `rel` parameter can hold several relation types separated by
+    /// spaces, each of which is compared case-insensitively.
+    pub fn has_rel(&self, rel: &str) -> bool {
+        self.param("rel").map_or(false, |rels| {
+            rels.split_whitespace().any(|r| UniCase(r) == UniCase(rel))
+        })
+    }
+}
+
+impl Header for Link {
+    fn header_name() -> &'static str {
+        "Link"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> ::Result<Link> {
+        let mut values = Vec::new();
+        for line in raw {
+            let line = try!(str::from_utf8(line));
+            for part in split_outside(line, b',') {
+                if !part.trim().is_empty() {
+                    values.push(try!(part.parse()));
+                }
+            }
+        }
+        if values.is_empty() {
+            return Err(::Error::Header);
+        }
+        Ok(Link(values))
+    }
+}
+
+impl HeaderFormat for Link {
+    fn fmt_header(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl fmt::Display for Link {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        parsing::fmt_comma_delimited(f, &self.0)
+    }
+}
+
+impl fmt::Display for LinkValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "<{}>", self.target));
+        for &(ref name, ref value) in &self.params {
+            if name.ends_with('*') {
+                try!(write!(f, "; {}={}", name, value));
+            } else {
+                try!(write!(f, "; {}=", name));
+                try!(parsing::write_token_or_quoted(f, value));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for LinkValue {
+    type Err = ::Error;
+
+    fn from_str(s: &str) -> ::Result<LinkValue> {
+        let mut parts = split_outside(s, b';').into_iter();
+        let target = match parts.next().map(str::trim) {
+            Some(t) if t.len() >= 2 && t.starts_with('<') && t.ends_with('>') => &t[1..t.len() - 1],
+            _ => return Err(::Error::Header),
+        };
+
+        let mut params = Vec::new();
+        for part in parts {
+            let mut param = part.splitn(2, '=');
+            let name = param.next().unwrap_or("").trim();
+            if parsing::needs_quoting(name) {
+                return Err(::Error::Header);
+            }
+            let value = match param.next().map(str::trim) {
+                Some(value) if name.ends_with('*') => value.to_owned(),
+                Some(value) if value.starts_with('"') => {
+                    try!(parsing::parse_token_or_quoted(value))
+                },
+                // unquoted values aren't only tokens, such as `type=text/html`
+                Some(value) if !value.is_empty() && value.bytes().all(|b| b > b' ' && b < 0x7f) => {
+                    value.to_owned()
+                },
+                Some(_) => return Err(::Error::Header),
+                None => String::new(),
+            };
+            params.push((name.to_owned(), value));
+        }
+
+        Ok(LinkValue {
+            target: target.to_owned(),
+            params: params,
+        })
+    }
+}
+
+// Split on `delim`, except within a quoted-string or an `<>` enclosed URI,
+// either of which may contain commas and semicolons.
+fn split_outside(s: &str, delim: u8) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut start, mut quoted, mut escaped, mut uri) = (0, false, false, false);
+    for (i, b) in s.bytes().enumerate() {
+        match b {
+            _ if escaped => escaped = false,
+            b'\\' if quoted => escaped = true,
+            b'"' if !uri => quoted = !quoted,
+            b'<' if !quoted => uri = true,
+            b'>' if !quoted => uri = false,
+            _ if b == delim && !quoted && !uri => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            },
+            _ => (),
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, Headers};
+    use super::{Link, LinkValue};
+
+    #[test]
+    fn test_parse() {
+        let link: Link = Header::parse_header(&[
+            b"<http://example.com/TheBook/chapter2>; rel=\"previous\"; \
+              title=\"previous; chapter, part 1\"".to_vec(),
+            b"</items?page=3,4>; REL=next; type=text/html, </a;b>; rel=\"first start\"".to_vec(),
+        ]).unwrap();
+        assert_eq!(link, Link(vec![
+            LinkValue::new("http://example.com/TheBook/chapter2")
+                .with_param("rel", "previous")
+                .with_param("title", "previous; chapter, part 1"),
+            LinkValue::new("/items?page=3,4")
+                .with_param("REL", "next")
+                .with_param("type", "text/html"),
+            LinkValue::new("/a;b").with_param("rel", "first start"),
+        ]));
+        assert_eq!(link.find_rel("NEXT").unwrap().param("rel"), Some("next"));
+        assert_eq!(link.find_rel("start").unwrap().target, "/a;b");
+        assert!(link.find_rel("last").is_none());
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        let parse = |s: &str| Link::parse_header(&[s.as_bytes().to_vec()]);
+        assert!(parse("").is_err());
+        assert!(parse("http://example.com/; rel=next").is_err());
+        assert!(parse("<http://example.com/>; rel=two words").is_err());
+        assert!(parse("<http://example.com/>; rel=\"unterminated").is_err());
+    }
+
+    #[test]
+    fn test_format() {
+        let mut headers = Headers::new();
+        headers.set(Link(vec![
+            LinkValue::new("/items?page=3")
+                .with_param("rel", "next")
+                .with_param("title", "Page \"3\"")
+                .with_param("title*", "UTF-8'de'n%c3%a4chstes"),
+            LinkValue::new("/items?page=1").with_param("rel", "first prev"),
+        ]));
+        let s = headers.to_string();
+        assert_eq!(s, "Link: </items?page=3>; rel=next; title=\"Page \\\"3\\\"\"; \
+                       title*=UTF-8'de'n%c3%a4chstes, </items?page=1>; rel=\"first prev\"\r\n");
+
+        let reparsed = Link::parse_header(&[s[6..s.len() - 2].as_bytes().to_vec()]).unwrap();
+        assert_eq!(&reparsed, headers.get::<Link>().unwrap());
+    }
+}
+
+bench_header!(bench, Link, {
+    vec![b"</items?page=3>; rel=\"next\", </items?page=1>; rel=\"first\"".to_vec()]
+});
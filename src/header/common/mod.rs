@@ -7,9 +7,11 @@
 //! is used, such as `ContentType(pub Mime)`.
 
 pub use self::accept::Accept;
+pub use self::access_control_allow_credentials::AccessControlAllowCredentials;
 pub use self::access_control_allow_headers::AccessControlAllowHeaders;
 pub use self::access_control_allow_methods::AccessControlAllowMethods;
 pub use self::access_control_allow_origin::AccessControlAllowOrigin;
+pub use self::access_control_expose_headers::AccessControlExposeHeaders;
 pub use self::access_control_max_age::AccessControlMaxAge;
 pub use self::access_control_request_headers::AccessControlRequestHeaders;
 pub use self::access_control_request_method::AccessControlRequestMethod;
@@ -18,19 +20,24 @@ pub use self::accept_encoding::AcceptEncoding;
 pub use self::accept_language::AcceptLanguage;
 pub use self::accept_ranges::{AcceptRanges, RangeUnit};
 pub use self::allow::Allow;
+pub use self::alt_svc::{AltSvc, AltService};
 pub use self::authorization::{Authorization, Scheme, Basic, Bearer};
 pub use self::cache_control::{CacheControl, CacheDirective};
 pub use self::connection::{Connection, ConnectionOption};
 pub use self::content_length::ContentLength;
+pub use self::content_disposition::{ContentDisposition, DispositionType, DispositionParam};
 pub use self::content_encoding::ContentEncoding;
 pub use self::content_language::ContentLanguage;
 pub use self::content_range::{ContentRange, ContentRangeSpec};
+pub use self::content_security_policy::{ContentSecurityPolicy, ContentSecurityPolicyReportOnly, CspDirective, CspSource};
 pub use self::content_type::ContentType;
 pub use self::cookie::Cookie;
 pub use self::date::Date;
+pub use self::digest::{Digest, DigestChallenge, DigestAlgorithm, Qop};
 pub use self::etag::ETag;
 pub use self::expect::Expect;
 pub use self::expires::Expires;
+pub use self::forwarded::{Forwarded, ForwardedElement, Node, NodeName, NodePort};
 pub use self::from::From;
 pub use self::host::Host;
 pub use self::if_match::IfMatch;
@@ -39,17 +46,30 @@ pub use self::if_none_match::IfNoneMatch;
 pub use self::if_unmodified_since::IfUnmodifiedSince;
 pub use self::if_range::IfRange;
 pub use self::last_modified::LastModified;
+pub use self::link::{Link, LinkValue};
 pub use self::location::Location;
+pub use self::origin::Origin;
 pub use self::pragma::Pragma;
+pub use self::prefer::{Prefer, PreferenceApplied, Preference};
+pub use self::proxy_authorization::ProxyAuthorization;
 pub use self::range::{Range, ByteRangeSpec};
 pub use self::referer::Referer;
+pub use self::retry_after::RetryAfter;
+pub use self::sec_websocket_extensions::{SecWebSocketExtensions, WebSocketExtension};
+pub use self::sec_websocket_key::{SecWebSocketKey, SecWebSocketAccept};
+pub use self::sec_websocket_protocol::SecWebSocketProtocol;
+pub use self::sec_websocket_version::SecWebSocketVersion;
 pub use self::server::Server;
-pub use self::set_cookie::SetCookie;
+pub use self::set_cookie::{SetCookie, SameSite, CookieSameSite};
 pub use self::strict_transport_security::StrictTransportSecurity;
+pub use self::te::Te;
+pub use self::trailer::Trailer;
 pub use self::transfer_encoding::TransferEncoding;
 pub use self::upgrade::{Upgrade, Protocol, ProtocolName};
 pub use self::user_agent::UserAgent;
 pub use self::vary::Vary;
+pub use self::warning::{Warning, WarningValue};
+pub use self::www_authenticate::WwwAuthenticate;
 
 #[macro_export]
 macro_rules! bench_header(
@@ -351,9 +371,11 @@ macro_rules! header {
 
 
 mod accept;
+mod access_control_allow_credentials;
 mod access_control_allow_headers;
 mod access_control_allow_methods;
 mod access_control_allow_origin;
+mod access_control_expose_headers;
 mod access_control_max_age;
 mod access_control_request_headers;
 mod access_control_request_method;
@@ -362,19 +384,24 @@ mod accept_encoding;
 mod accept_language;
 mod accept_ranges;
 mod allow;
+mod alt_svc;
 mod authorization;
 mod cache_control;
 mod cookie;
 mod connection;
+mod content_disposition;
 mod content_encoding;
 mod content_language;
 mod content_length;
 mod content_range;
+mod content_security_policy;
 mod content_type;
 mod date;
+mod digest;
 mod etag;
 mod expect;
 mod expires;
+mod forwarded;
 mod from;
 mod host;
 mod if_match;
@@ -383,14 +410,27 @@ mod if_none_match;
 mod if_range;
 mod if_unmodified_since;
 mod last_modified;
+mod link;
 mod location;
+mod origin;
 mod pragma;
+mod prefer;
+mod proxy_authorization;
 mod range;
 mod referer;
+mod retry_after;
+mod sec_websocket_extensions;
+mod sec_websocket_key;
+mod sec_websocket_protocol;
+mod sec_websocket_version;
 mod server;
 mod set_cookie;
 mod strict_transport_security;
+mod te;
+mod trailer;
 mod transfer_encoding;
 mod upgrade;
 mod user_agent;
 mod vary;
+mod warning;
+mod www_authenticate;
@@ -0,0 +1,212 @@
+use std::fmt;
+use std::str::FromStr;
+
+use header::{Header, HeaderFormat, parsing};
+
+/// `Origin` header, defined in [RFC6454](https://tools.ietf.org/html/rfc6454#section-7)
+///
+/// The `Origin` header field indicates the origin that caused the user
+/// agent to issue the request, such as the page whose script made a
+/// cross-origin request.
+///
+/// Origins are compared by scheme, host and port, so `Origin`s are kept
+/// normalized: the scheme and host in lowercase, and a port that is the
+/// scheme's default left out. Browsers send `null` for an origin they
+/// won't disclose, such as that of a sandboxed frame.
+///
+/// # ABNF
+/// ```plain
+/// origin              = "Origin:" OWS origin-list-or-null OWS
+/// origin-list-or-null = %x6E %x75 %x6C %x6C / origin-list
+/// origin-list         = serialized-origin *( SP serialized-origin )
+/// serialized-origin   = scheme "://" host [ ":" port ]
+/// ```
+///
+/// # Example values
+/// * `http://example.com`
+/// * `https://example.com:8443`
+/// * `null`
+///
+/// # Example
+/// ```
+/// use hyper::header::{Headers, Origin};
+///
+/// let mut headers = Headers::new();
+/// headers.set(Origin::new("HTTPS", "Example.com", Some(443)));
+///
+/// let allowed = "https://example.com".parse::<Origin>().unwrap();
+/// assert_eq!(headers.get::<Origin>(), Some(&allowed));
+/// assert_eq!(headers.to_string(), "Origin: https://example.com\r\n");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Origin(Option<(String, String, Option<u16>)>);
+
+fn default_port(scheme: &str) -> Option<u16> {
+    match scheme {
+        "http" | "ws" => Some(80),
+        "https" | "wss" => Some(443),
+        "ftp" => Some(21),
+        _ => None,
+    }
+}
+
+impl Origin {
+    /// The origin with `scheme`, `host` and, unless it is the scheme's
+    /// default, `port`.
+    pub fn new<S: Into<String>, H: Into<String>>(scheme: S, host: H, port: Option<u16>) -> Origin {
+        let scheme = scheme.into().to_lowercase();
+        let port = match port {
+            Some(port) if Some(port) == default_port(&scheme) => None,
+            port => port,
+        };
+        Origin(Some((scheme, host.into().to_lowercase(), port)))
+    }
+
+    /// The `null` origin.
+    pub fn null() -> Origin {
+        Origin(None)
+    }
+
+    /// Whether this is the `null` origin.
+    pub fn is_null(&self) -> bool {
+        self.0.is_none()
+    }
+
+    /// The scheme, in lowercase, unless this is the `null` origin.
+    pub fn scheme(&self) -> Option<&str> {
+        self.0.as_ref().map(|&(ref scheme, _, _)| &scheme[..])
+    }
+
+    /// The host, in lowercase, unless this is the `null` origin. An IPv6
+    /// address is within brackets.
+    pub fn host(&self) -> Option<&str> {
+        self.0.as_ref().map(|&(_, ref host, _)| &host[..])
+    }
+
+    /// The port, which is the scheme's default if none was sent, unless
+    /// this is the `null` origin or the scheme has no default.
+    pub fn port(&self) -> Option<u16> {
+        self.0.as_ref().and_then(|&(ref scheme, _, port)| port.or_else(|| default_port(scheme)))
+    }
+}
+
+impl FromStr for Origin {
+    type Err = ::Error;
+
+    fn from_str(s: &str) -> ::Result<Origin> {
+        if s == "null" {
+            return Ok(Origin::null());
+        }
+        let sep = try!(s.find("://").ok_or(::Error::Header));
+        let (scheme, authority) = (&s[..sep], &s[sep + 3..]);
+        let valid_scheme = scheme.bytes().next().map_or(false, |b| match b {
+            b'a'...b'z' | b'A'...b'Z' => true,
+            _ => false,
+        }) && scheme.bytes().all(|b| match b {
+            b'a'...b'z' | b'A'...b'Z' | b'0'...b'9' | b'+' | b'-' | b'.' => true,
+            _ => false,
+        });
+        if !valid_scheme {
+            return Err(::Error::Header);
+        }
+
+        // an IPv6 address has colons of its own, but is within brackets
+        let colon = match authority.rfind(']') {
+            Some(end) => authority[end..].find(':').map(|i| end + i),
+            None => authority.find(':'),
+        };
+        let (host, port) = match colon {
+            Some(i) => match authority[i + 1..].parse() {
+                Ok(port) => (&authority[..i], Some(port)),
+                Err(_) => return Err(::Error::Header),
+            },
+            None => (authority, None),
+        };
+        // no userinfo, path, or list of several origins
+        let valid_host = !host.is_empty() && host.bytes().all(|b| match b {
+            b'a'...b'z' | b'A'...b'Z' | b'0'...b'9' | b'-' | b'.' | b'_' | b'~' |
+            b'[' | b']' | b':' | b'%' => true,
+            _ => false,
+        });
+        if !valid_host {
+            return Err(::Error::Header);
+        }
+        Ok(Origin::new(scheme, host, port))
+    }
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            None => f.write_str("null"),
+            Some((ref scheme, ref host, None)) => write!(f, "{}://{}", scheme, host),
+            Some((ref scheme, ref host, Some(port))) => write!(f, "{}://{}:{}", scheme, host, port),
+        }
+    }
+}
+
+impl Header for Origin {
+    fn header_name() -> &'static str {
+        "Origin"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> ::Result<Origin> {
+        parsing::from_one_raw_str(raw)
+    }
+}
+
+impl HeaderFormat for Origin {
+    fn fmt_header(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::Header;
+    use super::Origin;
+
+    fn parse(s: &str) -> ::Result<Origin> {
+        Header::parse_header(&[s.as_bytes().to_vec()])
+    }
+
+    #[test]
+    fn test_parse() {
+        let origin = parse("https://example.com:8443").unwrap();
+        assert_eq!(origin.scheme(), Some("https"));
+        assert_eq!(origin.host(), Some("example.com"));
+        assert_eq!(origin.port(), Some(8443));
+
+        let origin = parse("http://[::1]").unwrap();
+        assert_eq!(origin.host(), Some("[::1]"));
+        assert_eq!(origin.port(), Some(80));
+        assert_eq!(parse("http://[::1]:8080").unwrap().port(), Some(8080));
+
+        let null = parse("null").unwrap();
+        assert!(null.is_null());
+        assert_eq!(null.scheme(), None);
+        assert_eq!(null.port(), None);
+
+        assert!(parse("").is_err());
+        assert!(parse("example.com").is_err());
+        assert!(parse("https://").is_err());
+        assert!(parse("https://example.com/").is_err());
+        assert!(parse("https://user@example.com").is_err());
+        assert!(parse("https://example.com:port").is_err());
+        assert!(parse("https://a.com https://b.com").is_err());
+        assert!(parse("1http://example.com").is_err());
+    }
+
+    #[test]
+    fn test_compare() {
+        let origin = parse("HTTPS://Example.COM:443").unwrap();
+        assert_eq!(origin, Origin::new("https", "example.com", None));
+        assert_eq!(origin.to_string(), "https://example.com");
+        assert!(origin != parse("http://example.com").unwrap());
+        assert!(origin != parse("https://example.com:8443").unwrap());
+        assert!(origin != Origin::null());
+        assert_eq!(Origin::new("foo", "example.com", None).port(), None);
+    }
+}
+
+bench_header!(bench, Origin, { vec![b"https://example.com:8443".to_vec()] });
@@ -0,0 +1,269 @@
+use std::fmt;
+use std::str::{self, FromStr};
+
+use unicase::UniCase;
+
+use header::{Header, HeaderFormat, parsing};
+
+/// `Prefer` header, defined in [RFC7240](https://tools.ietf.org/html/rfc7240)
+///
+/// The `Prefer` header field is used by a client to indicate that particular
+/// server behaviors are preferred, but not required, for the request.
+///
+/// # ABNF
+/// ```plain
+/// Prefer     = "Prefer" ":" 1#preference
+/// preference = token [ BWS "=" BWS word ]
+///              *( OWS ";" [ OWS parameter ] )
+/// parameter  = token [ BWS "=" BWS word ]
+/// ```
+///
+/// # Example values
+/// * `respond-async`
+/// * `return=minimal`
+/// * `respond-async, wait=100, handling=lenient`
+///
+/// # Example
+/// ```
+/// use hyper::header::{Headers, Prefer, Preference};
+///
+/// let mut headers = Headers::new();
+/// headers.set(Prefer(vec![Preference::RespondAsync, Preference::Wait(10)]));
+/// assert_eq!(headers.to_string(), "Prefer: respond-async, wait=10\r\n");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Prefer(pub Vec<Preference>);
+
+/// `Preference-Applied` header, defined in [RFC7240](https://tools.ietf.org/html/rfc7240#section-3)
+///
+/// The `Preference-Applied` response header field tells the client which of
+/// the preferences in its `Prefer` header the server applied.
+///
+/// # ABNF
+/// ```plain
+/// Preference-Applied = "Preference-Applied" ":" 1#applied-pref
+/// applied-pref = token [ BWS "=" BWS word ]
+/// ```
+///
+/// # Example values
+/// * `return=representation`
+///
+/// # Example
+/// ```
+/// use hyper::header::{Headers, PreferenceApplied, Preference};
+///
+/// let mut headers = Headers::new();
+/// headers.set(PreferenceApplied(vec![Preference::ReturnMinimal]));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct PreferenceApplied(pub Vec<Preference>);
+
+/// A preference, as sent in a `Prefer` or `PreferenceApplied` header.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Preference {
+    /// `respond-async`, to be answered with 202 Accepted rather than waiting
+    /// for a long running operation.
+    RespondAsync,
+    /// `return=representation`, for a response to include the resource.
+    ReturnRepresentation,
+    /// `return=minimal`, for a response to leave out the resource.
+    ReturnMinimal,
+    /// `handling=strict`, to reject a request with any error.
+    HandlingStrict,
+    /// `handling=lenient`, to process as much of a request as possible.
+    HandlingLenient,
+    /// `wait=n`, how many seconds the client is willing to wait.
+    Wait(u32),
+    /// Any other preference, with its name, value, which is empty if it has
+    /// none, and parameters.
+    Extension(String, String, Vec<(String, String)>),
+}
+
+impl FromStr for Preference {
+    type Err = ::Error;
+
+    fn from_str(s: &str) -> ::Result<Preference> {
+        let mut parts = parsing::split_delimited(s, b';');
+        let (name, value) = try!(parse_pair(parts.next().unwrap_or("")));
+
+        let mut params = Vec::new();
+        for param in parts {
+            if !param.trim().is_empty() {
+                params.push(try!(parse_pair(param)));
+            }
+        }
+        if params.is_empty() {
+            let (name, value) = (UniCase(&name[..]), UniCase(&value[..]));
+            if name == UniCase("respond-async") && value == UniCase("") {
+                return Ok(Preference::RespondAsync);
+            } else if name == UniCase("return") && value == UniCase("representation") {
+                return Ok(Preference::ReturnRepresentation);
+            } else if name == UniCase("return") && value == UniCase("minimal") {
+                return Ok(Preference::ReturnMinimal);
+            } else if name == UniCase("handling") && value == UniCase("strict") {
+                return Ok(Preference::HandlingStrict);
+            } else if name == UniCase("handling") && value == UniCase("lenient") {
+                return Ok(Preference::HandlingLenient);
+            } else if name == UniCase("wait") {
+                return value.parse().map(Preference::Wait).map_err(|_| ::Error::Header);
+            }
+        }
+        Ok(Preference::Extension(name, value, params))
+    }
+}
+
+// Parses `token [ BWS "=" BWS word ]`, where a missing value is empty.
+fn parse_pair(s: &str) -> ::Result<(String, String)> {
+    let mut pair = s.splitn(2, '=');
+    let name = pair.next().unwrap_or("").trim();
+    if parsing::needs_quoting(name) {
+        return Err(::Error::Header);
+    }
+    let value = match pair.next() {
+        Some(value) => try!(parsing::parse_token_or_quoted(value)),
+        None => String::new(),
+    };
+    Ok((name.to_owned(), value))
+}
+
+fn fmt_pair(f: &mut fmt::Formatter, name: &str, value: &str) -> fmt::Result {
+    try!(f.write_str(name));
+    if !value.is_empty() {
+        try!(f.write_str("="));
+        try!(parsing::write_token_or_quoted(f, value));
+    }
+    Ok(())
+}
+
+impl fmt::Display for Preference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Preference::RespondAsync => f.write_str("respond-async"),
+            Preference::ReturnRepresentation => f.write_str("return=representation"),
+            Preference::ReturnMinimal => f.write_str("return=minimal"),
+            Preference::HandlingStrict => f.write_str("handling=strict"),
+            Preference::HandlingLenient => f.write_str("handling=lenient"),
+            Preference::Wait(secs) => write!(f, "wait={}", secs),
+            Preference::Extension(ref name, ref value, ref params) => {
+                try!(fmt_pair(f, name, value));
+                for &(ref name, ref value) in params {
+                    try!(f.write_str("; "));
+                    try!(fmt_pair(f, name, value));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn parse_preferences(raw: &[Vec<u8>]) -> ::Result<Vec<Preference>> {
+    let mut preferences = Vec::new();
+    for line in raw {
+        for preference in parsing::split_comma_delimited(try!(str::from_utf8(line))) {
+            if !preference.trim().is_empty() {
+                preferences.push(try!(preference.parse()));
+            }
+        }
+    }
+    if preferences.is_empty() {
+        return Err(::Error::Header);
+    }
+    Ok(preferences)
+}
+
+impl Header for Prefer {
+    fn header_name() -> &'static str {
+        "Prefer"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> ::Result<Prefer> {
+        parse_preferences(raw).map(Prefer)
+    }
+}
+
+impl HeaderFormat for Prefer {
+    fn fmt_header(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        parsing::fmt_comma_delimited(f, &self.0)
+    }
+}
+
+impl Header for PreferenceApplied {
+    fn header_name() -> &'static str {
+        "Preference-Applied"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> ::Result<PreferenceApplied> {
+        parse_preferences(raw).map(PreferenceApplied)
+    }
+}
+
+impl HeaderFormat for PreferenceApplied {
+    fn fmt_header(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // applied preferences have no parameters
+        for (i, preference) in self.0.iter().enumerate() {
+            if i != 0 {
+                try!(f.write_str(", "));
+            }
+            match *preference {
+                Preference::Extension(ref name, ref value, _) => try!(fmt_pair(f, name, value)),
+                ref preference => try!(fmt::Display::fmt(preference, f)),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, Headers};
+    use super::{Prefer, PreferenceApplied, Preference};
+
+    #[test]
+    fn test_parse() {
+        let prefer: Prefer = Header::parse_header(&[
+            b"Respond-Async, wait = 100, RETURN=\"minimal\"".to_vec(),
+            b"handling=lenient, foo; bar=\"a, b\";baz, priority=5".to_vec(),
+        ]).unwrap();
+        assert_eq!(prefer, Prefer(vec![
+            Preference::RespondAsync,
+            Preference::Wait(100),
+            Preference::ReturnMinimal,
+            Preference::HandlingLenient,
+            Preference::Extension("foo".to_owned(), "".to_owned(), vec![
+                ("bar".to_owned(), "a, b".to_owned()),
+                ("baz".to_owned(), "".to_owned()),
+            ]),
+            Preference::Extension("priority".to_owned(), "5".to_owned(), vec![]),
+        ]));
+
+        assert!(Prefer::parse_header(&[b"".to_vec()]).is_err());
+        assert!(Prefer::parse_header(&[b"wait=soon".to_vec()]).is_err());
+        assert!(Prefer::parse_header(&[b"return=two words".to_vec()]).is_err());
+    }
+
+    #[test]
+    fn test_format() {
+        let mut headers = Headers::new();
+        let prefer = Prefer(vec![
+            Preference::ReturnRepresentation,
+            Preference::HandlingStrict,
+            Preference::Extension("foo".to_owned(), "a b".to_owned(),
+                                  vec![("bar".to_owned(), "".to_owned())]),
+        ]);
+        headers.set(prefer.clone());
+        assert_eq!(headers.to_string(),
+                   "Prefer: return=representation, handling=strict, foo=\"a b\"; bar\r\n");
+        assert_eq!(Prefer::parse_header(&[headers.get_raw("Prefer").unwrap()[0].clone()]).unwrap(),
+                   prefer);
+
+        headers.remove::<Prefer>();
+        headers.set(PreferenceApplied(vec![
+            Preference::RespondAsync,
+            Preference::Extension("foo".to_owned(), "1".to_owned(),
+                                  vec![("bar".to_owned(), "".to_owned())]),
+        ]));
+        assert_eq!(headers.to_string(), "Preference-Applied: respond-async, foo=1\r\n");
+    }
+}
+
+bench_header!(bench, Prefer, { vec![b"respond-async, wait=100, handling=lenient".to_vec()] });
@@ -1,8 +1,11 @@
+use std::cmp;
 use std::fmt::{self, Display};
 use std::str::FromStr;
 
-use header::{Header, HeaderFormat};
+use header::{Header, HeaderFormat, ContentRangeSpec};
 use header::parsing::{from_one_raw_str, from_one_comma_delimited};
+#[cfg(feature = "quickcheck")]
+use quickcheck::{Arbitrary, Gen};
 
 /// `Range` header, defined in [RFC7233](https://tools.ietf.org/html/rfc7233#section-3.1)
 ///
@@ -87,6 +90,104 @@ impl Range {
     pub fn bytes_multi(ranges: Vec<(u64, u64)>) -> Range {
         Range::Bytes(ranges.iter().map(|r| ByteRangeSpec::FromTo(r.0, r.1)).collect())
     }
+
+    /// Resolve every subrange against the length of the representation,
+    /// giving the first and last offsets of each, inclusive, as
+    /// [RFC7233 Section 2.1](https://tools.ietf.org/html/rfc7233#section-2.1)
+    /// describes.
+    ///
+    /// Unsatisfiable subranges are dropped, and the rest are sorted and
+    /// coalesced where they overlap or touch, so a client can't make a
+    /// server send the same bytes twice.
+    ///
+    /// Returns `None` if the header should be ignored and the whole
+    /// representation sent: when its unit isn't `bytes`, or when it has more
+    /// than `max_ranges` subranges, which would be costly to serve.
+    /// Returns an empty `Vec` if no subrange is satisfiable, to be answered
+    /// with `416 Range Not Satisfiable`.
+    ///
+    /// ```
+    /// use hyper::header::{Range, ByteRangeSpec};
+    ///
+    /// let range = Range::Bytes(vec![
+    ///     ByteRangeSpec::FromTo(0, 99),
+    ///     ByteRangeSpec::Last(50),
+    ///     ByteRangeSpec::FromTo(50, 120),
+    /// ]);
+    /// assert_eq!(range.to_satisfiable_ranges(200, 10), Some(vec![(0, 120), (150, 199)]));
+    /// assert_eq!(range.to_satisfiable_ranges(200, 2), None);
+    /// ```
+    pub fn to_satisfiable_ranges(&self, len: u64, max_ranges: usize) -> Option<Vec<(u64, u64)>> {
+        let specs = match *self {
+            Range::Bytes(ref specs) if specs.len() <= max_ranges => specs,
+            _ => return None,
+        };
+        let mut ranges: Vec<(u64, u64)> = specs.iter()
+            .filter_map(|spec| spec.to_satisfiable_range(len))
+            .collect();
+        ranges.sort();
+
+        let mut coalesced: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+        for (first, last) in ranges {
+            if let Some(prev) = coalesced.last_mut() {
+                if first <= prev.1 + 1 {
+                    prev.1 = cmp::max(prev.1, last);
+                    continue;
+                }
+            }
+            coalesced.push((first, last));
+        }
+        Some(coalesced)
+    }
+}
+
+impl ByteRangeSpec {
+    /// Resolve this range against the length of the representation, giving
+    /// its first and last offsets, inclusive.
+    ///
+    /// A range running past the end is cut short. Returns `None` if the
+    /// range is unsatisfiable: it starts at or after the end, or asks for the
+    /// last zero bytes.
+    ///
+    /// ```
+    /// use hyper::header::ByteRangeSpec;
+    ///
+    /// assert_eq!(ByteRangeSpec::FromTo(0, 499).to_satisfiable_range(100), Some((0, 99)));
+    /// assert_eq!(ByteRangeSpec::Last(10).to_satisfiable_range(100), Some((90, 99)));
+    /// assert_eq!(ByteRangeSpec::AllFrom(100).to_satisfiable_range(100), None);
+    /// ```
+    pub fn to_satisfiable_range(&self, len: u64) -> Option<(u64, u64)> {
+        match *self {
+            ByteRangeSpec::FromTo(first, last) if first < len && first <= last => {
+                Some((first, cmp::min(last, len - 1)))
+            },
+            ByteRangeSpec::AllFrom(first) if first < len => Some((first, len - 1)),
+            ByteRangeSpec::Last(suffix) if suffix > 0 && len > 0 => {
+                Some((len - cmp::min(suffix, len), len - 1))
+            },
+            _ => None,
+        }
+    }
+
+    /// The `Content-Range` to answer this range with, for a representation
+    /// `len` bytes long.
+    ///
+    /// If the range is satisfiable, this is the range to send with a
+    /// `206 Partial Content`. Otherwise it is `bytes */len`, to send with a
+    /// `416 Range Not Satisfiable`.
+    ///
+    /// ```
+    /// use hyper::header::{ByteRangeSpec, ContentRangeSpec};
+    ///
+    /// assert_eq!(ByteRangeSpec::Last(10).to_content_range(100).to_string(), "bytes 90-99/100");
+    /// assert_eq!(ByteRangeSpec::AllFrom(100).to_content_range(100).to_string(), "bytes */100");
+    /// ```
+    pub fn to_content_range(&self, len: u64) -> ContentRangeSpec {
+        ContentRangeSpec::Bytes {
+            range: self.to_satisfiable_range(len),
+            instance_length: Some(len),
+        }
+    }
 }
 
 
@@ -173,6 +274,40 @@ impl FromStr for ByteRangeSpec {
     }
 }
 
+#[cfg(feature = "quickcheck")]
+impl Arbitrary for ByteRangeSpec {
+    fn arbitrary(g: &mut Gen) -> ByteRangeSpec {
+        let (a, b) = (u64::arbitrary(g), u64::arbitrary(g));
+        match u8::arbitrary(g) % 3 {
+            0 if a <= b => ByteRangeSpec::FromTo(a, b),
+            0 => ByteRangeSpec::FromTo(b, a),
+            1 => ByteRangeSpec::AllFrom(a),
+            _ => ByteRangeSpec::Last(a),
+        }
+    }
+}
+
+#[cfg(feature = "quickcheck")]
+impl Arbitrary for Range {
+    fn arbitrary(g: &mut Gen) -> Range {
+        if u8::arbitrary(g) % 4 == 0 {
+            const UNIT: &'static [char] = &['a', 'b', 'x', 'y', 'z', '-'];
+            const SET: &'static [char] = &['0', '1', '9', '-', ',', 'x', '=', ' '];
+            let unit_len = 1 + usize::arbitrary(g) % 8;
+            let unit = (0..unit_len).map(|_| *g.choose(UNIT).unwrap()).collect();
+            let set_len = 1 + usize::arbitrary(g) % 16;
+            let set = (0..set_len).map(|_| *g.choose(SET).unwrap()).collect();
+            Range::Unregistered(unit, set)
+        } else {
+            let mut ranges = Vec::<ByteRangeSpec>::arbitrary(g);
+            if ranges.is_empty() {
+                ranges.push(ByteRangeSpec::arbitrary(g));
+            }
+            Range::Bytes(ranges)
+        }
+    }
+}
+
 impl Header for Range {
 
     fn header_name() -> &'static str {
@@ -222,6 +357,17 @@ fn test_parse_bytes_range_valid() {
 
 }
 
+#[test]
+fn test_parse_byte_range_set() {
+    let r: Range = Header::parse_header(&[b"bytes=0-499,510-520,-100".to_vec()]).unwrap();
+    assert_eq!(r, Range::Bytes(vec![
+        ByteRangeSpec::FromTo(0, 499),
+        ByteRangeSpec::FromTo(510, 520),
+        ByteRangeSpec::Last(100),
+    ]));
+    assert_eq!(r.to_string(), "bytes=0-499,510-520,-100");
+}
+
 #[test]
 fn test_parse_unregistered_range_valid() {
     let r: Range = Header::parse_header(&[b"custom=1-100,-100".to_vec()]).unwrap();
@@ -235,6 +381,10 @@ fn test_parse_unregistered_range_valid() {
     let r: Range = Header::parse_header(&[b"custom=xxx-yyy".to_vec()]).unwrap();
     let r2 =  Range::Unregistered("custom".to_owned(), "xxx-yyy".to_owned());
     assert_eq!(r, r2);
+
+    let r: Range = Header::parse_header(&[b"items=0-49".to_vec()]).unwrap();
+    assert_eq!(r, Range::Unregistered("items".to_owned(), "0-49".to_owned()));
+    assert_eq!(r.to_string(), "items=0-49");
 }
 
 #[test]
@@ -284,5 +434,45 @@ fn test_fmt() {
     assert_eq!(&headers.to_string(), "Range: custom=1-xxx\r\n");
 }
 
+#[test]
+fn test_to_satisfiable_range() {
+    assert_eq!(ByteRangeSpec::FromTo(10, 20).to_satisfiable_range(100), Some((10, 20)));
+    assert_eq!(ByteRangeSpec::FromTo(10, 200).to_satisfiable_range(100), Some((10, 99)));
+    assert_eq!(ByteRangeSpec::FromTo(100, 200).to_satisfiable_range(100), None);
+    assert_eq!(ByteRangeSpec::AllFrom(0).to_satisfiable_range(100), Some((0, 99)));
+    assert_eq!(ByteRangeSpec::AllFrom(0).to_satisfiable_range(0), None);
+    assert_eq!(ByteRangeSpec::Last(500).to_satisfiable_range(100), Some((0, 99)));
+    assert_eq!(ByteRangeSpec::Last(0).to_satisfiable_range(100), None);
+    assert_eq!(ByteRangeSpec::Last(10).to_satisfiable_range(0), None);
+}
+
+#[test]
+fn test_to_content_range() {
+    let satisfied = ByteRangeSpec::FromTo(10, 200).to_content_range(100);
+    assert_eq!(satisfied,
+               ContentRangeSpec::Bytes { range: Some((10, 99)), instance_length: Some(100) });
+    let unsatisfied = ByteRangeSpec::Last(0).to_content_range(100);
+    assert_eq!(unsatisfied, ContentRangeSpec::Bytes { range: None, instance_length: Some(100) });
+}
+
+#[test]
+fn test_to_satisfiable_ranges() {
+    let range = Range::bytes_multi(vec![(50, 59), (0, 9), (10, 19), (5, 7), (500, 600)]);
+    assert_eq!(range.to_satisfiable_ranges(100, 5), Some(vec![(0, 19), (50, 59)]));
+    assert_eq!(range.to_satisfiable_ranges(100, 4), None);
+
+    let range = Range::Bytes(vec![ByteRangeSpec::AllFrom(100), ByteRangeSpec::Last(0)]);
+    assert_eq!(range.to_satisfiable_ranges(100, 5), Some(vec![]));
+
+    let range = Range::Unregistered("custom".to_owned(), "0-10".to_owned());
+    assert_eq!(range.to_satisfiable_ranges(100, 5), None);
+}
+
+#[cfg(feature = "quickcheck")]
+#[test]
+fn test_round_trip() {
+    ::quickcheck::quickcheck(::header::round_trips as fn(Range) -> bool);
+}
+
 bench_header!(bytes_multi, Range, { vec![b"bytes=1-1001,2001-3001,10001-".to_vec()]});
 bench_header!(custom_unit, Range, { vec![b"other=0-100000".to_vec()]});
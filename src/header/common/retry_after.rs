@@ -0,0 +1,84 @@
+use std::fmt::{self, Display};
+use std::time::Duration;
+
+use header::{self, Header, HeaderFormat, HttpDate};
+
+/// `Retry-After` header, defined in [RFC7231](http://tools.ietf.org/html/rfc7231#section-7.1.3)
+///
+/// The `Retry-After` header field can be sent with a `503 Service
+/// Unavailable` or `429 Too Many Requests` response to say how long the
+/// client ought to wait before making another request, or with a
+/// redirection to say how long to wait before following it.
+///
+/// # ABNF
+/// ```plain
+/// Retry-After = HTTP-date / delay-seconds
+/// delay-seconds  = 1*DIGIT
+/// ```
+///
+/// # Example values
+/// * `Fri, 31 Dec 1999 23:59:59 GMT`
+/// * `120`
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use hyper::header::{Headers, RetryAfter};
+///
+/// let mut headers = Headers::new();
+/// headers.set(RetryAfter::Delay(Duration::from_secs(120)));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum RetryAfter {
+    /// Retry after this long; only whole seconds are sent.
+    Delay(Duration),
+    /// Retry after this date.
+    DateTime(HttpDate),
+}
+
+impl Header for RetryAfter {
+    fn header_name() -> &'static str {
+        "Retry-After"
+    }
+    fn parse_header(raw: &[Vec<u8>]) -> ::Result<RetryAfter> {
+        let secs: ::Result<u64> = header::parsing::from_one_raw_str(raw);
+        if let Ok(secs) = secs {
+            return Ok(RetryAfter::Delay(Duration::from_secs(secs)));
+        }
+        let date: ::Result<HttpDate> = header::parsing::from_one_raw_str(raw);
+        date.map(RetryAfter::DateTime)
+    }
+}
+
+impl HeaderFormat for RetryAfter {
+    fn fmt_header(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RetryAfter::Delay(ref delay) => Display::fmt(&delay.as_secs(), f),
+            RetryAfter::DateTime(ref date) => Display::fmt(date, f),
+        }
+    }
+}
+
+impl Display for RetryAfter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_header(f)
+    }
+}
+
+#[cfg(test)]
+mod test_retry_after {
+    use std::str;
+    use std::time::Duration;
+    use header::*;
+    use super::RetryAfter as HeaderField;
+    test_header!(test1, vec![b"Fri, 31 Dec 1999 23:59:59 GMT"]);
+    test_header!(test2, vec![b"120"]);
+    test_header!(test3, vec![b"soon"], None::<RetryAfter>);
+    test_header!(test4, vec![b"-1"], None::<RetryAfter>);
+
+    #[test]
+    fn test_delay() {
+        let parsed = HeaderField::parse_header(&[b"120".to_vec()]).ok();
+        assert_eq!(parsed, Some(RetryAfter::Delay(Duration::from_secs(120))));
+    }
+}
@@ -0,0 +1,184 @@
+use std::fmt;
+use std::str::{self, FromStr};
+
+use unicase::UniCase;
+
+use header::{Header, HeaderFormat, parsing};
+
+/// `Sec-WebSocket-Extensions` header, defined in [RFC6455](https://tools.ietf.org/html/rfc6455#section-11.3.2)
+///
+/// The `Sec-WebSocket-Extensions` header field is sent by a client in the
+/// opening handshake of a WebSocket connection, with the extensions it
+/// would use, such as `permessage-deflate`. A server accepting the
+/// connection answers with those of them it agreed to.
+///
+/// # ABNF
+/// ```plain
+/// Sec-WebSocket-Extensions = extension-list
+/// extension-list = 1#extension
+/// extension = extension-token *( ";" extension-param )
+/// extension-token = registered-token
+/// registered-token = token
+/// extension-param = token [ "=" (token | quoted-string) ]
+/// ```
+///
+/// # Example values
+/// * `permessage-deflate`
+/// * `permessage-deflate; client_max_window_bits, foo; bar="baz"`
+///
+/// # Example
+/// ```
+/// use hyper::header::{Headers, SecWebSocketExtensions, WebSocketExtension};
+///
+/// let mut deflate = WebSocketExtension::new("permessage-deflate");
+/// deflate.params.push(("client_max_window_bits".to_owned(), None));
+///
+/// let mut headers = Headers::new();
+/// headers.set(SecWebSocketExtensions(vec![deflate]));
+/// assert_eq!(headers.to_string(),
+///            "Sec-WebSocket-Extensions: permessage-deflate; client_max_window_bits\r\n");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SecWebSocketExtensions(pub Vec<WebSocketExtension>);
+
+/// A single extension within a `Sec-WebSocket-Extensions` header.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WebSocketExtension {
+    /// The name of the extension.
+    pub name: String,
+    /// The parameters of the extension, with their values, if any.
+    pub params: Vec<(String, Option<String>)>,
+}
+
+impl WebSocketExtension {
+    /// An extension without parameters.
+    pub fn new<N: Into<String>>(name: N) -> WebSocketExtension {
+        WebSocketExtension {
+            name: name.into(),
+            params: Vec::new(),
+        }
+    }
+
+    /// The value of the parameter `name`, which is `Some(None)` for a
+    /// parameter without a value.
+    pub fn param(&self, name: &str) -> Option<Option<&str>> {
+        self.params.iter()
+            .find(|&&(ref param, _)| UniCase(&param[..]) == UniCase(name))
+            .map(|&(_, ref value)| value.as_ref().map(|value| &value[..]))
+    }
+}
+
+impl FromStr for WebSocketExtension {
+    type Err = ::Error;
+
+    fn from_str(s: &str) -> ::Result<WebSocketExtension> {
+        let mut parts = parsing::split_delimited(s, b';');
+        let name = parts.next().unwrap_or("").trim();
+        if parsing::needs_quoting(name) {
+            return Err(::Error::Header);
+        }
+
+        let mut params = Vec::new();
+        for param in parts {
+            let mut pair = param.splitn(2, '=');
+            let name = pair.next().unwrap_or("").trim();
+            if parsing::needs_quoting(name) {
+                return Err(::Error::Header);
+            }
+            let value = match pair.next() {
+                Some(value) => Some(try!(parsing::parse_token_or_quoted(value))),
+                None => None,
+            };
+            params.push((name.to_owned(), value));
+        }
+        Ok(WebSocketExtension {
+            name: name.to_owned(),
+            params: params,
+        })
+    }
+}
+
+impl fmt::Display for WebSocketExtension {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(f.write_str(&self.name));
+        for &(ref name, ref value) in &self.params {
+            try!(write!(f, "; {}", name));
+            if let Some(ref value) = *value {
+                try!(f.write_str("="));
+                try!(parsing::write_token_or_quoted(f, value));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Header for SecWebSocketExtensions {
+    fn header_name() -> &'static str {
+        "Sec-WebSocket-Extensions"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> ::Result<SecWebSocketExtensions> {
+        let mut extensions = Vec::new();
+        for line in raw {
+            for extension in parsing::split_comma_delimited(try!(str::from_utf8(line))) {
+                if !extension.trim().is_empty() {
+                    extensions.push(try!(extension.parse()));
+                }
+            }
+        }
+        if extensions.is_empty() {
+            return Err(::Error::Header);
+        }
+        Ok(SecWebSocketExtensions(extensions))
+    }
+}
+
+impl HeaderFormat for SecWebSocketExtensions {
+    fn fmt_header(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        parsing::fmt_comma_delimited(f, &self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, Headers};
+    use super::{SecWebSocketExtensions, WebSocketExtension};
+
+    #[test]
+    fn test_parse() {
+        let extensions: SecWebSocketExtensions = Header::parse_header(&[
+            b"permessage-deflate; client_max_window_bits; server_max_window_bits=10".to_vec(),
+            b"foo; bar=\"baz\", qux".to_vec(),
+        ]).unwrap();
+        assert_eq!(extensions.0.len(), 3);
+        let deflate = &extensions.0[0];
+        assert_eq!(deflate.name, "permessage-deflate");
+        assert_eq!(deflate.param("client_max_window_bits"), Some(None));
+        assert_eq!(deflate.param("Server_Max_Window_Bits"), Some(Some("10")));
+        assert_eq!(deflate.param("server_no_context_takeover"), None);
+        assert_eq!(extensions.0[1].param("bar"), Some(Some("baz")));
+        assert_eq!(extensions.0[2], WebSocketExtension::new("qux"));
+
+        let parse = |s: &str| SecWebSocketExtensions::parse_header(&[s.as_bytes().to_vec()]);
+        assert!(parse("").is_err());
+        assert!(parse("; foo").is_err());
+        assert!(parse("foo; bar=two words").is_err());
+    }
+
+    #[test]
+    fn test_format() {
+        let mut foo = WebSocketExtension::new("foo");
+        foo.params.push(("bar".to_owned(), Some("a b".to_owned())));
+        foo.params.push(("baz".to_owned(), None));
+        let extensions = SecWebSocketExtensions(vec![WebSocketExtension::new("permessage-deflate"), foo]);
+
+        let mut headers = Headers::new();
+        headers.set(extensions.clone());
+        assert_eq!(headers.to_string(),
+                   "Sec-WebSocket-Extensions: permessage-deflate, foo; bar=\"a b\"; baz\r\n");
+        let raw = headers.get_raw("Sec-WebSocket-Extensions").unwrap().to_vec();
+        assert_eq!(SecWebSocketExtensions::parse_header(&raw).unwrap(), extensions);
+    }
+}
+
+bench_header!(bench, SecWebSocketExtensions, { vec![b"permessage-deflate; client_max_window_bits".to_vec()] });
@@ -0,0 +1,182 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serialize::base64::{ToBase64, FromBase64, STANDARD};
+
+use header::{Header, HeaderFormat, parsing};
+use super::digest::sha1;
+
+// Appended to a key before hashing it into the accept value.
+const GUID: &'static str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// `Sec-WebSocket-Key` header, defined in [RFC6455](https://tools.ietf.org/html/rfc6455#section-11.3.1)
+///
+/// The `Sec-WebSocket-Key` header field is sent by a client in the opening
+/// handshake of a WebSocket connection. It is a random 16 byte nonce,
+/// base64 encoded, which the server proves it read by answering with the
+/// matching `Sec-WebSocket-Accept`.
+///
+/// # ABNF
+/// ```plain
+/// Sec-WebSocket-Key = base64-value-non-empty
+/// base64-value-non-empty = (1*base64-data [ base64-padding ]) |
+///                          base64-padding
+/// ```
+///
+/// # Example values
+/// * `dGhlIHNhbXBsZSBub25jZQ==`
+///
+/// # Example
+/// ```
+/// use hyper::header::{Headers, SecWebSocketKey};
+///
+/// let mut headers = Headers::new();
+/// headers.set(SecWebSocketKey::new(*b"the sample nonce"));
+/// assert_eq!(headers.to_string(), "Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SecWebSocketKey(String);
+
+impl SecWebSocketKey {
+    /// The key encoding `nonce`, which should be picked at random for each
+    /// connection.
+    pub fn new(nonce: [u8; 16]) -> SecWebSocketKey {
+        SecWebSocketKey(nonce.to_base64(STANDARD))
+    }
+
+    /// The base64 encoded nonce, as sent.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for SecWebSocketKey {
+    type Err = ::Error;
+
+    fn from_str(s: &str) -> ::Result<SecWebSocketKey> {
+        SecWebSocketKey::parse_header(&[s.as_bytes().to_vec()])
+    }
+}
+
+impl Header for SecWebSocketKey {
+    fn header_name() -> &'static str {
+        "Sec-WebSocket-Key"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> ::Result<SecWebSocketKey> {
+        let key: String = try!(parsing::from_one_raw_str(raw));
+        match key.from_base64() {
+            Ok(ref nonce) if nonce.len() == 16 => Ok(SecWebSocketKey(key)),
+            _ => Err(::Error::Header),
+        }
+    }
+}
+
+impl HeaderFormat for SecWebSocketKey {
+    fn fmt_header(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// `Sec-WebSocket-Accept` header, defined in [RFC6455](https://tools.ietf.org/html/rfc6455#section-11.3.3)
+///
+/// The `Sec-WebSocket-Accept` header field is sent by a server accepting
+/// a WebSocket connection. Its value is derived from the client's
+/// `Sec-WebSocket-Key`, and a client fails the connection unless it is the
+/// one `SecWebSocketAccept::new` gives for the key it sent.
+///
+/// # ABNF
+/// ```plain
+/// Sec-WebSocket-Accept = base64-value-non-empty
+/// ```
+///
+/// # Example values
+/// * `s3pPLMBiTxaQ9kYGzzhZRbK+xOo=`
+///
+/// # Example
+/// ```
+/// use hyper::header::{Headers, SecWebSocketKey, SecWebSocketAccept};
+///
+/// let key = "dGhlIHNhbXBsZSBub25jZQ==".parse::<SecWebSocketKey>().unwrap();
+/// let mut headers = Headers::new();
+/// headers.set(SecWebSocketAccept::new(&key));
+/// assert_eq!(headers.to_string(), "Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SecWebSocketAccept(String);
+
+impl SecWebSocketAccept {
+    /// The accept value answering `key`: the base64 encoded SHA-1 hash of
+    /// the key followed by the WebSocket GUID.
+    pub fn new(key: &SecWebSocketKey) -> SecWebSocketAccept {
+        let mut data = key.0.clone();
+        data.push_str(GUID);
+        SecWebSocketAccept(sha1(data.as_bytes()).to_base64(STANDARD))
+    }
+
+    /// Whether this is the value answering `key`.
+    pub fn matches(&self, key: &SecWebSocketKey) -> bool {
+        *self == SecWebSocketAccept::new(key)
+    }
+
+    /// The base64 encoded hash, as sent.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Header for SecWebSocketAccept {
+    fn header_name() -> &'static str {
+        "Sec-WebSocket-Accept"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> ::Result<SecWebSocketAccept> {
+        let accept: String = try!(parsing::from_one_raw_str(raw));
+        match accept.from_base64() {
+            Ok(ref hash) if hash.len() == 20 => Ok(SecWebSocketAccept(accept)),
+            _ => Err(::Error::Header),
+        }
+    }
+}
+
+impl HeaderFormat for SecWebSocketAccept {
+    fn fmt_header(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, Headers};
+    use super::{SecWebSocketKey, SecWebSocketAccept};
+
+    #[test]
+    fn test_accept() {
+        // from RFC6455, section 1.3
+        let key: SecWebSocketKey = Header::parse_header(&[b"dGhlIHNhbXBsZSBub25jZQ==".to_vec()]).unwrap();
+        assert_eq!(key, SecWebSocketKey::new(*b"the sample nonce"));
+        let accept = SecWebSocketAccept::new(&key);
+        assert_eq!(accept.as_str(), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+
+        let parsed: SecWebSocketAccept = Header::parse_header(&[b"s3pPLMBiTxaQ9kYGzzhZRbK+xOo=".to_vec()]).unwrap();
+        assert!(parsed.matches(&key));
+        assert!(!parsed.matches(&SecWebSocketKey::new([0; 16])));
+
+        let mut headers = Headers::new();
+        headers.set(key);
+        headers.set(accept);
+        assert_eq!(headers.to_string(), "Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+                                         Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n");
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(SecWebSocketKey::parse_header(&[b"".to_vec()]).is_err());
+        assert!(SecWebSocketKey::parse_header(&[b"not base64!".to_vec()]).is_err());
+        // a 15 byte nonce
+        assert!(SecWebSocketKey::parse_header(&[b"dGhlIHNhbXBsZSBub25j".to_vec()]).is_err());
+        assert!(SecWebSocketAccept::parse_header(&[b"dGhlIHNhbXBsZSBub25jZQ==".to_vec()]).is_err());
+    }
+}
+
+bench_header!(bench, SecWebSocketKey, { vec![b"dGhlIHNhbXBsZSBub25jZQ==".to_vec()] });
@@ -0,0 +1,36 @@
+header! {
+    #[doc="`Sec-WebSocket-Protocol` header, defined in"]
+    #[doc="[RFC6455](https://tools.ietf.org/html/rfc6455#section-11.3.4)"]
+    #[doc=""]
+    #[doc="The `Sec-WebSocket-Protocol` header field is sent by a client in the"]
+    #[doc="opening handshake of a WebSocket connection, with the subprotocols it"]
+    #[doc="would speak over it, in order of preference. A server accepting the"]
+    #[doc="connection answers with the one of them it picked, if any."]
+    #[doc=""]
+    #[doc="# ABNF"]
+    #[doc="```plain"]
+    #[doc="Sec-WebSocket-Protocol-Client = 1#token"]
+    #[doc="Sec-WebSocket-Protocol-Server = token"]
+    #[doc="```"]
+    #[doc=""]
+    #[doc="# Example values"]
+    #[doc="* `chat, superchat`"]
+    #[doc="* `v2.bookings.example.net`"]
+    #[doc=""]
+    #[doc="# Example"]
+    #[doc="```"]
+    #[doc="use hyper::header::{Headers, SecWebSocketProtocol};"]
+    #[doc=""]
+    #[doc="let mut headers = Headers::new();"]
+    #[doc="headers.set(SecWebSocketProtocol(vec![\"chat\".to_owned(), \"superchat\".to_owned()]));"]
+    #[doc="```"]
+    (SecWebSocketProtocol, "Sec-WebSocket-Protocol") => (String)+
+
+    test_sec_websocket_protocol {
+        test_header!(test1, vec![b"chat, superchat"]);
+        test_header!(test2, vec![b"v2.bookings.example.net"],
+                     Some(HeaderField(vec!["v2.bookings.example.net".to_owned()])));
+    }
+}
+
+bench_header!(bench, SecWebSocketProtocol, { vec![b"chat, superchat".to_vec()] });
@@ -0,0 +1,38 @@
+header! {
+    #[doc="`Sec-WebSocket-Version` header, defined in"]
+    #[doc="[RFC6455](https://tools.ietf.org/html/rfc6455#section-11.3.5)"]
+    #[doc=""]
+    #[doc="The `Sec-WebSocket-Version` header field is sent by a client in the"]
+    #[doc="opening handshake of a WebSocket connection, with the version of the"]
+    #[doc="protocol it speaks, which is 13 for RFC6455. A server that doesn't"]
+    #[doc="speak it answers with `426 Upgrade Required` and the versions it does."]
+    #[doc=""]
+    #[doc="# ABNF"]
+    #[doc="```plain"]
+    #[doc="Sec-WebSocket-Version-Client = version"]
+    #[doc="Sec-WebSocket-Version-Server = 1#version"]
+    #[doc="version = DIGIT | (NZDIGIT DIGIT) |"]
+    #[doc="          (\"1\" DIGIT DIGIT) | (\"2\" DIGIT DIGIT)"]
+    #[doc="          ; Limited to 0-255 range, with no leading zeros"]
+    #[doc="```"]
+    #[doc=""]
+    #[doc="# Example values"]
+    #[doc="* `13`"]
+    #[doc="* `13, 8, 7`"]
+    #[doc=""]
+    #[doc="# Example"]
+    #[doc="```"]
+    #[doc="use hyper::header::{Headers, SecWebSocketVersion};"]
+    #[doc=""]
+    #[doc="let mut headers = Headers::new();"]
+    #[doc="headers.set(SecWebSocketVersion(vec![13]));"]
+    #[doc="```"]
+    (SecWebSocketVersion, "Sec-WebSocket-Version") => (u8)+
+
+    test_sec_websocket_version {
+        test_header!(test1, vec![b"13"], Some(HeaderField(vec![13])));
+        test_header!(test2, vec![b"13, 8, 7"], Some(HeaderField(vec![13, 8, 7])));
+    }
+}
+
+bench_header!(bench, SecWebSocketVersion, { vec![b"13".to_vec()] });
@@ -1,6 +1,6 @@
-use header::{Header, HeaderFormat};
+use header::{Header, HeaderFormat, HttpDate};
 use std::fmt::{self, Display};
-use std::str::from_utf8;
+use std::str::{FromStr, from_utf8};
 
 use cookie::Cookie;
 use cookie::CookieJar;
@@ -46,14 +46,19 @@ use cookie::CookieJar;
 /// path-value        = <any CHAR except CTLs or ";">
 /// secure-av         = "Secure"
 /// httponly-av       = "HttpOnly"
+/// samesite-av       = "SameSite=" ( "Strict" / "Lax" / "None" )
 /// extension-av      = <any CHAR except CTLs or ";">
 /// ```
 ///
+/// The attributes are sent in the order above, with `Expires` as an
+/// IMF-fixdate in GMT. `SameSite` is set with the `CookieSameSite` trait.
+///
 /// # Example values
 /// * `SID=31d4d96e407aad42`
 /// * `lang=en-US; Expires=Wed, 09 Jun 2021 10:18:14 GMT`
 /// * `lang=; Expires=Sun, 06 Nov 1994 08:49:37 GMT`
 /// * `lang=en-US; Path=/; Domain=example.com`
+/// * `SID=31d4d96e407aad42; Path=/; Secure; HttpOnly; SameSite=Lax`
 ///
 /// # Example
 /// ```
@@ -62,7 +67,7 @@ use cookie::CookieJar;
 /// # fn main() {
 /// // extern crate cookie;
 ///
-/// use hyper::header::{Headers, SetCookie};
+/// use hyper::header::{Headers, SetCookie, CookieSameSite, SameSite};
 /// use cookie::Cookie as CookiePair;
 ///
 /// let mut headers = Headers::new();
@@ -70,6 +75,7 @@ use cookie::CookieJar;
 ///
 /// cookie.path = Some("/path".to_owned());
 /// cookie.domain = Some("example.com".to_owned());
+/// cookie.set_same_site(Some(SameSite::Strict));
 ///
 /// headers.set(
 ///     SetCookie(vec![
@@ -113,14 +119,130 @@ impl HeaderFormat for SetCookie {
     fn fmt_header(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for (i, cookie) in self.0.iter().enumerate() {
             if i != 0 {
-                try!(f.write_str("\r\nSet-Cookie: "));
+                try!(f.write_str(", "));
+            }
+            try!(Display::fmt(&SetCookieString(cookie), f));
+        }
+        Ok(())
+    }
+
+    // an Expires date has a comma of its own, so each cookie needs a line
+    fn fmt_lines(&self) -> Option<Vec<String>> {
+        Some(self.0.iter().map(|cookie| SetCookieString(cookie).to_string()).collect())
+    }
+}
+
+// The `cookie` crate writes attributes in an order of its own, and `Expires`
+// in the local time zone, so a cookie is formatted here instead.
+struct SetCookieString<'a>(&'a Cookie);
+
+impl<'a> Display for SetCookieString<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cookie = self.0;
+        try!(Display::fmt(&cookie.pair(), f));
+        if let Some(ref expires) = cookie.expires {
+            try!(write!(f, "; Expires={}", HttpDate(*expires)));
+        }
+        if let Some(max_age) = cookie.max_age {
+            try!(write!(f, "; Max-Age={}", max_age));
+        }
+        if let Some(ref domain) = cookie.domain {
+            try!(write!(f, "; Domain={}", domain));
+        }
+        if let Some(ref path) = cookie.path {
+            try!(write!(f, "; Path={}", path));
+        }
+        if cookie.secure {
+            try!(f.write_str("; Secure"));
+        }
+        if cookie.httponly {
+            try!(f.write_str("; HttpOnly"));
+        }
+        if let Some(same_site) = cookie.same_site() {
+            try!(write!(f, "; SameSite={}", same_site));
+        }
+        for (name, value) in &cookie.custom {
+            if !name.eq_ignore_ascii_case(SAME_SITE) {
+                try!(write!(f, "; {}={}", name, value));
             }
-            try!(Display::fmt(cookie, f));
         }
         Ok(())
     }
 }
 
+const SAME_SITE: &'static str = "SameSite";
+
+/// The `SameSite` attribute of a cookie, defined in
+/// [draft-ietf-httpbis-rfc6265bis](https://tools.ietf.org/html/draft-ietf-httpbis-rfc6265bis-05#section-5.3.7),
+/// saying whether a browser sends the cookie with requests from other sites.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SameSite {
+    /// Only send the cookie with requests from the same site.
+    Strict,
+    /// Also send the cookie when navigating to the site from another one.
+    Lax,
+    /// Send the cookie with any request. Browsers require such a cookie to
+    /// be `Secure`.
+    None,
+}
+
+impl FromStr for SameSite {
+    type Err = ::Error;
+
+    fn from_str(s: &str) -> ::Result<SameSite> {
+        if s.eq_ignore_ascii_case("strict") {
+            Ok(SameSite::Strict)
+        } else if s.eq_ignore_ascii_case("lax") {
+            Ok(SameSite::Lax)
+        } else if s.eq_ignore_ascii_case("none") {
+            Ok(SameSite::None)
+        } else {
+            Err(::Error::Header)
+        }
+    }
+}
+
+impl Display for SameSite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        })
+    }
+}
+
+/// Access to the `SameSite` attribute of a `cookie::Cookie`, which keeps it
+/// among its custom attributes.
+pub trait CookieSameSite {
+    /// The `SameSite` attribute, unless absent or not recognized.
+    fn same_site(&self) -> Option<SameSite>;
+
+    /// Set, or with `None` remove, the `SameSite` attribute.
+    fn set_same_site(&mut self, same_site: Option<SameSite>);
+}
+
+impl CookieSameSite for Cookie {
+    fn same_site(&self) -> Option<SameSite> {
+        self.custom.iter()
+            .find(|&(name, _)| name.eq_ignore_ascii_case(SAME_SITE))
+            .and_then(|(_, value)| value.parse().ok())
+    }
+
+    fn set_same_site(&mut self, same_site: Option<SameSite>) {
+        let names: Vec<String> = self.custom.keys()
+            .filter(|name| name.eq_ignore_ascii_case(SAME_SITE))
+            .cloned()
+            .collect();
+        for name in names {
+            self.custom.remove(&name);
+        }
+        if let Some(same_site) = same_site {
+            self.custom.insert(SAME_SITE.to_owned(), same_site.to_string());
+        }
+    }
+}
+
 
 impl SetCookie {
     /// Use this to create SetCookie header from CookieJar using
@@ -138,6 +260,24 @@ impl SetCookie {
     }
 }
 
+impl From<Cookie> for SetCookie {
+    fn from(cookie: Cookie) -> SetCookie {
+        SetCookie(vec![cookie])
+    }
+}
+
+impl From<Vec<Cookie>> for SetCookie {
+    fn from(cookies: Vec<Cookie>) -> SetCookie {
+        SetCookie(cookies)
+    }
+}
+
+impl From<SetCookie> for Vec<Cookie> {
+    fn from(set_cookie: SetCookie) -> Vec<Cookie> {
+        set_cookie.0
+    }
+}
+
 
 #[test]
 fn test_parse() {
@@ -161,7 +301,41 @@ fn test_fmt() {
 
     assert_eq!(
         &headers.to_string()[..],
-        "Set-Cookie: foo=bar; HttpOnly; Path=/p\r\nSet-Cookie: baz=quux; Path=/\r\n");
+        "Set-Cookie: foo=bar; Path=/p; HttpOnly\r\nSet-Cookie: baz=quux; Path=/\r\n");
+}
+
+#[test]
+fn test_attributes() {
+    use time::{self, Timespec};
+    use header::Headers;
+
+    let mut cookie = Cookie::new("id".to_owned(), "a3fWa".to_owned());
+    cookie.expires = Some(time::at(Timespec::new(1445412480, 0)));
+    cookie.max_age = Some(3600);
+    cookie.domain = Some("example.com".to_owned());
+    cookie.secure = true;
+    cookie.httponly = true;
+    cookie.set_same_site(Some(SameSite::Strict));
+    cookie.custom.insert("Priority".to_owned(), "High".to_owned());
+    let mut headers = Headers::new();
+    headers.set(SetCookie(vec![cookie.clone()]));
+    assert_eq!(headers.to_string(), "Set-Cookie: id=a3fWa; Expires=Wed, 21 Oct 2015 07:28:00 GMT; \
+        Max-Age=3600; Domain=example.com; Path=/; Secure; HttpOnly; SameSite=Strict; \
+        Priority=High\r\n");
+
+    let parsed: SetCookie = Header::parse_header(&[
+        b"id=a3fWa; samesite=lax; Secure".to_vec(),
+        b"other=1; SameSite=Sideways".to_vec(),
+    ]).unwrap();
+    assert_eq!(parsed[0].same_site(), Some(SameSite::Lax));
+    assert_eq!(parsed[1].same_site(), None);
+
+    let mut cookie = parsed[0].clone();
+    cookie.set_same_site(Some(SameSite::None));
+    assert_eq!(cookie.same_site(), Some(SameSite::None));
+    assert_eq!(SetCookieString(&cookie).to_string(), "id=a3fWa; Path=/; Secure; SameSite=None");
+    cookie.set_same_site(None);
+    assert!(cookie.custom.is_empty());
 }
 
 #[test]
@@ -178,3 +352,15 @@ fn cookie_jar() {
     assert_eq!(jar.find("foo"), new_jar.find("foo"));
     assert_eq!(jar.iter().collect::<Vec<Cookie>>(), new_jar.iter().collect::<Vec<Cookie>>());
 }
+
+#[test]
+fn test_conversions() {
+    let mut cookie = Cookie::new("foo".to_owned(), "bar".to_owned());
+    cookie.httponly = true;
+    let header = SetCookie::from(cookie.clone());
+    assert_eq!(header, SetCookie(vec![cookie.clone()]));
+    assert_eq!(SetCookie::from(vec![cookie.clone()]), header);
+
+    let cookies: Vec<Cookie> = header.into();
+    assert_eq!(cookies, vec![cookie]);
+}
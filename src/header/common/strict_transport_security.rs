@@ -52,7 +52,12 @@ pub struct StrictTransportSecurity {
     /// Specifies the number of seconds, after the reception of the STS header
     /// field, during which the UA regards the host (from whom the message was
     /// received) as a Known HSTS Host.
-    pub max_age: u64
+    pub max_age: u64,
+
+    /// Signals that the host consents to being included in the HSTS preload
+    /// lists that browsers ship with. This isn't part of RFC6797, but is
+    /// required by those lists.
+    pub preload: bool
 }
 
 impl StrictTransportSecurity {
@@ -60,7 +65,8 @@ impl StrictTransportSecurity {
     pub fn including_subdomains(max_age: u64) -> StrictTransportSecurity {
         StrictTransportSecurity {
             max_age: max_age,
-            include_subdomains: true
+            include_subdomains: true,
+            preload: false
         }
     }
 
@@ -68,7 +74,17 @@ impl StrictTransportSecurity {
     pub fn excluding_subdomains(max_age: u64) -> StrictTransportSecurity {
         StrictTransportSecurity {
             max_age: max_age,
-            include_subdomains: false
+            include_subdomains: false,
+            preload: false
+        }
+    }
+
+    /// Also signal consent to preloading, which requires that subdomains
+    /// are included and a `max_age` of at least a year.
+    pub fn with_preload(self) -> StrictTransportSecurity {
+        StrictTransportSecurity {
+            preload: true,
+            ..self
         }
     }
 }
@@ -76,6 +92,7 @@ impl StrictTransportSecurity {
 enum Directive {
     MaxAge(u64),
     IncludeSubdomains,
+    Preload,
     Unknown
 }
 
@@ -87,6 +104,8 @@ impl FromStr for StrictTransportSecurity {
             .map(str::trim)
             .map(|sub| if UniCase(sub) == UniCase("includeSubdomains") {
                 Ok(Directive::IncludeSubdomains)
+            } else if UniCase(sub) == UniCase("preload") {
+                Ok(Directive::Preload)
             } else {
                 let mut sub = sub.splitn(2, '=');
                 match (sub.next(), sub.next()) {
@@ -101,18 +120,21 @@ impl FromStr for StrictTransportSecurity {
                     _ => Ok(Directive::Unknown)
                 }
             })
-            .fold(Ok((None, None)), |res, dir| match (res, dir) {
-                (Ok((None, sub)), Ok(Directive::MaxAge(age))) => Ok((Some(age), sub)),
-                (Ok((age, None)), Ok(Directive::IncludeSubdomains)) => Ok((age, Some(()))),
-                (Ok((Some(_), _)), Ok(Directive::MaxAge(_))) => Err(::Error::Header),
-                (Ok((_, Some(_))), Ok(Directive::IncludeSubdomains)) => Err(::Error::Header),
+            .fold(Ok((None, None, None)), |res, dir| match (res, dir) {
+                (Ok((None, sub, pre)), Ok(Directive::MaxAge(age))) => Ok((Some(age), sub, pre)),
+                (Ok((age, None, pre)), Ok(Directive::IncludeSubdomains)) => Ok((age, Some(()), pre)),
+                (Ok((age, sub, None)), Ok(Directive::Preload)) => Ok((age, sub, Some(()))),
+                (Ok((Some(_), _, _)), Ok(Directive::MaxAge(_))) => Err(::Error::Header),
+                (Ok((_, Some(_), _)), Ok(Directive::IncludeSubdomains)) => Err(::Error::Header),
+                (Ok((_, _, Some(_))), Ok(Directive::Preload)) => Err(::Error::Header),
                 (_, Err(_)) => Err(::Error::Header),
                 (res, _) => res
             })
             .and_then(|res| match res {
-                (Some(age), sub) => Ok(StrictTransportSecurity {
+                (Some(age), sub, pre) => Ok(StrictTransportSecurity {
                     max_age: age,
-                    include_subdomains: sub.is_some()
+                    include_subdomains: sub.is_some(),
+                    preload: pre.is_some()
                 }),
                 _ => Err(::Error::Header)
             })
@@ -131,23 +153,26 @@ impl Header for StrictTransportSecurity {
 
 impl HeaderFormat for StrictTransportSecurity {
     fn fmt_header(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "max-age={}", self.max_age));
         if self.include_subdomains {
-            write!(f, "max-age={}; includeSubdomains", self.max_age)
-        } else {
-            write!(f, "max-age={}", self.max_age)
+            try!(f.write_str("; includeSubdomains"));
         }
+        if self.preload {
+            try!(f.write_str("; preload"));
+        }
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::StrictTransportSecurity;
-    use header::Header;
+    use header::{Header, Headers};
 
     #[test]
     fn test_parse_max_age() {
         let h = Header::parse_header(&[b"max-age=31536000".to_vec()][..]);
-        assert_eq!(h.ok(), Some(StrictTransportSecurity { include_subdomains: false, max_age: 31536000u64 }));
+        assert_eq!(h.ok(), Some(StrictTransportSecurity { include_subdomains: false, max_age: 31536000u64, preload: false }));
     }
 
     #[test]
@@ -159,19 +184,19 @@ mod tests {
     #[test]
     fn test_parse_quoted_max_age() {
         let h = Header::parse_header(&[b"max-age=\"31536000\"".to_vec()][..]);
-        assert_eq!(h.ok(), Some(StrictTransportSecurity { include_subdomains: false, max_age: 31536000u64 }));
+        assert_eq!(h.ok(), Some(StrictTransportSecurity { include_subdomains: false, max_age: 31536000u64, preload: false }));
     }
 
     #[test]
     fn test_parse_spaces_max_age() {
         let h = Header::parse_header(&[b"max-age = 31536000".to_vec()][..]);
-        assert_eq!(h.ok(), Some(StrictTransportSecurity { include_subdomains: false, max_age: 31536000u64 }));
+        assert_eq!(h.ok(), Some(StrictTransportSecurity { include_subdomains: false, max_age: 31536000u64, preload: false }));
     }
 
     #[test]
     fn test_parse_include_subdomains() {
         let h = Header::parse_header(&[b"max-age=15768000 ; includeSubDomains".to_vec()][..]);
-        assert_eq!(h.ok(), Some(StrictTransportSecurity { include_subdomains: true, max_age: 15768000u64 }));
+        assert_eq!(h.ok(), Some(StrictTransportSecurity { include_subdomains: true, max_age: 15768000u64, preload: false }));
     }
 
     #[test]
@@ -189,6 +214,18 @@ mod tests {
     #[test]
     fn test_parse_duplicate_directives() {
         assert!(StrictTransportSecurity::parse_header(&[b"max-age=100; max-age=5; max-age=0".to_vec()][..]).is_err());
+        assert!(StrictTransportSecurity::parse_header(&[b"max-age=100; preload; preload".to_vec()][..]).is_err());
+    }
+
+    #[test]
+    fn test_preload() {
+        let h = Header::parse_header(&[b"max-age=31536000; includeSubDomains; Preload".to_vec()][..]);
+        assert_eq!(h.ok(), Some(StrictTransportSecurity { include_subdomains: true, max_age: 31536000u64, preload: true }));
+
+        let mut headers = Headers::new();
+        headers.set(StrictTransportSecurity::including_subdomains(31536000).with_preload());
+        assert_eq!(headers.to_string(),
+                   "Strict-Transport-Security: max-age=31536000; includeSubdomains; preload\r\n");
     }
 }
 
@@ -0,0 +1,56 @@
+use header::{Encoding, QualityItem};
+
+header! {
+    #[doc="`TE` header, defined in"]
+    #[doc="[RFC7230](http://tools.ietf.org/html/rfc7230#section-4.3)"]
+    #[doc=""]
+    #[doc="The `TE` header field in a request indicates what transfer codings,"]
+    #[doc="besides chunked, the client is willing to accept in response, and"]
+    #[doc="whether or not the client is willing to accept trailer fields in a"]
+    #[doc="chunked transfer coding."]
+    #[doc=""]
+    #[doc="# ABNF"]
+    #[doc="```plain"]
+    #[doc="TE        = #t-codings"]
+    #[doc="t-codings = \"trailers\" / ( transfer-coding [ t-ranking ] )"]
+    #[doc="```"]
+    #[doc=""]
+    #[doc="# Example values"]
+    #[doc="* `trailers`"]
+    #[doc="* `trailers, deflate;q=0.5`"]
+    #[doc="* ``"]
+    #[doc=""]
+    #[doc="# Example"]
+    #[doc="```"]
+    #[doc="use hyper::header::{Headers, Te, Encoding, qitem};"]
+    #[doc=""]
+    #[doc="let mut headers = Headers::new();"]
+    #[doc="headers.set(Te(vec![qitem(Encoding::Trailers)]));"]
+    #[doc="assert!(headers.get::<Te>().unwrap().accepts_trailers());"]
+    #[doc="```"]
+    (Te, "TE") => (QualityItem<Encoding>)*
+
+    test_te {
+        test_header!(test1, vec![b"trailers"], Some(HeaderField(vec![qitem(Encoding::Trailers)])));
+        test_header!(test2, vec![b"trailers, deflate; q=0.5"], Some(HeaderField(vec![
+            qitem(Encoding::Trailers),
+            QualityItem::new(Encoding::Deflate, Quality(500)),
+        ])));
+        test_header!(test3, vec![b""], Some(HeaderField(vec![])));
+
+        #[test]
+        fn test_accepts_trailers() {
+            assert!(HeaderField(vec![qitem(Encoding::Gzip), qitem(Encoding::Trailers)]).accepts_trailers());
+            assert!(!HeaderField(vec![qitem(Encoding::Gzip)]).accepts_trailers());
+        }
+    }
+}
+
+impl Te {
+    /// Whether the client accepts trailer fields after a chunked body.
+    pub fn accepts_trailers(&self) -> bool {
+        self.0.iter().any(|coding| coding.item == Encoding::Trailers)
+    }
+}
+
+bench_header!(bench, Te, { vec![b"trailers, deflate;q=0.5".to_vec()] });
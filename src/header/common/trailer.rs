@@ -0,0 +1,44 @@
+use unicase::UniCase;
+
+header! {
+    #[doc="`Trailer` header, defined in"]
+    #[doc="[RFC7230](http://tools.ietf.org/html/rfc7230#section-4.4)"]
+    #[doc=""]
+    #[doc="The `Trailer` header field allows the sender to indicate which fields"]
+    #[doc="will be present in the trailers of a chunked message, so that the"]
+    #[doc="recipient can prepare for them, such as a checksum of the body that"]
+    #[doc="is only known once all of it has been sent."]
+    #[doc=""]
+    #[doc="# ABNF"]
+    #[doc="```plain"]
+    #[doc="Trailer = 1#field-name"]
+    #[doc="```"]
+    #[doc=""]
+    #[doc="# Example values"]
+    #[doc="* `Content-MD5`"]
+    #[doc="* `Digest, Expires`"]
+    #[doc=""]
+    #[doc="# Example"]
+    #[doc="```"]
+    #[doc="# extern crate hyper;"]
+    #[doc="# extern crate unicase;"]
+    #[doc="# fn main() {"]
+    #[doc="// extern crate unicase;"]
+    #[doc=""]
+    #[doc="use hyper::header::{Headers, Trailer};"]
+    #[doc="use unicase::UniCase;"]
+    #[doc=""]
+    #[doc="let mut headers = Headers::new();"]
+    #[doc="headers.set(Trailer(vec![UniCase(\"Digest\".to_owned())]));"]
+    #[doc="# }"]
+    #[doc="```"]
+    (Trailer, "Trailer") => (UniCase<String>)+
+
+    test_trailer {
+        test_header!(test1, vec![b"Digest, Expires"]);
+        test_header!(test2, vec![b"grpc-status"],
+                     Some(HeaderField(vec![UniCase("grpc-status".to_owned())])));
+    }
+}
+
+bench_header!(bench, Trailer, { vec![b"Digest, Expires".to_vec()] });
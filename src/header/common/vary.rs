@@ -1,5 +1,9 @@
+use std::ascii::AsciiExt;
+
 use unicase::UniCase;
 
+use header::{Headers, parsing};
+
 header! {
     #[doc="`Vary` header, defined in [RFC7231](https://tools.ietf.org/html/rfc7231#section-7.1.4)"]
     #[doc=""]
@@ -62,5 +66,83 @@ header! {
                                                         "cookIE".parse().unwrap(),
                                                         "AlLOw".parse().unwrap(),])));
         }
+
+        #[test]
+        fn test_cache_key() {
+            let vary = Vary::Items(vec!["Cookie".parse().unwrap(),
+                                        "accept".parse().unwrap(),
+                                        "ACCEPT".parse().unwrap()]);
+            let mut headers = Headers::new();
+            assert_eq!(vary.cache_key(&headers).unwrap(), "accept\ncookie\n");
+
+            headers.set_raw("Cookie", vec![b"".to_vec()]);
+            assert_eq!(vary.cache_key(&headers).unwrap(), "accept\ncookie:\n");
+
+            headers.set_raw("Accept", vec![b" text/html ,, text/plain;q=\"0.5, x\"".to_vec(),
+                                           b"*/*".to_vec()]);
+            assert_eq!(vary.cache_key(&headers).unwrap(),
+                       "accept: text/html, text/plain;q=\"0.5, x\", */*\ncookie:\n");
+
+            assert_eq!(Vary::Any.cache_key(&headers), None);
+        }
+    }
+}
+
+impl Vary {
+    /// The secondary cache key of a response with this `Vary`, from the
+    /// `headers` of the request it answered.
+    ///
+    /// A stored response may only be reused for a request with the same
+    /// key. The key is normalized, so it doesn't change with the order or
+    /// case of the field names, with how a field is split across lines, or
+    /// with whitespace around list items. A field missing from the request
+    /// gives a different key from one sent empty.
+    ///
+    /// `Vary::Any` has no key, since a response varying on anything can't
+    /// be reused without revalidation.
+    ///
+    /// # Example
+    /// ```
+    /// use hyper::header::{Header, Headers, Vary};
+    ///
+    /// let vary = Vary::parse_header(&[b"Accept-Encoding, Accept-Language".to_vec()]).unwrap();
+    /// let mut one = Headers::new();
+    /// one.set_raw("Accept-Encoding", vec![b"gzip,  br".to_vec()]);
+    /// let mut two = Headers::new();
+    /// two.set_raw("accept-encoding", vec![b"gzip".to_vec(), b"br".to_vec()]);
+    /// assert_eq!(vary.cache_key(&one), vary.cache_key(&two));
+    /// assert_eq!(vary.cache_key(&one).unwrap(), "accept-encoding: gzip, br\naccept-language\n");
+    /// ```
+    pub fn cache_key(&self, headers: &Headers) -> Option<String> {
+        let names = match *self {
+            Vary::Any => return None,
+            Vary::Items(ref names) => names,
+        };
+        let mut names: Vec<String> = names.iter().map(|name| name.to_ascii_lowercase()).collect();
+        names.sort();
+        names.dedup();
+
+        let mut key = String::new();
+        for name in names {
+            key.push_str(&name);
+            if let Some(lines) = headers.get_raw(&name) {
+                key.push(':');
+                let mut first = true;
+                for line in lines {
+                    let line = String::from_utf8_lossy(line);
+                    for item in parsing::split_comma_delimited(&line) {
+                        let item = item.trim();
+                        if item.is_empty() {
+                            continue;
+                        }
+                        key.push_str(if first { " " } else { ", " });
+                        key.push_str(item);
+                        first = false;
+                    }
+                }
+            }
+            key.push('\n');
+        }
+        Some(key)
     }
 }
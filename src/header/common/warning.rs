@@ -0,0 +1,209 @@
+use std::fmt;
+use std::str::{self, FromStr};
+
+use header::{Header, HeaderFormat, HttpDate, parsing};
+
+/// `Warning` header, defined in [RFC7234](https://tools.ietf.org/html/rfc7234#section-5.5)
+///
+/// The `Warning` header field is used to carry additional information
+/// about the status or transformation of a message that might not be
+/// reflected in the status code. Caches add warnings such as:
+///
+/// * 110 Response is Stale
+/// * 111 Revalidation Failed
+/// * 112 Disconnected Operation
+/// * 113 Heuristic Expiration
+/// * 199 Miscellaneous Warning
+/// * 214 Transformation Applied
+/// * 299 Miscellaneous Persistent Warning
+///
+/// Warnings in the 1xx range must be removed by a cache after successfully
+/// revalidating a response, while those in the 2xx range are kept.
+///
+/// # ABNF
+/// ```plain
+/// Warning       = 1#warning-value
+/// warning-value = warn-code SP warn-agent SP warn-text
+///                 [ SP warn-date ]
+/// warn-code     = 3DIGIT
+/// warn-agent    = ( uri-host [ ":" port ] ) / pseudonym
+/// warn-text     = quoted-string
+/// warn-date     = DQUOTE HTTP-date DQUOTE
+/// ```
+///
+/// # Example values
+/// * `110 anderson/1.3.37 "Response is stale"`
+/// * `112 - "network down" "Sat, 25 Aug 2012 23:34:45 GMT"`
+///
+/// # Example
+/// ```
+/// use hyper::header::{Headers, Warning, WarningValue};
+///
+/// let mut headers = Headers::new();
+/// headers.set(Warning(vec![WarningValue::new(110, "proxy.example.com", "Response is Stale")]));
+/// assert_eq!(headers.to_string(), "Warning: 110 proxy.example.com \"Response is Stale\"\r\n");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Warning(pub Vec<WarningValue>);
+
+/// A single warning within a `Warning` header.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WarningValue {
+    /// The three digit warning code.
+    pub code: u16,
+    /// The host, with an optional port, or pseudonym of whoever added the
+    /// warning, or `-` if unknown.
+    pub agent: String,
+    /// The text describing the warning.
+    pub text: String,
+    /// The `Date` of the message the warning was added to. A recipient
+    /// ignores a warning whose date doesn't match the message's own.
+    pub date: Option<HttpDate>,
+}
+
+impl WarningValue {
+    /// A warning with no date.
+    pub fn new<A: Into<String>, T: Into<String>>(code: u16, agent: A, text: T) -> WarningValue {
+        WarningValue {
+            code: code,
+            agent: agent.into(),
+            text: text.into(),
+            date: None,
+        }
+    }
+}
+
+impl FromStr for WarningValue {
+    type Err = ::Error;
+
+    fn from_str(s: &str) -> ::Result<WarningValue> {
+        let s = s.trim();
+        let mut parts = s.splitn(3, ' ');
+        let code = match parts.next() {
+            Some(code) if code.len() == 3 && code.bytes().all(|b| b >= b'0' && b <= b'9') => {
+                try!(code.parse().map_err(|_| ::Error::Header))
+            },
+            _ => return Err(::Error::Header),
+        };
+        let agent = match parts.next() {
+            Some(agent) if !agent.is_empty() && !agent.contains('"') => agent.to_owned(),
+            _ => return Err(::Error::Header),
+        };
+        let rest = try!(parts.next().ok_or(::Error::Header));
+
+        // the text ends at the first quote that isn't escaped
+        let mut end = None;
+        let mut escaped = false;
+        for (i, b) in rest.bytes().enumerate().skip(1) {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => {
+                    end = Some(i);
+                    break;
+                },
+                _ => (),
+            }
+        }
+        let end = try!(end.ok_or(::Error::Header));
+        let text = try!(parsing::parse_token_or_quoted(&rest[..end + 1]));
+        let date = match rest[end + 1..].trim() {
+            "" => None,
+            date if date.len() >= 2 && date.starts_with('"') && date.ends_with('"') => {
+                Some(try!(date[1..date.len() - 1].parse()))
+            },
+            _ => return Err(::Error::Header),
+        };
+
+        Ok(WarningValue {
+            code: code,
+            agent: agent,
+            text: text,
+            date: date,
+        })
+    }
+}
+
+impl fmt::Display for WarningValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{:03} {} ", self.code, self.agent));
+        try!(parsing::write_quoted(f, &self.text));
+        if let Some(ref date) = self.date {
+            try!(write!(f, " \"{}\"", date));
+        }
+        Ok(())
+    }
+}
+
+impl Header for Warning {
+    fn header_name() -> &'static str {
+        "Warning"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> ::Result<Warning> {
+        let mut values = Vec::new();
+        for line in raw {
+            for value in parsing::split_comma_delimited(try!(str::from_utf8(line))) {
+                if !value.trim().is_empty() {
+                    values.push(try!(value.parse()));
+                }
+            }
+        }
+        if values.is_empty() {
+            return Err(::Error::Header);
+        }
+        Ok(Warning(values))
+    }
+}
+
+impl HeaderFormat for Warning {
+    fn fmt_header(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        parsing::fmt_comma_delimited(f, &self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, Headers};
+    use super::{Warning, WarningValue};
+
+    #[test]
+    fn test_parse() {
+        let warning: Warning = Header::parse_header(&[
+            b"110 anderson/1.3.37 \"Response is stale\", \
+              112 - \"network \\\"down\\\"\" \"Sat, 25 Aug 2012 23:34:45 GMT\"".to_vec(),
+            b"214 proxy.example.com:8080 \"Transformation, applied\"".to_vec(),
+        ]).unwrap();
+        assert_eq!(warning.0.len(), 3);
+        assert_eq!(warning.0[0], WarningValue::new(110, "anderson/1.3.37", "Response is stale"));
+        assert_eq!(warning.0[1].code, 112);
+        assert_eq!(warning.0[1].agent, "-");
+        assert_eq!(warning.0[1].text, "network \"down\"");
+        assert_eq!(warning.0[1].date.unwrap(), "Sat, 25 Aug 2012 23:34:45 GMT".parse().unwrap());
+        assert_eq!(warning.0[2], WarningValue::new(214, "proxy.example.com:8080",
+                                                   "Transformation, applied"));
+
+        let parse = |s: &str| Warning::parse_header(&[s.as_bytes().to_vec()]);
+        assert!(parse("").is_err());
+        assert!(parse("11 - \"too short\"").is_err());
+        assert!(parse("110 - unquoted").is_err());
+        assert!(parse("110 - \"unterminated").is_err());
+        assert!(parse("110 - \"text\" not-a-date").is_err());
+    }
+
+    #[test]
+    fn test_format() {
+        let mut dated = WarningValue::new(111, "-", "Revalidation \"Failed\"");
+        dated.date = Some("Sat, 25 Aug 2012 23:34:45 GMT".parse().unwrap());
+        let warning = Warning(vec![WarningValue::new(110, "cache", "Response is Stale"), dated]);
+
+        let mut headers = Headers::new();
+        headers.set(warning.clone());
+        assert_eq!(headers.to_string(), "Warning: 110 cache \"Response is Stale\", \
+            111 - \"Revalidation \\\"Failed\\\"\" \"Sat, 25 Aug 2012 23:34:45 GMT\"\r\n");
+        let raw = headers.get_raw("Warning").unwrap().to_vec();
+        assert_eq!(Warning::parse_header(&raw).unwrap(), warning);
+    }
+}
+
+bench_header!(bench, Warning, { vec![b"110 anderson/1.3.37 \"Response is stale\"".to_vec()] });
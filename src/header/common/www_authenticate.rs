@@ -0,0 +1,117 @@
+use std::any::Any;
+use std::fmt;
+use std::str::{FromStr, from_utf8};
+use std::ops::{Deref, DerefMut};
+
+use unicase::UniCase;
+
+use header::{Header, HeaderFormat, Scheme, parsing};
+
+/// `WWW-Authenticate` header, defined in [RFC7235](https://tools.ietf.org/html/rfc7235#section-4.1)
+///
+/// The `WWW-Authenticate` header field indicates the authentication
+/// scheme(s) and parameters applicable to the target resource. It is sent
+/// with a 401 (Unauthorized) response.
+///
+/// A server may offer several challenges, in one header field or in
+/// several. Parsing a `WwwAuthenticate<S>` finds the first challenge of the
+/// scheme `S`, ignoring any others.
+///
+/// # ABNF
+/// ```plain
+/// WWW-Authenticate = 1#challenge
+/// ```
+///
+/// # Example values
+/// * `Basic realm="WallyWorld"`
+/// * `Digest realm="http-auth@example.org", qop="auth", nonce="7ypf/xlj9XXwfDPEoM4URrv"`
+///
+/// # Example
+/// ```
+/// use hyper::header::{Headers, WwwAuthenticate};
+///
+/// let mut headers = Headers::new();
+/// headers.set(WwwAuthenticate("Basic realm=\"WallyWorld\"".to_owned()));
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct WwwAuthenticate<S: Scheme>(pub S);
+
+impl<S: Scheme> Deref for WwwAuthenticate<S> {
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        &self.0
+    }
+}
+
+impl<S: Scheme> DerefMut for WwwAuthenticate<S> {
+    fn deref_mut(&mut self) -> &mut S {
+        &mut self.0
+    }
+}
+
+impl<S: Scheme + Any> Header for WwwAuthenticate<S> where <S as FromStr>::Err: 'static {
+    fn header_name() -> &'static str {
+        "WWW-Authenticate"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> ::Result<WwwAuthenticate<S>> {
+        let scheme = match <S as Scheme>::scheme() {
+            Some(scheme) => scheme,
+            None => {
+                let lines = try!(raw.iter().map(|line| from_utf8(line)).collect::<Result<Vec<_>, _>>());
+                return lines.join(", ").parse().map(WwwAuthenticate).map_err(|_| ::Error::Header);
+            }
+        };
+        for line in raw {
+            let elements = parsing::split_comma_delimited(try!(from_utf8(line))).collect::<Vec<_>>();
+            for (i, element) in elements.iter().enumerate() {
+                let element = element.trim_left();
+                // a challenge starts with its scheme, then its first parameter
+                let is_start = element.len() > scheme.len() &&
+                    element.is_char_boundary(scheme.len()) &&
+                    UniCase(&element[..scheme.len()]) == UniCase(scheme) &&
+                    element[scheme.len()..].starts_with(' ');
+                if is_start {
+                    let mut challenge = element[scheme.len() + 1..].to_owned();
+                    for rest in &elements[i + 1..] {
+                        challenge.push(',');
+                        challenge.push_str(rest);
+                    }
+                    return challenge.parse().map(WwwAuthenticate).map_err(|_| ::Error::Header);
+                }
+            }
+        }
+        Err(::Error::Header)
+    }
+}
+
+impl<S: Scheme + Any> HeaderFormat for WwwAuthenticate<S> where <S as FromStr>::Err: 'static {
+    fn fmt_header(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(scheme) = <S as Scheme>::scheme() {
+            try!(write!(f, "{} ", scheme))
+        };
+        self.0.fmt_scheme(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, Headers};
+    use super::WwwAuthenticate;
+
+    #[test]
+    fn test_raw_challenges() {
+        let header: WwwAuthenticate<String> = Header::parse_header(&[
+            b"Basic realm=\"a\"".to_vec(), b"Newauth realm=\"b\"".to_vec()
+        ]).unwrap();
+        assert_eq!(header.0, "Basic realm=\"a\", Newauth realm=\"b\"");
+
+        let mut headers = Headers::new();
+        headers.set(header);
+        assert_eq!(headers.to_string(),
+                   "WWW-Authenticate: Basic realm=\"a\", Newauth realm=\"b\"\r\n");
+    }
+}
+
+bench_header!(raw, WwwAuthenticate<String>, { vec![b"Basic realm=\"WallyWorld\"".to_vec()] });
@@ -1,12 +1,15 @@
 use std::any::Any;
+use std::ascii::AsciiExt;
 use std::any::TypeId;
 use std::fmt;
+use std::io::{self, Write};
+use std::mem;
 use std::str::from_utf8;
 
 use typeable::Typeable;
 
 use super::cell::{OptCell, PtrMapCell};
-use header::{Header, HeaderFormat};
+use header::{Header, HeaderFormat, MultiValue};
 
 
 #[derive(Clone)]
@@ -47,13 +50,23 @@ impl Item {
             return &raw[..];
         }
 
-        let raw = vec![unsafe { self.typed.one() }.to_string().into_bytes()];
+        let typed = unsafe { self.typed.one() };
+        let raw = match typed.fmt_lines() {
+            Some(lines) => lines.into_iter().map(String::into_bytes).collect(),
+            None => vec![typed.to_string().into_bytes()],
+        };
         self.raw.set(raw);
 
         let raw = self.raw.as_ref().unwrap();
         &raw[..]
     }
 
+    /// The raw lines, taken without copying if the item has them.
+    pub fn into_raw(mut self) -> Vec<Vec<u8>> {
+        self.raw();
+        mem::replace(self.mut_raw(), Vec::new())
+    }
+
     pub fn typed<H: Header + HeaderFormat + Any>(&self) -> Option<&H> {
         let tid = TypeId::of::<H>();
         match self.typed.get(tid) {
@@ -94,23 +107,126 @@ fn parse<H: Header + HeaderFormat>(raw: &Vec<Vec<u8>>) ->
     })
 }
 
+impl Item {
+    /// Writes the field as `name: value` lines, with CRLF between them.
+    ///
+    /// Without a `style` to override it, a typed value is split into lines
+    /// if it has `fmt_lines`, and raw values only for `Set-Cookie`.
+    pub fn fmt_lines(&self, name: &str, style: Option<MultiValue>,
+                     f: &mut fmt::Formatter) -> fmt::Result {
+        match *self.raw {
+            Some(ref raw) if raw.is_empty() => write!(f, "{}: ", name),
+            Some(ref raw) => {
+                let style = style.unwrap_or(raw_style(name));
+                for (i, part) in raw.iter().enumerate() {
+                    try!(fmt_separator(i, name, style, f));
+                    try!(f.write_str(try!(utf8(part))));
+                }
+                Ok(())
+            },
+            None => {
+                let typed = unsafe { self.typed.one() };
+                match typed.fmt_lines() {
+                    Some(ref lines) if lines.is_empty() => write!(f, "{}: ", name),
+                    Some(lines) => {
+                        let style = style.unwrap_or(MultiValue::Lines);
+                        for (i, line) in lines.iter().enumerate() {
+                            try!(fmt_separator(i, name, style, f));
+                            try!(f.write_str(line));
+                        }
+                        Ok(())
+                    },
+                    None => write!(f, "{}: {}", name, typed),
+                }
+            }
+        }
+    }
+}
+
+// How raw values are written without a style set for them.
+fn raw_style(name: &str) -> MultiValue {
+    if name.eq_ignore_ascii_case("Set-Cookie") {
+        MultiValue::Lines
+    } else {
+        MultiValue::Joined
+    }
+}
+
+// Writes what comes before the `i`th value of a field.
+fn fmt_separator(i: usize, name: &str, style: MultiValue, f: &mut fmt::Formatter) -> fmt::Result {
+    match (i, style) {
+        (0, _) => write!(f, "{}: ", name),
+        (_, MultiValue::Lines) => write!(f, "\r\n{}: ", name),
+        (_, MultiValue::Joined) => f.write_str(", "),
+    }
+}
+
+impl Item {
+    /// Writes the field as `fmt_lines` does, but with raw values written as
+    /// the bytes they are, whether or not they are UTF-8.
+    pub fn write_lines<W: Write>(&self, name: &str, style: Option<MultiValue>,
+                                 w: &mut W) -> io::Result<()> {
+        let raw = match *self.raw {
+            Some(ref raw) => raw,
+            None => return write!(w, "{}", Lines(self, name, style)),
+        };
+        let style = style.unwrap_or(raw_style(name));
+        if raw.is_empty() {
+            try!(w.write_all(name.as_bytes()));
+            return w.write_all(b": ");
+        }
+        for (i, part) in raw.iter().enumerate() {
+            if i == 0 || style == MultiValue::Lines {
+                if i != 0 {
+                    try!(w.write_all(b"\r\n"));
+                }
+                try!(w.write_all(name.as_bytes()));
+                try!(w.write_all(b": "));
+            } else {
+                try!(w.write_all(b", "));
+            }
+            try!(w.write_all(part));
+        }
+        Ok(())
+    }
+}
+
+// Formats a field with `Item::fmt_lines`.
+struct Lines<'a>(&'a Item, &'a str, Option<MultiValue>);
+
+impl<'a> fmt::Display for Lines<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt_lines(self.1, self.2, f)
+    }
+}
+
+fn utf8(part: &[u8]) -> Result<&str, fmt::Error> {
+    from_utf8(part).map_err(|e| {
+        error!("raw header value is not utf8. header={:?}, error={:?}", part, e);
+        fmt::Error
+    })
+}
+
 impl fmt::Display for Item {
+    /// Formats the value alone, with several values joined by commas.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self.raw {
             Some(ref raw) => {
-                for part in raw.iter() {
-                    match from_utf8(&part[..]) {
-                        Ok(s) => try!(f.write_str(s)),
-                        Err(e) => {
-                            error!("raw header value is not utf8. header={:?}, error={:?}",
-                                part, e);
-                            return Err(fmt::Error);
-                        }
+                for (i, part) in raw.iter().enumerate() {
+                    if i != 0 {
+                        try!(f.write_str(", "));
                     }
+                    try!(f.write_str(try!(utf8(part))));
                 }
                 Ok(())
             },
-            None => fmt::Display::fmt(&unsafe { self.typed.one() }, f)
+            None => {
+                let typed = unsafe { self.typed.one() };
+                match typed.fmt_lines() {
+                    Some(lines) => f.write_str(&lines.join(", ")),
+                    None => fmt::Display::fmt(&typed, f)
+                }
+            }
         }
     }
 }
@@ -1,4 +1,6 @@
 pub use self::item::Item;
+pub use self::vec_map::{VecMap, Entry, Iter};
 
 mod cell;
 mod item;
+mod vec_map;
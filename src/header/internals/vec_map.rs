@@ -0,0 +1,197 @@
+use std::{cmp, mem, slice};
+
+/// A map keeping its entries in the order they were inserted.
+///
+/// Lookups are linear, which is faster than hashing for the handful of
+/// fields a message has.
+#[derive(Clone)]
+pub struct VecMap<K, V> {
+    vec: Vec<(K, V)>,
+}
+
+impl<K: PartialEq, V> VecMap<K, V> {
+    #[inline]
+    pub fn new() -> VecMap<K, V> {
+        VecMap {
+            vec: Vec::new(),
+        }
+    }
+
+    /// Inserts a value, in place of any under an equal key, which keeps its
+    /// position and is itself kept.
+    #[inline]
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.position(&key) {
+            Some(pos) => Some(mem::replace(&mut self.vec[pos].1, value)),
+            None => {
+                self.vec.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Like `insert`, but replaces an equal key too.
+    #[inline]
+    pub fn replace(&mut self, key: K, value: V) -> Option<V> {
+        match self.position(&key) {
+            Some(pos) => Some(mem::replace(&mut self.vec[pos], (key, value)).1),
+            None => {
+                self.vec.push((key, value));
+                None
+            }
+        }
+    }
+
+    #[inline]
+    pub fn entry(&mut self, key: K) -> Entry<K, V> {
+        match self.position(&key) {
+            Some(pos) => Entry::Occupied(OccupiedEntry {
+                vec: &mut self.vec,
+                pos: pos,
+            }),
+            None => Entry::Vacant(VacantEntry {
+                vec: &mut self.vec,
+                key: key,
+            })
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.position(key).map(|pos| &self.vec[pos].1)
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        match self.position(key) {
+            Some(pos) => Some(&mut self.vec[pos].1),
+            None => None,
+        }
+    }
+
+    #[inline]
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.position(key).is_some()
+    }
+
+    #[inline]
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    #[inline]
+    pub fn remove_entry(&mut self, key: &K) -> Option<(K, V)> {
+        self.position(key).map(|pos| self.vec.remove(pos))
+    }
+
+    /// Moves the entry under `key` to `index`, shifting those in between.
+    ///
+    /// Returns whether there was such an entry.
+    pub fn move_to(&mut self, key: &K, index: usize) -> bool {
+        match self.position(key) {
+            Some(pos) => {
+                let entry = self.vec.remove(pos);
+                let index = cmp::min(index, self.vec.len());
+                self.vec.insert(index, entry);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Sorts the entries by key, keeping the order of equal ones.
+    pub fn sort_by_key<F>(&mut self, mut compare: F)
+    where F: FnMut(&K, &K) -> cmp::Ordering {
+        self.vec.sort_by(|a, b| compare(&a.0, &b.0));
+    }
+
+    #[inline]
+    pub fn iter(&self) -> Iter<K, V> {
+        Iter {
+            inner: self.vec.iter(),
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.vec.len()
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.vec.clear()
+    }
+
+    #[inline]
+    fn position(&self, key: &K) -> Option<usize> {
+        self.vec.iter().position(|entry| entry.0 == *key)
+    }
+}
+
+pub struct Iter<'a, K: 'a, V: 'a> {
+    inner: slice::Iter<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    #[inline]
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        self.inner.next().map(|&(ref k, ref v)| (k, v))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+pub enum Entry<'a, K: 'a, V: 'a> {
+    Vacant(VacantEntry<'a, K, V>),
+    Occupied(OccupiedEntry<'a, K, V>),
+}
+
+impl<'a, K, V> Entry<'a, K, V> {
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Vacant(entry) => entry.insert(default()),
+            Entry::Occupied(entry) => entry.into_mut(),
+        }
+    }
+}
+
+pub struct VacantEntry<'a, K: 'a, V: 'a> {
+    vec: &'a mut Vec<(K, V)>,
+    key: K,
+}
+
+impl<'a, K, V> VacantEntry<'a, K, V> {
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.vec.push((self.key, value));
+        let pos = self.vec.len() - 1;
+        &mut self.vec[pos].1
+    }
+}
+
+pub struct OccupiedEntry<'a, K: 'a, V: 'a> {
+    vec: &'a mut Vec<(K, V)>,
+    pos: usize,
+}
+
+impl<'a, K, V> OccupiedEntry<'a, K, V> {
+    pub fn get(&self) -> &V {
+        &self.vec[self.pos].1
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.vec[self.pos].1
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.vec[self.pos].1
+    }
+
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(&mut self.vec[self.pos].1, value)
+    }
+}
@@ -79,18 +79,20 @@
 //! }
 //! ```
 use std::any::Any;
+use std::cmp::Ordering;
 use std::borrow::{Cow, ToOwned};
 use std::collections::HashMap;
-use std::collections::hash_map::{Iter, Entry};
+use std::io::{self, Write};
 use std::iter::{FromIterator, IntoIterator};
+use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
-use std::{mem, fmt};
+use std::{mem, fmt, slice};
 
 use {httparse, traitobject};
 use typeable::Typeable;
 use unicase::UniCase;
 
-use self::internals::Item;
+use self::internals::{Item, VecMap, Entry, Iter};
 
 #[cfg(feature = "serde-serialization")]
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
@@ -139,6 +141,36 @@ pub trait HeaderFormat: fmt::Debug + HeaderClone + Any + Typeable + Send + Sync
     /// by the passed-in Formatter.
     fn fmt_header(&self, f: &mut fmt::Formatter) -> fmt::Result;
 
+    /// Format each of the header's values for a line of its own.
+    ///
+    /// A header whose values can't be joined onto one line with commas,
+    /// such as `Set-Cookie`, returns them here, and they're written as
+    /// separate header lines. The default, `None`, writes the value from
+    /// `fmt_header` as a single line.
+    fn fmt_lines(&self) -> Option<Vec<String>> {
+        None
+    }
+}
+
+/// How a header field with several values is written out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MultiValue {
+    /// One line, the values separated by commas, as RFC 7230 allows for
+    /// list fields.
+    Joined,
+    /// A line for each value, repeating the field name.
+    Lines,
+}
+
+/// What `Headers::set_raw` does with a name that isn't a token, or a value
+/// containing a CR, LF or NUL, since sending it could let the value smuggle
+/// in other headers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RawPolicy {
+    /// Panic, as for a bug in the program. This is the default.
+    Panic,
+    /// Leave the header unchanged, logging the error.
+    Ignore,
 }
 
 #[doc(hidden)]
@@ -178,9 +210,16 @@ fn header_name<T: Header>() -> &'static str {
 }
 
 /// A map of header fields on requests and responses.
+///
+/// Fields are kept, and iterated and written, in the order they were first
+/// inserted. Setting a field again keeps its place, while removing it and
+/// setting it again moves it to the end.
 #[derive(Clone)]
 pub struct Headers {
-    data: HashMap<HeaderName, Item>
+    data: VecMap<HeaderName, Item>,
+    multi_value: HashMap<HeaderName, MultiValue>,
+    preserve_case: bool,
+    raw_policy: RawPolicy,
 }
 
 impl Headers {
@@ -188,7 +227,68 @@ impl Headers {
     /// Creates a new, empty headers map.
     pub fn new() -> Headers {
         Headers {
-            data: HashMap::new()
+            data: VecMap::new(),
+            multi_value: HashMap::new(),
+            preserve_case: false,
+            raw_policy: RawPolicy::Panic,
+        }
+    }
+
+    /// Write each field name in the case it was last received or set with,
+    /// rather than the case it first had.
+    ///
+    /// Names are looked up ignoring case either way. By default, setting a
+    /// field that is already present keeps its name as it was, so a name
+    /// received as `x-trace-id` stays so when set again as `X-Trace-ID`.
+    /// With this mode, `set_raw` and `extend` give it the new case, while
+    /// `set` still keeps the case of a field received or set raw, rather
+    /// than the typed header's own. A `Client` sending these headers keeps
+    /// the mode for the request it writes.
+    ///
+    /// ```
+    /// # use hyper::header::{Headers, ContentLength};
+    /// let mut headers = Headers::new();
+    /// headers.set_preserve_case(true);
+    /// headers.set_raw("content-length", vec![b"3".to_vec()]);
+    /// headers.set(ContentLength(5));
+    /// headers.set_raw("x-trace-id", vec![b"a".to_vec()]);
+    /// headers.set_raw("X-Trace-ID", vec![b"b".to_vec()]);
+    /// assert_eq!(headers.get_raw("X-TRACE-ID"), Some(&[b"b".to_vec()][..]));
+    ///
+    /// let mut names: Vec<_> = headers.iter().map(|h| h.name()).collect();
+    /// names.sort();
+    /// assert_eq!(names, ["X-Trace-ID", "content-length"]);
+    /// ```
+    pub fn set_preserve_case(&mut self, preserve: bool) {
+        self.preserve_case = preserve;
+    }
+
+    /// Whether names keep the case they were last received or set with, as
+    /// chosen with `set_preserve_case`.
+    pub fn preserves_case(&self) -> bool {
+        self.preserve_case
+    }
+
+    /// Choose what `set_raw` does with an invalid name or value, which is
+    /// to panic by default. `try_set_raw` returns an error either way.
+    ///
+    /// ```
+    /// # use hyper::header::{Headers, RawPolicy};
+    /// let mut headers = Headers::new();
+    /// headers.set_raw_policy(RawPolicy::Ignore);
+    /// headers.set_raw("x-name", vec![b"a\r\nSet-Cookie: b".to_vec()]);
+    /// assert!(headers.get_raw("x-name").is_none());
+    /// ```
+    pub fn set_raw_policy(&mut self, policy: RawPolicy) {
+        self.raw_policy = policy;
+    }
+
+    // Inserts a field under `name`, taking its case if preserving case.
+    fn insert_named(&mut self, name: HeaderName, item: Item) {
+        if self.preserve_case {
+            self.data.replace(name, item);
+        } else {
+            self.data.insert(name, item);
         }
     }
 
@@ -222,6 +322,10 @@ impl Headers {
     ///
     /// Prefer to use the typed getters instead.
     ///
+    /// The lines of a received field are borrowed as they were read, with
+    /// no allocation or UTF-8 check. Only a field set as a typed value is
+    /// formatted, once, on the first access.
+    ///
     /// Example:
     ///
     /// ```
@@ -235,6 +339,50 @@ impl Headers {
             .map(Item::raw)
     }
 
+    /// Access each occurrence of a header's raw value separately.
+    ///
+    /// A field that was received on several lines yields each line, and a
+    /// typed value that formats as several lines, such as a `SetCookie` with
+    /// more than one cookie, yields each of those.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use hyper::header::Headers;
+    /// let mut headers = Headers::new();
+    /// headers.set_raw("Warning", vec![b"199 - \"one\"".to_vec(), b"199 - \"two\"".to_vec()]);
+    /// assert_eq!(headers.get_all_raw("warning").count(), 2);
+    /// ```
+    pub fn get_all_raw<'a>(&'a self, name: &str) -> RawValues<'a> {
+        let lines = self.get_raw(name).unwrap_or(&[]);
+        RawValues {
+            lines: lines.iter(),
+        }
+    }
+
+    /// Parse each occurrence of a header separately.
+    ///
+    /// Unlike `get`, which parses every occurrence together as one value,
+    /// this yields a value per occurrence, in the order they appear.
+    /// Occurrences that can't be parsed as `H` are skipped.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use hyper::header::{Headers, SetCookie};
+    /// let mut headers = Headers::new();
+    /// headers.set_raw("Set-Cookie", vec![b"foo=bar".to_vec(), b"baz=quux".to_vec()]);
+    /// let cookies: Vec<SetCookie> = headers.get_all().collect();
+    /// assert_eq!(cookies.len(), 2);
+    /// assert_eq!(cookies[1][0].name, "baz");
+    /// ```
+    pub fn get_all<'a, H: Header + HeaderFormat>(&'a self) -> GetAll<'a, H> {
+        GetAll {
+            raw: self.get_all_raw(header_name::<H>()),
+            _marker: PhantomData,
+        }
+    }
+
     /// Set the raw value of a header, bypassing any typed headers.
     ///
     /// Example:
@@ -244,10 +392,122 @@ impl Headers {
     /// # let mut headers = Headers::new();
     /// headers.set_raw("content-length", vec![b"5".to_vec()]);
     /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the name isn't a token, or a value contains a CR, LF or NUL, since
+    /// sending it could let the value smuggle in other headers, unless
+    /// `set_raw_policy` chose otherwise. Use `try_set_raw` for names or
+    /// values that come from untrusted input.
     pub fn set_raw<K: Into<Cow<'static, str>> + fmt::Debug>(&mut self, name: K,
             value: Vec<Vec<u8>>) {
-        trace!("Headers.set_raw( {:?}, {:?} )", name, value);
-        self.data.insert(UniCase(CowStr(name.into())), Item::new_raw(value));
+        let name = name.into();
+        if let Err(e) = self.try_set_raw(name.clone(), value) {
+            match self.raw_policy {
+                RawPolicy::Panic => panic!("Headers.set_raw( {:?} ): {}", name, e),
+                RawPolicy::Ignore => error!("Headers.set_raw( {:?} ): {}", name, e),
+            }
+        }
+    }
+
+    /// Set the raw value of a header, unless the name isn't a token or a
+    /// value contains a CR, LF or NUL.
+    ///
+    /// Fails with `Error::Header`, leaving the headers unchanged, if so.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use hyper::header::Headers;
+    /// let mut headers = Headers::new();
+    /// assert!(headers.try_set_raw("x-name", vec![b"a\r\nSet-Cookie: b".to_vec()]).is_err());
+    /// assert!(headers.try_set_raw("x-name", vec![b"a".to_vec()]).is_ok());
+    /// ```
+    pub fn try_set_raw<K: Into<Cow<'static, str>> + fmt::Debug>(&mut self, name: K,
+            value: Vec<Vec<u8>>) -> ::Result<()> {
+        trace!("Headers.try_set_raw( {:?}, {:?} )", name, value);
+        let name = name.into();
+        if name.is_empty() || !name.bytes().all(parsing::is_tchar) {
+            return Err(::Error::Header);
+        }
+        if value.iter().any(|line| line.iter().any(|&b| b == b'\r' || b == b'\n' || b == 0)) {
+            return Err(::Error::Header);
+        }
+        self.insert_named(UniCase(CowStr(name)), Item::new_raw(value));
+        Ok(())
+    }
+
+    /// Remove a field, returning its raw lines.
+    ///
+    /// The lines of a received field are moved out rather than copied.
+    ///
+    /// ```
+    /// # use hyper::header::Headers;
+    /// let mut headers = Headers::new();
+    /// headers.set_raw("X-Request-Id", vec![b"abc".to_vec()]);
+    /// assert_eq!(headers.take_raw("x-request-id"), Some(vec![b"abc".to_vec()]));
+    /// assert!(headers.get_raw("x-request-id").is_none());
+    /// ```
+    pub fn take_raw(&mut self, name: &str) -> Option<Vec<Vec<u8>>> {
+        trace!("Headers.take_raw( {:?} )", name);
+        self.data.remove(
+            &UniCase(CowStr(Cow::Borrowed(unsafe { mem::transmute::<&str, &str>(name) })))
+        ).map(Item::into_raw)
+    }
+
+    /// Move a field into `to`, as a proxy forwards it, replacing any field
+    /// of the same name there.
+    ///
+    /// The name and values are moved as they are, so a received field is
+    /// sent on in the case and bytes it arrived in, UTF-8 or not, without
+    /// being copied, parsed or formatted again.
+    ///
+    /// Returns whether there was such a field.
+    ///
+    /// ```
+    /// # use hyper::header::Headers;
+    /// let mut incoming = Headers::new();
+    /// incoming.set_raw("x-trace", vec![b"a;b".to_vec()]);
+    ///
+    /// let mut outgoing = Headers::new();
+    /// assert!(incoming.forward("X-Trace", &mut outgoing));
+    /// assert_eq!(outgoing.to_string(), "x-trace: a;b\r\n");
+    /// assert!(!incoming.forward("X-Trace", &mut outgoing));
+    /// ```
+    pub fn forward(&mut self, name: &str, to: &mut Headers) -> bool {
+        let key = UniCase(CowStr(Cow::Borrowed(unsafe { mem::transmute::<&str, &str>(name) })));
+        match self.data.remove_entry(&key) {
+            Some((name, item)) => {
+                match self.multi_value.remove(&name) {
+                    Some(style) => to.multi_value.insert(name.clone(), style),
+                    None => to.multi_value.remove(&name),
+                };
+                to.data.replace(name, item);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Write the fields as they are sent, each line ending with a CRLF.
+    ///
+    /// Unlike formatting them with `Display`, this writes raw values as the
+    /// bytes they are, whether or not they are UTF-8.
+    ///
+    /// ```
+    /// # use hyper::header::Headers;
+    /// let mut headers = Headers::new();
+    /// headers.set_raw("X-Name", vec![b"caf\xe9".to_vec()]);
+    /// let mut buf = Vec::new();
+    /// headers.write_to(&mut buf).unwrap();
+    /// assert_eq!(buf, b"X-Name: caf\xe9\r\n");
+    /// ```
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        for header in self.iter() {
+            try!(header.1.write_lines(header.name(), header.2, w));
+            try!(w.write_all(b"\r\n"));
+        }
+        Ok(())
     }
 
     /// Remove a header set by set_raw
@@ -258,6 +518,28 @@ impl Headers {
         );
     }
 
+    /// Choose how a field with several values is written out, overriding
+    /// the header's own choice.
+    ///
+    /// By default, a typed header that formats separate lines, such as
+    /// `SetCookie`, is written a line per value, as is a raw `Set-Cookie`,
+    /// and every other field has its values joined with commas.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use hyper::header::{Headers, MultiValue};
+    /// let mut headers = Headers::new();
+    /// headers.set_raw("Warning", vec![b"199 - \"one\"".to_vec(), b"199 - \"two\"".to_vec()]);
+    /// assert_eq!(headers.to_string(), "Warning: 199 - \"one\", 199 - \"two\"\r\n");
+    ///
+    /// headers.set_multi_value("Warning", MultiValue::Lines);
+    /// assert_eq!(headers.to_string(), "Warning: 199 - \"one\"\r\nWarning: 199 - \"two\"\r\n");
+    /// ```
+    pub fn set_multi_value<K: Into<Cow<'static, str>>>(&mut self, name: K, style: MultiValue) {
+        self.multi_value.insert(UniCase(CowStr(name.into())), style);
+    }
+
     /// Get a reference to the header field's value, if it exists.
     pub fn get<H: Header + HeaderFormat>(&self) -> Option<&H> {
         self.data.get(&UniCase(CowStr(Cow::Borrowed(header_name::<H>()))))
@@ -285,16 +567,89 @@ impl Headers {
     }
 
     /// Removes a header from the map, if one existed.
-    /// Returns true if a header has been removed.
-    pub fn remove<H: Header + HeaderFormat>(&mut self) -> bool {
+    ///
+    /// Returns the removed value, or `None` if there was no such header or
+    /// its raw value couldn't be parsed. The field is removed either way.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use hyper::header::{Headers, ContentLength};
+    /// let mut headers = Headers::new();
+    /// headers.set(ContentLength(5));
+    /// assert_eq!(headers.remove::<ContentLength>(), Some(ContentLength(5)));
+    /// assert!(!headers.has::<ContentLength>());
+    /// ```
+    pub fn remove<H: Header + HeaderFormat>(&mut self) -> Option<H> {
         trace!("Headers.remove( {:?} )", header_name::<H>());
-        self.data.remove(&UniCase(CowStr(Cow::Borrowed(header_name::<H>())))).is_some()
+        self.data.remove(&UniCase(CowStr(Cow::Borrowed(header_name::<H>()))))
+            .and_then(|item| item.typed::<H>().cloned())
     }
 
-    /// Returns an iterator over the header fields.
+    /// Get a mutable reference to the header field's value, setting it to
+    /// the result of `default` first if it is missing.
+    ///
+    /// A present value that can't be parsed as `H` is replaced as well.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use hyper::header::{Headers, ContentLength};
+    /// let mut headers = Headers::new();
+    /// headers.get_or_insert_with(|| ContentLength(0)).0 += 5;
+    /// assert_eq!(headers.get(), Some(&ContentLength(5)));
+    /// ```
+    pub fn get_or_insert_with<H, F>(&mut self, default: F) -> &mut H
+    where H: Header + HeaderFormat, F: FnOnce() -> H {
+        let item = self.data.entry(UniCase(CowStr(Cow::Borrowed(header_name::<H>()))))
+            .or_insert_with(|| Item::new_raw(vec![]));
+        if item.typed_mut::<H>().is_none() {
+            *item = Item::new_typed(Box::new(default()));
+        }
+        item.typed_mut::<H>().expect("typed value was just inserted")
+    }
+
+    /// Move a field to `index`, or to the end if `index` is past it,
+    /// shifting the fields in between.
+    ///
+    /// Returns whether there was such a field.
+    ///
+    /// ```
+    /// # use hyper::header::{Headers, ContentLength, Host};
+    /// let mut headers = Headers::new();
+    /// headers.set(ContentLength(0));
+    /// headers.set(Host { hostname: "example.com".to_owned(), port: None });
+    /// assert!(headers.move_to("host", 0));
+    /// assert_eq!(headers.to_string(), "Host: example.com\r\nContent-Length: 0\r\n");
+    /// ```
+    pub fn move_to(&mut self, name: &str, index: usize) -> bool {
+        self.data.move_to(
+            &UniCase(CowStr(Cow::Borrowed(unsafe { mem::transmute::<&str, &str>(name) }))),
+            index
+        )
+    }
+
+    /// Sort the fields by name, keeping the order of fields that compare
+    /// equal.
+    ///
+    /// ```
+    /// # use hyper::header::Headers;
+    /// let mut headers = Headers::new();
+    /// headers.set_raw("X-Amz-Date", vec![b"20150830T123600Z".to_vec()]);
+    /// headers.set_raw("Host", vec![b"example.amazonaws.com".to_vec()]);
+    /// headers.sort_by(|a, b| a.to_lowercase().cmp(&b.to_lowercase()));
+    /// assert_eq!(headers.iter().map(|h| h.name()).collect::<Vec<_>>(), ["Host", "X-Amz-Date"]);
+    /// ```
+    pub fn sort_by<F>(&mut self, mut compare: F) where F: FnMut(&str, &str) -> Ordering {
+        self.data.sort_by_key(|a, b| compare(a, b));
+    }
+
+    /// Returns an iterator over the header fields, in the order they were
+    /// inserted.
     pub fn iter<'a>(&'a self) -> HeadersItems<'a> {
         HeadersItems {
-            inner: self.data.iter()
+            inner: self.data.iter(),
+            multi_value: &self.multi_value,
         }
     }
 
@@ -307,6 +662,124 @@ impl Headers {
     pub fn clear(&mut self) {
         self.data.clear()
     }
+
+    /// Merge every header field of `other` into this map.
+    ///
+    /// Fields that may appear more than once in a message, such as
+    /// `Set-Cookie`, `Via`, or the comma-separated lists like `Accept`, have
+    /// `other`'s values appended after any already present. List values are
+    /// combined onto one line, while each `Set-Cookie` is kept separate.
+    /// Every other field is replaced by its value in `other`.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use hyper::header::{Headers, ContentLength};
+    /// let mut headers = Headers::new();
+    /// headers.set_raw("Via", vec![b"1.1 first".to_vec()]);
+    /// headers.set(ContentLength(5));
+    ///
+    /// let mut other = Headers::new();
+    /// other.set_raw("Via", vec![b"1.1 second".to_vec()]);
+    /// other.set(ContentLength(10));
+    ///
+    /// headers.extend_from(&other);
+    /// assert_eq!(headers.get_raw("Via"), Some(&[b"1.1 first, 1.1 second".to_vec()][..]));
+    /// assert_eq!(headers.get(), Some(&ContentLength(10)));
+    /// ```
+    pub fn extend_from(&mut self, other: &Headers) {
+        for (name, item) in other.data.iter() {
+            match self.data.entry(name.clone()) {
+                Entry::Occupied(mut entry) => if is_repeatable(name) {
+                    let values = item.raw();
+                    // make sure a typed-only value has been formatted first
+                    entry.get().raw();
+                    let raw = entry.get_mut().mut_raw();
+                    if *name == UniCase(CowStr(Cow::Borrowed("Set-Cookie"))) {
+                        raw.extend(values.iter().cloned());
+                    } else {
+                        // a list field is parsed from a single line, so
+                        // combine the values as RFC 7230 section 3.2.2 allows
+                        let mut line = raw.concat();
+                        for value in values {
+                            if !line.is_empty() {
+                                line.extend_from_slice(b", ");
+                            }
+                            line.extend_from_slice(value);
+                        }
+                        *raw = vec![line];
+                    }
+                } else {
+                    entry.insert(item.clone());
+                },
+                Entry::Vacant(entry) => {
+                    entry.insert(item.clone());
+                }
+            }
+        }
+    }
+
+    /// Compare this map against `other`, field by field.
+    ///
+    /// Values are compared in their raw form, so a typed value and a raw
+    /// value that format the same are considered equal.
+    pub fn diff<'a>(&'a self, other: &'a Headers) -> HeadersDiff<'a> {
+        let mut diff = HeadersDiff {
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+        };
+        for (name, item) in self.data.iter() {
+            match other.data.get(name) {
+                Some(theirs) => if item.raw() != theirs.raw() {
+                    diff.changed.push((self.view(name, item), other.view(name, theirs)));
+                },
+                None => diff.removed.push(self.view(name, item)),
+            }
+        }
+        for (name, item) in other.data.iter() {
+            if !self.data.contains_key(name) {
+                diff.added.push(other.view(name, item));
+            }
+        }
+        diff
+    }
+
+    fn view<'a>(&'a self, name: &'a HeaderName, item: &'a Item) -> HeaderView<'a> {
+        HeaderView(name, item, self.multi_value.get(name).cloned())
+    }
+}
+
+/// Whether a field may legitimately be sent more than once, so its values
+/// should be combined rather than replaced.
+fn is_repeatable(name: &HeaderName) -> bool {
+    const REPEATABLE: &'static [&'static str] = &[
+        "Accept", "Accept-Charset", "Accept-Encoding", "Accept-Language",
+        "Accept-Ranges", "Allow", "Cache-Control", "Connection",
+        "Content-Encoding", "Content-Language", "If-Match", "If-None-Match",
+        "Pragma", "Set-Cookie", "Transfer-Encoding", "Upgrade", "Vary", "Via",
+        "Warning",
+    ];
+    REPEATABLE.iter().any(|r| UniCase(CowStr(Cow::Borrowed(*r))) == *name)
+}
+
+/// The differences between two `Headers` maps, returned by `Headers::diff`.
+#[derive(Debug)]
+pub struct HeadersDiff<'a> {
+    /// Fields present only in the other map.
+    pub added: Vec<HeaderView<'a>>,
+    /// Fields present only in this map.
+    pub removed: Vec<HeaderView<'a>>,
+    /// Fields present in both maps with different values, as
+    /// `(ours, theirs)`.
+    pub changed: Vec<(HeaderView<'a>, HeaderView<'a>)>,
+}
+
+impl<'a> HeadersDiff<'a> {
+    /// Returns true if the two maps had the same fields and values.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
 }
 
 impl fmt::Display for Headers {
@@ -375,34 +848,70 @@ impl Deserialize for Headers {
                 let mut result = Headers::new();
                 while let Some((key, value)) = try!(visitor.visit()) {
                     let (key, value): (String, String) = (key, value);
-                    result.set_raw(key, vec![value.into_bytes()]);
+                    if result.try_set_raw(key, vec![value.into_bytes()]).is_err() {
+                        return Err(de::Error::syntax_error());
+                    }
                 }
                 try!(visitor.end());
                 Ok(result)
             }
         }
 
-        let result = Headers::new();
-        try!(deserializer.visit_map(HeadersVisitor));
-        Ok(result)
+        deserializer.visit_map(HeadersVisitor)
     }
 }
 
 /// An `Iterator` over the fields in a `Headers` map.
 pub struct HeadersItems<'a> {
-    inner: Iter<'a, HeaderName, Item>
+    inner: Iter<'a, HeaderName, Item>,
+    multi_value: &'a HashMap<HeaderName, MultiValue>,
 }
 
 impl<'a> Iterator for HeadersItems<'a> {
     type Item = HeaderView<'a>;
 
     fn next(&mut self) -> Option<HeaderView<'a>> {
-        self.inner.next().map(|(k, v)| HeaderView(k, v))
+        let multi_value = self.multi_value;
+        self.inner.next().map(|(k, v)| HeaderView(k, v, multi_value.get(k).cloned()))
+    }
+}
+
+/// An `Iterator` over each raw occurrence of a header, returned by
+/// `Headers::get_all_raw`.
+pub struct RawValues<'a> {
+    lines: slice::Iter<'a, Vec<u8>>,
+}
+
+impl<'a> Iterator for RawValues<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<&'a [u8]> {
+        self.lines.next().map(|line| &line[..])
+    }
+}
+
+/// An `Iterator` over each occurrence of a header, parsed separately,
+/// returned by `Headers::get_all`.
+pub struct GetAll<'a, H> {
+    raw: RawValues<'a>,
+    _marker: PhantomData<H>,
+}
+
+impl<'a, H: Header> Iterator for GetAll<'a, H> {
+    type Item = H;
+
+    fn next(&mut self) -> Option<H> {
+        for raw in &mut self.raw {
+            if let Ok(value) = H::parse_header(&[raw.to_vec()]) {
+                return Some(value);
+            }
+        }
+        None
     }
 }
 
 /// Returned with the `HeadersItems` iterator.
-pub struct HeaderView<'a>(&'a HeaderName, &'a Item);
+pub struct HeaderView<'a>(&'a HeaderName, &'a Item, Option<MultiValue>);
 
 impl<'a> HeaderView<'a> {
     /// Check if a HeaderView is a certain Header.
@@ -431,8 +940,10 @@ impl<'a> HeaderView<'a> {
 }
 
 impl<'a> fmt::Display for HeaderView<'a> {
+    /// Formats the field as it's sent, which may be several lines
+    /// separated by CRLF, as chosen with `Headers::set_multi_value`.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}: {}", self.0, *self.1)
+        self.1.fmt_lines(self.0, self.2, f)
     }
 }
 
@@ -445,7 +956,10 @@ impl<'a> fmt::Debug for HeaderView<'a> {
 impl<'a> Extend<HeaderView<'a>> for Headers {
     fn extend<I: IntoIterator<Item=HeaderView<'a>>>(&mut self, iter: I) {
         for header in iter {
-            self.data.insert((*header.0).clone(), (*header.1).clone());
+            if let Some(style) = header.2 {
+                self.multi_value.insert((*header.0).clone(), style);
+            }
+            self.insert_named((*header.0).clone(), (*header.1).clone());
         }
     }
 }
@@ -486,6 +1000,20 @@ impl<'a, H: HeaderFormat> fmt::Debug for HeaderFormatter<'a, H> {
     }
 }
 
+/// Formats a header and parses it back, checking that nothing was lost.
+#[cfg(all(test, feature = "quickcheck"))]
+fn round_trips<H: Header + HeaderFormat + PartialEq + fmt::Debug>(header: H) -> bool {
+    let raw = HeaderFormatter(&header).to_string().into_bytes();
+    match H::parse_header(&[raw]) {
+        Ok(ref parsed) if *parsed == header => true,
+        other => {
+            println!("{:?} formats as {:?}, parses as {:?}",
+                     header, HeaderFormatter(&header).to_string(), other.ok());
+            false
+        }
+    }
+}
+
 #[derive(Clone, Hash, Eq, PartialEq, PartialOrd, Ord)]
 struct CowStr(Cow<'static, str>);
 
@@ -681,6 +1209,105 @@ mod tests {
         assert_eq!(headers.get(), Some(&ContentLength(20)));
     }
 
+    #[test]
+    fn test_insertion_order() {
+        let mut headers = Headers::from_raw(&raw!(b"Via: 1.1 a", b"Host: foo", b"via: 1.1 b"))
+            .unwrap();
+        headers.set(ContentLength(10));
+        headers.set_raw("host", vec![b"bar".to_vec()]);
+        assert_eq!(headers.to_string(),
+                   "Via: 1.1 a, 1.1 b\r\nHost: bar\r\nContent-Length: 10\r\n");
+
+        headers.remove_raw("Via");
+        headers.set_raw("Via", vec![b"1.1 c".to_vec()]);
+        assert!(!headers.move_to("Accept", 0));
+        assert!(headers.move_to("content-length", 0));
+        assert_eq!(headers.iter().map(|h| h.name()).collect::<Vec<_>>(),
+                   ["Content-Length", "Host", "Via"]);
+        assert!(headers.move_to("Content-Length", 10));
+        assert_eq!(headers.iter().map(|h| h.name()).collect::<Vec<_>>(),
+                   ["Host", "Via", "Content-Length"]);
+    }
+
+    #[test]
+    fn test_take_and_forward() {
+        let mut headers = Headers::from_raw(&raw!(b"content-length: 10", b"Via: 1.1 a")).unwrap();
+        let line_ptr = headers.get_raw("Via").unwrap()[0].as_ptr();
+        let mut to = Headers::new();
+        to.set(ContentLength(20));
+        assert!(headers.forward("Content-Length", &mut to));
+        assert!(headers.forward("via", &mut to));
+        assert_eq!(headers.len(), 0);
+        assert_eq!(to.to_string(), "content-length: 10\r\nVia: 1.1 a\r\n");
+        assert_eq!(to.get(), Some(&ContentLength(10)));
+
+        let lines = to.take_raw("VIA").unwrap();
+        assert_eq!(lines[0].as_ptr(), line_ptr);
+        assert_eq!(to.take_raw("Content-Length"), Some(vec![b"10".to_vec()]));
+        assert_eq!(to.take_raw("Content-Length"), None);
+
+        to.set(ContentLength(5));
+        assert_eq!(to.take_raw("Content-Length"), Some(vec![b"5".to_vec()]));
+    }
+
+    #[test]
+    fn test_preserve_case() {
+        let mut headers = Headers::from_raw(&raw!(b"content-length: 10")).unwrap();
+        headers.set(ContentLength(20));
+        headers.set_raw("X-FOO", vec![b"1".to_vec()]);
+        headers.set_raw("x-foo", vec![b"2".to_vec()]);
+        let s = headers.to_string();
+        assert!(s.contains("content-length: 20\r\n"), "{:?}", s);
+        assert!(s.contains("X-FOO: 2\r\n"), "{:?}", s);
+
+        headers.set_preserve_case(true);
+        headers.set_raw("x-foo", vec![b"3".to_vec()]);
+        headers.set(ContentLength(30));
+        let mut other = Headers::new();
+        other.set_raw("Content-LENGTH", vec![b"40".to_vec()]);
+        headers.extend(other.iter());
+        let s = headers.to_string();
+        assert!(s.contains("x-foo: 3\r\n"), "{:?}", s);
+        assert!(s.contains("Content-LENGTH: 40\r\n"), "{:?}", s);
+        assert_eq!(headers.get(), Some(&ContentLength(40)));
+    }
+
+    #[test]
+    fn test_try_set_raw_rejects_injection() {
+        let mut headers = Headers::new();
+        assert!(headers.try_set_raw("X-Foo", vec![b"a\r\nSet-Cookie: b=c".to_vec()]).is_err());
+        assert!(headers.try_set_raw("X-Foo", vec![b"a\nb".to_vec()]).is_err());
+        assert!(headers.try_set_raw("X-Foo", vec![b"a\0b".to_vec()]).is_err());
+        assert!(headers.try_set_raw("X-Foo: a\r\nX-Bar", vec![b"b".to_vec()]).is_err());
+        assert!(headers.try_set_raw("X Foo", vec![b"b".to_vec()]).is_err());
+        assert!(headers.try_set_raw("", vec![b"b".to_vec()]).is_err());
+        assert_eq!(headers.len(), 0);
+
+        assert!(headers.try_set_raw("X-Foo", vec![b"a\tb \xff".to_vec()]).is_ok());
+        assert_eq!(headers.get_raw("x-foo").unwrap(), &[b"a\tb \xff".to_vec()][..]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_set_raw_panics_on_injection() {
+        let mut headers = Headers::new();
+        headers.set_raw("X-Foo", vec![b"a\r\nSet-Cookie: b=c".to_vec()]);
+    }
+
+    #[test]
+    fn test_set_raw_policy_ignore() {
+        use super::RawPolicy;
+
+        let mut headers = Headers::new();
+        headers.set_raw("X-Foo", vec![b"a".to_vec()]);
+        headers.set_raw_policy(RawPolicy::Ignore);
+        headers.set_raw("X-Foo", vec![b"a\r\nSet-Cookie: b=c".to_vec()]);
+        headers.set_raw("X Bar", vec![b"b".to_vec()]);
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers.get_raw("x-foo").unwrap(), &[b"a".to_vec()][..]);
+        assert!(headers.try_set_raw("X-Foo", vec![b"a\nb".to_vec()]).is_err());
+    }
+
     #[test]
     fn test_remove_raw() {
         let mut headers = Headers::new();
@@ -723,6 +1350,132 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_get_all() {
+        let mut headers = Headers::new();
+        assert_eq!(headers.get_all_raw("Content-Length").next(), None);
+
+        headers.set_raw("Content-Length", vec![b"10".to_vec(), b"x".to_vec(), b"20".to_vec()]);
+        assert_eq!(headers.get_all_raw("content-length").collect::<Vec<_>>(),
+                   vec![&b"10"[..], &b"x"[..], &b"20"[..]]);
+        // a single `get` can't make sense of conflicting lengths
+        assert_eq!(headers.get::<ContentLength>(), None);
+        assert_eq!(headers.get_all::<ContentLength>().collect::<Vec<_>>(),
+                   vec![ContentLength(10), ContentLength(20)]);
+    }
+
+    #[test]
+    fn test_get_all_typed_multi_line() {
+        use cookie::Cookie as CookiePair;
+        use super::SetCookie;
+
+        let mut headers = Headers::new();
+        headers.set(SetCookie(vec![CookiePair::new("foo".to_owned(), "bar".to_owned()),
+                                   CookiePair::new("baz".to_owned(), "quux".to_owned())]));
+        assert_eq!(headers.get_all_raw("set-cookie").collect::<Vec<_>>(),
+                   vec![&b"foo=bar; Path=/"[..], &b"baz=quux; Path=/"[..]]);
+        assert_eq!(headers.get_all::<SetCookie>().count(), 2);
+    }
+
+    #[test]
+    fn test_multi_value_lines() {
+        use cookie::Cookie as CookiePair;
+        use super::{MultiValue, SetCookie};
+
+        let mut headers = Headers::from_raw(&raw!(b"Set-Cookie: a=1", b"Set-Cookie: b=2")).unwrap();
+        assert_eq!(headers.to_string(), "Set-Cookie: a=1\r\nSet-Cookie: b=2\r\n");
+
+        let mut vary = Headers::from_raw(&raw!(b"Vary: Accept", b"Vary: Cookie")).unwrap();
+        assert_eq!(vary.to_string(), "Vary: Accept, Cookie\r\n");
+        vary.set_multi_value("vary", MultiValue::Lines);
+        assert_eq!(vary.to_string(), "Vary: Accept\r\nVary: Cookie\r\n");
+
+        headers.set(SetCookie(vec![CookiePair::new("a".to_owned(), "1".to_owned()),
+                                   CookiePair::new("b".to_owned(), "2".to_owned())]));
+        assert_eq!(headers.to_string(), "Set-Cookie: a=1; Path=/\r\nSet-Cookie: b=2; Path=/\r\n");
+        headers.set_multi_value("Set-Cookie", MultiValue::Joined);
+        assert_eq!(headers.to_string(), "Set-Cookie: a=1; Path=/, b=2; Path=/\r\n");
+
+        let mut empty = Headers::new();
+        empty.set_raw("X-Empty", vec![]);
+        assert_eq!(empty.to_string(), "X-Empty: \r\n");
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut headers = Headers::new();
+        headers.set_raw("Content-Length", vec![b"10".to_vec()]);
+        assert_eq!(headers.remove::<ContentLength>(), Some(ContentLength(10)));
+        assert_eq!(headers.remove::<ContentLength>(), None);
+
+        headers.set_raw("Content-Length", vec![b"ten".to_vec()]);
+        assert_eq!(headers.remove::<ContentLength>(), None);
+        assert_eq!(headers.len(), 0);
+    }
+
+    #[test]
+    fn test_get_or_insert_with() {
+        let mut headers = Headers::new();
+        headers.set_raw("Content-Length", vec![b"10".to_vec()]);
+        assert_eq!(*headers.get_or_insert_with(|| ContentLength(0)), ContentLength(10));
+
+        headers.set_raw("Content-Length", vec![b"ten".to_vec()]);
+        assert_eq!(*headers.get_or_insert_with(|| ContentLength(0)), ContentLength(0));
+
+        headers.clear();
+        headers.get_or_insert_with(|| ContentLength(3)).0 += 1;
+        assert_eq!(headers.get_raw("content-length"), Some(&[b"4".to_vec()][..]));
+    }
+
+    #[test]
+    fn test_extend_from() {
+        let mut headers = Headers::new();
+        headers.set(ContentLength(10));
+        headers.set(Accept(vec![qitem(Mime(Text, Plain, vec![]))]));
+
+        let mut other = Headers::new();
+        other.set(ContentLength(20));
+        other.set_raw("Accept", vec![b"text/html".to_vec()]);
+        other.set(Host { hostname: "foo.bar".to_owned(), port: None });
+
+        headers.extend_from(&other);
+        assert_eq!(headers.len(), 3);
+        assert_eq!(headers.get(), Some(&ContentLength(20)));
+        assert_eq!(headers.get_raw("accept"), Some(&[b"text/plain, text/html".to_vec()][..]));
+        assert_eq!(headers.get::<Accept>().map(|a| a.len()), Some(2));
+        assert!(headers.has::<Host>());
+
+        let mut cookies = Headers::new();
+        cookies.set_raw("Set-Cookie", vec![b"foo=bar".to_vec()]);
+        cookies.extend_from(&cookies.clone());
+        assert_eq!(cookies.get_raw("set-cookie").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut headers = Headers::new();
+        headers.set(ContentLength(10));
+        headers.set_raw("X-Same", vec![b"same".to_vec()]);
+        headers.set_raw("X-Gone", vec![b"gone".to_vec()]);
+
+        let mut other = Headers::new();
+        other.set_raw("Content-Length", vec![b"11".to_vec()]);
+        other.set_raw("x-same", vec![b"same".to_vec()]);
+        other.set_raw("X-New", vec![b"new".to_vec()]);
+
+        let diff = headers.diff(&other);
+        assert!(!diff.is_empty());
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].name(), "X-New");
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].name(), "X-Gone");
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].0.value(), Some(&ContentLength(10)));
+        assert_eq!(diff.changed[0].1.value(), Some(&ContentLength(11)));
+
+        assert!(headers.diff(&headers.clone()).is_empty());
+    }
+
     #[cfg(feature = "nightly")]
     #[bench]
     fn bench_headers_new(b: &mut Bencher) {
@@ -3,6 +3,11 @@
 use std::str;
 use std::fmt::{self, Display};
 
+use url::percent_encoding;
+
+use header::shared::{Charset, Quality, QualityItem};
+use LanguageTag;
+
 /// Reads a single raw string when parsing a header
 pub fn from_one_raw_str<T: str::FromStr>(raw: &[Vec<u8>]) -> ::Result<T> {
     if raw.len() != 1 || unsafe { raw.get_unchecked(0) } == b"" { return Err(::Error::Header) }
@@ -26,9 +31,11 @@ pub fn from_comma_delimited<T: str::FromStr>(raw: &[Vec<u8>]) -> ::Result<Vec<T>
 }
 
 /// Reads a comma-delimited raw string into a Vec.
+///
+/// Commas within a `quoted-string` don't delimit elements.
 pub fn from_one_comma_delimited<T: str::FromStr>(raw: &[u8]) -> ::Result<Vec<T>> {
     let s = try!(str::from_utf8(raw));
-    Ok(s.split(',')
+    Ok(split_comma_delimited(s)
         .filter_map(|x| match x.trim() {
             "" => None,
             y => Some(y)
@@ -37,6 +44,62 @@ pub fn from_one_comma_delimited<T: str::FromStr>(raw: &[u8]) -> ::Result<Vec<T>>
         .collect())
 }
 
+/// Splits a string on the commas that aren't within a `quoted-string`.
+///
+/// The elements are returned as they are, still quoted and untrimmed.
+///
+/// ```
+/// use hyper::header::parsing::split_comma_delimited;
+///
+/// let elements: Vec<&str> = split_comma_delimited("no-cache=\"a, b\", private").collect();
+/// assert_eq!(elements, vec!["no-cache=\"a, b\"", " private"]);
+/// ```
+pub fn split_comma_delimited(s: &str) -> SplitCommaDelimited {
+    split_delimited(s, b',')
+}
+
+/// Splits a string on the `delim` bytes that aren't within a
+/// `quoted-string`, such as the semicolons between parameters.
+#[doc(hidden)]
+pub fn split_delimited(s: &str, delim: u8) -> SplitCommaDelimited {
+    SplitCommaDelimited { rest: Some(s), delim: delim }
+}
+
+/// An `Iterator` over the elements of a comma-delimited string, returned
+/// by `split_comma_delimited`.
+#[derive(Clone, Debug)]
+pub struct SplitCommaDelimited<'a> {
+    rest: Option<&'a str>,
+    delim: u8,
+}
+
+impl<'a> Iterator for SplitCommaDelimited<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let s = match self.rest {
+            Some(s) => s,
+            None => return None,
+        };
+        let mut quoted = false;
+        let mut escaped = false;
+        for (i, b) in s.bytes().enumerate() {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' if quoted => escaped = true,
+                b'"' => quoted = !quoted,
+                _ if b == self.delim && !quoted => {
+                    self.rest = Some(&s[i + 1..]);
+                    return Some(&s[..i]);
+                },
+                _ => (),
+            }
+        }
+        self.rest = None;
+        Some(s)
+    }
+}
+
 /// Format an array into a comma-delimited string.
 pub fn fmt_comma_delimited<T: Display>(f: &mut fmt::Formatter, parts: &[T]) -> fmt::Result {
     for (i, part) in parts.iter().enumerate() {
@@ -47,3 +110,376 @@ pub fn fmt_comma_delimited<T: Display>(f: &mut fmt::Formatter, parts: &[T]) -> f
     }
     Ok(())
 }
+
+/// Parses a `qvalue`, as defined in
+/// [RFC7231](https://tools.ietf.org/html/rfc7231#section-5.3.1): a number
+/// from 0 to 1, with at most three decimals.
+///
+/// ```
+/// use hyper::header::Quality;
+/// use hyper::header::parsing::parse_quality;
+///
+/// assert_eq!(parse_quality("0.5").unwrap(), Quality(500));
+/// assert_eq!(parse_quality("1.000").unwrap(), Quality(1000));
+/// assert!(parse_quality("1.5").is_err());
+/// assert!(parse_quality("0.2501").is_err());
+/// ```
+pub fn parse_quality(s: &str) -> ::Result<Quality> {
+    let (whole, decimals) = match s.find('.') {
+        Some(i) => (&s[..i], &s[i + 1..]),
+        None => (s, ""),
+    };
+    if decimals.len() > 3 || !decimals.bytes().all(|b| b >= b'0' && b <= b'9') {
+        return Err(::Error::Header);
+    }
+    let thousandths = decimals.bytes().chain("000".bytes()).take(3)
+        .fold(0, |n, b| n * 10 + (b - b'0') as u16);
+    match whole {
+        "0" => Ok(Quality(thousandths)),
+        "1" if thousandths == 0 => Ok(Quality(1000)),
+        _ => Err(::Error::Header),
+    }
+}
+
+/// The items with a non-zero quality, highest first, keeping the header's
+/// order between equal qualities.
+pub fn sort_by_quality<T>(items: &[QualityItem<T>]) -> Vec<&QualityItem<T>> {
+    let mut items: Vec<_> = items.iter().filter(|item| item.quality > Quality(0)).collect();
+    items.sort_by(|a, b| b.quality.cmp(&a.quality));
+    items
+}
+
+/// The quality that the `items` of an `Accept`-like header give `option`,
+/// or `None` if none of them matches it.
+///
+/// `specificity` says whether an item matches the option, and if so, how
+/// specifically: a wildcard, such as `*` or `text/*`, should be less
+/// specific than an exact match. The quality of the most specific match
+/// wins, and of the first one on a tie.
+pub fn match_quality<T, A, F>(items: &[QualityItem<T>], option: &A, specificity: F) -> Option<Quality>
+where F: Fn(&T, &A) -> Option<usize> {
+    let mut best: Option<(usize, Quality)> = None;
+    for item in items {
+        if let Some(specificity) = specificity(&item.item, option) {
+            if best.map_or(true, |(most, _)| specificity > most) {
+                best = Some((specificity, item.quality));
+            }
+        }
+    }
+    best.map(|(_, quality)| quality)
+}
+
+/// Picks the option with the highest non-zero quality, preferring earlier
+/// options on a tie.
+///
+/// ```
+/// use hyper::header::{AcceptCharset, Charset, Quality, QualityItem, qitem};
+/// use hyper::header::parsing::{match_quality, negotiate};
+///
+/// let accept = AcceptCharset(vec![
+///     QualityItem::new(Charset::Us_Ascii, Quality(500)),
+///     QualityItem::new(Charset::Ext("*".to_owned()), Quality(100)),
+///     QualityItem::new(Charset::Iso_8859_1, Quality(0)),
+/// ]);
+/// let available = [Charset::Iso_8859_1, Charset::Ext("UTF-8".to_owned()), Charset::Us_Ascii];
+/// let chosen = negotiate(&available, |charset| {
+///     match_quality(&accept, charset, |item, charset| match *item {
+///         Charset::Ext(ref star) if star == "*" => Some(0),
+///         ref item if item == charset => Some(1),
+///         _ => None,
+///     }).unwrap_or(Quality(0))
+/// });
+/// assert_eq!(chosen, Some(&Charset::Us_Ascii));
+/// ```
+pub fn negotiate<A, F>(available: &[A], quality: F) -> Option<&A> where F: Fn(&A) -> Quality {
+    let mut best: Option<(&A, Quality)> = None;
+    for option in available {
+        let q = quality(option);
+        if q > Quality(0) && best.map_or(true, |(_, best)| q > best) {
+            best = Some((option, q));
+        }
+    }
+    best.map(|(option, _)| option)
+}
+
+/// Checks whether a value must be sent as a `quoted-string`, because it
+/// isn't a valid `token`, as defined in
+/// [RFC7230](https://tools.ietf.org/html/rfc7230#section-3.2.6).
+pub fn needs_quoting(s: &str) -> bool {
+    s.is_empty() || !s.bytes().all(is_tchar)
+}
+
+/// Writes a value as a `quoted-string`, escaping any `"` and `\\` it
+/// contains.
+///
+/// Control characters other than tab can't be sent within a header at all,
+/// even quoted, so they are left out.
+pub fn write_quoted(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    try!(f.write_str("\""));
+    for c in s.chars() {
+        match c {
+            '"' | '\\' => try!(write!(f, "\\{}", c)),
+            '\t' => try!(f.write_str("\t")),
+            c if c < ' ' || c == '\x7f' => (),
+            c => try!(write!(f, "{}", c)),
+        }
+    }
+    f.write_str("\"")
+}
+
+/// Writes a value as a `token` if it is one, or else as a `quoted-string`.
+pub fn write_token_or_quoted(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    if needs_quoting(s) {
+        write_quoted(f, s)
+    } else {
+        f.write_str(s)
+    }
+}
+
+/// Parses a value that is either a `token` or a `quoted-string`, undoing
+/// any escaping in the latter.
+///
+/// ```
+/// use hyper::header::parsing::parse_token_or_quoted;
+///
+/// assert_eq!(parse_token_or_quoted("gzip").unwrap(), "gzip");
+/// assert_eq!(parse_token_or_quoted("\"a \\\"b\\\"\"").unwrap(), "a \"b\"");
+/// assert!(parse_token_or_quoted("not a token").is_err());
+/// ```
+pub fn parse_token_or_quoted(s: &str) -> ::Result<String> {
+    let s = s.trim();
+    if !s.starts_with('"') {
+        return if needs_quoting(s) { Err(::Error::Header) } else { Ok(s.to_owned()) };
+    }
+
+    let mut value = String::with_capacity(s.len());
+    let mut chars = s[1..].chars();
+    loop {
+        match chars.next() {
+            Some('"') => break,
+            Some('\\') => match chars.next() {
+                Some(c) if c == '\t' || (c >= ' ' && c != '\x7f') => value.push(c),
+                _ => return Err(::Error::Header),
+            },
+            Some(c) if c == '\t' || (c >= ' ' && c != '\x7f') => value.push(c),
+            _ => return Err(::Error::Header),
+        }
+    }
+    // nothing may follow the closing quote
+    match chars.next() {
+        None => Ok(value),
+        Some(_) => Err(::Error::Header),
+    }
+}
+
+#[doc(hidden)]
+pub fn is_tchar(b: u8) -> bool {
+    match b {
+        b'a'...b'z' | b'A'...b'Z' | b'0'...b'9' | b'!' | b'#' | b'$' | b'%' | b'&' |
+        b'\'' | b'*' | b'+' | b'-' | b'.' | b'^' | b'_' | b'`' | b'|' | b'~' => true,
+        _ => false,
+    }
+}
+
+/// An extended header parameter value: one tagged with a charset and,
+/// optionally, a language, as defined in
+/// [RFC8187](https://tools.ietf.org/html/rfc8187#section-3.2).
+///
+/// These are sent as the values of parameters whose name ends in `*`, such
+/// as the `filename*` of a `Content-Disposition`, and let them hold text
+/// that isn't ASCII.
+///
+/// ```
+/// use hyper::header::Charset;
+/// use hyper::header::parsing::{parse_extended_value, ExtendedValue};
+///
+/// let value = parse_extended_value("UTF-8'en'%C2%A3%20rates").unwrap();
+/// assert_eq!(value.charset, Charset::Ext("UTF-8".to_owned()));
+/// assert_eq!(value.value, "\u{a3} rates".as_bytes());
+/// assert_eq!(value.to_string(), "UTF-8'en'%C2%A3%20rates");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExtendedValue {
+    /// The charset the value is encoded in.
+    pub charset: Charset,
+    /// The language of the value, if given.
+    pub language_tag: Option<LanguageTag>,
+    /// The value itself, with any percent-encoding decoded.
+    pub value: Vec<u8>,
+}
+
+impl ExtendedValue {
+    /// Creates an extended value holding `value` in UTF-8.
+    pub fn utf8(value: &str) -> ExtendedValue {
+        ExtendedValue {
+            charset: Charset::Ext("UTF-8".to_owned()),
+            language_tag: None,
+            value: value.as_bytes().to_vec(),
+        }
+    }
+}
+
+/// Parses an extended parameter value, of the form
+/// `charset'[language]'value-chars`.
+pub fn parse_extended_value(val: &str) -> ::Result<ExtendedValue> {
+    let mut parts = val.splitn(3, '\'');
+
+    let charset = match parts.next() {
+        Some("") | None => return Err(::Error::Header),
+        Some(charset) => try!(charset.parse()),
+    };
+    let language_tag = match parts.next() {
+        Some("") => None,
+        Some(tag) => Some(try!(tag.parse().map_err(|_| ::Error::Header))),
+        None => return Err(::Error::Header),
+    };
+    let value = match parts.next() {
+        Some(value) => try!(percent_decode(value)),
+        None => return Err(::Error::Header),
+    };
+
+    Ok(ExtendedValue {
+        charset: charset,
+        language_tag: language_tag,
+        value: value,
+    })
+}
+
+impl Display for ExtendedValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let encoded = percent_encoding::percent_encode(&self.value,
+                                                       percent_encoding::HTTP_VALUE_ENCODE_SET);
+        match self.language_tag {
+            Some(ref tag) => write!(f, "{}'{}'{}", self.charset, tag, encoded),
+            None => write!(f, "{}''{}", self.charset, encoded),
+        }
+    }
+}
+
+// Only `attr-char`s and well formed `pct-encoded` octets are allowed.
+fn percent_decode(value: &str) -> ::Result<Vec<u8>> {
+    let bytes = value.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let valid = bytes.len() > i + 2 &&
+                    (bytes[i + 1] as char).is_digit(16) && (bytes[i + 2] as char).is_digit(16);
+                if !valid {
+                    return Err(::Error::Header);
+                }
+                i += 3;
+            },
+            b'a'...b'z' | b'A'...b'Z' | b'0'...b'9' | b'!' | b'#' | b'$' | b'&' | b'+' |
+            b'-' | b'.' | b'^' | b'_' | b'`' | b'|' | b'~' => i += 1,
+            _ => return Err(::Error::Header),
+        }
+    }
+    Ok(percent_encoding::percent_decode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt;
+
+    use header::shared::Charset;
+    use super::{parse_extended_value, ExtendedValue, needs_quoting, write_token_or_quoted,
+                parse_token_or_quoted, split_comma_delimited, from_one_comma_delimited};
+
+    struct TokenOrQuoted<'a>(&'a str);
+
+    impl<'a> fmt::Display for TokenOrQuoted<'a> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write_token_or_quoted(f, self.0)
+        }
+    }
+
+    #[test]
+    fn test_split_comma_delimited() {
+        let split = |s| split_comma_delimited(s).collect::<Vec<_>>();
+        assert_eq!(split("a, b,c"), vec!["a", " b", "c"]);
+        assert_eq!(split(""), vec![""]);
+        assert_eq!(split("a,"), vec!["a", ""]);
+        assert_eq!(split("\"a,b\", \"c\""), vec!["\"a,b\"", " \"c\""]);
+        assert_eq!(split("x=\"a\\\",b\", y"), vec!["x=\"a\\\",b\"", " y"]);
+        assert_eq!(split("\"unterminated, still"), vec!["\"unterminated, still"]);
+    }
+
+    #[test]
+    fn test_from_one_comma_delimited_quoted() {
+        use header::EntityTag;
+        let tags: Vec<EntityTag> = from_one_comma_delimited(b"\"a,b\", W/\"c\"").unwrap();
+        assert_eq!(tags, vec![EntityTag::strong("a,b".to_owned()),
+                              EntityTag::weak("c".to_owned())]);
+    }
+
+    #[test]
+    fn test_needs_quoting() {
+        assert!(!needs_quoting("max-age"));
+        assert!(!needs_quoting("!#$%&'*+-.^_`|~09azAZ"));
+        assert!(needs_quoting(""));
+        assert!(needs_quoting("two words"));
+        assert!(needs_quoting("a,b"));
+        assert!(needs_quoting("key=value"));
+        assert!(needs_quoting("\"quoted\""));
+    }
+
+    #[test]
+    fn test_write_token_or_quoted() {
+        assert_eq!(TokenOrQuoted("token").to_string(), "token");
+        assert_eq!(TokenOrQuoted("").to_string(), "\"\"");
+        assert_eq!(TokenOrQuoted("a \"b\" \\c").to_string(), "\"a \\\"b\\\" \\\\c\"");
+        assert_eq!(TokenOrQuoted("new\r\nline\ttab").to_string(), "\"newline\ttab\"");
+    }
+
+    #[test]
+    fn test_parse_token_or_quoted() {
+        assert_eq!(parse_token_or_quoted(" token ").unwrap(), "token");
+        assert_eq!(parse_token_or_quoted("\"\"").unwrap(), "");
+        assert_eq!(parse_token_or_quoted("\"a, b\"").unwrap(), "a, b");
+        assert_eq!(parse_token_or_quoted("\"a \\\"b\\\" \\\\c\"").unwrap(), "a \"b\" \\c");
+        assert!(parse_token_or_quoted("").is_err());
+        assert!(parse_token_or_quoted("a b").is_err());
+        assert!(parse_token_or_quoted("\"unterminated").is_err());
+        assert!(parse_token_or_quoted("\"trailing\"junk").is_err());
+        assert!(parse_token_or_quoted("\"bad\\").is_err());
+        assert!(parse_token_or_quoted("\"ctl\x01\"").is_err());
+
+        for value in &["", "plain", "with space", "\"q\"", "back\\slash"] {
+            let formatted = TokenOrQuoted(value).to_string();
+            assert_eq!(parse_token_or_quoted(&formatted).unwrap(), *value);
+        }
+    }
+
+    #[test]
+    fn test_parse_extended_value_with_encoding_and_language_tag() {
+        let result = parse_extended_value("iso-8859-1'en'%A3%20rates").unwrap();
+        assert_eq!(result.charset, Charset::Iso_8859_1);
+        assert_eq!(result.language_tag, Some(langtag!(en)));
+        assert_eq!(result.value, vec![163, b' ', b'r', b'a', b't', b'e', b's']);
+    }
+
+    #[test]
+    fn test_parse_extended_value_with_encoding() {
+        let result = parse_extended_value("UTF-8''%c2%a3%20and%20%e2%82%ac%20rates").unwrap();
+        assert_eq!(result, ExtendedValue::utf8("\u{a3} and \u{20ac} rates"));
+    }
+
+    #[test]
+    fn test_parse_extended_value_invalid() {
+        assert!(parse_extended_value("UTF-8'missing third part").is_err());
+        assert!(parse_extended_value("''%20").is_err());
+        assert!(parse_extended_value("UTF-8''no spaces").is_err());
+        assert!(parse_extended_value("UTF-8''%2").is_err());
+        assert!(parse_extended_value("UTF-8''%zz").is_err());
+    }
+
+    #[test]
+    fn test_fmt_extended_value() {
+        let mut value = ExtendedValue::utf8("\u{a3} rates");
+        assert_eq!(value.to_string(), "UTF-8''%C2%A3%20rates");
+        value.language_tag = Some(langtag!(en));
+        assert_eq!(value.to_string(), "UTF-8'en'%C2%A3%20rates");
+        assert_eq!(parse_extended_value(&value.to_string()).unwrap(), value);
+    }
+}
@@ -1,7 +1,7 @@
 use std::fmt;
 use std::str;
 
-pub use self::Encoding::{Chunked, Gzip, Deflate, Compress, Identity, EncodingExt};
+pub use self::Encoding::{Chunked, Brotli, Gzip, Deflate, Compress, Identity, Trailers, EncodingExt};
 
 /// A value to represent an encoding used in `Transfer-Encoding`
 /// or `Accept-Encoding` header.
@@ -9,6 +9,8 @@ pub use self::Encoding::{Chunked, Gzip, Deflate, Compress, Identity, EncodingExt
 pub enum Encoding {
     /// The `chunked` encoding.
     Chunked,
+    /// The `br` encoding, Brotli.
+    Brotli,
     /// The `gzip` encoding.
     Gzip,
     /// The `deflate` encoding.
@@ -17,6 +19,9 @@ pub enum Encoding {
     Compress,
     /// The `identity` encoding.
     Identity,
+    /// `trailers`, which isn't an encoding, but is sent in a `TE` header
+    /// to accept trailer fields after a chunked body.
+    Trailers,
     /// Some other encoding that is less common, can be any String.
     EncodingExt(String)
 }
@@ -25,10 +30,12 @@ impl fmt::Display for Encoding {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.write_str(match *self {
             Chunked => "chunked",
+            Brotli => "br",
             Gzip => "gzip",
             Deflate => "deflate",
             Compress => "compress",
             Identity => "identity",
+            Trailers => "trailers",
             EncodingExt(ref s) => s.as_ref()
         })
     }
@@ -39,10 +46,12 @@ impl str::FromStr for Encoding {
     fn from_str(s: &str) -> ::Result<Encoding> {
         match s {
             "chunked" => Ok(Chunked),
+            "br" => Ok(Brotli),
             "deflate" => Ok(Deflate),
             "gzip" => Ok(Gzip),
             "compress" => Ok(Compress),
             "identity" => Ok(Identity),
+            "trailers" => Ok(Trailers),
             _ => Ok(EncodingExt(s.to_owned()))
         }
     }
@@ -1,5 +1,8 @@
+use std::fs::Metadata;
+use std::io::{self, Write};
 use std::str::FromStr;
 use std::fmt::{self, Display};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 // check that each char in the slice is either:
 // 1. %x21, or
@@ -70,6 +73,23 @@ impl EntityTag {
         EntityTag::new(false, tag)
     }
 
+    /// Constructs a strong EntityTag from a file's size and modification
+    /// time, in the same `"mtime-size"` hexadecimal format as nginx.
+    ///
+    /// The modification time has a resolution of one second, so a file
+    /// changed twice within a second without changing size keeps its tag.
+    ///
+    /// ```no_run
+    /// use std::fs;
+    /// use hyper::header::EntityTag;
+    ///
+    /// let meta = fs::metadata("index.html").unwrap();
+    /// let etag = EntityTag::from_metadata(&meta);
+    /// ```
+    pub fn from_metadata(meta: &Metadata) -> EntityTag {
+        from_len_and_modified(meta.len(), meta.modified().ok())
+    }
+
     /// Get the tag.
     pub fn tag(&self) -> &str {
         self.tag.as_ref()
@@ -105,6 +125,81 @@ impl EntityTag {
     pub fn weak_ne(&self, other: &EntityTag) -> bool {
         !self.weak_eq(other)
     }
+
+    /// Whether any of `tags` is equivalent to this one under strong
+    /// comparison, as for `If-Match`.
+    pub fn strong_eq_any(&self, tags: &[EntityTag]) -> bool {
+        tags.iter().any(|tag| self.strong_eq(tag))
+    }
+
+    /// Whether any of `tags` is equivalent to this one under weak
+    /// comparison, as for `If-None-Match`.
+    pub fn weak_eq_any(&self, tags: &[EntityTag]) -> bool {
+        tags.iter().any(|tag| self.weak_eq(tag))
+    }
+}
+
+fn from_len_and_modified(len: u64, modified: Option<SystemTime>) -> EntityTag {
+    let mtime = modified.and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs());
+    EntityTag::strong(format!("{:x}-{:x}", mtime, len))
+}
+
+/// Derives a strong EntityTag from the bytes of a representation, as they
+/// are written to it.
+///
+/// The bytes are hashed with 64-bit FNV-1a, which is stable across
+/// platforms and releases, so servers sharing content generate the same
+/// tags. It is not a cryptographic hash: don't rely on it where a client
+/// could gain from producing a collision.
+///
+/// ```
+/// use std::io::Write;
+/// use hyper::header::EntityTagHasher;
+///
+/// let mut hasher = EntityTagHasher::new();
+/// hasher.write_all(b"hello ").unwrap();
+/// hasher.write_all(b"world").unwrap();
+/// assert_eq!(hasher.finish().to_string(), "\"779a65e7023cd2e7\"");
+/// ```
+#[derive(Clone, Debug)]
+pub struct EntityTagHasher {
+    state: u64,
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+impl EntityTagHasher {
+    /// Creates a hasher that has seen no bytes yet.
+    pub fn new() -> EntityTagHasher {
+        EntityTagHasher { state: FNV_OFFSET_BASIS }
+    }
+
+    /// Get the EntityTag for the bytes written so far.
+    pub fn finish(&self) -> EntityTag {
+        EntityTag::strong(format!("{:016x}", self.state))
+    }
+}
+
+impl Default for EntityTagHasher {
+    fn default() -> EntityTagHasher {
+        EntityTagHasher::new()
+    }
+}
+
+impl Write for EntityTagHasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &byte in buf {
+            self.state ^= byte as u64;
+            self.state = self.state.wrapping_mul(FNV_PRIME);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
 }
 
 impl Display for EntityTag {
@@ -126,11 +221,11 @@ impl FromStr for EntityTag {
             return Err(::Error::Header);
         }
         // The etag is weak if its first char is not a DQUOTE.
-        if slice.starts_with('"') && check_slice_validity(&slice[1..length-1]) {
+        if length >= 2 && slice.starts_with('"') && check_slice_validity(&slice[1..length-1]) {
             // No need to check if the last char is a DQUOTE,
             // we already did that above.
             return Ok(EntityTag { weak: false, tag: slice[1..length-1].to_owned() });
-        } else if slice.starts_with("W/\"") && check_slice_validity(&slice[3..length-1]) {
+        } else if length >= 4 && slice.starts_with("W/\"") && check_slice_validity(&slice[3..length-1]) {
             return Ok(EntityTag { weak: true, tag: slice[3..length-1].to_owned() });
         }
         Err(::Error::Header)
@@ -139,7 +234,9 @@ impl FromStr for EntityTag {
 
 #[cfg(test)]
 mod tests {
-    use super::EntityTag;
+    use std::io::Write;
+    use std::time::{Duration, UNIX_EPOCH};
+    use super::{EntityTag, EntityTagHasher, from_len_and_modified};
 
     #[test]
     fn test_etag_parse_success() {
@@ -164,6 +261,8 @@ mod tests {
         assert!("\"unmatched-dquotes1".parse::<EntityTag>().is_err());
         assert!("unmatched-dquotes2\"".parse::<EntityTag>().is_err());
         assert!("matched-\"dquotes\"".parse::<EntityTag>().is_err());
+        assert!("\"".parse::<EntityTag>().is_err());
+        assert!("W/\"".parse::<EntityTag>().is_err());
     }
 
     #[test]
@@ -211,4 +310,38 @@ mod tests {
         assert!(!etag1.strong_ne(&etag2));
         assert!(!etag1.weak_ne(&etag2));
     }
+
+    #[test]
+    fn test_cmp_any() {
+        let tags = vec![EntityTag::weak("1".to_owned()), EntityTag::strong("2".to_owned())];
+        assert!(!EntityTag::strong("1".to_owned()).strong_eq_any(&tags));
+        assert!(EntityTag::strong("1".to_owned()).weak_eq_any(&tags));
+        assert!(EntityTag::strong("2".to_owned()).strong_eq_any(&tags));
+        assert!(!EntityTag::weak("2".to_owned()).strong_eq_any(&tags));
+        assert!(EntityTag::weak("2".to_owned()).weak_eq_any(&tags));
+        assert!(!EntityTag::strong("3".to_owned()).weak_eq_any(&tags));
+        assert!(!EntityTag::strong("1".to_owned()).weak_eq_any(&[]));
+    }
+
+    #[test]
+    fn test_from_len_and_modified() {
+        let modified = UNIX_EPOCH + Duration::from_secs(0x5d8c72a5);
+        assert_eq!(from_len_and_modified(0x264, Some(modified)),
+                   EntityTag::strong("5d8c72a5-264".to_owned()));
+        assert_eq!(from_len_and_modified(10, None), EntityTag::strong("0-a".to_owned()));
+    }
+
+    #[test]
+    fn test_hasher() {
+        assert_eq!(EntityTagHasher::new().finish(),
+                   EntityTag::strong("cbf29ce484222325".to_owned()));
+
+        let mut whole = EntityTagHasher::new();
+        whole.write_all(b"foobar").unwrap();
+        let mut parts = EntityTagHasher::new();
+        parts.write_all(b"foo").unwrap();
+        parts.write_all(b"bar").unwrap();
+        assert_eq!(whole.finish(), parts.finish());
+        assert_eq!(whole.finish(), EntityTag::strong("85944171f73967e8".to_owned()));
+    }
 }
@@ -1,7 +1,13 @@
+use std::cmp::Ordering;
 use std::str::FromStr;
 use std::fmt::{self, Display};
+use std::ops::{Add, Sub};
 
-use time;
+use time::{self, Duration};
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, TimeZone, Utc};
+#[cfg(feature = "quickcheck")]
+use quickcheck::{Arbitrary, Gen};
 
 /// A `time::Time` with HTTP formatting and parsing
 ///
@@ -27,32 +33,143 @@ use time;
 //   header field that contains one or more timestamps defined as
 //   HTTP-date, the sender MUST generate those timestamps in the
 //   IMF-fixdate format.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug)]
 pub struct HttpDate(pub time::Tm);
 
+// Compare the instants, since a parsed `Tm` won't have every field filled in.
+impl PartialEq for HttpDate {
+    fn eq(&self, other: &HttpDate) -> bool {
+        self.0.to_timespec() == other.0.to_timespec()
+    }
+}
+
+impl Eq for HttpDate {}
+
+impl PartialOrd for HttpDate {
+    fn partial_cmp(&self, other: &HttpDate) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HttpDate {
+    fn cmp(&self, other: &HttpDate) -> Ordering {
+        self.0.to_timespec().cmp(&other.0.to_timespec())
+    }
+}
+
+// The formats of an HTTP-date, the preferred one first.
+const IMF_FIXDATE: &'static str = "%a, %d %b %Y %T GMT";
+const RFC_850: &'static str = "%A, %d-%b-%y %T GMT";
+const ASCTIME: &'static str = "%a %b %e %T %Y";
+
+// `strptime` lets through some strings that don't match `format`, such as
+// ones in other time zones or with more text after the date, so the date is
+// formatted again to check it.
+fn parse_exact(s: &str, format: &str) -> Option<time::Tm> {
+    let tm = match time::strptime(s, format) {
+        Ok(tm) => tm,
+        Err(_) => return None,
+    };
+    match time::strftime(format, &tm) {
+        Ok(ref formatted) if formatted == s => Some(tm),
+        _ => None,
+    }
+}
+
+// A two digit year is in the century that puts it at most 50 years from
+// now, as RFC 7231 requires.
+fn full_year(tm_year: i32) -> i32 {
+    let now = time::now_utc().tm_year;
+    let year = now - now % 100 + tm_year % 100;
+    if year > now + 50 { year - 100 } else { year }
+}
+
 impl FromStr for HttpDate {
     type Err = ::Error;
     fn from_str(s: &str) -> ::Result<HttpDate> {
-        match time::strptime(s, "%a, %d %b %Y %T %Z").or_else(|_| {
-            time::strptime(s, "%A, %d-%b-%y %T %Z")
-            }).or_else(|_| {
-                time::strptime(s, "%c")
-                }) {
-                    Ok(t) => Ok(HttpDate(t)),
-                    Err(_) => Err(::Error::Header),
-                    }
+        let tm = if let Some(tm) = parse_exact(s, IMF_FIXDATE) {
+            tm
+        } else if let Some(mut tm) = parse_exact(s, RFC_850) {
+            tm.tm_year = full_year(tm.tm_year);
+            tm
+        } else if let Some(tm) = parse_exact(s, ASCTIME) {
+            tm
+        } else {
+            return Err(::Error::Header);
+        };
+        // fill in the day of the week and of the year from the instant
+        Ok(HttpDate(time::at_utc(tm.to_timespec())))
     }
 }
 
 impl Display for HttpDate {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Display::fmt(&self.0.to_utc().rfc822(), f)
+        let tm = time::at_utc(self.0.to_timespec());
+        fmt::Display::fmt(&tm.strftime(IMF_FIXDATE).unwrap(), f)
+    }
+}
+
+impl From<time::Tm> for HttpDate {
+    fn from(tm: time::Tm) -> HttpDate {
+        HttpDate(tm)
+    }
+}
+
+impl From<HttpDate> for time::Tm {
+    fn from(date: HttpDate) -> time::Tm {
+        date.0
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<DateTime<Utc>> for HttpDate {
+    fn from(date: DateTime<Utc>) -> HttpDate {
+        let spec = time::Timespec::new(date.timestamp(), date.timestamp_subsec_nanos() as i32);
+        HttpDate(time::at_utc(spec))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<HttpDate> for DateTime<Utc> {
+    fn from(date: HttpDate) -> DateTime<Utc> {
+        let spec = date.0.to_timespec();
+        Utc.timestamp_opt(spec.sec, spec.nsec as u32).unwrap()
+    }
+}
+
+#[cfg(feature = "quickcheck")]
+impl Arbitrary for HttpDate {
+    fn arbitrary(g: &mut Gen) -> HttpDate {
+        // any second from 1970 up to the end of the year 9999
+        let secs = u64::arbitrary(g) % 253402300800;
+        HttpDate(time::at_utc(time::Timespec::new(secs as i64, 0)))
+    }
+}
+
+impl Add<Duration> for HttpDate {
+    type Output = HttpDate;
+    fn add(self, rhs: Duration) -> HttpDate {
+        HttpDate(self.0 + rhs)
+    }
+}
+
+impl Sub<Duration> for HttpDate {
+    type Output = HttpDate;
+    fn sub(self, rhs: Duration) -> HttpDate {
+        HttpDate(self.0 - rhs)
+    }
+}
+
+impl Sub<HttpDate> for HttpDate {
+    type Output = Duration;
+    fn sub(self, rhs: HttpDate) -> Duration {
+        self.0 - rhs.0
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use time::Tm;
+    use time::{Duration, Tm};
     use super::HttpDate;
 
     const NOV_07: HttpDate = HttpDate(Tm {
@@ -84,8 +201,68 @@ mod tests {
         assert_eq!("Sun Nov  7 08:48:37 1994".parse::<HttpDate>().unwrap(), NOV_07);
     }
 
+    #[test]
+    fn test_rfc_850_year() {
+        let date = "Wednesday, 09-Jun-21 10:18:14 GMT".parse::<HttpDate>().unwrap();
+        assert_eq!(date.to_string(), "Wed, 09 Jun 2021 10:18:14 GMT");
+    }
+
     #[test]
     fn test_no_date() {
         assert!("this-is-no-date".parse::<HttpDate>().is_err());
+        assert!("Sun, 06 Nov 1994 08:49:37".parse::<HttpDate>().is_err());
+        assert!("Sun, 06 Nov 1994 08:49:37 +0100".parse::<HttpDate>().is_err());
+        assert!("Sun, 06 Nov 1994 08:49:37 GMT and more".parse::<HttpDate>().is_err());
+        assert!("Sunday, 06-Nov-1994 08:49:37 GMT".parse::<HttpDate>().is_err());
+    }
+
+    #[test]
+    fn test_serialize() {
+        // always as an IMF-fixdate, with the right day of the week
+        for s in &["Sun, 06 Nov 1994 08:49:37 GMT", "Sunday, 06-Nov-94 08:49:37 GMT",
+                   "Sun Nov  6 08:49:37 1994", "Mon, 06 Nov 1994 08:49:37 GMT"] {
+            assert_eq!(s.parse::<HttpDate>().unwrap().to_string(), "Sun, 06 Nov 1994 08:49:37 GMT");
+        }
+        assert_eq!(NOV_07.to_string(), "Mon, 07 Nov 1994 08:48:37 GMT");
+    }
+
+    #[test]
+    fn test_tm_conversions() {
+        let tm: Tm = NOV_07.into();
+        assert_eq!(HttpDate::from(tm), NOV_07);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let later = NOV_07 + Duration::hours(1);
+        assert_eq!(later.to_string(), "Mon, 07 Nov 1994 09:48:37 GMT");
+        assert_eq!(later - NOV_07, Duration::hours(1));
+        assert_eq!((later - Duration::hours(1)) - NOV_07, Duration::zero());
+    }
+
+    #[test]
+    fn test_ordering() {
+        let later = NOV_07 + Duration::seconds(1);
+        assert!(later > NOV_07);
+        assert!(NOV_07 + Duration::minutes(1) > later);
+    }
+
+    #[cfg(feature = "quickcheck")]
+    #[test]
+    fn test_round_trip() {
+        fn prop(date: HttpDate) -> bool {
+            ::header::round_trips(::header::Date(date))
+        }
+        ::quickcheck::quickcheck(prop as fn(HttpDate) -> bool);
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono_conversions() {
+        use chrono::{DateTime, TimeZone, Utc};
+        let date = Utc.with_ymd_and_hms(1994, 11, 7, 8, 48, 37).unwrap();
+        let http_date = HttpDate::from(date);
+        assert_eq!(http_date.to_string(), "Mon, 07 Nov 1994 08:48:37 GMT");
+        assert_eq!(DateTime::<Utc>::from(http_date), date);
     }
 }
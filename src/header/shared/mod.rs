@@ -1,6 +1,6 @@
 pub use self::charset::Charset;
 pub use self::encoding::Encoding;
-pub use self::entity::EntityTag;
+pub use self::entity::{EntityTag, EntityTagHasher};
 pub use self::httpdate::HttpDate;
 pub use self::quality_item::{Quality, QualityItem, qitem, q};
 
@@ -3,6 +3,11 @@ use std::default::Default;
 use std::fmt;
 use std::str;
 
+use header::parsing;
+
+#[cfg(feature = "quickcheck")]
+use quickcheck::{Arbitrary, Gen};
+
 /// Represents a quality used in quality values.
 ///
 /// Can be created with the `q` function.
@@ -75,32 +80,15 @@ impl<T: str::FromStr> str::FromStr for QualityItem<T> {
     fn from_str(s: &str) -> ::Result<QualityItem<T>> {
         // Set defaults used if parsing fails.
         let mut raw_item = s;
-        let mut quality = 1f32;
+        let mut quality = Quality(1000);
 
         let parts: Vec<&str> = s.rsplitn(2, ';').map(|x| x.trim()).collect();
-        if parts.len() == 2 {
-            let start = &parts[0][0..2];
-            if start == "q=" || start == "Q=" {
-                let q_part = &parts[0][2..parts[0].len()];
-                if q_part.len() > 5 {
-                    return Err(::Error::Header);
-                }
-                match q_part.parse::<f32>() {
-                    Ok(q_value) => {
-                        if 0f32 <= q_value && q_value <= 1f32 {
-                            quality = q_value;
-                            raw_item = parts[1];
-                            } else {
-                                return Err(::Error::Header);
-                            }
-                        },
-                    Err(_) => return Err(::Error::Header),
-                }
-            }
+        if parts.len() == 2 && (parts[0].starts_with("q=") || parts[0].starts_with("Q=")) {
+            quality = try!(parsing::parse_quality(&parts[0][2..]));
+            raw_item = parts[1];
         }
         match raw_item.parse::<T>() {
-            // we already checked above that the quality is within range
-            Ok(item) => Ok(QualityItem::new(item, from_f32(quality))),
+            Ok(item) => Ok(QualityItem::new(item, quality)),
             Err(_) => return Err(::Error::Header),
         }
     }
@@ -111,7 +99,21 @@ fn from_f32(f: f32) -> Quality {
     // should be done before calling this method. Just in case, this
     // debug_assert should catch if we were forgetful
     debug_assert!(f >= 0f32 && f <= 1f32, "q value must be between 0.0 and 1.0");
-    Quality((f * 1000f32) as u16)
+    Quality((f * 1000f32).round() as u16)
+}
+
+#[cfg(feature = "quickcheck")]
+impl Arbitrary for Quality {
+    fn arbitrary(g: &mut Gen) -> Quality {
+        Quality(u16::arbitrary(g) % 1001)
+    }
+}
+
+#[cfg(feature = "quickcheck")]
+impl<T: Arbitrary> Arbitrary for QualityItem<T> {
+    fn arbitrary(g: &mut Gen) -> QualityItem<T> {
+        QualityItem::new(T::arbitrary(g), Quality::arbitrary(g))
+    }
 }
 
 /// Convinience function to wrap a value in a `QualityItem`
@@ -172,6 +174,11 @@ mod tests {
         assert_eq!(x.unwrap(), QualityItem{ item: Gzip, quality: Quality(273), });
     }
     #[test]
+    fn test_quality_item_from_str_rounding() {
+        let x: ::Result<QualityItem<Encoding>> = "gzip; q=0.251".parse();
+        assert_eq!(x.unwrap(), QualityItem{ item: Gzip, quality: Quality(251), });
+    }
+    #[test]
     fn test_quality_item_from_str5() {
         let x: ::Result<QualityItem<Encoding>> = "gzip; q=0.2739999".parse();
         assert!(x.is_err());
@@ -182,6 +189,18 @@ mod tests {
         assert!(x.is_err());
     }
     #[test]
+    fn test_quality_item_from_str7() {
+        for s in &["gzip; q=1e-1", "gzip; q=+0.5", "gzip; q=1.001", "gzip; q="] {
+            let x: ::Result<QualityItem<Encoding>> = s.parse();
+            assert!(x.is_err(), "{:?} should not parse", s);
+        }
+        // a parameter too short to be a q-value
+        let x: ::Result<QualityItem<Encoding>> = "gzip; q".parse();
+        assert_eq!(x.unwrap().quality, Quality(1000));
+        let x: ::Result<QualityItem<Encoding>> = "gzip; Q=0.".parse();
+        assert_eq!(x.unwrap(), QualityItem{ item: Gzip, quality: Quality(0), });
+    }
+    #[test]
     fn test_quality_item_ordering() {
         let x: QualityItem<Encoding> = "gzip; q=0.5".parse().ok().unwrap();
         let y: QualityItem<Encoding> = "gzip; q=0.273".parse().ok().unwrap();
@@ -189,6 +208,15 @@ mod tests {
         assert!(comparision_result)
     }
 
+    #[cfg(feature = "quickcheck")]
+    #[test]
+    fn test_round_trip() {
+        fn prop(item: QualityItem<u32>) -> bool {
+            item.to_string().parse::<QualityItem<u32>>().ok() == Some(item)
+        }
+        ::quickcheck::quickcheck(prop as fn(QualityItem<u32>) -> bool);
+    }
+
     #[test]
     fn test_quality() {
         assert_eq!(q(0.5), Quality(500));
@@ -4,15 +4,19 @@ use std::cmp::min;
 use std::fmt;
 use std::io::{self, Write, BufWriter, BufRead, Read};
 use std::net::Shutdown;
+use std::sync::Arc;
+use std::time::Duration;
 
 use httparse;
 
-use buffer::BufReader;
+use buffer::{self, BufReader};
 use Error;
+use extensions::Extensions;
 use header::{Headers, ContentLength, TransferEncoding};
 use header::Encoding::Chunked;
 use method::{Method};
-use net::{NetworkConnector, NetworkStream};
+use metrics::{ClientEvents, Tag};
+use net::{NetworkConnector, NetworkStream, Timeouts};
 use status::StatusCode;
 use version::HttpVersion;
 use version::HttpVersion::{Http10, Http11};
@@ -37,6 +41,21 @@ pub struct Http11Message {
     stream: Option<Box<NetworkStream + Send>>,
     writer: Option<HttpWriter<BufWriter<Box<NetworkStream + Send>>>>,
     reader: Option<HttpReader<BufReader<Box<NetworkStream + Send>>>>,
+    // the method of the outgoing request, which decides whether a response
+    // has a body
+    method: Option<Method>,
+    limits: HeadLimits,
+    // the read timeout of the stream, put back after waiting for a
+    // `100 Continue` with another
+    read_timeout: Option<Duration>,
+    // a final response that came while waiting for a `100 Continue`
+    early_head: Option<Incoming<RawStatus>>,
+    // whether the request target is the whole URL, for a proxy
+    absolute_form: bool,
+    events: Option<(Arc<ClientEvents>, Tag)>,
+    // the body bytes written and read, for the events
+    written: u64,
+    read: Option<u64>,
 }
 
 impl Write for Http11Message {
@@ -45,7 +64,11 @@ impl Write for Http11Message {
         match self.writer {
             None => Err(io::Error::new(io::ErrorKind::Other,
                                           "Not in a writable state")),
-            Some(ref mut writer) => writer.write(buf),
+            Some(ref mut writer) => {
+                let n = try!(writer.write(buf));
+                self.written += n as u64;
+                Ok(n)
+            },
         }
     }
     #[inline]
@@ -64,7 +87,20 @@ impl Read for Http11Message {
         match self.reader {
             None => Err(io::Error::new(io::ErrorKind::Other,
                                           "Not in a readable state")),
-            Some(ref mut reader) => reader.read(buf),
+            Some(ref mut reader) => {
+                let n = try!(reader.read(buf));
+                if let Some(read) = self.read {
+                    if n > 0 {
+                        self.read = Some(read + n as u64);
+                    } else if !buf.is_empty() {
+                        self.read = None;
+                        if let Some((ref events, tag)) = self.events {
+                            events.response_complete(tag, read);
+                        }
+                    }
+                }
+                Ok(n)
+            },
         }
     }
 }
@@ -77,12 +113,29 @@ impl HttpMessage for Http11Message {
                         "Message not idle, cannot start new outgoing")));
         }
         let mut stream = BufWriter::new(self.stream.take().unwrap());
+        self.method = Some(head.method.clone());
 
-        let mut uri = head.url.serialize_path().unwrap();
-        if let Some(ref q) = head.url.query {
-            uri.push('?');
-            uri.push_str(&q[..]);
-        }
+        let uri = if head.method == Method::Connect {
+            // a tunnel's target is in authority-form
+            match (head.url.serialize_host(), head.url.port_or_default()) {
+                (Some(host), Some(port)) => format!("{}:{}", host, port),
+                _ => return Err(Error::Uri(::url::ParseError::EmptyHost)),
+            }
+        } else {
+            let mut uri = head.url.serialize_path().unwrap();
+            if let Some(ref q) = head.url.query {
+                uri.push('?');
+                uri.push_str(&q[..]);
+            }
+            if self.absolute_form {
+                // the URL without its credentials or fragment
+                let host = try!(head.url.serialize_host()
+                                    .ok_or(Error::Uri(::url::ParseError::EmptyHost)));
+                let port = head.url.port().map_or(String::new(), |port| format!(":{}", port));
+                uri = format!("{}://{}{}{}", head.url.scheme, host, port, uri);
+            }
+            uri
+        };
 
         let version = version::HttpVersion::Http11;
         debug!("request line: {:?} {:?} {:?}", head.method, uri, version);
@@ -90,9 +143,10 @@ impl HttpMessage for Http11Message {
                     head.method, uri, version, LINE_ENDING));
 
         let stream = match head.method {
-            Method::Get | Method::Head => {
+            Method::Get | Method::Head | Method::Connect => {
                 debug!("headers={:?}", head.headers);
-                try!(write!(&mut stream, "{}{}", head.headers, LINE_ENDING));
+                try!(head.headers.write_to(&mut stream));
+                try!(stream.write_all(LINE_ENDING.as_bytes()));
                 EmptyWriter(stream)
             },
             _ => {
@@ -125,7 +179,8 @@ impl HttpMessage for Http11Message {
                 }
 
                 debug!("headers={:?}", head.headers);
-                try!(write!(&mut stream, "{}{}", head.headers, LINE_ENDING));
+                try!(head.headers.write_to(&mut stream));
+                try!(stream.write_all(LINE_ENDING.as_bytes()));
 
                 if chunked {
                     ChunkedWriter(stream)
@@ -141,7 +196,12 @@ impl HttpMessage for Http11Message {
     }
 
     fn get_incoming(&mut self) -> ::Result<ResponseHead> {
-        try!(self.flush_outgoing());
+        let early_head = self.early_head.take();
+        if early_head.is_some() {
+            try!(self.abandon_outgoing());
+        } else {
+            try!(self.flush_outgoing());
+        }
         if self.stream.is_none() {
             // The message was already in the reading state...
             // TODO Decide what happens in case we try to get a new incoming at that point
@@ -153,11 +213,26 @@ impl HttpMessage for Http11Message {
         let stream = self.stream.take().unwrap();
         let mut stream = BufReader::new(stream);
 
-        let head = try!(parse_response(&mut stream));
+        if let Some((ref events, tag)) = self.events {
+            if early_head.is_none() {
+                try!(stream.fill_buf());
+            }
+            events.first_byte(tag);
+        }
+        let head = match early_head {
+            Some(head) => head,
+            None => try!(parse_final_response(&mut stream, &self.limits)),
+        };
         let raw_status = head.subject;
         let headers = head.headers;
 
-        let body = if headers.has::<TransferEncoding>() {
+        // after switching protocols, or opening a tunnel, the rest of the
+        // connection isn't HTTP
+        let upgraded = raw_status.0 == 101 ||
+            (self.method == Some(Method::Connect) && raw_status.0 / 100 == 2);
+        let body = if upgraded {
+            EmptyReader(stream)
+        } else if headers.has::<TransferEncoding>() {
             match headers.get::<TransferEncoding>() {
                 Some(&TransferEncoding(ref codings)) => {
                     if codings.len() > 1 {
@@ -165,7 +240,7 @@ impl HttpMessage for Http11Message {
                     };
 
                     if codings.contains(&Chunked) {
-                        ChunkedReader(stream, None)
+                        ChunkedReader(stream, None, None, self.limits)
                     } else {
                         trace!("not chuncked. read till eof");
                         EofReader(stream)
@@ -184,6 +259,7 @@ impl HttpMessage for Http11Message {
         };
 
         self.reader = Some(body);
+        self.read = Some(0);
 
         Ok(ResponseHead {
             headers: headers,
@@ -192,10 +268,48 @@ impl HttpMessage for Http11Message {
         })
     }
 
+    fn wait_for_continue(&mut self, timeout: Duration) -> ::Result<bool> {
+        let limits = self.limits;
+        let stream = match self.writer {
+            Some(ref mut writer) => {
+                try!(writer.flush());
+                writer.get_mut().get_mut()
+            },
+            None => return Ok(true),
+        };
+        if stream.set_read_timeout(Some(timeout)).is_err() {
+            // the stream can't time out, so waiting could block forever
+            return Ok(true);
+        }
+
+        let result = read_interim(stream, &limits);
+        try!(stream.set_read_timeout(self.read_timeout));
+        match try!(result) {
+            Some(head) => {
+                debug!("final response ({}) before the body was sent", head.subject.0);
+                self.early_head = Some(head);
+                Ok(false)
+            },
+            None => Ok(true),
+        }
+    }
+
+    fn trailers(&self) -> Option<&Headers> {
+        self.reader.as_ref().and_then(|reader| reader.trailers())
+    }
+
     fn close_connection(&mut self) -> ::Result<()> {
         try!(self.get_mut().close(Shutdown::Both));
         Ok(())
     }
+
+    fn tag(&mut self) -> Option<Tag> {
+        self.get_mut().tag()
+    }
+
+    fn set_events(&mut self, events: Arc<ClientEvents>, tag: Tag) {
+        self.events = Some((events, tag));
+    }
 }
 
 impl Http11Message {
@@ -212,6 +326,19 @@ impl Http11Message {
         }
     }
 
+    /// Consumes the `Http11Message`, returning the underlying `NetworkStream` along with any bytes
+    /// that were read from it past the response head, but not yet read out.
+    pub fn into_upgraded(mut self) -> (Box<NetworkStream + Send>, Vec<u8>) {
+        match self.reader.take() {
+            Some(reader) => {
+                let reader = reader.into_inner();
+                let buffered = reader.get_buf().to_vec();
+                (reader.into_inner(), buffered)
+            },
+            None => (self.into_inner(), Vec::new()),
+        }
+    }
+
     /// Gets a mutable reference to the underlying `NetworkStream`, regardless of the state of the
     /// `Http11Message`.
     pub fn get_mut(&mut self) -> &mut Box<NetworkStream + Send> {
@@ -233,7 +360,37 @@ impl Http11Message {
             stream: Some(stream),
             writer: None,
             reader: None,
+            method: None,
+            limits: HeadLimits::default(),
+            read_timeout: None,
+            early_head: None,
+            absolute_form: false,
+            events: None,
+            written: 0,
+            read: None,
+        }
+    }
+
+    /// Set the limits on the size of the response head to read.
+    pub fn set_head_limits(&mut self, limits: HeadLimits) {
+        self.limits = limits;
+    }
+
+    /// Send the whole URL as the request target, as a proxy expects, rather
+    /// than only its path and query.
+    pub fn set_absolute_form(&mut self, absolute_form: bool) {
+        self.absolute_form = absolute_form;
+    }
+
+    // Drops the rest of the outgoing body, which leaves the connection
+    // unusable for any more requests, so it's shut down for writing.
+    fn abandon_outgoing(&mut self) -> ::Result<()> {
+        if let Some(writer) = self.writer.take() {
+            let mut raw = try!(writer.into_inner().into_inner().map_err(io::Error::from));
+            try!(raw.close(Shutdown::Write));
+            self.stream = Some(raw);
         }
+        Ok(())
     }
 
     /// Flushes the current outgoing content and moves the stream into the `stream` property.
@@ -248,11 +405,65 @@ impl Http11Message {
         let writer = self.writer.take().unwrap();
         let raw = try!(writer.end()).into_inner().unwrap(); // end() already flushes
         self.stream = Some(raw);
+        if let Some((ref events, tag)) = self.events {
+            events.request_written(tag, self.written);
+        }
 
         Ok(())
     }
 }
 
+// Parses response heads until the final one, skipping interim responses,
+// such as a `100 Continue` that came too late to be waited for.
+fn parse_final_response<R: Read>(rdr: &mut BufReader<R>, limits: &HeadLimits)
+                                 -> ::Result<Incoming<RawStatus>> {
+    loop {
+        let head = try!(parse_response_with(rdr, limits));
+        match head.subject.0 {
+            100 | 102...199 => trace!("skipping interim response {}", head.subject.0),
+            _ => return Ok(head),
+        }
+    }
+}
+
+// Reads response heads until a `100 Continue`, returning `None`, or a final
+// response, which is returned. Timing out before any response starts is the
+// same as a `100 Continue`.
+//
+// The heads are read a byte at a time, so that none of what follows them is
+// read along with them.
+fn read_interim(stream: &mut Box<NetworkStream + Send>, limits: &HeadLimits)
+                -> ::Result<Option<Incoming<RawStatus>>> {
+    loop {
+        let mut head = Vec::new();
+        let mut byte = [0u8];
+        while !head.ends_with(b"\r\n\r\n") && !head.ends_with(b"\n\n") {
+            match stream.read(&mut byte) {
+                Ok(0) => return Err(Error::Io(io::Error::new(io::ErrorKind::ConnectionAborted,
+                                                             "connection closed"))),
+                Ok(_) => head.push(byte[0]),
+                Err(ref e) if head.is_empty() &&
+                    (e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut) => {
+                    trace!("no response to Expect: 100-continue in time");
+                    return Ok(None);
+                },
+                Err(e) => return Err(Error::Io(e)),
+            }
+            if head.len() > limits.max_size {
+                return Err(Error::TooLarge);
+            }
+        }
+
+        let head = try!(parse_response_with(&mut BufReader::new(&head[..]), limits));
+        match head.subject.0 {
+            100 => return Ok(None),
+            // other interim responses, such as 103 Early Hints, are skipped
+            102...199 => continue,
+            _ => return Ok(Some(head)),
+        }
+    }
+}
+
 /// The `Protocol` implementation provides HTTP/1.1 messages.
 pub struct Http11Protocol {
     connector: Connector,
@@ -264,6 +475,37 @@ impl Protocol for Http11Protocol {
 
         Ok(Box::new(Http11Message::with_stream(stream)))
     }
+
+    fn new_message_with(&self, host: &str, port: u16, scheme: &str, extensions: &Extensions)
+                        -> ::Result<Box<HttpMessage>> {
+        let mut stream: Box<NetworkStream + Send> =
+            try!(self.connector.connect_with(host, port, scheme, extensions)).into();
+        // a reused connection still has the timeouts of the last request on
+        // it, so they are always set, if only to none, which a stream that
+        // can't time out already has
+        let timeouts = match extensions.get::<Timeouts>() {
+            Some(timeouts) => {
+                try!(stream.set_read_timeout(timeouts.read));
+                try!(stream.set_write_timeout(timeouts.write));
+                *timeouts
+            },
+            None => {
+                let _ = stream.set_read_timeout(None);
+                let _ = stream.set_write_timeout(None);
+                Timeouts::default()
+            },
+        };
+
+        let mut message = Http11Message::with_stream(stream);
+        message.read_timeout = timeouts.read;
+        if let Some(limits) = extensions.get::<HeadLimits>() {
+            message.set_head_limits(*limits);
+        }
+        if extensions.contains::<AbsoluteForm>() {
+            message.set_absolute_form(true);
+        }
+        Ok(Box::new(message))
+    }
 }
 
 impl Http11Protocol {
@@ -288,6 +530,11 @@ impl<C: NetworkConnector<Stream=S> + Send + Sync, S: NetworkStream + Send>
         -> ::Result<Box<NetworkStream + Send>> {
         Ok(try!(self.0.connect(host, port, scheme)).into())
     }
+    #[inline]
+    fn connect_with(&self, host: &str, port: u16, scheme: &str, extensions: &Extensions)
+        -> ::Result<Box<NetworkStream + Send>> {
+        Ok(try!(self.0.connect_with(host, port, scheme, extensions)).into())
+    }
 }
 
 struct Connector(Box<NetworkConnector<Stream=Box<NetworkStream + Send>> + Send + Sync>);
@@ -299,6 +546,11 @@ impl NetworkConnector for Connector {
         -> ::Result<Box<NetworkStream + Send>> {
         Ok(try!(self.0.connect(host, port, scheme)).into())
     }
+    #[inline]
+    fn connect_with(&self, host: &str, port: u16, scheme: &str, extensions: &Extensions)
+        -> ::Result<Box<NetworkStream + Send>> {
+        Ok(try!(self.0.connect_with(host, port, scheme, extensions)).into())
+    }
 }
 
 
@@ -310,7 +562,11 @@ pub enum HttpReader<R> {
     /// A Reader used when a Content-Length header is passed with a positive integer.
     SizedReader(R, u64),
     /// A Reader used when Transfer-Encoding is `chunked`.
-    ChunkedReader(R, Option<u64>),
+    ///
+    /// Once the last chunk is read, the trailer fields that followed it are
+    /// kept, and can be had from `trailers`. They are read within the
+    /// `HeadLimits`, as the head was.
+    ChunkedReader(R, Option<u64>, Option<Headers>, HeadLimits),
     /// A Reader used for responses that don't indicate a length or chunked.
     ///
     /// Note: This should only used for `Response`s. It is illegal for a
@@ -338,17 +594,26 @@ impl<R: Read> HttpReader<R> {
     pub fn into_inner(self) -> R {
         match self {
             SizedReader(r, _) => r,
-            ChunkedReader(r, _) => r,
+            ChunkedReader(r, _, _, _) => r,
             EofReader(r) => r,
             EmptyReader(r) => r,
         }
     }
 
+    /// The trailer fields sent after a chunked body, once all of it has
+    /// been read.
+    pub fn trailers(&self) -> Option<&Headers> {
+        match *self {
+            ChunkedReader(_, _, Some(ref trailers), _) => Some(trailers),
+            _ => None,
+        }
+    }
+
     /// Gets a mutable reference to the underlying Reader.
     pub fn get_mut(&mut self) -> &mut R {
         match *self {
             SizedReader(ref mut r, _) => r,
-            ChunkedReader(ref mut r, _) => r,
+            ChunkedReader(ref mut r, _, _, _) => r,
             EofReader(ref mut r) => r,
             EmptyReader(ref mut r) => r,
         }
@@ -359,8 +624,8 @@ impl<R> fmt::Debug for HttpReader<R> {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             SizedReader(_,rem) => write!(fmt, "SizedReader(remaining={:?})", rem),
-            ChunkedReader(_, None) => write!(fmt, "ChunkedReader(chunk_remaining=unknown)"),
-            ChunkedReader(_, Some(rem)) => write!(fmt, "ChunkedReader(chunk_remaining={:?})", rem),
+            ChunkedReader(_, None, _, _) => write!(fmt, "ChunkedReader(chunk_remaining=unknown)"),
+            ChunkedReader(_, Some(rem), _, _) => write!(fmt, "ChunkedReader(chunk_remaining={:?})", rem),
             EofReader(_) => write!(fmt, "EofReader"),
             EmptyReader(_) => write!(fmt, "EmptyReader"),
         }
@@ -384,11 +649,17 @@ impl<R: Read> Read for HttpReader<R> {
                     Ok(num as usize)
                 }
             },
-            ChunkedReader(ref mut body, ref mut opt_remaining) => {
+            ChunkedReader(ref mut body, ref mut opt_remaining, ref mut trailers, ref limits) => {
                 let mut rem = match *opt_remaining {
                     Some(ref rem) => *rem,
                     // None means we don't know the size of the next chunk
-                    None => try!(read_chunk_size(body))
+                    None => {
+                        let size = try!(read_chunk_size(body));
+                        if size == 0 {
+                            *trailers = Some(try!(read_trailers(body, limits)));
+                        }
+                        size
+                    }
                 };
                 trace!("Chunked read, remaining={:?}", rem);
 
@@ -436,6 +707,33 @@ fn eat<R: Read>(rdr: &mut R, bytes: &[u8]) -> io::Result<()> {
     Ok(())
 }
 
+/// The last chunk is followed by trailer fields, if any, and an empty line.
+fn read_trailers<R: Read>(rdr: &mut R, limits: &HeadLimits) -> io::Result<Headers> {
+    fn invalid() -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidInput, "Invalid trailer section")
+    }
+
+    let mut raw = Vec::new();
+    let mut line = 0;
+    let mut byte = [0];
+    while &raw[..] != LINE_ENDING.as_bytes() && !raw.ends_with(b"\r\n\r\n") {
+        match try!(rdr.read(&mut byte)) {
+            1 => raw.push(byte[0]),
+            _ => return Err(invalid()),
+        }
+        line = if byte[0] == LF { 0 } else { line + 1 };
+        if raw.len() > limits.max_size || line > limits.max_line_size {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Trailer section too large"));
+        }
+    }
+
+    let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+    match httparse::parse_headers(&raw, &mut headers) {
+        Ok(httparse::Status::Complete((_, headers))) => Headers::from_raw(headers).map_err(|_| invalid()),
+        _ => Err(invalid()),
+    }
+}
+
 /// Chunked chunks start with 1*HEXDIGIT, indicating the size of the chunk.
 fn read_chunk_size<R: Read>(rdr: &mut R) -> io::Result<u64> {
     macro_rules! byte (
@@ -566,6 +864,32 @@ impl<W: Write> HttpWriter<W> {
         try!(self.flush());
         Ok(self.into_inner())
     }
+
+    /// Ends a chunked body with `trailers` after its last chunk, and returns
+    /// the underlying Writer.
+    ///
+    /// Only the ChunkedWriter variant can send trailers; the others fail with
+    /// `InvalidInput`, as do trailers with a field that frames or routes the
+    /// message, `Content-Length`, `Transfer-Encoding` or `Host`, which
+    /// [RFC7230 Section 4.1.2](https://tools.ietf.org/html/rfc7230#section-4.1.2)
+    /// forbids there.
+    pub fn end_with_trailers(mut self, trailers: &Headers) -> io::Result<W> {
+        if let Some(name) = FORBIDDEN_TRAILERS.iter().find(|&name| trailers.get_raw(name).is_some()) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      format!("{} can't be sent as a trailer", name)));
+        }
+        match self {
+            ChunkedWriter(ref mut w) => {
+                try!(write!(w, "0{}", LINE_ENDING));
+                try!(trailers.write_to(w));
+                try!(w.write_all(LINE_ENDING.as_bytes()));
+                try!(w.flush());
+            },
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                           "Trailers need a chunked body")),
+        }
+        Ok(self.into_inner())
+    }
 }
 
 impl<W: Write> Write for HttpWriter<W> {
@@ -627,26 +951,118 @@ impl<W: Write> fmt::Debug for HttpWriter<W> {
 
 const MAX_HEADERS: usize = 100;
 
+// The fields that can't be trailers, since the message has already been
+// framed and routed by the time they would arrive.
+const FORBIDDEN_TRAILERS: [&'static str; 3] = ["Content-Length", "Transfer-Encoding", "Host"];
+
+/// Marks a request as one to an HTTP proxy, which `Http11Protocol` sends
+/// with the whole URL as its target, such as
+/// `GET http://example.domain/path HTTP/1.1`.
+///
+/// A `Client` with a proxy puts this in the extensions of its `http`
+/// requests, and connects to the proxy for them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AbsoluteForm;
+
+/// Limits on the size of a message head being read, past which reading it
+/// fails with `Error::TooLarge`.
+///
+/// The trailer fields after a chunked body are held to the same limits,
+/// and reading a body whose trailers break them fails with `InvalidData`.
+///
+/// A `Server` answers such a request with
+/// `431 Request Header Fields Too Large`. Larger limits let through the
+/// likes of big tokens in an `Authorization` header, while smaller ones
+/// bound the memory a client can make a server buffer.
+///
+/// ```
+/// use hyper::http::h1::HeadLimits;
+///
+/// let limits = HeadLimits {
+///     max_line_size: 16 * 1024,
+///     ..HeadLimits::default()
+/// };
+/// assert_eq!(limits.max_size, 8192 + 4096 * 100);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HeadLimits {
+    /// The most bytes the whole head may have, from the start line to the
+    /// empty line ending it.
+    ///
+    /// Defaults to a little over 400KB.
+    pub max_size: usize,
+    /// The most bytes a single line of the head may have, such as a header
+    /// field and its value.
+    ///
+    /// Defaults to `max_size`.
+    pub max_line_size: usize,
+}
+
+impl Default for HeadLimits {
+    fn default() -> HeadLimits {
+        HeadLimits {
+            max_size: buffer::MAX_BUFFER_SIZE,
+            max_line_size: buffer::MAX_BUFFER_SIZE,
+        }
+    }
+}
+
+impl HeadLimits {
+    // Whether a complete `head` breaks a limit.
+    fn exceeded_by(&self, head: &[u8]) -> bool {
+        head.len() > self.max_size ||
+            head.split(|&b| b == b'\n').any(|line| line.len() > self.max_line_size)
+    }
+
+    // Whether the start of a head already breaks a limit. Only its last line
+    // is checked, since the others are checked once the head is complete.
+    fn exceeded_by_partial(&self, head: &[u8]) -> bool {
+        head.len() > self.max_size ||
+            head.rsplit(|&b| b == b'\n').next().map_or(false, |line| line.len() > self.max_line_size)
+    }
+}
+
 /// Parses a request into an Incoming message head.
 #[inline]
 pub fn parse_request<R: Read>(buf: &mut BufReader<R>) -> ::Result<Incoming<(Method, RequestUri)>> {
-    parse::<R, httparse::Request, (Method, RequestUri)>(buf)
+    parse_request_with(buf, &HeadLimits::default())
+}
+
+/// Parses a request into an Incoming message head, within `limits`.
+#[inline]
+pub fn parse_request_with<R: Read>(buf: &mut BufReader<R>, limits: &HeadLimits)
+                                   -> ::Result<Incoming<(Method, RequestUri)>> {
+    parse::<R, httparse::Request, (Method, RequestUri)>(buf, limits)
 }
 
 /// Parses a response into an Incoming message head.
 #[inline]
 pub fn parse_response<R: Read>(buf: &mut BufReader<R>) -> ::Result<Incoming<RawStatus>> {
-    parse::<R, httparse::Response, RawStatus>(buf)
+    parse_response_with(buf, &HeadLimits::default())
 }
 
-fn parse<R: Read, T: TryParse<Subject=I>, I>(rdr: &mut BufReader<R>) -> ::Result<Incoming<I>> {
+/// Parses a response into an Incoming message head, within `limits`.
+#[inline]
+pub fn parse_response_with<R: Read>(buf: &mut BufReader<R>, limits: &HeadLimits)
+                                    -> ::Result<Incoming<RawStatus>> {
+    parse::<R, httparse::Response, RawStatus>(buf, limits)
+}
+
+fn parse<R: Read, T: TryParse<Subject=I>, I>(rdr: &mut BufReader<R>, limits: &HeadLimits)
+                                             -> ::Result<Incoming<I>> {
+    rdr.set_max_capacity(limits.max_size);
     loop {
         match try!(try_parse::<R, T, I>(rdr)) {
             httparse::Status::Complete((inc, len)) => {
+                if limits.exceeded_by(&rdr.get_buf()[..len]) {
+                    return Err(Error::TooLarge);
+                }
                 rdr.consume(len);
                 return Ok(inc);
             },
-            _partial => ()
+            _partial => if limits.exceeded_by_partial(rdr.get_buf()) {
+                return Err(Error::TooLarge);
+            }
         }
         match try!(rdr.read_into_buf()) {
             0 if rdr.get_buf().is_empty() => {
@@ -759,6 +1175,61 @@ mod tests {
         assert_eq!(s, "7\r\nfoo bar\r\nD\r\nbaz quux herp\r\n0\r\n\r\n");
     }
 
+    #[test]
+    fn test_trailers() {
+        use std::io::Read;
+        use header::Headers;
+        use super::HeadLimits;
+        use super::HttpReader::ChunkedReader;
+
+        let mut trailers = Headers::new();
+        trailers.set_raw("Digest", vec![b"sha-256=abc".to_vec()]);
+        let w = super::HttpWriter::ChunkedWriter(Vec::new());
+        let buf = w.end_with_trailers(&trailers).unwrap();
+        assert_eq!(&buf[..], &b"0\r\nDigest: sha-256=abc\r\n\r\n"[..]);
+        assert!(super::HttpWriter::SizedWriter(Vec::new(), 0).end_with_trailers(&trailers).is_err());
+
+        // fields framing or routing the message can't come after it
+        for name in &["Content-Length", "transfer-encoding", "Host"] {
+            let mut forbidden = trailers.clone();
+            forbidden.set_raw(*name, vec![b"1".to_vec()]);
+            let w = super::HttpWriter::ChunkedWriter(Vec::new());
+            assert_eq!(w.end_with_trailers(&forbidden).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        }
+
+        let mut input = &b"3\r\nfoo\r\n0\r\nDigest: sha-256=abc\r\nX-Status: 0\r\n\r\nnext"[..];
+        let mut body = String::new();
+        {
+            let mut r = ChunkedReader(&mut input, None, None, HeadLimits::default());
+            assert!(r.trailers().is_none());
+            r.read_to_string(&mut body).unwrap();
+            let trailers = r.trailers().unwrap();
+            assert_eq!(trailers.get_raw("digest").unwrap(), &[b"sha-256=abc".to_vec()][..]);
+            assert_eq!(trailers.get_raw("X-Status").unwrap(), &[b"0".to_vec()][..]);
+        }
+        assert_eq!(body, "foo");
+        // the next message is left to be read
+        assert_eq!(input, b"next");
+
+        let mut input = &b"0\r\n\r\nnext"[..];
+        {
+            let mut r = ChunkedReader(&mut input, None, None, HeadLimits::default());
+            assert_eq!(r.read(&mut [0; 8]).unwrap(), 0);
+            assert_eq!(r.trailers().unwrap().len(), 0);
+        }
+        assert_eq!(input, b"next");
+
+        // trailers are held to the limits of a head
+        let input = b"0\r\nDigest: sha-256=abc\r\nX-Status: 0\r\n\r\n";
+        let read = |limits| ChunkedReader(&input[..], None, None, limits).read(&mut [0; 8]);
+        let fits = HeadLimits { max_size: input.len() - 3, max_line_size: 20 };
+        assert_eq!(read(fits).unwrap(), 0);
+        let too_long = read(HeadLimits { max_size: input.len() - 4, ..fits }).unwrap_err();
+        assert_eq!(too_long.kind(), io::ErrorKind::InvalidData);
+        let line_too_long = read(HeadLimits { max_line_size: 19, ..fits }).unwrap_err();
+        assert_eq!(line_too_long.kind(), io::ErrorKind::InvalidData);
+    }
+
     #[test]
     fn test_write_sized() {
         use std::str::from_utf8;
@@ -835,6 +1306,83 @@ mod tests {
         assert_eq!(res.subject.1, "Howdy");
     }
 
+    #[test]
+    fn test_parse_head_limits() {
+        use error::Error;
+        use super::{HeadLimits, parse_request_with};
+
+        fn parse(input: &[u8], limits: HeadLimits) -> ::Result<()> {
+            let mut raw = MockStream::with_input(input);
+            let mut buf = BufReader::new(&mut raw);
+            parse_request_with(&mut buf, &limits).map(|_| ())
+        }
+
+        let head = b"GET / HTTP/1.1\r\nHost: hyper.rs\r\nX-Token: 0123456789\r\n\r\n";
+        let fits = HeadLimits { max_size: head.len(), max_line_size: 20 };
+        parse(head, fits).unwrap();
+        match parse(head, HeadLimits { max_size: head.len() - 1, ..fits }) {
+            Err(Error::TooLarge) => (),
+            other => panic!("expected TooLarge, got {:?}", other),
+        }
+        match parse(head, HeadLimits { max_line_size: 19, ..fits }) {
+            Err(Error::TooLarge) => (),
+            other => panic!("expected TooLarge, got {:?}", other),
+        }
+
+        // a head larger than the default buffer
+        let mut big = b"GET / HTTP/1.1\r\nAuthorization: Bearer ".to_vec();
+        big.extend(vec![b'a'; 500 * 1024]);
+        big.extend_from_slice(b"\r\n\r\n");
+        match parse(&big, HeadLimits::default()) {
+            Err(Error::TooLarge) => (),
+            other => panic!("expected TooLarge, got {:?}", other),
+        }
+        parse(&big, HeadLimits { max_size: 1024 * 1024, max_line_size: 1024 * 1024 }).unwrap();
+    }
+
+    #[test]
+    fn test_wait_for_continue() {
+        use std::io::Read;
+        use std::time::Duration;
+        use header::{Headers, ContentLength};
+        use http::{HttpMessage, RequestHead};
+        use method::Method;
+        use url::Url;
+        use super::Http11Message;
+
+        fn upload(input: &[u8]) -> (bool, u16, String, Vec<u8>) {
+            let mut message = Http11Message::with_stream(Box::new(MockStream::with_input(input)));
+            let mut headers = Headers::new();
+            headers.set(ContentLength(6));
+            message.set_outgoing(RequestHead {
+                headers: headers,
+                method: Method::Post,
+                url: Url::parse("http://hyper.rs/upload").unwrap(),
+            }).unwrap();
+            let sent = message.wait_for_continue(Duration::from_secs(1)).unwrap();
+            if sent {
+                message.write_all(b"upload").unwrap();
+            }
+            let head = message.get_incoming().unwrap();
+            let mut body = String::new();
+            message.read_to_string(&mut body).unwrap();
+            let stream = message.into_inner().downcast::<MockStream>().ok().unwrap();
+            (sent, head.raw_status.0, body, stream.write)
+        }
+
+        let (sent, status, body, written) = upload(b"HTTP/1.1 100 Continue\r\n\r\n\
+            HTTP/1.1 103 Early Hints\r\nLink: </style.css>; rel=preload\r\n\r\n\
+            HTTP/1.1 201 Created\r\nContent-Length: 2\r\n\r\nok");
+        assert!(sent);
+        assert_eq!((status, &body[..]), (201, "ok"));
+        assert!(written.ends_with(b"\r\n\r\nupload"));
+
+        let (sent, status, body, written) = upload(b"HTTP/1.1 413 Payload Too Large\r\n\
+            Content-Length: 8\r\n\r\ntoo big!");
+        assert!(!sent);
+        assert_eq!((status, &body[..]), (413, "too big!"));
+        assert!(written.ends_with(b"Content-Length: 6\r\n\r\n"));
+    }
 
     #[test]
     fn test_parse_tcp_closed() {
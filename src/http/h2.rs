@@ -12,6 +12,7 @@ use http::{
     ResponseHead,
     RawStatus,
 };
+use extensions::Extensions;
 use net::{NetworkStream, NetworkConnector};
 use net::{HttpConnector, HttpStream};
 use url::Url;
@@ -146,7 +147,12 @@ impl<C, S> Http2Protocol<C, S> where C: NetworkConnector<Stream=S> + Send + 'sta
 impl<C, S> Protocol for Http2Protocol<C, S> where C: NetworkConnector<Stream=S> + Send + 'static,
                                                   S: NetworkStream + Send + Clone {
     fn new_message(&self, host: &str, port: u16, scheme: &str) -> ::Result<Box<HttpMessage>> {
-        let stream = try!(self.connector.connect(host, port, scheme)).into();
+        self.new_message_with(host, port, scheme, &Extensions::new())
+    }
+
+    fn new_message_with(&self, host: &str, port: u16, scheme: &str, extensions: &Extensions)
+                        -> ::Result<Box<HttpMessage>> {
+        let stream = try!(self.connector.connect_with(host, port, scheme, extensions)).into();
 
         let scheme = match scheme {
             "http" => HttpScheme::Http,
@@ -275,7 +281,7 @@ fn prepare_path(url: Url) -> Vec<u8> {
 ///
 /// Adapts the `Headers` into a list of octet string pairs.
 fn prepare_headers(mut headers: Headers) -> Vec<Http2Header> {
-    if headers.remove::<header::Connection>() {
+    if headers.remove::<header::Connection>().is_some() {
         warn!("The `Connection` header is not valid for an HTTP/2 connection.");
     }
     let mut http2_headers: Vec<_> = headers.iter().filter_map(|h| {
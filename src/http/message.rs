@@ -6,11 +6,15 @@ use std::any::{Any, TypeId};
 use std::io::{Read, Write};
 
 use std::mem;
+use std::sync::Arc;
+use std::time::Duration;
 
 use typeable::Typeable;
 
+use extensions::Extensions;
 use header::Headers;
 use http::RawStatus;
+use metrics::{ClientEvents, Tag};
 use url::Url;
 
 use method;
@@ -22,6 +26,16 @@ use traitobject;
 pub trait Protocol {
     /// Creates a fresh `HttpMessage` bound to the given host, based on the given protocol scheme.
     fn new_message(&self, host: &str, port: u16, scheme: &str) -> ::Result<Box<HttpMessage>>;
+
+    /// Creates a fresh `HttpMessage` for a request that carries `extensions`,
+    /// passing them on to the connector if a new connection is needed.
+    ///
+    /// By default the extensions are ignored and this calls `new_message`.
+    fn new_message_with(&self, host: &str, port: u16, scheme: &str, extensions: &Extensions)
+                        -> ::Result<Box<HttpMessage>> {
+        let _ = extensions;
+        self.new_message(host, port, scheme)
+    }
 }
 
 /// Describes a request.
@@ -60,11 +74,42 @@ pub trait HttpMessage: Write + Read + Send + Any + Typeable + Debug {
     /// After this, the `HttpMessage` instance can be used as an `io::Read` in order to read out
     /// the response body.
     fn get_incoming(&mut self) -> ::Result<ResponseHead>;
+    /// Waits up to `timeout` for a `100 Continue` to a request sent with
+    /// `Expect: 100-continue`, before its body is written.
+    ///
+    /// Returns whether to go on writing the body, which is `false` if the
+    /// server already sent its final response, for `get_incoming` to
+    /// return. If nothing arrives in time, the body should be sent anyway.
+    ///
+    /// By default, this doesn't wait, and returns `true`.
+    fn wait_for_continue(&mut self, _timeout: Duration) -> ::Result<bool> {
+        Ok(true)
+    }
+    /// The trailer fields sent after the incoming body, once all of it has
+    /// been read.
+    ///
+    /// By default this is `None`.
+    fn trailers(&self) -> Option<&Headers> {
+        None
+    }
     /// Set the read timeout duration for this message.
     #[cfg(feature = "timeouts")]
     fn set_read_timeout(&self, dur: Option<Duration>) -> ::Result<()>;
     /// Closes the underlying HTTP connection.
     fn close_connection(&mut self) -> ::Result<()>;
+    /// Identifies the connection this message is sent on, and the request's
+    /// position on it, if the connection keeps track.
+    ///
+    /// By default this is `None`.
+    fn tag(&mut self) -> Option<Tag> {
+        None
+    }
+    /// Reports the progress of the request, identified by `tag`, to
+    /// `events`: when it was written, and when its response started to
+    /// arrive and was read to its end.
+    ///
+    /// By default, nothing is reported.
+    fn set_events(&mut self, _events: Arc<ClientEvents>, _tag: Tag) {}
 }
 
 impl HttpMessage {
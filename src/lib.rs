@@ -135,9 +135,18 @@ extern crate url;
 extern crate openssl;
 #[cfg(feature = "serde-serialization")]
 extern crate serde;
+#[cfg(feature = "brotli")]
+extern crate brotli;
+#[cfg(feature = "chrono")]
+extern crate chrono;
+#[cfg(feature = "flate2")]
+extern crate flate2;
+#[cfg(feature = "quickcheck")]
+extern crate quickcheck;
 extern crate cookie;
 extern crate unicase;
 extern crate httparse;
+extern crate libc;
 extern crate num_cpus;
 extern crate traitobject;
 extern crate typeable;
@@ -157,10 +166,12 @@ extern crate test;
 
 
 pub use url::Url;
+#[cfg(feature = "client")]
 pub use client::Client;
 pub use error::{Result, Error};
 pub use method::Method::{Get, Head, Post, Delete};
 pub use status::StatusCode::{Ok, BadRequest, NotFound};
+#[cfg(feature = "server")]
 pub use server::Server;
 pub use language_tags::LanguageTag;
 
@@ -178,21 +189,30 @@ macro_rules! inspect(
     })
 );
 
-#[cfg(test)]
+#[cfg(any(test, feature = "mock"))]
 #[macro_use]
-mod mock;
+pub mod mock;
 #[doc(hidden)]
 pub mod buffer;
+#[cfg(feature = "client")]
 pub mod client;
+pub mod clock;
+#[cfg(any(feature = "flate2", feature = "brotli"))]
+pub mod compress;
 pub mod error;
+pub mod extensions;
 pub mod method;
+pub mod metrics;
 pub mod header;
 pub mod http;
+pub mod lines;
 pub mod net;
+#[cfg(feature = "server")]
 pub mod server;
 pub mod status;
 pub mod uri;
 pub mod version;
+pub mod ws;
 
 /// Re-exporting the mime crate, for convenience.
 pub mod mime {
@@ -201,14 +221,18 @@ pub mod mime {
 
 #[allow(unconditional_recursion)]
 fn _assert_send<T: Send>() {
-    _assert_send::<Client>();
-    _assert_send::<client::Request<net::Fresh>>();
-    _assert_send::<client::Response>();
+    #[cfg(feature = "client")]
+    {
+        _assert_send::<Client>();
+        _assert_send::<client::Request<net::Fresh>>();
+        _assert_send::<client::Response>();
+    }
     _assert_send::<error::Error>();
 }
 
 #[allow(unconditional_recursion)]
 fn _assert_sync<T: Sync>() {
+    #[cfg(feature = "client")]
     _assert_sync::<Client>();
     _assert_sync::<error::Error>();
 }
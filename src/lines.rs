@@ -0,0 +1,212 @@
+//! Reading streamed bodies a line at a time.
+//!
+//! Streaming APIs, such as event feeds, send a body that never ends, made
+//! of one line per event. `Lines` reads such a body, whether a client
+//! `Response` or a server `Request`, yielding each line as soon as it has
+//! arrived whole, however the line was split into chunks. `NdJson` reads
+//! each line as a JSON record.
+//!
+//! ```no_run
+//! use hyper::Client;
+//! use hyper::lines::NdJson;
+//!
+//! let client = Client::new();
+//! let res = client.get("http://example.domain/events").send().unwrap();
+//! for event in NdJson::new(res, 64 * 1024) {
+//!     println!("{}", event.unwrap());
+//! }
+//! ```
+use std::io::{self, Read};
+
+use serialize::json::Json;
+
+/// An iterator over the lines of a body.
+///
+/// Lines end with `\n` or `\r\n`, which are not included. A last line that
+/// has no line ending is yielded when the body ends.
+#[derive(Debug)]
+pub struct Lines<R> {
+    inner: R,
+    buf: Vec<u8>,
+    // how much of buf is known to have no line ending
+    searched: usize,
+    max_len: usize,
+    done: bool,
+}
+
+impl<R: Read> Lines<R> {
+    /// Read the lines of `inner`.
+    ///
+    /// A line longer than `max_len` bytes is an `InvalidData` error, after
+    /// which the iterator ends, rather than buffering without bound.
+    pub fn new(inner: R, max_len: usize) -> Lines<R> {
+        Lines {
+            inner: inner,
+            buf: Vec::new(),
+            searched: 0,
+            max_len: max_len,
+            done: false,
+        }
+    }
+
+    /// Get the underlying reader back.
+    ///
+    /// Any bytes read from it that weren't yielded yet as a line are lost.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn next_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            if let Some(pos) = self.buf[self.searched..].iter().position(|&b| b == b'\n') {
+                let end = self.searched + pos;
+                let mut line = self.buf.drain(..end + 1).collect::<Vec<_>>();
+                self.searched = 0;
+                line.pop();
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+                if line.len() > self.max_len {
+                    return Err(too_long());
+                }
+                return Ok(Some(line));
+            }
+            self.searched = self.buf.len();
+            // a `\r` of the line ending isn't part of the line
+            if self.buf.len() > self.max_len + 1 {
+                return Err(too_long());
+            }
+
+            let mut chunk = [0; 4096];
+            let n = try!(self.inner.read(&mut chunk));
+            if n == 0 {
+                if self.buf.is_empty() {
+                    return Ok(None);
+                }
+                let line = self.buf.drain(..).collect::<Vec<_>>();
+                self.searched = 0;
+                if line.len() > self.max_len {
+                    return Err(too_long());
+                }
+                return Ok(Some(line));
+            }
+            self.buf.extend_from_slice(&chunk[..n]);
+        }
+    }
+}
+
+fn too_long() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "line is too long")
+}
+
+impl<R: Read> Iterator for Lines<R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<io::Result<String>> {
+        if self.done {
+            return None;
+        }
+        match self.next_line() {
+            Ok(Some(line)) => Some(String::from_utf8(line).map_err(|e| {
+                io::Error::new(io::ErrorKind::InvalidData, e)
+            })),
+            Ok(None) => {
+                self.done = true;
+                None
+            },
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// An iterator over the records of a newline delimited JSON body.
+///
+/// Blank lines between records are skipped. A record that isn't valid JSON
+/// is an `InvalidData` error, but the records after it can still be read.
+#[derive(Debug)]
+pub struct NdJson<R> {
+    lines: Lines<R>,
+}
+
+impl<R: Read> NdJson<R> {
+    /// Read the records of `inner`, each of at most `max_len` bytes.
+    pub fn new(inner: R, max_len: usize) -> NdJson<R> {
+        NdJson { lines: Lines::new(inner, max_len) }
+    }
+
+    /// Get the underlying reader back.
+    pub fn into_inner(self) -> R {
+        self.lines.into_inner()
+    }
+}
+
+impl<R: Read> Iterator for NdJson<R> {
+    type Item = io::Result<Json>;
+
+    fn next(&mut self) -> Option<io::Result<Json>> {
+        loop {
+            let line = match self.lines.next() {
+                Some(Ok(line)) => line,
+                Some(Err(e)) => return Some(Err(e)),
+                None => return None,
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            return Some(Json::from_str(&line).map_err(|e| {
+                io::Error::new(io::ErrorKind::InvalidData, e)
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read};
+
+    use serialize::json::Json;
+
+    use super::{Lines, NdJson};
+
+    // Reads out a few bytes at a time, as a body arriving in small chunks.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl<'a> Read for Trickle<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = ::std::cmp::min(3, buf.len());
+            self.0.read(&mut buf[..n])
+        }
+    }
+
+    #[test]
+    fn test_lines_across_chunks() {
+        let lines = Lines::new(Trickle(b"first\r\nsecond line\n\nlast"), 64)
+            .collect::<io::Result<Vec<_>>>().unwrap();
+        assert_eq!(lines, vec!["first", "second line", "", "last"]);
+    }
+
+    #[test]
+    fn test_lines_max_len() {
+        let mut lines = Lines::new(Trickle(b"short\r\nmuch too long\nshort"), 5);
+        assert_eq!(lines.next().unwrap().unwrap(), "short");
+        assert_eq!(lines.next().unwrap().unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert!(lines.next().is_none());
+
+        // nor is an unterminated line buffered without bound
+        let mut lines = Lines::new(io::repeat(b'a'), 100);
+        assert_eq!(lines.next().unwrap().unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_ndjson() {
+        let body = b"{\"id\":1}\n\n{\"id\":\n{\"id\":3}\n";
+        let mut records = NdJson::new(Trickle(body), 64);
+        assert_eq!(records.next().unwrap().unwrap(), Json::from_str("{\"id\":1}").unwrap());
+        assert_eq!(records.next().unwrap().unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(records.next().unwrap().unwrap(), Json::from_str("{\"id\":3}").unwrap());
+        assert!(records.next().is_none());
+    }
+}
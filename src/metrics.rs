@@ -0,0 +1,385 @@
+//! Metrics collection for clients and servers.
+//!
+//! Both the `Client` and the `Server` can be given a `Metrics`
+//! implementation, which they will call into as they open connections,
+//! move bytes, and complete requests. Every method has a default
+//! implementation that does nothing, so an exporter only needs to implement
+//! the measurements it cares about.
+//!
+//! A `Client` can also be given `ClientEvents`, told as each request
+//! resolves, connects, and is written and answered, for a breakdown of
+//! where its time goes.
+//!
+//! ```
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//! use hyper::metrics::{Metrics, Side};
+//!
+//! #[derive(Default)]
+//! struct Connections(AtomicUsize);
+//!
+//! impl Metrics for Connections {
+//!     fn connection_opened(&self, _: Side) {
+//!         self.0.fetch_add(1, Ordering::Relaxed);
+//!     }
+//! }
+//! ```
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, Shutdown};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use extensions::Extensions;
+use method::Method;
+use net::{NetworkConnector, NetworkStream, TlsInfo};
+use status::StatusCode;
+use Error;
+
+/// Which half of an HTTP exchange a measurement was taken from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Side {
+    /// Measured by a `Client`.
+    Client,
+    /// Measured by a `Server`.
+    Server,
+}
+
+/// Identifies the connection, and the request on it, that a measurement or
+/// event concerns.
+///
+/// Connections opened by a `Client` and those accepted by a `Server` share
+/// one series of identifiers, so that within a process a request can be
+/// followed from the client pool to the server worker that answered it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Tag {
+    /// An identifier for the connection, unique for the life of the process.
+    pub connection: usize,
+    /// The position of the request on its connection, starting at 1.
+    ///
+    /// A request on a pooled connection that was reused has a position
+    /// above 1. This is `None` for events about the connection as a whole.
+    pub request: Option<usize>,
+}
+
+static NEXT_CONNECTION_ID: AtomicUsize = AtomicUsize::new(1);
+
+/// Take an identifier for a newly opened connection.
+#[doc(hidden)]
+pub fn next_connection_id() -> usize {
+    NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Receives measurements from a `Client` or `Server`.
+///
+/// Implementations are shared between threads, and so must synchronize any
+/// state they keep.
+pub trait Metrics: Send + Sync {
+    /// A new connection was opened, or accepted.
+    fn connection_opened(&self, _side: Side) {}
+
+    /// Bytes were read from a connection.
+    fn bytes_read(&self, _side: Side, _count: usize) {}
+
+    /// Bytes were written to a connection.
+    fn bytes_written(&self, _side: Side, _count: usize) {}
+
+    /// A request was started, by sending it or receiving its head.
+    fn request(&self, _side: Side, _tag: Tag, _method: &Method) {}
+
+    /// A response completed with this status, `duration` after its request
+    /// was started. A server counts from when the head of the request was
+    /// received, so not the time a connection was idle before it.
+    ///
+    /// Use `StatusCode::class` to aggregate by status class.
+    fn response(&self, _side: Side, _tag: Tag, _status: StatusCode, _duration: Duration) {}
+}
+
+/// A `Metrics` that discards every measurement.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoMetrics;
+
+impl Metrics for NoMetrics {}
+
+/// Receives the events of a `Client`'s requests, set with
+/// `Client::set_events`.
+///
+/// The events of a request arrive in order, on the thread sending it:
+/// resolving the host and connecting to it, when the request needs a new
+/// connection, then writing the request and reading its response. Only the
+/// attempts to connect to the IPv6 and IPv4 addresses of a host, which race
+/// each other, are reported from threads of their own.
+///
+/// Every method has a default implementation that does nothing.
+///
+/// ```
+/// use std::sync::Mutex;
+/// use std::time::Instant;
+/// use hyper::metrics::{ClientEvents, Tag};
+///
+/// #[derive(Default)]
+/// struct TimeToFirstByte(Mutex<Option<Instant>>);
+///
+/// impl ClientEvents for TimeToFirstByte {
+///     fn request_written(&self, _: Tag, _: u64) {
+///         *self.0.lock().unwrap() = Some(Instant::now());
+///     }
+///
+///     fn first_byte(&self, tag: Tag) {
+///         if let Some(written) = self.0.lock().unwrap().take() {
+///             println!("[{}] waited {:?} for the response", tag.connection, written.elapsed());
+///         }
+///     }
+/// }
+/// ```
+pub trait ClientEvents: Send + Sync {
+    /// Resolving `host` started.
+    fn dns_start(&self, _host: &str) {}
+
+    /// Resolving `host` ended, with its addresses, or the error it failed
+    /// with.
+    fn dns_end(&self, _host: &str, _result: Result<&[SocketAddr], &io::Error>) {}
+
+    /// Connecting to `addr` started.
+    fn connect_start(&self, _addr: SocketAddr) {}
+
+    /// Connecting to `addr` ended, or failed with an error.
+    fn connect_end(&self, _addr: SocketAddr, _result: Result<(), &io::Error>) {}
+
+    /// A TLS handshake with `host` started, once connected.
+    fn tls_start(&self, _host: &str) {}
+
+    /// The TLS handshake with `host` ended, or failed with an error.
+    fn tls_end(&self, _host: &str, _result: Result<(), &Error>) {}
+
+    /// The request was written, with a body of `body_bytes` bytes.
+    fn request_written(&self, _tag: Tag, _body_bytes: u64) {}
+
+    /// The first byte of the response arrived.
+    fn first_byte(&self, _tag: Tag) {}
+
+    /// The body of the response was read to its end, `body_bytes` bytes of
+    /// it.
+    fn response_complete(&self, _tag: Tag, _body_bytes: u64) {}
+}
+
+impl<'a> fmt::Debug for ClientEvents + 'a {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad("ClientEvents")
+    }
+}
+
+/// A `NetworkStream` wrapper that reports the bytes moved through it.
+#[derive(Clone)]
+pub struct MeteredStream<S> {
+    inner: S,
+    side: Side,
+    metrics: Arc<Metrics>,
+}
+
+impl<S> MeteredStream<S> {
+    /// Wrap a stream, reporting its traffic to `metrics`.
+    pub fn new(inner: S, side: Side, metrics: Arc<Metrics>) -> MeteredStream<S> {
+        MeteredStream {
+            inner: inner,
+            side: side,
+            metrics: metrics,
+        }
+    }
+
+    /// Get a reference to the wrapped stream.
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Get the wrapped stream back.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Read> Read for MeteredStream<S> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = try!(self.inner.read(buf));
+        self.metrics.bytes_read(self.side, n);
+        Ok(n)
+    }
+}
+
+impl<S: Write> Write for MeteredStream<S> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = try!(self.inner.write(buf));
+        self.metrics.bytes_written(self.side, n);
+        Ok(n)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<S: NetworkStream> NetworkStream for MeteredStream<S> {
+    #[inline]
+    fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        self.inner.peer_addr()
+    }
+
+    #[inline]
+    fn close(&mut self, how: Shutdown) -> io::Result<()> {
+        self.inner.close(how)
+    }
+
+    #[inline]
+    fn local_addr(&mut self) -> Option<SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    #[inline]
+    fn tls_info(&mut self) -> Option<TlsInfo> {
+        self.inner.tls_info()
+    }
+
+    #[inline]
+    fn peer_certificate(&mut self) -> Option<Vec<u8>> {
+        self.inner.peer_certificate()
+    }
+
+    #[inline]
+    fn set_read_timeout(&mut self, dur: Option<Duration>) -> io::Result<()> {
+        self.inner.set_read_timeout(dur)
+    }
+
+    #[inline]
+    fn set_write_timeout(&mut self, dur: Option<Duration>) -> io::Result<()> {
+        self.inner.set_write_timeout(dur)
+    }
+
+    #[inline]
+    fn set_read_deadline(&mut self, deadline: Option<Instant>) -> io::Result<()> {
+        self.inner.set_read_deadline(deadline)
+    }
+
+    #[inline]
+    fn set_write_deadline(&mut self, deadline: Option<Instant>) -> io::Result<()> {
+        self.inner.set_write_deadline(deadline)
+    }
+
+    #[inline]
+    fn tag(&mut self) -> Option<Tag> {
+        self.inner.tag()
+    }
+}
+
+/// A `NetworkConnector` wrapper that reports opened connections, and the
+/// bytes moved through them, to a `Metrics`.
+///
+/// A `Client` only measures requests and responses itself; to measure its
+/// connections too, create it with this connector.
+///
+/// ```
+/// use std::sync::Arc;
+/// use hyper::Client;
+/// use hyper::client::Pool;
+/// use hyper::metrics::{MeteredConnector, NoMetrics};
+/// use hyper::net::HttpConnector;
+///
+/// let metrics = Arc::new(NoMetrics);
+/// let connector = MeteredConnector::new(HttpConnector, metrics.clone());
+/// let mut client = Client::with_connector(Pool::with_connector(Default::default(), connector));
+/// client.set_metrics(metrics);
+/// ```
+pub struct MeteredConnector<C> {
+    inner: C,
+    metrics: Arc<Metrics>,
+}
+
+impl<C> MeteredConnector<C> {
+    /// Wrap a connector, reporting to `metrics`.
+    pub fn new(inner: C, metrics: Arc<Metrics>) -> MeteredConnector<C> {
+        MeteredConnector {
+            inner: inner,
+            metrics: metrics,
+        }
+    }
+}
+
+impl<C, S> NetworkConnector for MeteredConnector<C>
+where C: NetworkConnector<Stream=S>, S: NetworkStream + Send {
+    type Stream = MeteredStream<S>;
+
+    fn connect(&self, host: &str, port: u16, scheme: &str) -> ::Result<MeteredStream<S>> {
+        self.connect_with(host, port, scheme, &Extensions::new())
+    }
+
+    fn connect_with(&self, host: &str, port: u16, scheme: &str, extensions: &Extensions)
+                    -> ::Result<MeteredStream<S>> {
+        let stream = try!(self.inner.connect_with(host, port, scheme, extensions));
+        self.metrics.connection_opened(Side::Client);
+        Ok(MeteredStream::new(stream, Side::Client, self.metrics.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::sync::{Arc, Mutex};
+
+    use mock::MockStream;
+    use net::NetworkConnector;
+    use super::{Metrics, MeteredConnector, MeteredStream, Side};
+
+    #[derive(Default)]
+    struct Counts {
+        read: Mutex<usize>,
+        written: Mutex<usize>,
+        opened: Mutex<usize>,
+    }
+
+    impl Metrics for Counts {
+        fn connection_opened(&self, _: Side) {
+            *self.opened.lock().unwrap() += 1;
+        }
+
+        fn bytes_read(&self, _: Side, count: usize) {
+            *self.read.lock().unwrap() += count;
+        }
+
+        fn bytes_written(&self, _: Side, count: usize) {
+            *self.written.lock().unwrap() += count;
+        }
+    }
+
+    #[test]
+    fn test_metered_stream() {
+        let counts = Arc::new(Counts::default());
+        let mut stream = MeteredStream::new(MockStream::with_input(b"hello"),
+                                            Side::Server, counts.clone());
+        let mut buf = String::new();
+        stream.read_to_string(&mut buf).unwrap();
+        stream.write_all(b"hi").unwrap();
+
+        assert_eq!(*counts.read.lock().unwrap(), 5);
+        assert_eq!(*counts.written.lock().unwrap(), 2);
+        assert_eq!(stream.into_inner().write, b"hi");
+    }
+
+    #[test]
+    fn test_metered_connector() {
+        struct Connector;
+        impl NetworkConnector for Connector {
+            type Stream = MockStream;
+            fn connect(&self, _: &str, _: u16, _: &str) -> ::Result<MockStream> {
+                Ok(MockStream::new())
+            }
+        }
+
+        let counts = Arc::new(Counts::default());
+        let connector = MeteredConnector::new(Connector, counts.clone());
+        connector.connect("127.0.0.1", 80, "http").unwrap();
+        connector.connect("127.0.0.1", 80, "http").unwrap();
+        assert_eq!(*counts.opened.lock().unwrap(), 2);
+    }
+}
@@ -0,0 +1,156 @@
+//! An in-memory loopback between a `Client` and a `Handler`.
+//!
+//! A `Loopback` is a connector whose connections are served directly by a
+//! `Handler`, exactly as a `Server` would serve them, but without any sockets
+//! or threads. Requests and responses still go through the full HTTP/1
+//! framing, so headers, bodies, and keep-alive behave as they would over TCP.
+//!
+//! ```
+//! # extern crate hyper;
+//! # fn main() {
+//! use std::io::Read;
+//! use hyper::Client;
+//! use hyper::mock::loopback::Loopback;
+//! use hyper::server::{Request, Response};
+//!
+//! let client = Client::with_connector(Loopback::new(|req: Request, res: Response| {
+//!     let body = format!("you sent a {}", req.method);
+//!     res.send(body.as_bytes()).unwrap();
+//! }));
+//!
+//! let mut res = client.get("http://loopback/hello").send().unwrap();
+//! let mut body = String::new();
+//! res.read_to_string(&mut body).unwrap();
+//! assert_eq!(body, "you sent a GET");
+//! # }
+//! ```
+use std::io::{self, Cursor, Read, Write};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use net::{NetworkConnector, NetworkStream};
+use server::{self, Handler};
+use super::MockStream;
+
+/// A connector whose connections are served in memory by a `Handler`.
+pub struct Loopback<H> {
+    handler: Arc<H>,
+}
+
+impl<H: Handler + 'static> Loopback<H> {
+    /// Create a connector that serves every connection with `handler`.
+    pub fn new(handler: H) -> Loopback<H> {
+        Loopback {
+            handler: Arc::new(handler),
+        }
+    }
+}
+
+impl<H: Handler + 'static> NetworkConnector for Loopback<H> {
+    type Stream = LoopbackStream<H>;
+
+    fn connect(&self, _host: &str, _port: u16, _scheme: &str) -> ::Result<LoopbackStream<H>> {
+        Ok(LoopbackStream {
+            handler: self.handler.clone(),
+            request: Vec::new(),
+            response: None,
+        })
+    }
+}
+
+/// The client end of a `Loopback` connection.
+///
+/// Everything written is buffered until the client first reads, at which
+/// point the buffered requests are served by the handler, and its responses
+/// become readable. Writing again afterwards begins a new exchange on the
+/// same connection.
+pub struct LoopbackStream<H> {
+    handler: Arc<H>,
+    request: Vec<u8>,
+    response: Option<Cursor<Vec<u8>>>,
+}
+
+impl<H: Handler + 'static> Read for LoopbackStream<H> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.response.is_none() {
+            let mut stream = MockStream::with_input(&self.request);
+            server::serve_stream(&*self.handler, &mut stream);
+            self.request.clear();
+            self.response = Some(Cursor::new(stream.write));
+        }
+        self.response.as_mut().unwrap().read(buf)
+    }
+}
+
+impl<H: Handler + 'static> Write for LoopbackStream<H> {
+    fn write(&mut self, msg: &[u8]) -> io::Result<usize> {
+        self.response = None;
+        self.request.extend(msg);
+        Ok(msg.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<H: Handler + 'static> NetworkStream for LoopbackStream<H> {
+    fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        Ok("127.0.0.1:1337".parse().unwrap())
+    }
+}
+
+#[cfg(all(test, feature = "client"))]
+mod tests {
+    use std::io::Read;
+
+    use client::{Client, Pool};
+    use header::{ContentLength, UserAgent};
+    use method::Method;
+    use server::{Request, Response};
+    use status::StatusCode;
+    use super::Loopback;
+
+    fn echo(mut req: Request, mut res: Response) {
+        let mut body = Vec::new();
+        req.read_to_end(&mut body).unwrap();
+        if let Some(agent) = req.headers.get::<UserAgent>() {
+            res.headers_mut().set(agent.clone());
+        }
+        if req.method == Method::Delete {
+            *res.status_mut() = StatusCode::MethodNotAllowed;
+        }
+        res.send(&body).unwrap();
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let client = Client::with_connector(Loopback::new(echo));
+        let mut res = client.post("http://loopback/")
+            .header(UserAgent("loopback-test".to_owned()))
+            .body("ping")
+            .send().unwrap();
+
+        assert_eq!(res.status, StatusCode::Ok);
+        assert_eq!(res.headers.get(), Some(&ContentLength(4)));
+        assert_eq!(res.headers.get(), Some(&UserAgent("loopback-test".to_owned())));
+        let mut body = String::new();
+        res.read_to_string(&mut body).unwrap();
+        assert_eq!(body, "ping");
+
+        let res = client.delete("http://loopback/").send().unwrap();
+        assert_eq!(res.status, StatusCode::MethodNotAllowed);
+    }
+
+    #[test]
+    fn test_keep_alive_pooled() {
+        let pool = Pool::with_connector(Default::default(), Loopback::new(echo));
+        let client = Client::with_connector(pool);
+        for body in &["one", "two", "three"] {
+            let mut res = client.post("http://loopback/").body(*body).send().unwrap();
+            let mut read = String::new();
+            res.read_to_string(&mut read).unwrap();
+            assert_eq!(read, *body);
+        }
+    }
+}
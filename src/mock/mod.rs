@@ -0,0 +1,583 @@
+//! Mock streams and connectors, for testing HTTP code without sockets.
+//!
+//! This module is available with the `mock` feature.
+//!
+//! ```
+//! # #[macro_use] extern crate hyper;
+//! # fn main() {
+//! use std::io::Read;
+//! use hyper::Client;
+//! use hyper::mock::ScriptedConnector;
+//!
+//! let connector = ScriptedConnector::new();
+//! connector.respond("http://example.domain", b"\
+//!     HTTP/1.1 200 OK\r\n\
+//!     Content-Length: 5\r\n\
+//!     \r\n\
+//!     hello\
+//! ");
+//!
+//! let client = Client::with_connector(connector.clone());
+//! let mut res = client.get("http://example.domain/greeting").send().unwrap();
+//! let mut body = String::new();
+//! res.read_to_string(&mut body).unwrap();
+//! assert_eq!(body, "hello");
+//!
+//! let request = String::from_utf8(connector.written().remove(0)).unwrap();
+//! assert!(request.starts_with("GET /greeting HTTP/1.1\r\n"));
+//! connector.assert_done();
+//! # }
+//! ```
+use std::fmt;
+use std::ascii::AsciiExt;
+use std::io::{self, Read, Write, Cursor};
+use std::cell::RefCell;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use solicit::http::HttpScheme;
+use solicit::http::transport::TransportStream;
+use solicit::http::frame::{SettingsFrame, Frame};
+use solicit::http::connection::{HttpConnection, EndStream, DataChunk};
+
+use header::Headers;
+use metrics::Tag;
+use net::{NetworkStream, NetworkConnector, TlsInfo};
+
+#[cfg(feature = "server")]
+pub mod loopback;
+pub mod vcr;
+
+/// A `NetworkStream` that reads from a buffer, and records what is written.
+pub struct MockStream {
+    /// The bytes that will be read from this stream.
+    pub read: Cursor<Vec<u8>>,
+    /// The bytes that have been written to this stream.
+    pub write: Vec<u8>,
+}
+
+impl Clone for MockStream {
+    fn clone(&self) -> MockStream {
+        MockStream {
+            read: Cursor::new(self.read.get_ref().clone()),
+            write: self.write.clone()
+        }
+    }
+}
+
+impl fmt::Debug for MockStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MockStream {{ read: {:?}, write: {:?} }}", self.read.get_ref(), self.write)
+    }
+}
+
+impl PartialEq for MockStream {
+    fn eq(&self, other: &MockStream) -> bool {
+        self.read.get_ref() == other.read.get_ref() && self.write == other.write
+    }
+}
+
+impl MockStream {
+    /// Creates a `MockStream` with nothing to read.
+    pub fn new() -> MockStream {
+        MockStream {
+            read: Cursor::new(vec![]),
+            write: vec![],
+        }
+    }
+
+    /// Creates a `MockStream` that will read the provided bytes.
+    pub fn with_input(input: &[u8]) -> MockStream {
+        MockStream {
+            read: Cursor::new(input.to_vec()),
+            write: vec![]
+        }
+    }
+
+    /// Asserts that exactly `expected` has been written to this stream.
+    ///
+    /// # Panics
+    ///
+    /// Panics with both byte sequences, shown as text, if they differ.
+    pub fn assert_written(&self, expected: &[u8]) {
+        if self.write != expected {
+            panic!("MockStream written bytes differ:\n written: {:?}\nexpected: {:?}",
+                   String::from_utf8_lossy(&self.write), String::from_utf8_lossy(expected));
+        }
+    }
+}
+
+impl Read for MockStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read.read(buf)
+    }
+}
+
+impl Write for MockStream {
+    fn write(&mut self, msg: &[u8]) -> io::Result<usize> {
+        Write::write(&mut self.write, msg)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl NetworkStream for MockStream {
+    fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        Ok("127.0.0.1:1337".parse().unwrap())
+    }
+
+    // nothing blocks, so there is nothing to time out
+    fn set_read_timeout(&mut self, _dur: Option<Duration>) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn set_write_timeout(&mut self, _dur: Option<Duration>) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A wrapper around a `MockStream` that allows one to clone it and keep an independent copy to the
+/// same underlying stream.
+#[derive(Clone)]
+pub struct CloneableMockStream {
+    /// The shared stream.
+    pub inner: Arc<Mutex<MockStream>>,
+}
+
+impl Write for CloneableMockStream {
+    fn write(&mut self, msg: &[u8]) -> io::Result<usize> {
+        self.inner.lock().unwrap().write(msg)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.lock().unwrap().flush()
+    }
+}
+
+impl Read for CloneableMockStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.lock().unwrap().read(buf)
+    }
+}
+
+impl TransportStream for CloneableMockStream {
+    fn try_split(&self) -> Result<CloneableMockStream, io::Error> {
+        Ok(self.clone())
+    }
+
+    fn close(&mut self) -> Result<(), io::Error> {
+        Ok(())
+    }
+}
+
+impl NetworkStream for CloneableMockStream {
+    fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        self.inner.lock().unwrap().peer_addr()
+    }
+}
+
+impl CloneableMockStream {
+    /// Wraps a `MockStream` so that it can be shared.
+    pub fn with_stream(stream: MockStream) -> CloneableMockStream {
+        CloneableMockStream {
+            inner: Arc::new(Mutex::new(stream)),
+        }
+    }
+}
+
+/// A connector whose every connection is a new, empty `MockStream`.
+pub struct MockConnector;
+
+impl NetworkConnector for MockConnector {
+    type Stream = MockStream;
+
+    fn connect(&self, _host: &str, _port: u16, _scheme: &str) -> ::Result<MockStream> {
+        Ok(MockStream::new())
+    }
+}
+
+/// Defines a connector type which responds to each `"scheme://host"` with a
+/// fixed response.
+///
+/// new connectors must be created if you wish to intercept requests.
+///
+/// ```
+/// # #[macro_use] extern crate hyper;
+/// # fn main() {
+/// mock_connector!(Example {
+///     "http://example.domain" => "HTTP/1.1 204 No Content\r\n\r\n"
+/// });
+///
+/// let client = hyper::Client::with_connector(Example);
+/// let res = client.get("http://example.domain").send().unwrap();
+/// assert_eq!(res.status, hyper::status::StatusCode::NoContent);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! mock_connector (
+    ($name:ident {
+        $($url:expr => $res:expr)*
+    }) => (
+
+        struct $name;
+
+        impl $crate::net::NetworkConnector for $name {
+            type Stream = $crate::mock::MockStream;
+            fn connect(&self, host: &str, _port: u16, scheme: &str)
+                    -> $crate::Result<$crate::mock::MockStream> {
+                use std::collections::HashMap;
+                let mut map = HashMap::new();
+                $(map.insert($url, $res);)*
+
+
+                let key = format!("{}://{}", scheme, host);
+                // ignore port for now
+                match map.get(&*key) {
+                    Some(&res) => Ok($crate::mock::MockStream::with_input(res.as_bytes())),
+                    None => panic!("{:?} doesn't know url {}", stringify!($name), key)
+                }
+            }
+        }
+
+    )
+);
+
+/// A stream wrapper that fails with an error of the given kind once a number
+/// of bytes have been read from, or written to, it.
+pub struct FaultyStream<S> {
+    inner: S,
+    kind: io::ErrorKind,
+    read_budget: Option<usize>,
+    write_budget: Option<usize>,
+}
+
+impl<S> FaultyStream<S> {
+    /// Wraps a stream, which will fail with errors of `kind`.
+    ///
+    /// Until `fail_read_after` or `fail_write_after` are used, the stream
+    /// behaves exactly like the one it wraps.
+    pub fn new(inner: S, kind: io::ErrorKind) -> FaultyStream<S> {
+        FaultyStream {
+            inner: inner,
+            kind: kind,
+            read_budget: None,
+            write_budget: None,
+        }
+    }
+
+    /// Fail reads once `count` bytes have been read.
+    pub fn fail_read_after(mut self, count: usize) -> FaultyStream<S> {
+        self.read_budget = Some(count);
+        self
+    }
+
+    /// Fail writes once `count` bytes have been written.
+    pub fn fail_write_after(mut self, count: usize) -> FaultyStream<S> {
+        self.write_budget = Some(count);
+        self
+    }
+
+    /// Get a reference to the wrapped stream.
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+}
+
+fn spend(budget: &mut Option<usize>, kind: io::ErrorKind, len: usize) -> io::Result<usize> {
+    match *budget {
+        Some(0) => Err(io::Error::new(kind, "injected failure")),
+        Some(ref mut left) => {
+            let len = if len < *left { len } else { *left };
+            *left -= len;
+            Ok(len)
+        }
+        None => Ok(len)
+    }
+}
+
+impl<S: Read> Read for FaultyStream<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = try!(spend(&mut self.read_budget, self.kind, buf.len()));
+        let n = try!(self.inner.read(&mut buf[..len]));
+        self.read_budget.as_mut().map(|left| *left += len - n);
+        Ok(n)
+    }
+}
+
+impl<S: Write> Write for FaultyStream<S> {
+    fn write(&mut self, msg: &[u8]) -> io::Result<usize> {
+        let len = try!(spend(&mut self.write_budget, self.kind, msg.len()));
+        let n = try!(self.inner.write(&msg[..len]));
+        self.write_budget.as_mut().map(|left| *left += len - n);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<S: NetworkStream> NetworkStream for FaultyStream<S> {
+    fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        self.inner.peer_addr()
+    }
+
+    fn local_addr(&mut self) -> Option<SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    fn tls_info(&mut self) -> Option<TlsInfo> {
+        self.inner.tls_info()
+    }
+
+    fn peer_certificate(&mut self) -> Option<Vec<u8>> {
+        self.inner.peer_certificate()
+    }
+
+    fn set_read_timeout(&mut self, dur: Option<Duration>) -> io::Result<()> {
+        self.inner.set_read_timeout(dur)
+    }
+
+    fn set_write_timeout(&mut self, dur: Option<Duration>) -> io::Result<()> {
+        self.inner.set_write_timeout(dur)
+    }
+
+    fn set_read_deadline(&mut self, deadline: Option<Instant>) -> io::Result<()> {
+        self.inner.set_read_deadline(deadline)
+    }
+
+    fn set_write_deadline(&mut self, deadline: Option<Instant>) -> io::Result<()> {
+        self.inner.set_write_deadline(deadline)
+    }
+
+    fn tag(&mut self) -> Option<Tag> {
+        self.inner.tag()
+    }
+}
+
+/// A connector that answers connections from a script of responses.
+///
+/// Each call to `respond` queues one response for a `"scheme://host"`, which
+/// is used up by the next connection to that host. Connecting to a host with
+/// no queued response fails with `ConnectionRefused`.
+///
+/// Clones of a `ScriptedConnector` share the same script, so a clone can be
+/// kept to inspect what was written after the original is given to a `Client`.
+#[derive(Clone)]
+pub struct ScriptedConnector {
+    inner: Arc<Mutex<Script>>,
+}
+
+struct Script {
+    responses: Vec<(String, Vec<u8>, Option<(usize, io::ErrorKind)>)>,
+    streams: Vec<CloneableMockStream>,
+}
+
+impl ScriptedConnector {
+    /// Creates a connector with an empty script.
+    pub fn new() -> ScriptedConnector {
+        ScriptedConnector {
+            inner: Arc::new(Mutex::new(Script {
+                responses: Vec::new(),
+                streams: Vec::new(),
+            }))
+        }
+    }
+
+    /// Queues the raw bytes of a response for the next connection to `origin`,
+    /// such as `"http://example.domain"`.
+    pub fn respond(&self, origin: &str, response: &[u8]) {
+        self.inner.lock().unwrap().responses.push((origin.to_owned(), response.to_vec(), None));
+    }
+
+    /// Queues a response whose connection fails with an error of `kind`
+    /// once `after` bytes of it have been read.
+    pub fn respond_with_error(&self, origin: &str, response: &[u8], after: usize,
+                              kind: io::ErrorKind) {
+        self.inner.lock().unwrap().responses.push(
+            (origin.to_owned(), response.to_vec(), Some((after, kind))));
+    }
+
+    /// The bytes written on each connection so far, in connection order.
+    pub fn written(&self) -> Vec<Vec<u8>> {
+        self.inner.lock().unwrap().streams.iter().map(|stream| {
+            stream.inner.lock().unwrap().write.clone()
+        }).collect()
+    }
+
+    /// Asserts that every queued response has been used.
+    ///
+    /// # Panics
+    ///
+    /// Panics listing the origins whose responses were never requested.
+    pub fn assert_done(&self) {
+        let script = self.inner.lock().unwrap();
+        if !script.responses.is_empty() {
+            let origins: Vec<&str> = script.responses.iter().map(|r| &r.0[..]).collect();
+            panic!("ScriptedConnector responses never used: {:?}", origins);
+        }
+    }
+}
+
+impl NetworkConnector for ScriptedConnector {
+    type Stream = FaultyStream<CloneableMockStream>;
+
+    fn connect(&self, host: &str, _port: u16, scheme: &str)
+            -> ::Result<FaultyStream<CloneableMockStream>> {
+        let key = format!("{}://{}", scheme, host);
+        let mut script = self.inner.lock().unwrap();
+        let pos = match script.responses.iter().position(|r| r.0 == key) {
+            Some(pos) => pos,
+            None => return Err(io::Error::new(io::ErrorKind::ConnectionRefused,
+                                              "no scripted response").into())
+        };
+        let (_, response, fault) = script.responses.remove(pos);
+        let stream = CloneableMockStream::with_stream(MockStream::with_input(&response));
+        script.streams.push(stream.clone());
+        Ok(match fault {
+            Some((after, kind)) => FaultyStream::new(stream, kind).fail_read_after(after),
+            None => FaultyStream::new(stream, io::ErrorKind::Other),
+        })
+    }
+}
+
+impl TransportStream for MockStream {
+    fn try_split(&self) -> Result<MockStream, io::Error> {
+        Ok(self.clone())
+    }
+
+    fn close(&mut self) -> Result<(), io::Error> {
+        Ok(())
+    }
+}
+
+impl MockStream {
+    /// Creates a new `MockStream` that will return the response described by the parameters as an
+    /// HTTP/2 response. This will also include the correct server preface.
+    pub fn new_http2_response(status: &[u8], headers: &Headers, body: Option<Vec<u8>>)
+            -> MockStream {
+        let resp_bytes = build_http2_response(status, headers, body);
+        MockStream::with_input(&resp_bytes)
+    }
+}
+
+/// Builds up a sequence of bytes that represent a server's response based on the given parameters.
+pub fn build_http2_response(status: &[u8], headers: &Headers, body: Option<Vec<u8>>) -> Vec<u8> {
+    let mut conn = HttpConnection::new(MockStream::new(), MockStream::new(), HttpScheme::Http);
+    // Server preface first
+    conn.sender.write(&SettingsFrame::new().serialize()).unwrap();
+
+    let mut resp_headers: Vec<_> = headers.iter().map(|h| {
+        (h.name().to_ascii_lowercase().into_bytes(), h.value_string().into_bytes())
+    }).collect();
+    resp_headers.insert(0, (b":status".to_vec(), status.into()));
+
+    let end = if body.is_none() {
+        EndStream::Yes
+    } else {
+        EndStream::No
+    };
+    conn.send_headers(resp_headers, 1, end).unwrap();
+    if body.is_some() {
+        let chunk = DataChunk::new_borrowed(&body.as_ref().unwrap()[..], 1, EndStream::Yes);
+        conn.send_data(chunk).unwrap();
+    }
+
+    conn.sender.write
+}
+
+/// A mock connector that produces `MockStream`s that are set to return HTTP/2 responses.
+///
+/// This means that the streams' payloads are fairly opaque byte sequences (as HTTP/2 is a binary
+/// protocol), which can be understood only be HTTP/2 clients.
+pub struct MockHttp2Connector {
+    /// The list of streams that the connector returns, in the given order.
+    pub streams: RefCell<Vec<CloneableMockStream>>,
+}
+
+impl MockHttp2Connector {
+    /// Creates a new `MockHttp2Connector` with no streams.
+    pub fn new() -> MockHttp2Connector {
+        MockHttp2Connector {
+            streams: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Adds a new `CloneableMockStream` to the end of the connector's stream queue.
+    ///
+    /// Streams are returned in a FIFO manner.
+    pub fn add_stream(&mut self, stream: CloneableMockStream) {
+        self.streams.borrow_mut().push(stream);
+    }
+
+    /// Adds a new response stream that will be placed to the end of the connector's stream queue.
+    ///
+    /// Returns a separate `CloneableMockStream` that allows the user to inspect what is written
+    /// into the original stream.
+    pub fn new_response_stream(&mut self, status: &[u8], headers: &Headers, body: Option<Vec<u8>>)
+            -> CloneableMockStream {
+        let stream = MockStream::new_http2_response(status, headers, body);
+        let stream = CloneableMockStream::with_stream(stream);
+        let ret = stream.clone();
+        self.add_stream(stream);
+
+        ret
+    }
+}
+
+impl NetworkConnector for MockHttp2Connector {
+    type Stream = CloneableMockStream;
+    #[inline]
+    fn connect(&self, _host: &str, _port: u16, _scheme: &str)
+            -> ::Result<CloneableMockStream> {
+        Ok(self.streams.borrow_mut().remove(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read, Write};
+
+    use net::NetworkConnector;
+    use super::{FaultyStream, MockStream, ScriptedConnector};
+
+    #[test]
+    fn test_faulty_stream_read() {
+        let mut stream = FaultyStream::new(MockStream::with_input(b"hello world"),
+                                           io::ErrorKind::ConnectionReset).fail_read_after(5);
+        let mut buf = [0; 20];
+        assert_eq!(stream.read(&mut buf).unwrap(), 5);
+        assert_eq!(&buf[..5], b"hello");
+        assert_eq!(stream.read(&mut buf).unwrap_err().kind(), io::ErrorKind::ConnectionReset);
+    }
+
+    #[test]
+    fn test_faulty_stream_write() {
+        let mut stream = FaultyStream::new(MockStream::new(), io::ErrorKind::BrokenPipe)
+            .fail_write_after(3);
+        assert_eq!(stream.write(b"hello").unwrap(), 3);
+        assert_eq!(stream.write(b"lo").unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+        stream.get_ref().assert_written(b"hel");
+    }
+
+    #[test]
+    fn test_scripted_connector() {
+        let connector = ScriptedConnector::new();
+        connector.respond("http://a.domain", b"first");
+        connector.respond("http://b.domain", b"second");
+
+        let mut buf = String::new();
+        let mut b = connector.connect("b.domain", 80, "http").unwrap();
+        b.read_to_string(&mut buf).unwrap();
+        assert_eq!(buf, "second");
+        b.write_all(b"to b").unwrap();
+
+        assert!(connector.connect("b.domain", 80, "http").is_err());
+        connector.connect("a.domain", 80, "http").unwrap();
+        connector.assert_done();
+        assert_eq!(connector.written(), vec![b"to b".to_vec(), vec![]]);
+    }
+}
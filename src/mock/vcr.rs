@@ -0,0 +1,397 @@
+//! Record-and-replay of HTTP traffic, for deterministic tests.
+//!
+//! A `Vcr` wraps a real connector, and a cassette file. If the cassette does
+//! not exist yet, the `Vcr` records: connections are made with the wrapped
+//! connector, and the bytes exchanged on each are saved to the cassette. Once
+//! the cassette exists, the `Vcr` replays: no connections are made, and each
+//! request is answered with the response of the first unused recorded
+//! interaction that it matches.
+//!
+//! Each connection is recorded as one interaction, so the `Client` should not
+//! pool connections.
+//!
+//! ```no_run
+//! # #[macro_use] extern crate hyper;
+//! # fn main() {
+//! use hyper::Client;
+//! use hyper::mock::vcr::Vcr;
+//! use hyper::net::HttpConnector;
+//!
+//! let vcr = Vcr::new("tests/cassettes/example.json", HttpConnector).unwrap();
+//! let client = Client::with_connector(vcr);
+//! let res = client.get("http://example.domain").send().unwrap();
+//! # }
+//! ```
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Cursor, Read, Write};
+use std::net::{SocketAddr, Shutdown};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serialize::base64::{FromBase64, ToBase64, STANDARD};
+use serialize::json::Json;
+
+use extensions::Extensions;
+use net::{NetworkConnector, NetworkStream, TlsInfo};
+
+/// How a request is matched against recorded interactions when replaying.
+///
+/// Requests are only ever matched against interactions recorded for the
+/// same origin.
+#[derive(Clone, Copy)]
+pub enum Matching {
+    /// Match the request line, such as `GET /path HTTP/1.1`.
+    RequestLine,
+    /// Match every byte of the request.
+    Exact,
+    /// Match using a function given the recorded request, and the new one.
+    Custom(fn(&[u8], &[u8]) -> bool),
+}
+
+impl Matching {
+    fn matches(&self, recorded: &[u8], request: &[u8]) -> bool {
+        match *self {
+            Matching::RequestLine => request_line(recorded) == request_line(request),
+            Matching::Exact => recorded == request,
+            Matching::Custom(f) => f(recorded, request),
+        }
+    }
+}
+
+fn request_line(request: &[u8]) -> &[u8] {
+    match request.windows(2).position(|w| w == b"\r\n") {
+        Some(pos) => &request[..pos],
+        None => request,
+    }
+}
+
+struct Interaction {
+    origin: String,
+    request: Vec<u8>,
+    response: Vec<u8>,
+}
+
+struct Cassette {
+    path: PathBuf,
+    interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    fn load(path: &Path) -> ::Result<Vec<Interaction>> {
+        let json = match Json::from_reader(&mut try!(File::open(path))) {
+            Ok(json) => json,
+            Err(e) => return Err(invalid(&format!("{}", e)).into())
+        };
+        let list = match json.find("interactions").and_then(|j| j.as_array()) {
+            Some(list) => list,
+            None => return Err(invalid("missing interactions").into())
+        };
+        let mut interactions = Vec::with_capacity(list.len());
+        for item in list {
+            let field = |name: &str| item.find(name).and_then(|j| j.as_string());
+            match (field("origin"), field("request"), field("response")) {
+                (Some(origin), Some(request), Some(response)) => {
+                    interactions.push(Interaction {
+                        origin: origin.to_owned(),
+                        request: try!(request.from_base64().map_err(|_| invalid("bad request"))),
+                        response: try!(response.from_base64().map_err(|_| invalid("bad response"))),
+                    });
+                }
+                _ => return Err(invalid("malformed interaction").into())
+            }
+        }
+        Ok(interactions)
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let list = self.interactions.iter().map(|interaction| {
+            let mut obj = BTreeMap::new();
+            obj.insert("origin".to_owned(), Json::String(interaction.origin.clone()));
+            obj.insert("request".to_owned(), Json::String(interaction.request.to_base64(STANDARD)));
+            obj.insert("response".to_owned(),
+                       Json::String(interaction.response.to_base64(STANDARD)));
+            Json::Object(obj)
+        }).collect();
+        let mut root = BTreeMap::new();
+        root.insert("interactions".to_owned(), Json::Array(list));
+        let mut file = try!(File::create(&self.path));
+        write!(file, "{}", Json::Object(root).pretty())
+    }
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("invalid cassette: {}", msg))
+}
+
+/// A connector that records traffic to a cassette file, or replays it.
+pub struct Vcr<C> {
+    inner: C,
+    matching: Matching,
+    replaying: bool,
+    cassette: Arc<Mutex<Cassette>>,
+}
+
+impl<C: NetworkConnector> Vcr<C> {
+    /// Create a `Vcr` for the cassette at `path`.
+    ///
+    /// If the file exists, its interactions are loaded to be replayed.
+    /// Otherwise, connections made with `inner` are recorded to it.
+    pub fn new<P: AsRef<Path>>(path: P, inner: C) -> ::Result<Vcr<C>> {
+        let path = path.as_ref().to_path_buf();
+        let replaying = path.exists();
+        let interactions = if replaying {
+            try!(Cassette::load(&path))
+        } else {
+            Vec::new()
+        };
+        Ok(Vcr {
+            inner: inner,
+            matching: Matching::RequestLine,
+            replaying: replaying,
+            cassette: Arc::new(Mutex::new(Cassette {
+                path: path,
+                interactions: interactions,
+            })),
+        })
+    }
+
+    /// Set how requests are matched when replaying.
+    ///
+    /// The default is `Matching::RequestLine`.
+    pub fn set_matching(&mut self, matching: Matching) {
+        self.matching = matching;
+    }
+
+    /// Whether this `Vcr` is replaying a cassette, rather than recording one.
+    pub fn is_replaying(&self) -> bool {
+        self.replaying
+    }
+}
+
+impl<C: NetworkConnector> NetworkConnector for Vcr<C> {
+    type Stream = VcrStream;
+
+    fn connect(&self, host: &str, port: u16, scheme: &str) -> ::Result<VcrStream> {
+        self.connect_with(host, port, scheme, &Extensions::new())
+    }
+
+    fn connect_with(&self, host: &str, port: u16, scheme: &str, extensions: &Extensions)
+                    -> ::Result<VcrStream> {
+        let kind = if self.replaying {
+            Kind::Replaying(None)
+        } else {
+            let stream = try!(self.inner.connect_with(host, port, scheme, extensions));
+            Kind::Recording(stream.into())
+        };
+        Ok(VcrStream {
+            origin: format!("{}://{}:{}", scheme, host, port),
+            matching: self.matching,
+            cassette: self.cassette.clone(),
+            kind: kind,
+            request: Vec::new(),
+            response: Vec::new(),
+        })
+    }
+}
+
+/// A stream being recorded or replayed by a `Vcr`.
+pub struct VcrStream {
+    origin: String,
+    matching: Matching,
+    cassette: Arc<Mutex<Cassette>>,
+    kind: Kind,
+    request: Vec<u8>,
+    response: Vec<u8>,
+}
+
+enum Kind {
+    Recording(Box<NetworkStream + Send>),
+    Replaying(Option<Cursor<Vec<u8>>>),
+}
+
+impl VcrStream {
+    fn replay(&self) -> io::Result<Cursor<Vec<u8>>> {
+        let mut cassette = self.cassette.lock().unwrap();
+        let pos = cassette.interactions.iter().position(|interaction| {
+            interaction.origin == self.origin &&
+                self.matching.matches(&interaction.request, &self.request)
+        });
+        match pos {
+            Some(pos) => Ok(Cursor::new(cassette.interactions.remove(pos).response)),
+            None => Err(io::Error::new(io::ErrorKind::NotFound, format!(
+                "no recorded interaction for {} matches {:?}", self.origin,
+                String::from_utf8_lossy(request_line(&self.request)))))
+        }
+    }
+}
+
+impl Read for VcrStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Kind::Replaying(None) = self.kind {
+            let response = try!(self.replay());
+            self.kind = Kind::Replaying(Some(response));
+        }
+        match self.kind {
+            Kind::Recording(ref mut stream) => {
+                let n = try!(stream.read(buf));
+                self.response.extend(&buf[..n]);
+                Ok(n)
+            }
+            Kind::Replaying(Some(ref mut response)) => response.read(buf),
+            Kind::Replaying(None) => unreachable!(),
+        }
+    }
+}
+
+impl Write for VcrStream {
+    fn write(&mut self, msg: &[u8]) -> io::Result<usize> {
+        let n = match self.kind {
+            Kind::Recording(ref mut stream) => try!(stream.write(msg)),
+            Kind::Replaying(_) => msg.len(),
+        };
+        self.request.extend(&msg[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.kind {
+            Kind::Recording(ref mut stream) => stream.flush(),
+            Kind::Replaying(_) => Ok(()),
+        }
+    }
+}
+
+impl NetworkStream for VcrStream {
+    fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        match self.kind {
+            Kind::Recording(ref mut stream) => stream.peer_addr(),
+            Kind::Replaying(_) => Ok("127.0.0.1:0".parse().unwrap()),
+        }
+    }
+
+    fn close(&mut self, how: Shutdown) -> io::Result<()> {
+        match self.kind {
+            Kind::Recording(ref mut stream) => stream.close(how),
+            Kind::Replaying(_) => Ok(()),
+        }
+    }
+
+    fn local_addr(&mut self) -> Option<SocketAddr> {
+        match self.kind {
+            Kind::Recording(ref mut stream) => stream.local_addr(),
+            Kind::Replaying(_) => None,
+        }
+    }
+
+    fn tls_info(&mut self) -> Option<TlsInfo> {
+        match self.kind {
+            Kind::Recording(ref mut stream) => stream.tls_info(),
+            Kind::Replaying(_) => None,
+        }
+    }
+
+    fn peer_certificate(&mut self) -> Option<Vec<u8>> {
+        match self.kind {
+            Kind::Recording(ref mut stream) => stream.peer_certificate(),
+            Kind::Replaying(_) => None,
+        }
+    }
+
+    fn set_read_timeout(&mut self, dur: Option<Duration>) -> io::Result<()> {
+        match self.kind {
+            Kind::Recording(ref mut stream) => stream.set_read_timeout(dur),
+            Kind::Replaying(_) => Ok(()),
+        }
+    }
+
+    fn set_write_timeout(&mut self, dur: Option<Duration>) -> io::Result<()> {
+        match self.kind {
+            Kind::Recording(ref mut stream) => stream.set_write_timeout(dur),
+            Kind::Replaying(_) => Ok(()),
+        }
+    }
+
+    fn set_read_deadline(&mut self, deadline: Option<Instant>) -> io::Result<()> {
+        match self.kind {
+            Kind::Recording(ref mut stream) => stream.set_read_deadline(deadline),
+            Kind::Replaying(_) => Ok(()),
+        }
+    }
+
+    fn set_write_deadline(&mut self, deadline: Option<Instant>) -> io::Result<()> {
+        match self.kind {
+            Kind::Recording(ref mut stream) => stream.set_write_deadline(deadline),
+            Kind::Replaying(_) => Ok(()),
+        }
+    }
+}
+
+impl Drop for VcrStream {
+    fn drop(&mut self) {
+        if let Kind::Recording(_) = self.kind {
+            if self.request.is_empty() {
+                return;
+            }
+            let mut cassette = self.cassette.lock().unwrap();
+            cassette.interactions.push(Interaction {
+                origin: self.origin.clone(),
+                request: ::std::mem::replace(&mut self.request, Vec::new()),
+                response: ::std::mem::replace(&mut self.response, Vec::new()),
+            });
+            if let Err(e) = cassette.save() {
+                error!("error saving cassette {:?}: {}", cassette.path, e);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "client"))]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::io::Read;
+
+    use client::Client;
+    use mock::{MockConnector, ScriptedConnector};
+    use super::{Matching, Vcr};
+
+    #[test]
+    fn test_record_then_replay() {
+        let path = env::temp_dir().join(format!("hyper-vcr-{}.json", ::std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let connector = ScriptedConnector::new();
+        connector.respond("http://example.domain", b"HTTP/1.1 200 OK\r\n\
+            Content-Length: 3\r\nConnection: close\r\n\r\nfoo");
+        connector.respond("http://example.domain", b"HTTP/1.1 200 OK\r\n\
+            Content-Length: 3\r\nConnection: close\r\n\r\nbar");
+
+        {
+            let vcr = Vcr::new(&path, connector).unwrap();
+            assert!(!vcr.is_replaying());
+            let client = Client::with_connector(vcr);
+            let mut body = String::new();
+            client.get("http://example.domain/foo").send().unwrap()
+                .read_to_string(&mut body).unwrap();
+            client.get("http://example.domain/bar").send().unwrap()
+                .read_to_string(&mut body).unwrap();
+            assert_eq!(body, "foobar");
+        }
+
+        let mut vcr = Vcr::new(&path, MockConnector).unwrap();
+        assert!(vcr.is_replaying());
+        vcr.set_matching(Matching::RequestLine);
+        let client = Client::with_connector(vcr);
+        let mut body = String::new();
+        client.get("http://example.domain/bar").send().unwrap()
+            .read_to_string(&mut body).unwrap();
+        client.get("http://example.domain/foo").send().unwrap()
+            .read_to_string(&mut body).unwrap();
+        assert_eq!(body, "barfoo");
+        assert!(client.get("http://example.domain/baz").send().is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
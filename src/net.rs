@@ -1,16 +1,26 @@
 //! A collection of traits abstracting over Listeners and Streams.
 use std::any::{Any, TypeId};
+use std::cmp;
+use std::collections::HashMap;
 use std::fmt;
 use std::io::{self, ErrorKind, Read, Write};
 use std::net::{SocketAddr, ToSocketAddrs, TcpStream, TcpListener, Shutdown};
 use std::mem;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
 
 #[cfg(feature = "openssl")]
-pub use self::openssl::Openssl;
+pub use self::openssl::{ClientCertError, Openssl};
 
+use httparse;
 use typeable::Typeable;
 use traitobject;
 
+use buffer;
+use extensions::Extensions;
+use metrics::{ClientEvents, Tag};
+
 /// The write-status indicating headers have not been written.
 pub enum Fresh {}
 
@@ -27,6 +37,23 @@ pub trait NetworkListener: Clone {
     /// Returns an iterator of streams.
     fn accept(&mut self) -> ::Result<Self::Stream>;
 
+    /// Accepts a connection, leaving any handshake that `accept` would make
+    /// on it, such as that of TLS, for `finish` to make. One thread can then
+    /// accept connections for others to finish, without a client that is
+    /// slow to make its handshake holding up the rest.
+    fn accept_pending(&mut self) -> ::Result<Pending<Self::Stream>> {
+        self.accept().map(Pending::ready)
+    }
+
+    /// Makes the handshake left on a connection by `accept_pending`.
+    fn finish(&self, pending: Pending<Self::Stream>) -> ::Result<Self::Stream> {
+        match pending.0 {
+            PendingState::Ready(stream) => Ok(stream),
+            PendingState::Handshake(..) => Err(::Error::Io(io::Error::new(io::ErrorKind::Other,
+                "the listener has no handshake to finish the connection with"))),
+        }
+    }
+
     /// Get the address this Listener ended up listening on.
     fn local_addr(&mut self) -> io::Result<SocketAddr>;
 
@@ -39,6 +66,31 @@ pub trait NetworkListener: Clone {
     }
 }
 
+/// A connection accepted by `NetworkListener::accept_pending`, which may
+/// still have to be finished with a handshake before it can be used.
+pub struct Pending<S>(PendingState<S>);
+
+enum PendingState<S> {
+    Ready(S),
+    Handshake(HttpStream),
+}
+
+impl<S> Pending<S> {
+    /// A connection that needs no handshake.
+    pub fn ready(stream: S) -> Pending<S> {
+        Pending(PendingState::Ready(stream))
+    }
+}
+
+impl<S> fmt::Debug for Pending<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            PendingState::Ready(..) => f.pad("Pending(Ready)"),
+            PendingState::Handshake(..) => f.pad("Pending(Handshake)"),
+        }
+    }
+}
+
 /// An iterator wrapper over a NetworkAcceptor.
 pub struct NetworkConnections<'a, N: NetworkListener + 'a>(&'a mut N);
 
@@ -58,6 +110,91 @@ pub trait NetworkStream: Read + Write + Any + Send + Typeable {
     fn close(&mut self, _how: Shutdown) -> io::Result<()> {
         Ok(())
     }
+
+    /// Get the local address of the underlying connection, if it has one.
+    #[inline]
+    fn local_addr(&mut self) -> Option<SocketAddr> {
+        None
+    }
+
+    /// Get the parameters negotiated by the TLS handshake, if the stream is
+    /// protected by TLS and its implementation reports any of them.
+    ///
+    /// hyper's `openssl` streams report the ALPN protocol with the `alpn`
+    /// feature, and nothing without it.
+    #[inline]
+    fn tls_info(&mut self) -> Option<TlsInfo> {
+        None
+    }
+
+    /// Get the certificate the peer presented, PEM-encoded, if the stream is
+    /// protected by TLS and the peer sent one.
+    #[inline]
+    fn peer_certificate(&mut self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Limit how long each read may block, or stop limiting it with `None`.
+    ///
+    /// By default this fails, for streams that can't time out.
+    #[inline]
+    fn set_read_timeout(&mut self, _dur: Option<Duration>) -> io::Result<()> {
+        Err(unsupported("read timeouts"))
+    }
+
+    /// Limit how long each write may block, or stop limiting it with `None`.
+    ///
+    /// By default this fails, for streams that can't time out.
+    #[inline]
+    fn set_write_timeout(&mut self, _dur: Option<Duration>) -> io::Result<()> {
+        Err(unsupported("write timeouts"))
+    }
+
+    /// Fail reads that would end after `deadline`, or stop doing so with
+    /// `None`.
+    ///
+    /// Unlike a timeout, a deadline bounds the total time of every read
+    /// until it is changed, however many there are. By default this fails;
+    /// wrap a stream that supports timeouts in a `DeadlineStream` to get
+    /// deadlines.
+    #[inline]
+    fn set_read_deadline(&mut self, _deadline: Option<Instant>) -> io::Result<()> {
+        Err(unsupported("read deadlines"))
+    }
+
+    /// Fail writes that would end after `deadline`, or stop doing so with
+    /// `None`.
+    ///
+    /// See `set_read_deadline`.
+    #[inline]
+    fn set_write_deadline(&mut self, _deadline: Option<Instant>) -> io::Result<()> {
+        Err(unsupported("write deadlines"))
+    }
+
+    /// Identify the connection, and how many requests have been started on
+    /// it, if the stream keeps track, as a `client::pool::PooledStream`
+    /// does.
+    #[inline]
+    fn tag(&mut self) -> Option<Tag> {
+        None
+    }
+}
+
+fn unsupported(what: &str) -> io::Error {
+    io::Error::new(ErrorKind::Other, format!("{} are not supported by this stream", what))
+}
+
+/// The parameters negotiated by a TLS handshake.
+///
+/// Each is `None` if the TLS implementation doesn't report it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TlsInfo {
+    /// The protocol version, such as `"TLSv1.2"`.
+    pub protocol: Option<String>,
+    /// The name of the cipher suite.
+    pub cipher: Option<String>,
+    /// The protocol chosen by ALPN, such as `b"h2"`.
+    pub alpn_protocol: Option<Vec<u8>>,
 }
 
 /// A connector creates a NetworkStream.
@@ -66,6 +203,18 @@ pub trait NetworkConnector {
     type Stream: Into<Box<NetworkStream + Send>>;
     /// Connect to a remote address.
     fn connect(&self, host: &str, port: u16, scheme: &str) -> ::Result<Self::Stream>;
+
+    /// Connect to a remote address for a request that carries `extensions`.
+    ///
+    /// A connector that makes transport decisions per request, such as
+    /// which interface or proxy to use, can read them from the
+    /// `extensions` a `RequestBuilder` was given. By default they are
+    /// ignored and this calls `connect`.
+    fn connect_with(&self, host: &str, port: u16, scheme: &str, extensions: &Extensions)
+                    -> ::Result<Self::Stream> {
+        let _ = extensions;
+        self.connect(host, port, scheme)
+    }
 }
 
 impl<T: NetworkStream + Send> From<T> for Box<NetworkStream + Send> {
@@ -136,12 +285,18 @@ impl NetworkStream + Send {
 }
 
 /// A `NetworkListener` for `HttpStream`s.
-pub struct HttpListener(TcpListener);
+pub struct HttpListener {
+    listener: TcpListener,
+    nodelay: bool,
+}
 
 impl Clone for HttpListener {
     #[inline]
     fn clone(&self) -> HttpListener {
-        HttpListener(self.0.try_clone().unwrap())
+        HttpListener {
+            listener: self.listener.try_clone().unwrap(),
+            nodelay: self.nodelay,
+        }
     }
 }
 
@@ -149,9 +304,57 @@ impl HttpListener {
 
     /// Start listening to an address over HTTP.
     pub fn new<To: ToSocketAddrs>(addr: To) -> ::Result<HttpListener> {
-        Ok(HttpListener(try!(TcpListener::bind(addr))))
+        Ok(HttpListener {
+            listener: try!(TcpListener::bind(addr)),
+            nodelay: false,
+        })
+    }
+
+    /// Start listening to an address over HTTP, creating the socket with
+    /// the given options.
+    ///
+    /// Like `new`, each address `addr` resolves to is tried in turn until
+    /// one can be bound.
+    pub fn with_options<To: ToSocketAddrs>(addr: To, options: &ListenOptions)
+                                           -> ::Result<HttpListener> {
+        let mut last_err = None;
+        for addr in try!(addr.to_socket_addrs()) {
+            match sockopt::bind(&addr, options) {
+                Ok(listener) => return Ok(HttpListener {
+                    listener: listener,
+                    nodelay: false,
+                }),
+                Err(e) => last_err = Some(e),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(ErrorKind::InvalidInput, "could not resolve to any addresses")
+        }).into())
+    }
+
+    /// Set `TCP_NODELAY` on each accepted connection, so small responses
+    /// aren't held back waiting to be coalesced.
+    pub fn set_nodelay(&mut self, nodelay: bool) {
+        self.nodelay = nodelay;
+    }
+
+    /// Only accept a connection once the client has sent data on it, or
+    /// `timeout` has passed, using `TCP_DEFER_ACCEPT`. `None` turns this off.
+    ///
+    /// Fails on platforms other than Linux.
+    pub fn set_defer_accept(&self, timeout: Option<Duration>) -> io::Result<()> {
+        let secs = timeout.map_or(0, |t| cmp::max(t.as_secs(), 1));
+        sockopt::defer_accept(&self.listener, cmp::min(secs, i32::MAX as u64) as i32)
     }
 
+    /// Accept data in the SYN of connections from clients holding a TCP
+    /// Fast Open cookie, with at most `queue_len` such connections pending.
+    /// `0` turns this off.
+    ///
+    /// Fails on platforms other than Linux.
+    pub fn set_fast_open(&self, queue_len: u32) -> io::Result<()> {
+        sockopt::fast_open(&self.listener, cmp::min(queue_len, i32::MAX as u32) as i32)
+    }
 }
 
 impl NetworkListener for HttpListener {
@@ -159,12 +362,165 @@ impl NetworkListener for HttpListener {
 
     #[inline]
     fn accept(&mut self) -> ::Result<HttpStream> {
-        Ok(HttpStream(try!(self.0.accept()).0))
+        let stream = try!(self.listener.accept()).0;
+        if self.nodelay {
+            try!(stream.set_nodelay(true));
+        }
+        Ok(HttpStream(stream))
     }
 
     #[inline]
     fn local_addr(&mut self) -> io::Result<SocketAddr> {
-        self.0.local_addr()
+        self.listener.local_addr()
+    }
+}
+
+/// Options for creating a listening socket, for `HttpListener::with_options`.
+///
+/// These are only applied on Unix; elsewhere the socket is created the way
+/// `TcpListener::bind` creates it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ListenOptions {
+    /// The most connections the system queues before they are accepted.
+    ///
+    /// Defaults to 128, as `TcpListener::bind` uses.
+    pub backlog: u32,
+    /// Whether to set `SO_REUSEADDR`, so the address can be bound again
+    /// while connections from an earlier listener linger in `TIME_WAIT`.
+    ///
+    /// Defaults to `true`.
+    pub reuse_addr: bool,
+    /// For an IPv6 address, whether to set `IPV6_V6ONLY`, accepting only
+    /// IPv6 connections rather than IPv4 ones too.
+    ///
+    /// Defaults to `None`, leaving the system's default, which differs
+    /// between platforms.
+    pub only_v6: Option<bool>,
+}
+
+impl Default for ListenOptions {
+    fn default() -> ListenOptions {
+        ListenOptions {
+            backlog: 128,
+            reuse_addr: true,
+            only_v6: None,
+        }
+    }
+}
+
+// Socket options std doesn't expose.
+mod sockopt {
+    use std::io;
+    use std::net::{SocketAddr, TcpListener};
+
+    use super::ListenOptions;
+
+    #[cfg(unix)]
+    pub fn bind(addr: &SocketAddr, options: &ListenOptions) -> io::Result<TcpListener> {
+        use std::cmp;
+        use std::mem;
+        use std::os::unix::io::FromRawFd;
+        use libc;
+
+        fn check(ret: libc::c_int) -> io::Result<libc::c_int> {
+            if ret == -1 { Err(io::Error::last_os_error()) } else { Ok(ret) }
+        }
+
+        unsafe fn set(fd: libc::c_int, level: libc::c_int, name: libc::c_int, value: bool)
+                      -> io::Result<()> {
+            let value = value as libc::c_int;
+            check(libc::setsockopt(fd, level, name,
+                                   &value as *const libc::c_int as *const libc::c_void,
+                                   mem::size_of::<libc::c_int>() as libc::socklen_t))
+                .map(|_| ())
+        }
+
+        unsafe fn setup(fd: libc::c_int, addr: &SocketAddr, options: &ListenOptions)
+                        -> io::Result<()> {
+            try!(check(libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC)));
+            try!(set(fd, libc::SOL_SOCKET, libc::SO_REUSEADDR, options.reuse_addr));
+            let ret = match *addr {
+                SocketAddr::V4(ref a) => {
+                    let mut raw: libc::sockaddr_in = mem::zeroed();
+                    raw.sin_family = libc::AF_INET as libc::sa_family_t;
+                    raw.sin_port = a.port().to_be();
+                    raw.sin_addr.s_addr = u32::from(*a.ip()).to_be();
+                    libc::bind(fd, &raw as *const _ as *const libc::sockaddr,
+                               mem::size_of_val(&raw) as libc::socklen_t)
+                },
+                SocketAddr::V6(ref a) => {
+                    if let Some(only_v6) = options.only_v6 {
+                        try!(set(fd, libc::IPPROTO_IPV6, libc::IPV6_V6ONLY, only_v6));
+                    }
+                    let mut raw: libc::sockaddr_in6 = mem::zeroed();
+                    raw.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+                    raw.sin6_port = a.port().to_be();
+                    raw.sin6_flowinfo = a.flowinfo();
+                    raw.sin6_addr.s6_addr = a.ip().octets();
+                    raw.sin6_scope_id = a.scope_id();
+                    libc::bind(fd, &raw as *const _ as *const libc::sockaddr,
+                               mem::size_of_val(&raw) as libc::socklen_t)
+                },
+            };
+            try!(check(ret));
+            let backlog = cmp::min(options.backlog, libc::c_int::max_value() as u32);
+            check(libc::listen(fd, backlog as libc::c_int)).map(|_| ())
+        }
+
+        let family = match *addr {
+            SocketAddr::V4(..) => libc::AF_INET,
+            SocketAddr::V6(..) => libc::AF_INET6,
+        };
+        unsafe {
+            let fd = try!(check(libc::socket(family, libc::SOCK_STREAM, 0)));
+            match setup(fd, addr, options) {
+                Ok(()) => Ok(TcpListener::from_raw_fd(fd)),
+                Err(e) => {
+                    libc::close(fd);
+                    Err(e)
+                }
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn bind(addr: &SocketAddr, _: &ListenOptions) -> io::Result<TcpListener> {
+        TcpListener::bind(addr)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn set_tcp_option(listener: &TcpListener, name: ::libc::c_int, value: ::libc::c_int)
+                      -> io::Result<()> {
+        use std::mem;
+        use std::os::unix::io::AsRawFd;
+        use libc;
+
+        let ret = unsafe {
+            libc::setsockopt(listener.as_raw_fd(), libc::IPPROTO_TCP, name,
+                             &value as *const libc::c_int as *const libc::c_void,
+                             mem::size_of::<libc::c_int>() as libc::socklen_t)
+        };
+        if ret == 0 { Ok(()) } else { Err(io::Error::last_os_error()) }
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn defer_accept(listener: &TcpListener, secs: i32) -> io::Result<()> {
+        set_tcp_option(listener, ::libc::TCP_DEFER_ACCEPT, secs)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn fast_open(listener: &TcpListener, queue_len: i32) -> io::Result<()> {
+        set_tcp_option(listener, ::libc::TCP_FASTOPEN, queue_len)
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    pub fn defer_accept(_: &TcpListener, _: i32) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "TCP_DEFER_ACCEPT is not supported"))
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    pub fn fast_open(_: &TcpListener, _: i32) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "TCP_FASTOPEN is not supported"))
     }
 }
 
@@ -208,6 +564,21 @@ impl NetworkStream for HttpStream {
             self.0.peer_addr()
     }
 
+    #[inline]
+    fn local_addr(&mut self) -> Option<SocketAddr> {
+        self.0.local_addr().ok()
+    }
+
+    #[inline]
+    fn set_read_timeout(&mut self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_read_timeout(dur)
+    }
+
+    #[inline]
+    fn set_write_timeout(&mut self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_write_timeout(dur)
+    }
+
     #[inline]
     fn close(&mut self, how: Shutdown) -> io::Result<()> {
         match self.0.shutdown(how) {
@@ -220,6 +591,11 @@ impl NetworkStream for HttpStream {
 }
 
 /// A connector that will produce HttpStreams.
+///
+/// A host with both IPv6 and IPv4 addresses is connected to as in
+/// [RFC 8305](https://tools.ietf.org/html/rfc8305): the families take turns,
+/// and each attempt gets 250ms before the next starts alongside it, so a
+/// broken IPv6 network doesn't hold up every connection.
 #[derive(Debug, Clone, Default)]
 pub struct HttpConnector;
 
@@ -227,11 +603,15 @@ impl NetworkConnector for HttpConnector {
     type Stream = HttpStream;
 
     fn connect(&self, host: &str, port: u16, scheme: &str) -> ::Result<HttpStream> {
-        let addr = &(host, port);
+        self.connect_with(host, port, scheme, &Extensions::new())
+    }
+
+    fn connect_with(&self, host: &str, port: u16, scheme: &str, extensions: &Extensions)
+                    -> ::Result<HttpStream> {
         Ok(try!(match scheme {
             "http" => {
                 debug!("http scheme");
-                Ok(HttpStream(try!(TcpStream::connect(addr))))
+                Ok(HttpStream(try!(connect_tcp(host, port, extensions))))
             },
             _ => {
                 Err(io::Error::new(io::ErrorKind::InvalidInput,
@@ -305,6 +685,193 @@ impl<S: NetworkStream> NetworkStream for HttpsStream<S> {
             HttpsStream::Https(ref mut s) => s.close(how)
         }
     }
+
+    #[inline]
+    fn local_addr(&mut self) -> Option<SocketAddr> {
+        match *self {
+            HttpsStream::Http(ref mut s) => s.local_addr(),
+            HttpsStream::Https(ref mut s) => s.local_addr()
+        }
+    }
+
+    #[inline]
+    fn tls_info(&mut self) -> Option<TlsInfo> {
+        match *self {
+            HttpsStream::Http(_) => None,
+            HttpsStream::Https(ref mut s) => s.tls_info()
+        }
+    }
+
+    #[inline]
+    fn peer_certificate(&mut self) -> Option<Vec<u8>> {
+        match *self {
+            HttpsStream::Http(_) => None,
+            HttpsStream::Https(ref mut s) => s.peer_certificate()
+        }
+    }
+
+    #[inline]
+    fn set_read_timeout(&mut self, dur: Option<Duration>) -> io::Result<()> {
+        match *self {
+            HttpsStream::Http(ref mut s) => s.set_read_timeout(dur),
+            HttpsStream::Https(ref mut s) => s.set_read_timeout(dur)
+        }
+    }
+
+    #[inline]
+    fn set_write_timeout(&mut self, dur: Option<Duration>) -> io::Result<()> {
+        match *self {
+            HttpsStream::Http(ref mut s) => s.set_write_timeout(dur),
+            HttpsStream::Https(ref mut s) => s.set_write_timeout(dur)
+        }
+    }
+
+    #[inline]
+    fn set_read_deadline(&mut self, deadline: Option<Instant>) -> io::Result<()> {
+        match *self {
+            HttpsStream::Http(ref mut s) => s.set_read_deadline(deadline),
+            HttpsStream::Https(ref mut s) => s.set_read_deadline(deadline)
+        }
+    }
+
+    #[inline]
+    fn set_write_deadline(&mut self, deadline: Option<Instant>) -> io::Result<()> {
+        match *self {
+            HttpsStream::Http(ref mut s) => s.set_write_deadline(deadline),
+            HttpsStream::Https(ref mut s) => s.set_write_deadline(deadline)
+        }
+    }
+}
+
+/// A stream that enforces read and write deadlines on a stream that only
+/// supports timeouts.
+///
+/// Before each read or write, the time left until its deadline becomes the
+/// inner stream's timeout, and once the deadline has passed the call fails
+/// with `TimedOut`.
+#[derive(Debug)]
+pub struct DeadlineStream<S> {
+    inner: S,
+    read_deadline: Option<Instant>,
+    write_deadline: Option<Instant>,
+}
+
+impl<S: NetworkStream> DeadlineStream<S> {
+    /// Wrap a stream, initially without deadlines.
+    pub fn new(inner: S) -> DeadlineStream<S> {
+        DeadlineStream {
+            inner: inner,
+            read_deadline: None,
+            write_deadline: None,
+        }
+    }
+
+    /// Get a reference to the inner stream.
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner stream.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Get the inner stream back.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+// The time left until a deadline, or an error if it has passed.
+fn remaining(deadline: Instant) -> io::Result<Duration> {
+    let now = Instant::now();
+    if now >= deadline {
+        Err(io::Error::new(ErrorKind::TimedOut, "deadline has passed"))
+    } else {
+        Ok(deadline - now)
+    }
+}
+
+impl<S: NetworkStream> Read for DeadlineStream<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Some(deadline) = self.read_deadline {
+            let left = try!(remaining(deadline));
+            try!(self.inner.set_read_timeout(Some(left)));
+        }
+        self.inner.read(buf)
+    }
+}
+
+impl<S: NetworkStream> Write for DeadlineStream<S> {
+    fn write(&mut self, msg: &[u8]) -> io::Result<usize> {
+        if let Some(deadline) = self.write_deadline {
+            let left = try!(remaining(deadline));
+            try!(self.inner.set_write_timeout(Some(left)));
+        }
+        self.inner.write(msg)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<S: NetworkStream> NetworkStream for DeadlineStream<S> {
+    #[inline]
+    fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        self.inner.peer_addr()
+    }
+
+    #[inline]
+    fn close(&mut self, how: Shutdown) -> io::Result<()> {
+        self.inner.close(how)
+    }
+
+    #[inline]
+    fn local_addr(&mut self) -> Option<SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    #[inline]
+    fn tls_info(&mut self) -> Option<TlsInfo> {
+        self.inner.tls_info()
+    }
+
+    #[inline]
+    fn peer_certificate(&mut self) -> Option<Vec<u8>> {
+        self.inner.peer_certificate()
+    }
+
+    #[inline]
+    fn set_read_timeout(&mut self, dur: Option<Duration>) -> io::Result<()> {
+        self.inner.set_read_timeout(dur)
+    }
+
+    #[inline]
+    fn set_write_timeout(&mut self, dur: Option<Duration>) -> io::Result<()> {
+        self.inner.set_write_timeout(dur)
+    }
+
+    fn set_read_deadline(&mut self, deadline: Option<Instant>) -> io::Result<()> {
+        if deadline.is_none() && self.read_deadline.is_some() {
+            try!(self.inner.set_read_timeout(None));
+        }
+        self.read_deadline = deadline;
+        Ok(())
+    }
+
+    fn set_write_deadline(&mut self, deadline: Option<Instant>) -> io::Result<()> {
+        if deadline.is_none() && self.write_deadline.is_some() {
+            try!(self.inner.set_write_timeout(None));
+        }
+        self.write_deadline = deadline;
+        Ok(())
+    }
+
+    #[inline]
+    fn tag(&mut self) -> Option<Tag> {
+        self.inner.tag()
+    }
 }
 
 /// A Http Listener over SSL.
@@ -324,6 +891,22 @@ impl<S: Ssl> HttpsListener<S> {
         })
     }
 
+    /// Start listening to an address over HTTPS, creating the socket with
+    /// the given options.
+    pub fn with_options<To: ToSocketAddrs>(addr: To, ssl: S, options: &ListenOptions)
+                                           -> ::Result<HttpsListener<S>> {
+        HttpListener::with_options(addr, options).map(|l| HttpsListener {
+            listener: l,
+            ssl: ssl
+        })
+    }
+
+    /// Get a mutable reference to the underlying `HttpListener`, to set
+    /// its socket options.
+    pub fn http_listener_mut(&mut self) -> &mut HttpListener {
+        &mut self.listener
+    }
+
 }
 
 impl<S: Ssl + Clone> NetworkListener for HttpsListener<S> {
@@ -334,6 +917,17 @@ impl<S: Ssl + Clone> NetworkListener for HttpsListener<S> {
         self.listener.accept().and_then(|s| self.ssl.wrap_server(s))
     }
 
+    fn accept_pending(&mut self) -> ::Result<Pending<S::Stream>> {
+        self.listener.accept().map(|s| Pending(PendingState::Handshake(s)))
+    }
+
+    fn finish(&self, pending: Pending<S::Stream>) -> ::Result<S::Stream> {
+        match pending.0 {
+            PendingState::Ready(stream) => Ok(stream),
+            PendingState::Handshake(stream) => self.ssl.wrap_server(stream),
+        }
+    }
+
     #[inline]
     fn local_addr(&mut self) -> io::Result<SocketAddr> {
         self.listener.local_addr()
@@ -341,6 +935,11 @@ impl<S: Ssl + Clone> NetworkListener for HttpsListener<S> {
 }
 
 /// A connector that can protect HTTP streams using SSL.
+///
+/// How they are protected is up to the `Ssl`, such as an `Openssl` created
+/// with `Openssl::with_context`, to trust other root CAs, or to limit the
+/// protocol versions and ciphers used. The name sent in the handshake is
+/// the requested host, unless a request has a `ServerName`.
 #[derive(Debug, Default)]
 pub struct HttpsConnector<S: Ssl> {
     ssl: S
@@ -357,13 +956,445 @@ impl<S: Ssl> NetworkConnector for HttpsConnector<S> {
     type Stream = HttpsStream<S::Stream>;
 
     fn connect(&self, host: &str, port: u16, scheme: &str) -> ::Result<Self::Stream> {
-        let addr = &(host, port);
+        self.connect_with(host, port, scheme, &Extensions::new())
+    }
+
+    fn connect_with(&self, host: &str, port: u16, scheme: &str, extensions: &Extensions)
+                    -> ::Result<Self::Stream> {
         if scheme == "https" {
             debug!("https scheme");
-            let stream = HttpStream(try!(TcpStream::connect(addr)));
-            self.ssl.wrap_client(stream, host).map(HttpsStream::Https)
+            let stream = HttpStream(try!(connect_tcp(host, port, extensions)));
+            let name = extensions.get::<ServerName>().map_or(host, |name| &name.0[..]);
+            let events = extensions.get::<Arc<ClientEvents>>();
+            if let Some(events) = events {
+                events.tls_start(name);
+            }
+            let wrapped = self.ssl.wrap_client(stream, name);
+            if let Some(events) = events {
+                events.tls_end(name, wrapped.as_ref().map(|_| ()));
+            }
+            wrapped.map(HttpsStream::Https)
         } else {
-            HttpConnector.connect(host, port, scheme).map(HttpsStream::Http)
+            HttpConnector.connect_with(host, port, scheme, extensions).map(HttpsStream::Http)
+        }
+    }
+}
+
+/// The name an `HttpsConnector` gives its `Ssl` for the server, instead of
+/// the requested host, when found in the extensions of a request.
+///
+/// It is sent in the handshake, as the server name (SNI), and an `Ssl`
+/// that verifies the server's certificate checks it against this name.
+/// This lets a request to an IP address, or through another name of the
+/// server, still be protected as one to the name the server is known by.
+/// A `Pool` only reuses the connection for requests with the same name.
+///
+/// ```no_run
+/// use hyper::Client;
+/// use hyper::net::ServerName;
+///
+/// let client = Client::new();
+/// let res = client.get("https://10.0.0.7/health")
+///     .extension(ServerName("api.example.domain".to_owned()))
+///     .send()
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ServerName(pub String);
+
+/// Fixed addresses to connect to for some hosts, instead of resolving them,
+/// like curl's `--resolve`.
+///
+/// The `HttpConnector` and `HttpsConnector` look for these in the
+/// extensions of a request, where a `Client` puts the ones given to
+/// `Client::set_resolve_overrides`. The `Host` header and the name sent for
+/// TLS are still the requested host's, unless a `ServerName` is given.
+///
+/// ```
+/// use hyper::net::ResolveOverrides;
+///
+/// let mut overrides = ResolveOverrides::new();
+/// overrides.insert("example.domain", 443, "127.0.0.1:8443".parse().unwrap());
+/// assert_eq!(overrides.get("EXAMPLE.domain", 443), "127.0.0.1:8443".parse().ok());
+/// assert_eq!(overrides.get("example.domain", 80), None);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResolveOverrides {
+    addrs: HashMap<(String, u16), SocketAddr>,
+}
+
+impl ResolveOverrides {
+    /// Creates an empty set of overrides.
+    pub fn new() -> ResolveOverrides {
+        ResolveOverrides { addrs: HashMap::new() }
+    }
+
+    /// Connect to `addr` for requests to `host` on `port`.
+    pub fn insert(&mut self, host: &str, port: u16, addr: SocketAddr) {
+        self.addrs.insert((host.to_ascii_lowercase(), port), addr);
+    }
+
+    /// The address to connect to for `host` on `port`, if it is overridden.
+    pub fn get(&self, host: &str, port: u16) -> Option<SocketAddr> {
+        self.addrs.get(&(host.to_ascii_lowercase(), port)).cloned()
+    }
+
+    /// Returns true if no host is overridden.
+    pub fn is_empty(&self) -> bool {
+        self.addrs.is_empty()
+    }
+}
+
+/// Turns host names into the addresses to connect to, such as with a cache,
+/// an `/etc/hosts` of its own, service discovery or DNS-over-HTTPS.
+///
+/// The `HttpConnector` and `HttpsConnector` look for an `Arc<Resolve>` in
+/// the extensions of a request, where a `Client` puts the one given to
+/// `Client::set_resolver`, and use the `SystemResolver` without one. Hosts
+/// in the `ResolveOverrides` aren't resolved at all.
+///
+/// ```
+/// use std::io;
+/// use std::net::SocketAddr;
+/// use hyper::net::{Resolve, SystemResolver};
+///
+/// struct Discovery;
+///
+/// impl Resolve for Discovery {
+///     fn resolve(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+///         if host.ends_with(".service") {
+///             Ok(vec!["10.0.0.7:8080".parse().unwrap()])
+///         } else {
+///             SystemResolver.resolve(host, port)
+///         }
+///     }
+/// }
+///
+/// assert_eq!(Discovery.resolve("users.service", 80).unwrap()[0].port(), 8080);
+/// ```
+pub trait Resolve: Send + Sync {
+    /// The addresses to connect to for `host` on `port`, in the order to
+    /// try them.
+    fn resolve(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>>;
+}
+
+impl<'a> fmt::Debug for Resolve + 'a {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad("Resolve")
+    }
+}
+
+impl<R: Resolve + ?Sized> Resolve for Arc<R> {
+    fn resolve(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+        (**self).resolve(host, port)
+    }
+}
+
+/// A `Resolve` asking the system resolver, which blocks for as long as the
+/// system takes.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemResolver;
+
+impl Resolve for SystemResolver {
+    fn resolve(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+        (host, port).to_socket_addrs().map(|addrs| addrs.collect())
+    }
+}
+
+/// A `Resolve` running another on a thread of its own for each host, and
+/// failing with a `TimedOut` error if it doesn't answer within a timeout.
+///
+/// A resolution that times out is left to finish on its thread.
+///
+/// ```
+/// use std::time::Duration;
+/// use hyper::net::{Resolve, SystemResolver, ThreadedResolver};
+///
+/// let resolver = ThreadedResolver::new(SystemResolver, Duration::from_secs(5));
+/// assert!(resolver.resolve("localhost", 80).is_ok());
+/// ```
+#[derive(Debug)]
+pub struct ThreadedResolver<R> {
+    resolver: Arc<R>,
+    timeout: Duration,
+}
+
+impl<R: Resolve + 'static> ThreadedResolver<R> {
+    /// Runs `resolver` on other threads, for up to `timeout` per host.
+    pub fn new(resolver: R, timeout: Duration) -> ThreadedResolver<R> {
+        ThreadedResolver {
+            resolver: Arc::new(resolver),
+            timeout: timeout,
+        }
+    }
+}
+
+impl<R: Resolve + 'static> Resolve for ThreadedResolver<R> {
+    fn resolve(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+        resolve_within(self.resolver.clone(), host, port, self.timeout)
+    }
+}
+
+/// A proxy to reach a host through, with a `CONNECT` tunnel.
+///
+/// The `HttpConnector` and `HttpsConnector` look for this in the extensions
+/// of a request, where a `Client` with a proxy puts it for `https` URLs.
+/// They connect to the proxy instead of the host, and ask it to open a
+/// tunnel to the host, through which TLS then runs end to end.
+///
+/// ```
+/// use hyper::net::Tunnel;
+///
+/// let tunnel = Tunnel {
+///     host: "proxy.example.domain".to_owned(),
+///     port: 3128,
+///     authorization: Some("Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==".to_owned()),
+/// };
+/// # drop(tunnel);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tunnel {
+    /// The host of the proxy.
+    pub host: String,
+    /// The port of the proxy.
+    pub port: u16,
+    /// Credentials for the proxy, sent as the `Proxy-Authorization` of the
+    /// `CONNECT` request.
+    pub authorization: Option<String>,
+}
+
+/// How long connecting, and each read and write, may take before failing
+/// with a `TimedOut` or `WouldBlock` error, where `None` is no limit.
+///
+/// The `HttpConnector` and `HttpsConnector` look for these in the
+/// extensions of a request, where a `Client` with timeouts puts them, and
+/// bound resolving the host and connecting to it by the connect timeout.
+/// The read and write timeouts apply from the start, so they also bound a
+/// TLS handshake, and `Http11Protocol` sets them on every connection it
+/// sends a request on, including reused ones.
+///
+/// ```
+/// use std::time::Duration;
+/// use hyper::net::Timeouts;
+///
+/// let timeouts = Timeouts {
+///     connect: Some(Duration::from_secs(5)),
+///     ..Timeouts::default()
+/// };
+/// assert_eq!(timeouts.read, None);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Timeouts {
+    /// The longest to take resolving the host and connecting to it.
+    pub connect: Option<Duration>,
+    /// The longest each read may block.
+    pub read: Option<Duration>,
+    /// The longest each write may block.
+    pub write: Option<Duration>,
+}
+
+fn connect_tcp(host: &str, port: u16, extensions: &Extensions) -> io::Result<TcpStream> {
+    let timeouts = extensions.get::<Timeouts>().cloned().unwrap_or_default();
+    let deadline = timeouts.connect.map(|timeout| Instant::now() + timeout);
+    let mut stream = match extensions.get::<Tunnel>() {
+        Some(tunnel) => {
+            debug!("tunneling to {}:{} through {}:{}", host, port, tunnel.host, tunnel.port);
+            try!(connect_addr(&tunnel.host, tunnel.port, extensions, deadline))
+        },
+        None => try!(connect_addr(host, port, extensions, deadline)),
+    };
+    try!(stream.set_read_timeout(timeouts.read));
+    try!(stream.set_write_timeout(timeouts.write));
+    if let Some(tunnel) = extensions.get::<Tunnel>() {
+        try!(open_tunnel(&mut stream, host, port, tunnel));
+    }
+    Ok(stream)
+}
+
+// Asks a proxy to open a tunnel to `host`. Its response is read a byte at
+// a time, to leave whatever follows the head to the tunnel.
+fn open_tunnel(stream: &mut TcpStream, host: &str, port: u16, tunnel: &Tunnel) -> io::Result<()> {
+    let authority = format!("{}:{}", host, port);
+    let mut head = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", authority);
+    if let Some(ref credentials) = tunnel.authorization {
+        head.push_str(&format!("Proxy-Authorization: {}\r\n", credentials));
+    }
+    head.push_str("\r\n");
+    try!(stream.write_all(head.as_bytes()));
+
+    let mut raw = Vec::new();
+    let mut byte = [0];
+    while !raw.ends_with(b"\r\n\r\n") {
+        if raw.len() >= buffer::MAX_BUFFER_SIZE {
+            return Err(io::Error::new(ErrorKind::InvalidData, "proxy response head too large"));
+        }
+        match try!(stream.read(&mut byte)) {
+            0 => return Err(io::Error::new(ErrorKind::UnexpectedEof,
+                                           "proxy closed the connection")),
+            _ => raw.push(byte[0]),
+        }
+    }
+
+    let mut headers = [httparse::EMPTY_HEADER; 64];
+    let mut res = httparse::Response::new(&mut headers);
+    match res.parse(&raw) {
+        Ok(httparse::Status::Complete(_)) => match res.code {
+            Some(200...299) => Ok(()),
+            code => {
+                debug!("proxy refused tunnel to {}: {:?} {:?}", authority, code, res.reason);
+                Err(io::Error::new(ErrorKind::Other,
+                                   format!("proxy refused tunnel with status {}",
+                                           code.unwrap_or(0))))
+            },
+        },
+        _ => Err(io::Error::new(ErrorKind::InvalidData, "invalid proxy response")),
+    }
+}
+
+fn connect_addr(host: &str, port: u16, extensions: &Extensions, deadline: Option<Instant>)
+                -> io::Result<TcpStream> {
+    let events = extensions.get::<Arc<ClientEvents>>().cloned();
+    let addrs = match extensions.get::<ResolveOverrides>().and_then(|o| o.get(host, port)) {
+        Some(addr) => {
+            debug!("connecting to {} for {}:{}", addr, host, port);
+            vec![addr]
+        },
+        None => {
+            let resolver = extensions.get::<Arc<Resolve>>().cloned()
+                .unwrap_or_else(|| Arc::new(SystemResolver));
+            if let Some(ref events) = events {
+                events.dns_start(host);
+            }
+            let resolved = match deadline {
+                Some(deadline) => {
+                    remaining(deadline).and_then(|left| resolve_within(resolver, host, port, left))
+                },
+                None => resolver.resolve(host, port),
+            };
+            if let Some(ref events) = events {
+                events.dns_end(host, resolved.as_ref().map(|addrs| &addrs[..]));
+            }
+            try!(resolved)
+        },
+    };
+
+    if addrs.iter().any(|addr| addr.is_ipv6()) && addrs.iter().any(|addr| addr.is_ipv4()) {
+        return race(interleave(addrs), deadline, CONNECTION_ATTEMPT_DELAY, move |addr, deadline| {
+            connect_tcp_addr(addr, deadline, events.as_ref())
+        });
+    }
+    let mut last_err = None;
+    for addr in addrs {
+        match connect_tcp_addr(addr, deadline, events.as_ref()) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_err = Some(e),
+        }
+    }
+    Err(last_err.unwrap_or_else(|| {
+        io::Error::new(ErrorKind::InvalidInput, "could not resolve to any addresses")
+    }))
+}
+
+fn connect_tcp_addr(addr: SocketAddr, deadline: Option<Instant>, events: Option<&Arc<ClientEvents>>)
+                    -> io::Result<TcpStream> {
+    if let Some(events) = events {
+        events.connect_start(addr);
+    }
+    let connected = match deadline {
+        Some(deadline) => {
+            remaining(deadline).and_then(|left| TcpStream::connect_timeout(&addr, left))
+        },
+        None => TcpStream::connect(&addr),
+    };
+    if let Some(events) = events {
+        events.connect_end(addr, connected.as_ref().map(|_| ()));
+    }
+    connected
+}
+
+// How long to wait for an attempt to connect before starting the next one
+// alongside it, as recommended by RFC 8305.
+const CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+// Orders addresses alternating between IPv6 and IPv4, starting with the
+// family of the first, so that a broken family only delays the other by one
+// attempt.
+fn interleave(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let first_v6 = addrs.first().map_or(false, |addr| addr.is_ipv6());
+    let (first, other): (Vec<_>, Vec<_>) = addrs.into_iter()
+        .partition(|addr| addr.is_ipv6() == first_v6);
+    let mut other = other.into_iter();
+    let mut addrs = Vec::new();
+    for addr in first {
+        addrs.push(addr);
+        addrs.extend(other.next());
+    }
+    addrs.extend(other);
+    addrs
+}
+
+// Connects to the addresses in turn, each on a thread of its own, starting
+// the next one as soon as an attempt fails or after `delay`, and returns the
+// first connection made. Connections made later are closed.
+fn race<T, F>(addrs: Vec<SocketAddr>, deadline: Option<Instant>, delay: Duration, connect: F)
+              -> io::Result<T>
+where T: Send + 'static, F: Fn(SocketAddr, Option<Instant>) -> io::Result<T> + Send + Sync + 'static {
+    let connect = Arc::new(connect);
+    let (tx, rx) = mpsc::channel();
+    let mut addrs = addrs.into_iter();
+    let mut pending = 0;
+    let mut last_err = None;
+    loop {
+        if let Some(addr) = addrs.next() {
+            debug!("racing connection to {}", addr);
+            let (connect, tx) = (connect.clone(), tx.clone());
+            try!(thread::Builder::new().name("hyper-connect".to_owned()).spawn(move || {
+                let _ = tx.send(connect(addr, deadline));
+            }));
+            pending += 1;
+        } else if pending == 0 {
+            break;
+        }
+
+        let mut wait = if addrs.len() > 0 { Some(delay) } else { None };
+        if let Some(deadline) = deadline {
+            let left = try!(remaining(deadline));
+            wait = Some(wait.map_or(left, |wait| cmp::min(wait, left)));
+        }
+        let connected = match wait {
+            Some(wait) => match rx.recv_timeout(wait) {
+                Ok(connected) => connected,
+                Err(_) => continue,
+            },
+            // the sender kept here means this can't fail
+            None => rx.recv().unwrap(),
+        };
+        pending -= 1;
+        match connected {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_err = Some(e),
+        }
+    }
+    Err(last_err.unwrap_or_else(|| {
+        io::Error::new(ErrorKind::InvalidInput, "could not resolve to any addresses")
+    }))
+}
+
+// Resolves `host` on a thread of its own, since the system resolver can't
+// be given a timeout. A resolution that times out is left to finish there.
+fn resolve_within<R>(resolver: Arc<R>, host: &str, port: u16, timeout: Duration)
+                     -> io::Result<Vec<SocketAddr>>
+where R: Resolve + ?Sized + 'static {
+    let (tx, rx) = mpsc::channel();
+    let name = host.to_owned();
+    try!(thread::Builder::new().name("hyper-resolve".to_owned()).spawn(move || {
+        let _ = tx.send(resolver.resolve(&name, port));
+    }));
+    match rx.recv_timeout(timeout) {
+        Ok(addrs) => addrs,
+        Err(_) => {
+            debug!("timed out resolving {}", host);
+            Err(io::Error::new(ErrorKind::TimedOut, "timed out resolving host"))
         }
     }
 }
@@ -379,15 +1410,20 @@ pub type DefaultConnector = HttpsConnector<self::openssl::Openssl>;
 
 #[cfg(feature = "openssl")]
 mod openssl {
+    use std::error::Error as StdError;
+    use std::fmt;
     use std::io;
+    use std::mem;
     use std::net::{SocketAddr, Shutdown};
     use std::path::Path;
     use std::sync::Arc;
+    use std::time::Duration;
+    use openssl::crypto::pkey::PKey;
     use openssl::ssl::{Ssl, SslContext, SslStream, SslMethod, SSL_VERIFY_NONE};
     use openssl::ssl::error::StreamError as SslIoError;
-    use openssl::ssl::error::SslError;
-    use openssl::x509::X509FileType;
-    use super::{NetworkStream, HttpStream};
+    use openssl::ssl::error::{OpensslError, SslError};
+    use openssl::x509::{X509, X509FileType};
+    use super::{NetworkStream, HttpStream, TlsInfo};
 
 
     /// An implementation of `Ssl` for OpenSSL.
@@ -402,8 +1438,10 @@ mod openssl {
     /// Server::https("0.0.0.0:443", ssl).unwrap();
     /// ```
     ///
-    /// For complete control, create a `SslContext` with the options you desire
-    /// and then create `Openssl { context: ctx }
+    /// To adjust cipher suites, protocol versions, CA bundles, or certificate
+    /// verification, use `Openssl::with_context`. For complete control,
+    /// create a `SslContext` with the options you desire and then create
+    /// `Openssl { context: ctx }`, or use `Openssl::from(ctx)`.
     #[derive(Debug, Clone)]
     pub struct Openssl {
         /// The `SslContext` from openssl crate.
@@ -426,12 +1464,108 @@ mod openssl {
         /// Ease creating an `Openssl` with a certificate and key.
         pub fn with_cert_and_key<C, K>(cert: C, key: K) -> Result<Openssl, SslError>
         where C: AsRef<Path>, K: AsRef<Path> {
+            Openssl::with_context(|ctx| {
+                try!(ctx.set_cipher_list("DEFAULT"));
+                try!(ctx.set_certificate_file(cert.as_ref(), X509FileType::PEM));
+                try!(ctx.set_private_key_file(key.as_ref(), X509FileType::PEM));
+                ctx.set_verify(SSL_VERIFY_NONE, None);
+                Ok(())
+            })
+        }
+
+        /// Create an `Openssl` for a client that authenticates itself to
+        /// servers with a certificate, for mutual TLS, reading the
+        /// certificate and its private key from files in `format`.
+        ///
+        /// A PEM certificate file may go on with the rest of the chain, up
+        /// to the CA, after the client's own certificate.
+        ///
+        /// # Example
+        ///
+        /// ```no_run
+        /// # extern crate hyper;
+        /// # extern crate openssl;
+        /// # fn main() {
+        /// use hyper::Client;
+        /// use hyper::net::{HttpsConnector, Openssl};
+        /// use openssl::x509::X509FileType;
+        ///
+        /// let ssl = Openssl::with_client_cert("client.crt", "client.key", X509FileType::PEM).unwrap();
+        /// let client = Client::with_connector(HttpsConnector::new(ssl));
+        /// # }
+        /// ```
+        pub fn with_client_cert<C, K>(cert: C, key: K, format: X509FileType) -> Result<Openssl, SslError>
+        where C: AsRef<Path>, K: AsRef<Path> {
+            Openssl::with_context(|ctx| {
+                match format {
+                    X509FileType::PEM => try!(ctx.set_certificate_chain_file(cert.as_ref(), format)),
+                    _ => try!(ctx.set_certificate_file(cert.as_ref(), format)),
+                }
+                try!(ctx.set_private_key_file(key.as_ref(), format));
+                ctx.check_private_key()
+            })
+        }
+
+        /// Create an `Openssl` for a client that authenticates itself to
+        /// servers with a certificate, as `with_client_cert` does, taking the
+        /// certificate, and the rest of its chain after it, and its private
+        /// key in PEM.
+        pub fn with_client_cert_pem(cert: &[u8], key: &[u8]) -> Result<Openssl, SslError> {
+            let mut chain = Vec::new();
+            for pem in pem_blocks(cert) {
+                chain.push(try!(X509::from_pem(&mut &pem[..])));
+            }
+            let key = try!(PKey::private_key_from_pem(&mut &key[..]));
+            Openssl::with_context(|ctx| {
+                let mut chain = chain.into_iter();
+                if let Some(cert) = chain.next() {
+                    try!(ctx.set_certificate(&cert));
+                }
+                for cert in chain {
+                    try!(ctx.add_extra_chain_cert(&cert));
+                    // the context has taken it over, and frees it
+                    mem::forget(cert);
+                }
+                try!(ctx.set_private_key(&key));
+                ctx.check_private_key()
+            })
+        }
+
+        /// Create an `Openssl` with a new `SslContext`, letting the provided
+        /// function configure it before it is shared between connections.
+        ///
+        /// # Example
+        ///
+        /// ```no_run
+        /// # extern crate hyper;
+        /// # extern crate openssl;
+        /// # fn main() {
+        /// use hyper::Client;
+        /// use hyper::net::{HttpsConnector, Openssl};
+        /// use openssl::ssl::{SSL_OP_NO_SSLV2, SSL_OP_NO_SSLV3, SSL_OP_NO_TLSV1};
+        /// use openssl::ssl::SSL_VERIFY_PEER;
+        ///
+        /// let ssl = Openssl::with_context(|ctx| {
+        ///     try!(ctx.set_cipher_list("HIGH:!aNULL:!MD5"));
+        ///     ctx.set_options(SSL_OP_NO_SSLV2 | SSL_OP_NO_SSLV3 | SSL_OP_NO_TLSV1);
+        ///     try!(ctx.set_CA_file("/etc/ssl/certs/ca-bundle.crt"));
+        ///     ctx.set_verify(SSL_VERIFY_PEER, None);
+        ///     Ok(())
+        /// }).unwrap();
+        /// let client = Client::with_connector(HttpsConnector::new(ssl));
+        /// # }
+        /// ```
+        pub fn with_context<F>(configure: F) -> Result<Openssl, SslError>
+        where F: FnOnce(&mut SslContext) -> Result<(), SslError> {
             let mut ctx = try!(SslContext::new(SslMethod::Sslv23));
-            try!(ctx.set_cipher_list("DEFAULT"));
-            try!(ctx.set_certificate_file(cert.as_ref(), X509FileType::PEM));
-            try!(ctx.set_private_key_file(key.as_ref(), X509FileType::PEM));
-            ctx.set_verify(SSL_VERIFY_NONE, None);
-            Ok(Openssl { context: Arc::new(ctx) })
+            try!(configure(&mut ctx));
+            Ok(Openssl::from(ctx))
+        }
+    }
+
+    impl From<SslContext> for Openssl {
+        fn from(context: SslContext) -> Openssl {
+            Openssl { context: Arc::new(context) }
         }
     }
 
@@ -444,7 +1578,13 @@ mod openssl {
             //}
             let ssl = try!(Ssl::new(&self.context));
             try!(ssl.set_hostname(host));
-            SslStream::new_from(ssl, stream).map_err(From::from)
+            SslStream::new_from(ssl, stream).map_err(|e| {
+                if is_client_cert_alert(&e) {
+                    ::Error::Ssl(Box::new(ClientCertError(e)))
+                } else {
+                    e.into()
+                }
+            })
         }
 
         fn wrap_server(&self, stream: HttpStream) -> ::Result<Self::Stream> {
@@ -458,6 +1598,62 @@ mod openssl {
         }
     }
 
+    // Splits PEM into its blocks, such as the certificates of a chain.
+    fn pem_blocks(pem: &[u8]) -> Vec<&[u8]> {
+        const BEGIN: &'static [u8] = b"-----BEGIN ";
+        let starts: Vec<usize> = (0..pem.len()).filter(|&i| pem[i..].starts_with(BEGIN)).collect();
+        if starts.is_empty() {
+            // for OpenSSL to say what is wrong with it
+            return vec![pem];
+        }
+        starts.iter().enumerate().map(|(n, &start)| {
+            let end = starts.get(n + 1).cloned().unwrap_or(pem.len());
+            &pem[start..end]
+        }).collect()
+    }
+
+    /// A TLS handshake that the server ended over the client's certificate.
+    ///
+    /// The server requires a certificate of its clients, and either none
+    /// was configured, with `Openssl::with_client_cert`, or it rejected the
+    /// one sent. OpenSSL only tells of the alert the server ended the
+    /// handshake with, and servers also send a `handshake_failure` for
+    /// other reasons, such as sharing no cipher with the client, so this
+    /// is a likely cause rather than a certain one.
+    #[derive(Debug)]
+    pub struct ClientCertError(pub SslError);
+
+    impl fmt::Display for ClientCertError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}, probably because the server requires a client certificate, \
+                       and none was configured or it was rejected: {}", self.description(), self.0)
+        }
+    }
+
+    impl StdError for ClientCertError {
+        fn description(&self) -> &str {
+            "The server ended the TLS handshake"
+        }
+
+        fn cause(&self) -> Option<&StdError> {
+            Some(&self.0)
+        }
+    }
+
+    // Whether the server ended the handshake with an alert that it sends
+    // when a client's certificate is missing or unacceptable.
+    fn is_client_cert_alert(e: &SslError) -> bool {
+        match *e {
+            SslError::OpenSslErrors(ref errors) => errors.iter().any(|error| {
+                let OpensslError::UnknownError { ref reason, .. } = *error;
+                reason.ends_with("alert handshake failure") ||
+                    reason.ends_with("alert bad certificate") ||
+                    reason.ends_with("alert certificate required")
+            }),
+            _ => false,
+        }
+    }
+
     impl<S: NetworkStream> NetworkStream for SslStream<S> {
         #[inline]
         fn peer_addr(&mut self) -> io::Result<SocketAddr> {
@@ -467,13 +1663,48 @@ mod openssl {
         fn close(&mut self, how: Shutdown) -> io::Result<()> {
             self.get_mut().close(how)
         }
+
+        fn local_addr(&mut self) -> Option<SocketAddr> {
+            self.get_mut().local_addr()
+        }
+
+        fn set_read_timeout(&mut self, dur: Option<Duration>) -> io::Result<()> {
+            self.get_mut().set_read_timeout(dur)
+        }
+
+        fn set_write_timeout(&mut self, dur: Option<Duration>) -> io::Result<()> {
+            self.get_mut().set_write_timeout(dur)
+        }
+
+        // this version of openssl doesn't report the protocol or cipher, and
+        // only reports what ALPN chose with its `alpn` feature, so without it
+        // there is nothing to tell
+        #[cfg(feature = "alpn")]
+        fn tls_info(&mut self) -> Option<TlsInfo> {
+            Some(TlsInfo {
+                alpn_protocol: self.get_selected_alpn_protocol().map(|protocol| protocol.to_vec()),
+                ..TlsInfo::default()
+            })
+        }
+
+        fn peer_certificate(&mut self) -> Option<Vec<u8>> {
+            self.get_peer_certificate().and_then(|cert| {
+                let mut pem = Vec::new();
+                cert.write_pem(&mut pem).ok().map(|_| pem)
+            })
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::io::{ErrorKind, Read, Write};
+    use std::net::TcpListener;
+    use std::time::{Duration, Instant};
+
     use mock::MockStream;
-    use super::{NetworkStream};
+    use super::{NetworkStream, NetworkListener, HttpListener, HttpStream, HttpsStream,
+                DeadlineStream, ListenOptions};
 
     #[test]
     fn test_downcast_box_stream() {
@@ -494,4 +1725,336 @@ mod tests {
         assert_eq!(mock, Box::new(MockStream::new()));
 
     }
+
+    #[test]
+    fn test_connection_metadata() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let stream = HttpStream(::std::net::TcpStream::connect(addr).unwrap());
+        let mut stream: HttpsStream<MockStream> = HttpsStream::Http(stream);
+        assert_eq!(stream.peer_addr().unwrap(), addr);
+        assert_eq!(stream.local_addr().map(|a| a.ip()), Some(addr.ip()));
+        assert_eq!(stream.tls_info(), None);
+        assert_eq!(stream.peer_certificate(), None);
+
+        assert_eq!(MockStream::new().local_addr(), None);
+    }
+
+    #[test]
+    fn test_listener_options() {
+        let mut listener = HttpListener::new("127.0.0.1:0").unwrap();
+        listener.set_nodelay(true);
+        if cfg!(target_os = "linux") {
+            listener.set_defer_accept(Some(Duration::from_secs(5))).unwrap();
+            listener.set_defer_accept(None).unwrap();
+            listener.set_fast_open(16).unwrap();
+        }
+
+        let addr = listener.local_addr().unwrap();
+        let _client = ::std::net::TcpStream::connect(addr).unwrap();
+        let stream = listener.accept().unwrap();
+        assert!(stream.0.nodelay().unwrap());
+    }
+
+    #[test]
+    fn test_listen_options() {
+        let options = ListenOptions { backlog: 4, ..ListenOptions::default() };
+        let mut listener = HttpListener::with_options("127.0.0.1:0", &options).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let _client = ::std::net::TcpStream::connect(addr).unwrap();
+        assert!(listener.accept().is_ok());
+
+        // the address is still in use by the first listener
+        let options = ListenOptions { reuse_addr: false, ..ListenOptions::default() };
+        assert!(HttpListener::with_options(addr, &options).is_err());
+    }
+
+    #[test]
+    fn test_listen_options_v6_only() {
+        let options = ListenOptions { only_v6: Some(true), ..ListenOptions::default() };
+        // the sandbox might not have IPv6 at all
+        if let Ok(mut listener) = HttpListener::with_options("[::1]:0", &options) {
+            assert!(listener.local_addr().unwrap().is_ipv6());
+        }
+    }
+
+    #[test]
+    fn test_deadline_stream() {
+        let mut stream = DeadlineStream::new(MockStream::with_input(b"abc"));
+        assert!(MockStream::new().set_read_deadline(Some(Instant::now())).is_err());
+
+        stream.set_read_deadline(Some(Instant::now() + Duration::from_secs(60))).unwrap();
+        let mut buf = [0; 1];
+        assert_eq!(stream.read(&mut buf).unwrap(), 1);
+
+        stream.set_read_deadline(Some(Instant::now())).unwrap();
+        assert_eq!(stream.read(&mut buf).unwrap_err().kind(), ErrorKind::TimedOut);
+        stream.set_write_deadline(Some(Instant::now())).unwrap();
+        assert_eq!(stream.write(b"x").unwrap_err().kind(), ErrorKind::TimedOut);
+
+        stream.set_read_deadline(None).unwrap();
+        stream.set_write_deadline(None).unwrap();
+        assert_eq!(stream.read(&mut buf).unwrap(), 1);
+        assert_eq!(stream.write(b"x").unwrap(), 1);
+    }
+
+    #[test]
+    fn test_deadline_spans_reads() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let tcp = ::std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut server, _) = listener.accept().unwrap();
+        let mut stream = DeadlineStream::new(HttpStream(tcp));
+
+        let start = Instant::now();
+        stream.set_read_deadline(Some(start + Duration::from_millis(100))).unwrap();
+        server.write_all(b"a").unwrap();
+        let mut buf = [0; 1];
+        assert_eq!(stream.read(&mut buf).unwrap(), 1);
+        assert!(stream.read(&mut buf).is_err());
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_tunnel() {
+        use std::io::{BufRead, BufReader};
+        use std::thread;
+        use extensions::Extensions;
+        use super::{NetworkConnector, HttpConnector, Tunnel};
+
+        fn proxy(response: &'static [u8]) -> (u16, thread::JoinHandle<Vec<String>>) {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let port = listener.local_addr().unwrap().port();
+            (port, thread::spawn(move || {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut head = vec![];
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" {
+                        break;
+                    }
+                    head.push(line.trim_right().to_owned());
+                }
+                let mut stream = reader.into_inner();
+                stream.write_all(response).unwrap();
+                // echo what comes through the tunnel
+                let mut buf = [0; 4];
+                if stream.read_exact(&mut buf).is_ok() {
+                    stream.write_all(&buf).unwrap();
+                }
+                head
+            }))
+        }
+
+        let (port, proxied) = proxy(b"HTTP/1.1 200 Connection Established\r\n\r\n");
+        let mut extensions = Extensions::new();
+        extensions.insert(Tunnel {
+            host: "127.0.0.1".to_owned(),
+            port: port,
+            authorization: Some("Basic QWxhZGRpbjo=".to_owned()),
+        });
+        let mut stream = HttpConnector.connect_with("example.invalid", 443, "http", &extensions).unwrap();
+        stream.write_all(b"ping").unwrap();
+        let mut buf = [0; 4];
+        stream.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"ping");
+        assert_eq!(proxied.join().unwrap(), vec!["CONNECT example.invalid:443 HTTP/1.1",
+                                                  "Host: example.invalid:443",
+                                                  "Proxy-Authorization: Basic QWxhZGRpbjo="]);
+
+        let (port, proxied) = proxy(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n");
+        extensions.insert(Tunnel { host: "127.0.0.1".to_owned(), port: port, authorization: None });
+        assert!(HttpConnector.connect_with("example.invalid", 443, "http", &extensions).is_err());
+        proxied.join().unwrap();
+    }
+
+    #[test]
+    fn test_connect_timeout() {
+        use extensions::Extensions;
+        use super::{NetworkConnector, HttpConnector, Timeouts};
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut extensions = Extensions::new();
+        extensions.insert(Timeouts { connect: Some(Duration::from_secs(5)), ..Timeouts::default() });
+        assert!(HttpConnector.connect_with("localhost", port, "http", &extensions).is_ok());
+
+        extensions.insert(Timeouts { connect: Some(Duration::from_secs(0)), ..Timeouts::default() });
+        match HttpConnector.connect_with("localhost", port, "http", &extensions) {
+            Err(::Error::Io(ref e)) if e.kind() == ErrorKind::TimedOut => (),
+            other => panic!("connected despite the timeout: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_interleave() {
+        use std::net::SocketAddr;
+        use super::interleave;
+
+        let addrs: Vec<SocketAddr> = ["[::1]:80", "[::2]:80", "[::3]:80", "10.0.0.1:80", "10.0.0.2:80"]
+            .iter().map(|addr| addr.parse().unwrap()).collect();
+        let order: Vec<String> = interleave(addrs.clone()).iter().map(|addr| addr.to_string()).collect();
+        assert_eq!(order, ["[::1]:80", "10.0.0.1:80", "[::2]:80", "10.0.0.2:80", "[::3]:80"]);
+        let order: Vec<String> = interleave(addrs[2..].iter().rev().cloned().collect())
+            .iter().map(|addr| addr.to_string()).collect();
+        assert_eq!(order, ["10.0.0.2:80", "[::3]:80", "10.0.0.1:80"]);
+    }
+
+    #[test]
+    fn test_race() {
+        use std::io;
+        use std::net::SocketAddr;
+        use std::thread;
+        use super::race;
+
+        // IPv6 is broken, hanging or failing at once
+        fn connect(addr: SocketAddr, _: Option<Instant>) -> io::Result<SocketAddr> {
+            match addr.port() {
+                1 => thread::sleep(Duration::from_secs(2)),
+                2 => (),
+                _ => return Ok(addr),
+            }
+            Err(io::Error::new(ErrorKind::Other, "unreachable"))
+        }
+        let addrs = |addrs: &[&str]| addrs.iter().map(|addr| addr.parse().unwrap()).collect::<Vec<_>>();
+        let delay = Duration::from_millis(100);
+
+        let start = Instant::now();
+        let addr = race(addrs(&["[::1]:1", "10.0.0.1:80"]), None, delay, connect).unwrap();
+        assert_eq!(addr.to_string(), "10.0.0.1:80");
+        assert!(start.elapsed() >= delay && start.elapsed() < Duration::from_secs(1));
+
+        let start = Instant::now();
+        let addr = race(addrs(&["[::1]:2", "10.0.0.1:80"]), None, delay, connect).unwrap();
+        assert_eq!(addr.to_string(), "10.0.0.1:80");
+        assert!(start.elapsed() < delay);
+
+        assert_eq!(race(addrs(&["[::1]:2", "10.0.0.1:2"]), None, delay, connect).unwrap_err().kind(),
+                   ErrorKind::Other);
+        let deadline = Instant::now() + Duration::from_millis(300);
+        assert_eq!(race(addrs(&["[::1]:1", "10.0.0.1:1"]), Some(deadline), delay, connect)
+                       .unwrap_err().kind(),
+                   ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn test_resolver() {
+        use std::io;
+        use std::net::SocketAddr;
+        use std::sync::{Arc, Mutex};
+        use std::thread;
+        use extensions::Extensions;
+        use super::{NetworkConnector, HttpConnector, Resolve, ThreadedResolver, Timeouts};
+
+        struct Table(SocketAddr, Mutex<Vec<String>>);
+
+        impl Resolve for Table {
+            fn resolve(&self, host: &str, _: u16) -> io::Result<Vec<SocketAddr>> {
+                self.1.lock().unwrap().push(host.to_owned());
+                if host == "slow.invalid" {
+                    thread::sleep(Duration::from_secs(1));
+                }
+                Ok(vec![self.0])
+            }
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let table = Arc::new(Table(listener.local_addr().unwrap(), Mutex::new(Vec::new())));
+        let mut extensions = Extensions::new();
+        extensions.insert(table.clone() as Arc<Resolve>);
+        assert!(HttpConnector.connect_with("service.invalid", 1, "http", &extensions).is_ok());
+        extensions.insert(Timeouts { connect: Some(Duration::from_secs(5)), ..Timeouts::default() });
+        assert!(HttpConnector.connect_with("other.invalid", 1, "http", &extensions).is_ok());
+        assert_eq!(*table.1.lock().unwrap(), vec!["service.invalid", "other.invalid"]);
+
+        let threaded = ThreadedResolver::new(table.clone(), Duration::from_millis(200));
+        assert!(threaded.resolve("fast.invalid", 1).is_ok());
+        match threaded.resolve("slow.invalid", 1) {
+            Err(ref e) if e.kind() == ErrorKind::TimedOut => (),
+            other => panic!("resolved despite the timeout: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_events() {
+        use std::io;
+        use std::net::SocketAddr;
+        use std::sync::{Arc, Mutex};
+        use extensions::Extensions;
+        use metrics::ClientEvents;
+        use super::{NetworkConnector, HttpConnector};
+
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<String>>);
+
+        impl ClientEvents for Recorder {
+            fn dns_start(&self, host: &str) {
+                self.0.lock().unwrap().push(format!("dns_start {}", host));
+            }
+            fn dns_end(&self, host: &str, result: Result<&[SocketAddr], &io::Error>) {
+                self.0.lock().unwrap().push(format!("dns_end {} {:?}", host, result.ok()));
+            }
+            fn connect_start(&self, addr: SocketAddr) {
+                self.0.lock().unwrap().push(format!("connect_start {}", addr));
+            }
+            fn connect_end(&self, addr: SocketAddr, result: Result<(), &io::Error>) {
+                self.0.lock().unwrap().push(format!("connect_end {} {}", addr, result.is_ok()));
+            }
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let recorder = Arc::new(Recorder::default());
+        let mut extensions = Extensions::new();
+        extensions.insert(recorder.clone() as Arc<ClientEvents>);
+        assert!(HttpConnector.connect_with("127.0.0.1", addr.port(), "http", &extensions).is_ok());
+        drop(listener);
+        assert!(HttpConnector.connect_with("127.0.0.1", addr.port(), "http", &extensions).is_err());
+
+        let connected = vec![
+            "dns_start 127.0.0.1".to_owned(),
+            format!("dns_end 127.0.0.1 Some([{}])", addr),
+            format!("connect_start {}", addr),
+        ];
+        let events = recorder.0.lock().unwrap();
+        assert_eq!(events[..3], connected[..]);
+        assert_eq!(events[3], format!("connect_end {} true", addr));
+        assert_eq!(events[4..7], connected[..]);
+        assert_eq!(events[7], format!("connect_end {} false", addr));
+        assert_eq!(events.len(), 8);
+    }
+
+    #[test]
+    fn test_server_name() {
+        use std::sync::Mutex;
+        use extensions::Extensions;
+        use super::{NetworkConnector, HttpsConnector, ServerName, Ssl};
+
+        #[derive(Default)]
+        struct Names(Mutex<Vec<String>>);
+
+        impl Ssl for Names {
+            type Stream = HttpStream;
+
+            fn wrap_client(&self, stream: HttpStream, host: &str) -> ::Result<HttpStream> {
+                self.0.lock().unwrap().push(host.to_owned());
+                Ok(stream)
+            }
+
+            fn wrap_server(&self, stream: HttpStream) -> ::Result<HttpStream> {
+                Ok(stream)
+            }
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let connector = HttpsConnector::new(Names::default());
+        assert!(connector.connect("127.0.0.1", port, "https").is_ok());
+        let mut extensions = Extensions::new();
+        extensions.insert(ServerName("api.example.domain".to_owned()));
+        assert!(connector.connect_with("127.0.0.1", port, "https", &extensions).is_ok());
+        assert!(connector.connect_with("127.0.0.1", port, "http", &extensions).is_ok());
+        assert_eq!(*connector.ssl.0.lock().unwrap(), vec!["127.0.0.1", "api.example.domain"]);
+    }
 }
@@ -0,0 +1,162 @@
+//! `multipart/byteranges` response bodies.
+//!
+//! A request for several ranges of a representation is answered with a
+//! `206 Partial Content` whose body holds each range as a part, as
+//! [RFC7233 Appendix A](https://tools.ietf.org/html/rfc7233#appendix-A)
+//! describes.
+//!
+//! ```
+//! use std::io::Cursor;
+//! use hyper::mime::Mime;
+//! use hyper::mime::TopLevel::Text;
+//! use hyper::mime::SubLevel::Plain;
+//! use hyper::server::byteranges::ByteRangesBody;
+//!
+//! let source = Cursor::new(b"hello, world".to_vec());
+//! let ranges = vec![(0, 4), (7, 11)];
+//! let mut body = ByteRangesBody::new(source, Mime(Text, Plain, vec![]), 12, ranges);
+//!
+//! let mut out = Vec::new();
+//! body.write_to(&mut out).unwrap();
+//! assert_eq!(out.len() as u64, body.content_length());
+//! ```
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use time;
+
+use header::ContentRangeSpec;
+use mime::{Mime, TopLevel, SubLevel, Attr, Value};
+
+static NEXT_BOUNDARY: AtomicUsize = AtomicUsize::new(0);
+
+/// A `multipart/byteranges` body, streaming each range from a seekable
+/// source.
+#[derive(Debug)]
+pub struct ByteRangesBody<R> {
+    source: R,
+    boundary: String,
+    ranges: Vec<(u64, u64)>,
+    heads: Vec<String>,
+}
+
+impl<R: Read + Seek> ByteRangesBody<R> {
+    /// Creates a body for the `ranges` of `source`, a representation of
+    /// `content_type` that is `len` bytes long.
+    ///
+    /// The ranges are first and last offsets, inclusive, like those from
+    /// `Range::to_satisfiable_ranges`. A boundary is generated that is
+    /// unique to this process and practically never found in content.
+    pub fn new(source: R, content_type: Mime, len: u64, ranges: Vec<(u64, u64)>)
+               -> ByteRangesBody<R> {
+        let boundary = format!("{:016x}{:08x}", time::precise_time_ns(),
+                               NEXT_BOUNDARY.fetch_add(1, Ordering::Relaxed));
+        let heads = ranges.iter().enumerate().map(|(i, &range)| {
+            let content_range = ContentRangeSpec::Bytes {
+                range: Some(range),
+                instance_length: Some(len),
+            };
+            format!("{}--{}\r\nContent-Type: {}\r\nContent-Range: {}\r\n\r\n",
+                    if i == 0 { "" } else { "\r\n" }, boundary, content_type, content_range)
+        }).collect();
+
+        ByteRangesBody {
+            source: source,
+            boundary: boundary,
+            ranges: ranges,
+            heads: heads,
+        }
+    }
+
+    /// The boundary delimiting the parts.
+    pub fn boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    /// The `Content-Type` to send the body with, including the boundary.
+    pub fn content_type(&self) -> Mime {
+        Mime(TopLevel::Multipart, SubLevel::Ext("byteranges".to_owned()),
+             vec![(Attr::Boundary, Value::Ext(self.boundary.clone()))])
+    }
+
+    /// The exact length of the body, so it can be sent with a
+    /// `Content-Length` rather than chunked.
+    pub fn content_length(&self) -> u64 {
+        let parts = self.heads.iter().zip(self.ranges.iter())
+            .fold(0, |sum, (head, &(first, last))| sum + head.len() as u64 + last - first + 1);
+        parts + self.tail().len() as u64
+    }
+
+    /// Write the whole body, reading each range from the source in turn.
+    pub fn write_to<W: Write>(&mut self, w: &mut W) -> io::Result<()> {
+        for (head, &(first, last)) in self.heads.iter().zip(self.ranges.iter()) {
+            try!(w.write_all(head.as_bytes()));
+            try!(self.source.seek(SeekFrom::Start(first)));
+            let want = last - first + 1;
+            let copied = try!(io::copy(&mut (&mut self.source).take(want), w));
+            if copied != want {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof,
+                                          "byte range source ended early"));
+            }
+        }
+        w.write_all(self.tail().as_bytes())
+    }
+
+    /// Get the source back.
+    pub fn into_inner(self) -> R {
+        self.source
+    }
+
+    fn tail(&self) -> String {
+        format!("\r\n--{}--\r\n", self.boundary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use mime::Mime;
+    use mime::TopLevel::Text;
+    use mime::SubLevel::Plain;
+    use super::ByteRangesBody;
+
+    #[test]
+    fn test_write_to() {
+        let source = Cursor::new(b"0123456789".to_vec());
+        let mut body = ByteRangesBody::new(source, Mime(Text, Plain, vec![]), 10,
+                                           vec![(0, 1), (8, 9)]);
+        let mut out = Vec::new();
+        body.write_to(&mut out).unwrap();
+
+        let expected = format!("--{b}\r\n\
+                                Content-Type: text/plain\r\n\
+                                Content-Range: bytes 0-1/10\r\n\
+                                \r\n\
+                                01\r\n\
+                                --{b}\r\n\
+                                Content-Type: text/plain\r\n\
+                                Content-Range: bytes 8-9/10\r\n\
+                                \r\n\
+                                89\r\n\
+                                --{b}--\r\n", b = body.boundary());
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+        assert_eq!(body.content_length(), expected.len() as u64);
+        assert_eq!(body.content_type().to_string(),
+                   format!("multipart/byteranges; boundary={}", body.boundary()));
+    }
+
+    #[test]
+    fn test_unique_boundaries() {
+        let a = ByteRangesBody::new(Cursor::new(vec![]), Mime(Text, Plain, vec![]), 0, vec![]);
+        let b = ByteRangesBody::new(Cursor::new(vec![]), Mime(Text, Plain, vec![]), 0, vec![]);
+        assert!(a.boundary() != b.boundary());
+    }
+
+    #[test]
+    fn test_short_source() {
+        let source = Cursor::new(b"0123".to_vec());
+        let mut body = ByteRangesBody::new(source, Mime(Text, Plain, vec![]), 10, vec![(2, 8)]);
+        assert!(body.write_to(&mut Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,72 @@
+//! Instrumentation hooks for a `Server`.
+//!
+//! A `Server` reports what it is doing as a series of `Event`s, each tagged
+//! with the connection and request it concerns, to an `Instrument`. By
+//! default these events are written to the `log` crate, but any type
+//! implementing `Instrument` can be given to `Server::set_instrument` to
+//! correlate them however it likes.
+//!
+//! ```no_run
+//! use hyper::server::{Server, Request, Response};
+//! use hyper::server::instrument::{Event, Tag};
+//!
+//! let mut server = Server::http("0.0.0.0:0").unwrap();
+//! server.set_instrument(|tag: Tag, event: &Event| {
+//!     println!("conn={} req={:?} {:?}", tag.connection, tag.request, event);
+//! });
+//! server.handle(|_: Request, _: Response| {}).unwrap();
+//! ```
+use std::net::SocketAddr;
+
+use Error;
+use method::Method;
+pub use metrics::Tag;
+use status::StatusCode;
+use uri::RequestUri;
+use version::HttpVersion;
+
+/// Something that happened while a `Server` was handling a connection.
+#[derive(Debug)]
+pub enum Event<'a> {
+    /// A connection was accepted from the peer at this address.
+    Accepted(SocketAddr),
+    /// The head of a request was read.
+    RequestParsed(&'a Method, &'a RequestUri, HttpVersion),
+    /// The head of a response was written with this status.
+    ResponseWritten(StatusCode),
+    /// An error ended the connection.
+    Error(&'a Error),
+    /// The connection was turned away with a `503 Service Unavailable`,
+    /// because too many were waiting to be served.
+    Shed,
+    /// The connection is no longer being served.
+    Closed,
+}
+
+/// Receives the `Event`s a `Server` emits.
+///
+/// Events for a single connection arrive in order, from the thread serving
+/// that connection. Events for different connections may arrive concurrently.
+pub trait Instrument: Send + Sync {
+    /// Called for every `Event`.
+    fn event(&self, tag: Tag, event: &Event);
+}
+
+impl<F> Instrument for F where F: Fn(Tag, &Event) + Send + Sync {
+    fn event(&self, tag: Tag, event: &Event) {
+        self(tag, event)
+    }
+}
+
+/// The default `Instrument`, writing every `Event` to the `log` crate.
+#[derive(Clone, Copy, Debug)]
+pub struct Log;
+
+impl Instrument for Log {
+    fn event(&self, tag: Tag, event: &Event) {
+        match *event {
+            Event::Error(e) => error!("[{}:{:?}] {:?}", tag.connection, tag.request, e),
+            ref e => debug!("[{}:{:?}] {:?}", tag.connection, tag.request, e),
+        }
+    }
+}
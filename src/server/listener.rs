@@ -1,7 +1,9 @@
-use std::sync::{Arc, mpsc};
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, mpsc};
 use std::thread;
+use std::time::{Duration, Instant};
 
-use net::NetworkListener;
+use net::{NetworkListener, Pending};
 
 pub struct ListenerPool<A: NetworkListener> {
     acceptor: A
@@ -37,6 +39,133 @@ impl<A: NetworkListener + Send + 'static> ListenerPool<A> {
             spawn_with(super_tx.clone(), work.clone(), self.acceptor.clone());
         }
     }
+
+    /// Runs the acceptor pool, with a single thread accepting connections
+    /// onto a queue that `threads` workers take them from. Blocks until the
+    /// acceptor is closed.
+    ///
+    /// A connection is handed to `shed` rather than `work` if, when it is
+    /// accepted, more than `max_queue` connections are already waiting for
+    /// a worker, or if it waited longer than `max_wait` for one. Shedding
+    /// happens on a thread of its own. Any handshake, such as that of TLS,
+    /// is made by the thread the connection is handed to, rather than the
+    /// one accepting.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if threads == 0.
+    pub fn accept_queued<F, S>(self, work: F, shed: S, threads: usize, max_queue: usize,
+                               max_wait: Option<Duration>)
+        where F: Fn(A::Stream) + Send + Sync + 'static,
+              S: Fn(A::Stream) + Send + Sync + 'static {
+        assert!(threads != 0, "Can't accept on 0 threads.");
+
+        let (super_tx, supervisor_rx) = mpsc::channel();
+
+        let work = Arc::new(work);
+        let queue = Arc::new(Queue {
+            state: Mutex::new(QueueState { conns: VecDeque::new(), idle: 0 }),
+            ready: Condvar::new(),
+        });
+
+        let (shed_tx, shed_rx) = mpsc::sync_channel::<Pending<A::Stream>>(SHED_BACKLOG);
+        {
+            let acceptor = self.acceptor.clone();
+            thread::spawn(move || {
+                for pending in shed_rx.iter() {
+                    match acceptor.finish(pending) {
+                        Ok(stream) => shed(stream),
+                        Err(e) => debug!("Connection failed: {}", e),
+                    }
+                }
+            });
+        }
+
+        for _ in 0..threads {
+            spawn_queued(super_tx.clone(), work.clone(), shed_tx.clone(), queue.clone(),
+                         self.acceptor.clone(), max_wait);
+        }
+
+        {
+            let queue = queue.clone();
+            let shed_tx = shed_tx.clone();
+            let mut acceptor = self.acceptor.clone();
+            thread::spawn(move || loop {
+                let pending = match acceptor.accept_pending() {
+                    Ok(pending) => pending,
+                    Err(e) => {
+                        error!("Connection failed: {}", e);
+                        continue;
+                    }
+                };
+                let mut state = queue.state.lock().unwrap();
+                if state.conns.len() >= state.idle + max_queue {
+                    drop(state);
+                    send_shed(&shed_tx, pending);
+                } else {
+                    state.conns.push_back((Instant::now(), pending));
+                    queue.ready.notify_one();
+                }
+            });
+        }
+
+        for _ in supervisor_rx.iter() {
+            spawn_queued(super_tx.clone(), work.clone(), shed_tx.clone(), queue.clone(),
+                         self.acceptor.clone(), max_wait);
+        }
+    }
+}
+
+// The most connections waiting to be shed before more are simply closed.
+const SHED_BACKLOG: usize = 64;
+
+fn send_shed<T>(shed_tx: &mpsc::SyncSender<T>, pending: T) {
+    // the shedding thread is behind as well, so just close it
+    if shed_tx.try_send(pending).is_err() {
+        debug!("dropping connection, shedding is backed up");
+    }
+}
+
+struct Queue<T> {
+    state: Mutex<QueueState<T>>,
+    ready: Condvar,
+}
+
+struct QueueState<T> {
+    conns: VecDeque<(Instant, T)>,
+    // workers waiting for a connection
+    idle: usize,
+}
+
+fn spawn_queued<A, F>(supervisor: mpsc::Sender<()>, work: Arc<F>,
+                      shed_tx: mpsc::SyncSender<Pending<A::Stream>>,
+                      queue: Arc<Queue<Pending<A::Stream>>>, acceptor: A, max_wait: Option<Duration>)
+where A: NetworkListener + Send + 'static,
+      F: Fn(A::Stream) + Send + Sync + 'static {
+
+    thread::spawn(move || {
+        let _sentinel = Sentinel::new(supervisor, ());
+
+        loop {
+            let (queued_at, pending) = {
+                let mut state = queue.state.lock().unwrap();
+                state.idle += 1;
+                while state.conns.is_empty() {
+                    state = queue.ready.wait(state).unwrap();
+                }
+                state.idle -= 1;
+                state.conns.pop_front().unwrap()
+            };
+            if max_wait.map_or(false, |max_wait| queued_at.elapsed() > max_wait) {
+                send_shed(&shed_tx, pending);
+                continue;
+            }
+            match acceptor.finish(pending) {
+                Ok(stream) => work(stream),
+                Err(e) => error!("Connection failed: {}", e),
+            }
+        }
+    });
 }
 
 fn spawn_with<A, F>(supervisor: mpsc::Sender<()>, work: Arc<F>, mut acceptor: A)
@@ -107,10 +107,13 @@
 //! out by calling `start` on the `Request<Fresh>`. This will return a new
 //! `Request<Streaming>` object, that no longer has `headers_mut()`, but does
 //! implement `Write`.
+use std::cell::Cell;
 use std::fmt;
-use std::io::{ErrorKind, BufWriter, Write};
-use std::net::{SocketAddr, ToSocketAddrs};
+use std::io::{self, ErrorKind, BufWriter, Read, Write};
+use std::net::{Shutdown, SocketAddr, ToSocketAddrs};
+use std::sync::Arc;
 use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
 use num_cpus;
 
@@ -121,16 +124,25 @@ pub use net::{Fresh, Streaming};
 
 use Error;
 use buffer::BufReader;
-use header::{Headers, Expect, Connection};
+use clock::{Clock, SystemClock};
+use header::{Headers, Expect, Connection, ContentType, RetryAfter};
 use http;
+use http::h1::HeadLimits;
 use method::Method;
-use net::{NetworkListener, NetworkStream, HttpListener, HttpsListener, Ssl};
+use metrics::{self, Metrics, MeteredStream, Side};
+use mime::{Mime, TopLevel, SubLevel};
+use net::{NetworkListener, NetworkStream, HttpListener, HttpsListener, ListenOptions, Ssl};
 use status::StatusCode;
 use uri::RequestUri;
 use version::HttpVersion::Http11;
 
+use self::instrument::{Event, Instrument, Log, Tag};
 use self::listener::ListenerPool;
 
+pub mod byteranges;
+pub mod instrument;
+pub mod negotiate;
+pub mod precondition;
 pub mod request;
 pub mod response;
 
@@ -140,9 +152,155 @@ mod listener;
 ///
 /// Once listening, it will create a `Request`/`Response` pair for each
 /// incoming connection, and hand them to the provided handler.
-#[derive(Debug)]
 pub struct Server<L = HttpListener> {
     listener: L,
+    settings: Settings,
+}
+
+// Everything a `Worker` needs besides the `Handler`.
+struct Settings {
+    instrument: Box<Instrument>,
+    metrics: Option<Arc<Metrics>>,
+    clock: Box<Clock>,
+    load_shedding: Option<LoadShedding>,
+    trace: Option<TraceEcho>,
+    request_deadline: Option<Duration>,
+    head_limits: HeadLimits,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            instrument: Box::new(Log),
+            metrics: None,
+            clock: Box::new(SystemClock),
+            load_shedding: None,
+            trace: None,
+            request_deadline: None,
+            head_limits: HeadLimits::default(),
+        }
+    }
+}
+
+/// When a server should turn connections away with a
+/// `503 Service Unavailable`, rather than queue them for longer than they
+/// would want to wait.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use hyper::server::{Server, Request, Response, LoadShedding};
+///
+/// let mut server = Server::http("0.0.0.0:0").unwrap();
+/// server.set_load_shedding(LoadShedding {
+///     max_wait: Some(Duration::from_millis(500)),
+///     ..LoadShedding::default()
+/// });
+/// server.handle(|_: Request, res: Response| {
+///     res.send(b"Hello World!").unwrap();
+/// }).unwrap();
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LoadShedding {
+    /// The most connections to keep waiting for a free thread. Any more are
+    /// turned away as soon as they are accepted.
+    ///
+    /// Defaults to 128.
+    pub max_queue: usize,
+    /// The longest a connection may wait for a free thread, after which
+    /// it is turned away instead of served.
+    ///
+    /// Defaults to `None`, which waits as long as it takes.
+    pub max_wait: Option<Duration>,
+    /// The `Retry-After` to send with each `503 Service Unavailable`.
+    ///
+    /// Defaults to 1 second.
+    pub retry_after: Option<Duration>,
+}
+
+impl Default for LoadShedding {
+    fn default() -> LoadShedding {
+        LoadShedding {
+            max_queue: 128,
+            max_wait: None,
+            retry_after: Some(Duration::from_secs(1)),
+        }
+    }
+}
+
+/// How a server answers `TRACE` requests itself, by sending back the
+/// request head it received as a `message/http` body.
+///
+/// Echoing requests can leak credentials to scripts that shouldn't see
+/// them, so the headers named in `filtered_headers` are left out.
+///
+/// ```no_run
+/// use hyper::server::{Server, Request, Response, TraceEcho};
+///
+/// let mut server = Server::http("0.0.0.0:0").unwrap();
+/// let mut trace = TraceEcho::default();
+/// trace.filtered_headers.push("X-Api-Key".to_owned());
+/// server.set_trace_echo(trace);
+/// server.handle(|_: Request, res: Response| {
+///     res.send(b"Hello World!").unwrap();
+/// }).unwrap();
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceEcho {
+    /// The names of headers to leave out of the echoed request.
+    ///
+    /// Defaults to `Authorization`, `Proxy-Authorization` and `Cookie`.
+    pub filtered_headers: Vec<String>,
+}
+
+impl Default for TraceEcho {
+    fn default() -> TraceEcho {
+        TraceEcho {
+            filtered_headers: vec![
+                "Authorization".to_owned(),
+                "Proxy-Authorization".to_owned(),
+                "Cookie".to_owned(),
+            ],
+        }
+    }
+}
+
+impl TraceEcho {
+    fn echo(&self, mut req: Request, mut res: Response<Fresh>) {
+        let mut headers = req.headers.clone();
+        for name in &self.filtered_headers {
+            headers.remove_raw(name);
+        }
+        // the raw lines, since a value needn't be UTF-8
+        let mut head = format!("{} {} {}\r\n", req.method, req.uri, req.version).into_bytes();
+        for header in headers.iter() {
+            for line in headers.get_all_raw(header.name()) {
+                head.extend_from_slice(header.name().as_bytes());
+                head.extend_from_slice(b": ");
+                head.extend_from_slice(line);
+                head.extend_from_slice(b"\r\n");
+            }
+        }
+        head.extend_from_slice(b"\r\n");
+
+        // a TRACE shouldn't have a body, but one must still be read past
+        if let Err(e) = io::copy(&mut req, &mut io::sink()) {
+            debug!("error reading TRACE body: {:?}", e);
+            return;
+        }
+        res.headers_mut().set(ContentType(Mime(TopLevel::Message,
+                                               SubLevel::Ext("http".to_owned()), vec![])));
+        if let Err(e) = res.send(&head) {
+            debug!("error echoing TRACE: {:?}", e);
+        }
+    }
+}
+
+impl<L: fmt::Debug> fmt::Debug for Server<L> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Server")
+            .field("listener", &self.listener)
+            .finish()
+    }
 }
 
 macro_rules! try_option(
@@ -159,9 +317,66 @@ impl<L: NetworkListener> Server<L> {
     #[inline]
     pub fn new(listener: L) -> Server<L> {
         Server {
-            listener: listener
+            listener: listener,
+            settings: Settings::default(),
         }
     }
+
+    /// Set the `Instrument` that receives events about connections and
+    /// requests handled by this server.
+    ///
+    /// By default, events are written to the `log` crate.
+    pub fn set_instrument<I: Instrument + 'static>(&mut self, instrument: I) {
+        self.settings.instrument = Box::new(instrument);
+    }
+
+    /// Set the `Metrics` to report connections, bytes, and requests to.
+    pub fn set_metrics(&mut self, metrics: Arc<Metrics>) {
+        self.settings.metrics = Some(metrics);
+    }
+
+    /// Set the `Clock` used to generate `Date` headers.
+    ///
+    /// By default, this is the `SystemClock`.
+    pub fn set_clock<C: Clock + 'static>(&mut self, clock: C) {
+        self.settings.clock = Box::new(clock);
+    }
+
+    /// Turn connections away with a `503 Service Unavailable` once too
+    /// many are waiting for a thread to serve them.
+    ///
+    /// By default, connections wait for a free thread in the listener's
+    /// backlog, for however long it takes.
+    pub fn set_load_shedding(&mut self, load_shedding: LoadShedding) {
+        self.settings.load_shedding = Some(load_shedding);
+    }
+
+    /// Answer `TRACE` requests by echoing them back, instead of passing them
+    /// to the handler.
+    ///
+    /// By default, `TRACE` requests are handled like any other.
+    pub fn set_trace_echo(&mut self, trace: TraceEcho) {
+        self.settings.trace = Some(trace);
+    }
+
+    /// Limit how long a handler may take to write its response, counted
+    /// from when the head of the request was received.
+    ///
+    /// Once the deadline has passed, writing to the `Response` fails with a
+    /// `TimedOut` error, and the connection is closed, since the response
+    /// can't be finished. By default, there is no deadline.
+    pub fn set_request_deadline(&mut self, deadline: Option<Duration>) {
+        self.settings.request_deadline = deadline;
+    }
+
+    /// Limit the size of request heads, answering any larger with a
+    /// `431 Request Header Fields Too Large` before closing the connection.
+    ///
+    /// By default, a head may be a little over 400KB, as may any one of its
+    /// lines.
+    pub fn set_head_limits(&mut self, limits: HeadLimits) {
+        self.settings.head_limits = limits;
+    }
 }
 
 impl Server<HttpListener> {
@@ -169,6 +384,30 @@ impl Server<HttpListener> {
     pub fn http<To: ToSocketAddrs>(addr: To) -> ::Result<Server<HttpListener>> {
         HttpListener::new(addr).map(Server::new)
     }
+
+    /// Creates a new server that will handle `HttpStream`s, creating the
+    /// listening socket with the given options.
+    pub fn http_with_options<To: ToSocketAddrs>(addr: To, options: &ListenOptions)
+                                                -> ::Result<Server<HttpListener>> {
+        HttpListener::with_options(addr, options).map(Server::new)
+    }
+
+    /// Set `TCP_NODELAY` on each accepted connection.
+    pub fn set_nodelay(&mut self, nodelay: bool) {
+        self.listener.set_nodelay(nodelay);
+    }
+
+    /// Only accept connections once the client has sent data, or `timeout`
+    /// has passed. See `HttpListener::set_defer_accept`.
+    pub fn set_defer_accept(&mut self, timeout: Option<Duration>) -> ::Result<()> {
+        Ok(try!(self.listener.set_defer_accept(timeout)))
+    }
+
+    /// Enable TCP Fast Open with the given queue length, or disable it with
+    /// `0`. See `HttpListener::set_fast_open`.
+    pub fn set_fast_open(&mut self, queue_len: u32) -> ::Result<()> {
+        Ok(try!(self.listener.set_fast_open(queue_len)))
+    }
 }
 
 impl<S: Ssl + Clone + Send> Server<HttpsListener<S>> {
@@ -178,31 +417,73 @@ impl<S: Ssl + Clone + Send> Server<HttpsListener<S>> {
     pub fn https<A: ToSocketAddrs>(addr: A, ssl: S) -> ::Result<Server<HttpsListener<S>>> {
         HttpsListener::new(addr, ssl).map(Server::new)
     }
+
+    /// Creates a new server that will handle `HttpStream`s over SSL,
+    /// creating the listening socket with the given options.
+    pub fn https_with_options<A: ToSocketAddrs>(addr: A, ssl: S, options: &ListenOptions)
+                                                -> ::Result<Server<HttpsListener<S>>> {
+        HttpsListener::with_options(addr, ssl, options).map(Server::new)
+    }
+
+    /// Set `TCP_NODELAY` on each accepted connection.
+    pub fn set_nodelay(&mut self, nodelay: bool) {
+        self.listener.http_listener_mut().set_nodelay(nodelay);
+    }
+
+    /// Only accept connections once the client has sent data, or `timeout`
+    /// has passed. See `HttpListener::set_defer_accept`.
+    pub fn set_defer_accept(&mut self, timeout: Option<Duration>) -> ::Result<()> {
+        Ok(try!(self.listener.http_listener_mut().set_defer_accept(timeout)))
+    }
+
+    /// Enable TCP Fast Open with the given queue length, or disable it with
+    /// `0`. See `HttpListener::set_fast_open`.
+    pub fn set_fast_open(&mut self, queue_len: u32) -> ::Result<()> {
+        Ok(try!(self.listener.http_listener_mut().set_fast_open(queue_len)))
+    }
 }
 
 impl<L: NetworkListener + Send + 'static> Server<L> {
     /// Binds to a socket and starts handling connections.
     pub fn handle<H: Handler + 'static>(self, handler: H) -> ::Result<Listening> {
-        with_listener(handler, self.listener, num_cpus::get() * 5 / 4)
+        with_listener(handler, self, num_cpus::get() * 5 / 4)
     }
     /// Binds to a socket and starts handling connections with the provided
     /// number of threads.
     pub fn handle_threads<H: Handler + 'static>(self, handler: H,
             threads: usize) -> ::Result<Listening> {
-        with_listener(handler, self.listener, threads)
+        with_listener(handler, self, threads)
     }
 }
 
-fn with_listener<H, L>(handler: H, mut listener: L, threads: usize) -> ::Result<Listening>
+fn with_listener<H, L>(handler: H, server: Server<L>, threads: usize) -> ::Result<Listening>
 where H: Handler + 'static,
 L: NetworkListener + Send + 'static {
+    let Server { mut listener, settings } = server;
     let socket = try!(listener.local_addr());
 
     debug!("threads = {:?}", threads);
     let pool = ListenerPool::new(listener);
-    let work = move |mut stream| Worker(&handler).handle_connection(&mut stream);
+    let settings = Arc::new(settings);
+    let load_shedding = settings.load_shedding;
+    let shed_settings = settings.clone();
+    let shed = move |mut stream: L::Stream| shed(&mut stream, &shed_settings);
+    let work = move |mut stream| {
+        let worker = Worker::new(&handler, &settings);
+        match settings.metrics {
+            Some(ref metrics) => {
+                metrics.connection_opened(Side::Server);
+                let mut stream = MeteredStream::new(stream, Side::Server, metrics.clone());
+                worker.handle_connection(&mut stream)
+            }
+            None => worker.handle_connection(&mut stream)
+        }
+    };
 
-    let guard = thread::spawn(move || pool.accept(work, threads));
+    let guard = thread::spawn(move || match load_shedding {
+        Some(ls) => pool.accept_queued(work, shed, threads, ls.max_queue, ls.max_wait),
+        None => pool.accept(work, threads),
+    });
 
     Ok(Listening {
         _guard: Some(guard),
@@ -210,19 +491,86 @@ L: NetworkListener + Send + 'static {
     })
 }
 
-struct Worker<'a, H: Handler + 'static>(&'a H);
+/// Serve the requests on a single stream with `handler`, until it closes.
+///
+/// This is how the `mock::loopback` harness runs handlers without a listener.
+#[cfg(any(test, feature = "mock"))]
+#[doc(hidden)]
+pub fn serve_stream<H, S>(handler: &H, stream: &mut S)
+where H: Handler + 'static, S: NetworkStream + Clone {
+    Worker::new(handler, &Settings::default()).handle_connection(stream)
+}
+
+// Turn a connection away with a `503 Service Unavailable`, without reading
+// its request.
+fn shed<S: NetworkStream>(stream: &mut S, settings: &Settings) {
+    let tag = Tag {
+        connection: metrics::next_connection_id(),
+        request: None,
+    };
+    settings.instrument.event(tag, &Event::Shed);
+
+    let mut headers = Headers::new();
+    headers.set(Connection::close());
+    if let Some(retry_after) = settings.load_shedding.and_then(|ls| ls.retry_after) {
+        headers.set(RetryAfter::Delay(retry_after));
+    }
+    let sent = {
+        let mut wrt = BufWriter::new(&mut *stream);
+        let mut res = Response::new(&mut wrt, &mut headers);
+        *res.status_mut() = StatusCode::ServiceUnavailable;
+        res.use_clock(&*settings.clock);
+        res.send(b"")
+    };
+    if let Err(e) = sent {
+        debug!("error shedding connection: {:?}", e);
+        return;
+    }
+
+    let _ = stream.close(Shutdown::Write);
+    let _ = stream.set_read_timeout(Some(Duration::from_millis(SHED_LINGER_MS)));
+    let _ = io::copy(&mut (&mut *stream).take(SHED_LINGER_BYTES), &mut io::sink());
+}
+
+// How long, and for how many bytes, to wait for a shed client to finish
+// sending, so that closing doesn't reset the connection before the client
+// has read the response.
+const SHED_LINGER_MS: u64 = 100;
+const SHED_LINGER_BYTES: u64 = 64 * 1024;
+
+struct Worker<'a, H: Handler + 'static> {
+    handler: &'a H,
+    settings: &'a Settings,
+    connection: usize,
+}
 
 impl<'a, H: Handler + 'static> Worker<'a, H> {
 
+    fn new(handler: &'a H, settings: &'a Settings) -> Worker<'a, H> {
+        Worker {
+            handler: handler,
+            settings: settings,
+            connection: metrics::next_connection_id(),
+        }
+    }
+
+    fn event(&self, request: Option<usize>, event: Event) {
+        let tag = Tag {
+            connection: self.connection,
+            request: request,
+        };
+        self.settings.instrument.event(tag, &event);
+    }
+
     fn handle_connection<S>(&self, mut stream: &mut S) where S: NetworkStream + Clone {
-        debug!("Incoming stream");
         let addr = match stream.peer_addr() {
             Ok(addr) => addr,
             Err(e) => {
-                error!("Peer Name error: {:?}", e);
+                self.event(None, Event::Error(&Error::Io(e)));
                 return;
             }
         };
+        self.event(None, Event::Accepted(addr));
 
         // FIXME: Use Type ascription
         let stream_clone: &mut NetworkStream = &mut stream.clone();
@@ -230,14 +578,16 @@ impl<'a, H: Handler + 'static> Worker<'a, H> {
         let wrt = BufWriter::new(stream);
 
         self.keep_alive_loop(rdr, wrt, addr);
-        debug!("keep_alive loop ending for {}", addr);
+        self.event(None, Event::Closed);
     }
 
     fn keep_alive_loop<W: Write>(&self, mut rdr: BufReader<&mut NetworkStream>,
             mut wrt: W, addr: SocketAddr) {
         let mut keep_alive = true;
+        let mut count = 0;
         while keep_alive {
-            let req = match Request::new(&mut rdr, addr) {
+            count += 1;
+            let mut req = match Request::with_limits(&mut rdr, addr, &self.settings.head_limits) {
                 Ok(req) => req,
                 Err(Error::Io(ref e)) if e.kind() == ErrorKind::ConnectionAborted => {
                     trace!("tcp closed, cancelling keep-alive loop");
@@ -247,13 +597,27 @@ impl<'a, H: Handler + 'static> Worker<'a, H> {
                     debug!("ioerror in keepalive loop = {:?}", e);
                     break;
                 }
+                Err(Error::TooLarge) => {
+                    let _ = wrt.write_all(b"HTTP/1.1 431 Request Header Fields Too Large\r\n\
+                                            Content-Length: 0\r\nConnection: close\r\n\r\n")
+                        .and_then(|_| wrt.flush());
+                    self.event(Some(count), Event::Error(&Error::TooLarge));
+                    break;
+                }
                 Err(e) => {
                     //TODO: send a 400 response
-                    error!("request error = {:?}", e);
+                    self.event(Some(count), Event::Error(&e));
                     break;
                 }
             };
-
+            // not counting the time spent waiting for the request to come
+            let started = Instant::now();
+            let tag = Tag { connection: self.connection, request: Some(count) };
+            req.set_tag(tag);
+            self.event(Some(count), Event::RequestParsed(&req.method, &req.uri, req.version));
+            if let Some(ref metrics) = self.settings.metrics {
+                metrics.request(Side::Server, tag, &req.method);
+            }
 
             if !self.handle_expect(&req, &mut wrt) {
                 break;
@@ -265,10 +629,31 @@ impl<'a, H: Handler + 'static> Worker<'a, H> {
             if !keep_alive {
                 res_headers.set(Connection::close());
             }
+            let status = Cell::new(None);
+            let deadline_exceeded = Cell::new(false);
             {
                 let mut res = Response::new(&mut wrt, &mut res_headers);
                 res.version = version;
-                self.0.handle(req, res);
+                res.report_status_to(&status);
+                res.use_clock(&*self.settings.clock);
+                if let Some(deadline) = self.settings.request_deadline {
+                    res.set_deadline(started + deadline, &deadline_exceeded);
+                }
+                match self.settings.trace {
+                    Some(ref trace) if req.method == Method::Trace => trace.echo(req, res),
+                    _ => self.handler.handle(req, res),
+                }
+            }
+            if let Some(status) = status.get() {
+                self.event(Some(count), Event::ResponseWritten(status));
+                if let Some(ref metrics) = self.settings.metrics {
+                    metrics.response(Side::Server, tag, status, started.elapsed());
+                }
+            }
+
+            if deadline_exceeded.get() {
+                debug!("response deadline exceeded, closing connection to {}", addr);
+                break;
             }
 
             // if the request was keep-alive, we need to check that the server agrees
@@ -284,7 +669,7 @@ impl<'a, H: Handler + 'static> Worker<'a, H> {
 
     fn handle_expect<W: Write>(&self, req: &Request, wrt: &mut W) -> bool {
          if req.version == Http11 && req.headers.get() == Some(&Expect::Continue) {
-            let status = self.0.check_continue((&req.method, &req.uri, &req.headers));
+            let status = self.handler.check_continue((&req.method, &req.uri, &req.headers));
             match write!(wrt, "{} {}\r\n\r\n", Http11, status) {
                 Ok(..) => (),
                 Err(e) => {
@@ -356,13 +741,15 @@ impl<F> Handler for F where F: Fn(Request, Response<Fresh>), F: Sync + Send {
 
 #[cfg(test)]
 mod tests {
+    use std::io;
+
     use header::Headers;
     use method::Method;
     use mock::MockStream;
     use status::StatusCode;
     use uri::RequestUri;
 
-    use super::{Request, Response, Fresh, Handler, Worker};
+    use super::{Request, Response, Fresh, Handler, Settings, Worker};
 
     #[test]
     fn test_check_continue_default() {
@@ -379,7 +766,7 @@ mod tests {
             res.start().unwrap().end().unwrap();
         }
 
-        Worker(&handle).handle_connection(&mut mock);
+        Worker::new(&handle, &Settings::default()).handle_connection(&mut mock);
         let cont = b"HTTP/1.1 100 Continue\r\n\r\n";
         assert_eq!(&mock.write[..cont.len()], cont);
         let res = b"HTTP/1.1 200 OK\r\n";
@@ -408,7 +795,404 @@ mod tests {
             1234567890\
         ");
 
-        Worker(&Reject).handle_connection(&mut mock);
+        Worker::new(&Reject, &Settings::default()).handle_connection(&mut mock);
         assert_eq!(mock.write, &b"HTTP/1.1 417 Expectation Failed\r\n\r\n"[..]);
     }
+
+    #[test]
+    fn test_head_limits() {
+        use http::h1::HeadLimits;
+
+        let mut mock = MockStream::with_input(b"\
+            GET /foo HTTP/1.1\r\n\
+            Host: example.domain\r\n\
+            Cookie: session=a-rather-long-cookie\r\n\
+            \r\n\
+        ");
+
+        fn handle(_: Request, res: Response<Fresh>) {
+            res.start().unwrap().end().unwrap();
+        }
+
+        let mut settings = Settings::default();
+        settings.head_limits = HeadLimits { max_size: 1024, max_line_size: 32 };
+        Worker::new(&handle, &settings).handle_connection(&mut mock);
+        assert_eq!(mock.write, &b"HTTP/1.1 431 Request Header Fields Too Large\r\n\
+                                  Content-Length: 0\r\nConnection: close\r\n\r\n"[..]);
+    }
+
+    #[test]
+    fn test_instrument_events() {
+        use std::sync::{Arc, Mutex};
+        use super::instrument::{Event, Tag};
+
+        let mut mock = MockStream::with_input(b"\
+            GET /foo HTTP/1.1\r\n\
+            Host: example.domain\r\n\
+            Connection: close\r\n\
+            \r\n\
+        ");
+
+        fn handle(_: Request, mut res: Response<Fresh>) {
+            *res.status_mut() = StatusCode::NotFound;
+        }
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = events.clone();
+        let record = move |tag: Tag, event: &Event| {
+            let name = match *event {
+                Event::Accepted(..) => "accepted",
+                Event::RequestParsed(..) => "parsed",
+                Event::ResponseWritten(StatusCode::NotFound) => "written",
+                Event::ResponseWritten(..) => "wrong status",
+                Event::Error(..) => "error",
+                Event::Closed => "closed",
+                Event::Shed => "shed",
+            };
+            recorded.lock().unwrap().push((tag.request, name));
+        };
+        let settings = Settings { instrument: Box::new(record), ..Settings::default() };
+        Worker::new(&handle, &settings).handle_connection(&mut mock);
+
+        assert_eq!(*events.lock().unwrap(), vec![
+            (None, "accepted"),
+            (Some(1), "parsed"),
+            (Some(1), "written"),
+            (None, "closed"),
+        ]);
+    }
+
+    #[test]
+    fn test_metrics_requests() {
+        use std::sync::{Arc, Mutex};
+        use std::time::Duration;
+        use metrics::{Metrics, Side, Tag};
+
+        #[derive(Default)]
+        struct Statuses(Mutex<Vec<(Option<usize>, Method, StatusCode)>>);
+
+        impl Metrics for Statuses {
+            fn request(&self, side: Side, tag: Tag, method: &Method) {
+                assert_eq!(side, Side::Server);
+                self.0.lock().unwrap().push((tag.request, method.clone(), StatusCode::Continue));
+            }
+
+            fn response(&self, _: Side, tag: Tag, status: StatusCode, _: Duration) {
+                let mut statuses = self.0.lock().unwrap();
+                let last = statuses.last_mut().unwrap();
+                assert_eq!(last.0, tag.request);
+                last.2 = status;
+            }
+        }
+
+        let mut mock = MockStream::with_input(b"\
+            GET / HTTP/1.1\r\n\
+            Host: example.domain\r\n\
+            \r\n\
+            DELETE / HTTP/1.1\r\n\
+            Host: example.domain\r\n\
+            Connection: close\r\n\
+            \r\n\
+        ");
+
+        fn handle(req: Request, mut res: Response<Fresh>) {
+            assert!(req.tag().is_some());
+            if req.method == Method::Delete {
+                *res.status_mut() = StatusCode::Forbidden;
+            }
+        }
+
+        let statuses = Arc::new(Statuses::default());
+        let settings = Settings { metrics: Some(statuses.clone()), ..Settings::default() };
+        Worker::new(&handle, &settings).handle_connection(&mut mock);
+        assert_eq!(*statuses.0.lock().unwrap(), vec![
+            (Some(1), Method::Get, StatusCode::Ok),
+            (Some(2), Method::Delete, StatusCode::Forbidden),
+        ]);
+    }
+
+    #[test]
+    fn test_metrics_duration() {
+        use std::io::{Read, Write};
+        use std::net::TcpStream;
+        use std::sync::{Arc, Mutex};
+        use std::thread;
+        use std::time::Duration;
+        use metrics::{Metrics, Side, Tag};
+        use super::Server;
+
+        #[derive(Default)]
+        struct Durations(Mutex<Vec<Duration>>);
+
+        impl Metrics for Durations {
+            fn response(&self, _: Side, _: Tag, _: StatusCode, duration: Duration) {
+                self.0.lock().unwrap().push(duration);
+            }
+        }
+
+        let durations = Arc::new(Durations::default());
+        let mut server = Server::http("127.0.0.1:0").unwrap();
+        server.set_metrics(durations.clone());
+        let mut listening = server.handle_threads(|_: Request, res: Response<Fresh>| {
+            res.send(b"ok").unwrap();
+        }, 1).unwrap();
+
+        let mut conn = TcpStream::connect(listening.socket).unwrap();
+        conn.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        conn.write_all(b"GET / HTTP/1.1\r\nHost: a\r\n\r\n").unwrap();
+        let mut first = Vec::new();
+        while !first.ends_with(b"\r\n\r\nok") {
+            let mut buf = [0; 256];
+            let n = conn.read(&mut buf).unwrap();
+            assert!(n > 0);
+            first.extend_from_slice(&buf[..n]);
+        }
+
+        // the time the connection is idle between requests isn't counted
+        thread::sleep(Duration::from_millis(250));
+        conn.write_all(b"GET / HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n").unwrap();
+        conn.read_to_end(&mut Vec::new()).unwrap();
+        listening.close().unwrap();
+
+        let durations = durations.0.lock().unwrap();
+        assert_eq!(durations.len(), 2);
+        assert!(durations[1] < Duration::from_millis(200), "{:?}", durations[1]);
+    }
+
+    #[test]
+    fn test_date_from_clock() {
+        use time::{self, Timespec};
+        use clock::ManualClock;
+
+        let mut mock = MockStream::with_input(b"\
+            GET / HTTP/1.1\r\n\
+            Host: example.domain\r\n\
+            Connection: close\r\n\
+            \r\n\
+        ");
+
+        fn handle(_: Request, res: Response<Fresh>) {
+            res.send(b"").unwrap();
+        }
+
+        let clock = ManualClock::new(time::at_utc(Timespec::new(784111777, 0)));
+        let settings = Settings { clock: Box::new(clock), ..Settings::default() };
+        Worker::new(&handle, &settings).handle_connection(&mut mock);
+
+        let written = String::from_utf8(mock.write).unwrap();
+        assert!(written.contains("Date: Sun, 06 Nov 1994 08:49:37 GMT\r\n"), "{}", written);
+    }
+
+    #[test]
+    fn test_load_shedding() {
+        use std::io::{Read, Write};
+        use std::net::TcpStream;
+        use std::sync::{mpsc, Mutex};
+        use std::time::Duration;
+        use super::{Server, LoadShedding};
+
+        let (started_tx, started_rx) = mpsc::channel();
+        let (release_tx, release_rx) = mpsc::channel();
+        let (started_tx, release_rx) = (Mutex::new(started_tx), Mutex::new(release_rx));
+        let mut server = Server::http("127.0.0.1:0").unwrap();
+        server.set_load_shedding(LoadShedding {
+            max_queue: 1,
+            retry_after: Some(Duration::from_secs(3)),
+            ..LoadShedding::default()
+        });
+        let mut listening = server.handle_threads(move |_: Request, res: Response<Fresh>| {
+            started_tx.lock().unwrap().send(()).unwrap();
+            release_rx.lock().unwrap().recv().unwrap();
+            res.send(b"ok").unwrap();
+        }, 1).unwrap();
+
+        let request = |addr| {
+            let mut conn = TcpStream::connect(addr).unwrap();
+            conn.write_all(b"GET / HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n").unwrap();
+            conn
+        };
+        let read = |mut conn: TcpStream| {
+            let mut res = String::new();
+            conn.read_to_string(&mut res).unwrap();
+            res
+        };
+
+        // the first is being served and the second is queued, so the
+        // third is turned away
+        let first = request(listening.socket);
+        started_rx.recv().unwrap();
+        let second = request(listening.socket);
+        let shed = read(request(listening.socket));
+        assert!(shed.starts_with("HTTP/1.1 503 Service Unavailable\r\n"), "{}", shed);
+        assert!(shed.contains("Retry-After: 3\r\n"), "{}", shed);
+        assert!(shed.contains("Connection: close\r\n"), "{}", shed);
+
+        release_tx.send(()).unwrap();
+        release_tx.send(()).unwrap();
+        assert!(read(first).starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(read(second).starts_with("HTTP/1.1 200 OK\r\n"));
+        listening.close().unwrap();
+    }
+
+    #[test]
+    fn test_load_shedding_handshake() {
+        use std::io::{Read, Write};
+        use std::net::TcpStream;
+        use std::time::Duration;
+        use net::{HttpStream, Ssl};
+        use super::{Server, LoadShedding};
+
+        // a handshake that waits for the client to send a byte
+        #[derive(Clone)]
+        struct Handshake;
+
+        impl Ssl for Handshake {
+            type Stream = HttpStream;
+
+            fn wrap_client(&self, stream: HttpStream, _: &str) -> ::Result<HttpStream> {
+                Ok(stream)
+            }
+
+            fn wrap_server(&self, mut stream: HttpStream) -> ::Result<HttpStream> {
+                try!(stream.read_exact(&mut [0]));
+                Ok(stream)
+            }
+        }
+
+        let mut server = Server::https("127.0.0.1:0", Handshake).unwrap();
+        server.set_load_shedding(LoadShedding::default());
+        let mut listening = server.handle_threads(|_: Request, res: Response<Fresh>| {
+            res.send(b"ok").unwrap();
+        }, 2).unwrap();
+
+        // a client that never makes its handshake holds up only its worker
+        let stalled = TcpStream::connect(listening.socket).unwrap();
+        let mut conn = TcpStream::connect(listening.socket).unwrap();
+        conn.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        conn.write_all(b"!GET / HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n").unwrap();
+        let mut res = String::new();
+        conn.read_to_string(&mut res).unwrap();
+        assert!(res.starts_with("HTTP/1.1 200 OK\r\n"), "{}", res);
+        drop(stalled);
+        listening.close().unwrap();
+    }
+
+    #[test]
+    fn test_trace_echo() {
+        use super::TraceEcho;
+
+        let input = b"\
+            TRACE /where?x=1 HTTP/1.1\r\n\
+            Host: example.domain\r\n\
+            Cookie: session=secret\r\n\
+            \r\n\
+            TRACE / HTTP/1.1\r\n\
+            Host: example.domain\r\n\
+            Connection: close\r\n\
+            \r\n\
+        ";
+
+        fn handle(_: Request, mut res: Response<Fresh>) {
+            *res.status_mut() = StatusCode::MethodNotAllowed;
+        }
+
+        let mut mock = MockStream::with_input(input);
+        Worker::new(&handle, &Settings::default()).handle_connection(&mut mock);
+        let written = String::from_utf8(mock.write).unwrap();
+        assert!(written.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"), "{}", written);
+
+        let mut mock = MockStream::with_input(input);
+        let settings = Settings { trace: Some(TraceEcho::default()), ..Settings::default() };
+        Worker::new(&handle, &settings).handle_connection(&mut mock);
+        let written = String::from_utf8(mock.write).unwrap();
+        assert!(written.starts_with("HTTP/1.1 200 OK\r\n"), "{}", written);
+        assert!(written.contains("Content-Type: message/http\r\n"), "{}", written);
+        assert!(written.contains("\r\n\r\nTRACE /where?x=1 HTTP/1.1\r\nHost: example.domain\r\n\r\n"),
+                "{}", written);
+        assert!(!written.contains("secret"), "{}", written);
+        assert!(written.contains("\r\n\r\nTRACE / HTTP/1.1\r\n"), "{}", written);
+
+        // values that aren't UTF-8 are echoed as they came
+        let mut mock = MockStream::with_input(b"\
+            TRACE / HTTP/1.1\r\n\
+            Host: example.domain\r\n\
+            X-Name: caf\xe9\r\n\
+            Connection: close\r\n\
+            \r\n\
+        ");
+        Worker::new(&handle, &settings).handle_connection(&mut mock);
+        assert!(mock.write.starts_with(b"HTTP/1.1 200 OK\r\n"));
+        let line = b"X-Name: caf\xe9\r\n";
+        assert!(mock.write.windows(line.len()).any(|w| w == line));
+    }
+
+    #[test]
+    fn test_request_deadline() {
+        use std::io::Write;
+        use std::thread;
+        use std::time::Duration;
+
+        let input = b"\
+            GET / HTTP/1.1\r\n\
+            Host: example.domain\r\n\
+            \r\n\
+            GET / HTTP/1.1\r\n\
+            Host: example.domain\r\n\
+            \r\n\
+        ";
+
+        fn handle(_: Request, res: Response<Fresh>) {
+            assert!(res.deadline().is_some());
+            let mut res = res.start().unwrap();
+            res.write_all(b"partial").unwrap();
+            thread::sleep(Duration::from_millis(60));
+            assert_eq!(res.write_all(b"late").unwrap_err().kind(), io::ErrorKind::TimedOut);
+        }
+
+        let mut mock = MockStream::with_input(input);
+        let settings = Settings {
+            request_deadline: Some(Duration::from_millis(50)),
+            ..Settings::default()
+        };
+        Worker::new(&handle, &settings).handle_connection(&mut mock);
+        let written = String::from_utf8(mock.write).unwrap();
+        // the body is left unfinished, and the second request is not served
+        assert!(written.ends_with("7\r\npartial\r\n"), "{}", written);
+        assert_eq!(written.matches("HTTP/1.1 200 OK").count(), 1, "{}", written);
+    }
+
+    #[test]
+    fn test_request_deadline_idle() {
+        use std::io::{Read, Write};
+        use std::net::TcpStream;
+        use std::thread;
+        use std::time::Duration;
+        use super::Server;
+
+        let mut server = Server::http("127.0.0.1:0").unwrap();
+        server.set_request_deadline(Some(Duration::from_millis(100)));
+        let mut listening = server.handle_threads(|_: Request, res: Response<Fresh>| {
+            res.send(b"ok").unwrap();
+        }, 1).unwrap();
+
+        let mut conn = TcpStream::connect(listening.socket).unwrap();
+        conn.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        conn.write_all(b"GET / HTTP/1.1\r\nHost: a\r\n\r\n").unwrap();
+        let mut first = Vec::new();
+        while !first.ends_with(b"\r\n\r\nok") {
+            let mut buf = [0; 256];
+            let n = conn.read(&mut buf).unwrap();
+            assert!(n > 0);
+            first.extend_from_slice(&buf[..n]);
+        }
+        assert!(first.starts_with(b"HTTP/1.1 200 OK\r\n"));
+
+        // the time a keep-alive connection is idle doesn't count
+        thread::sleep(Duration::from_millis(250));
+        conn.write_all(b"GET / HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n").unwrap();
+        let mut second = String::new();
+        conn.read_to_string(&mut second).unwrap();
+        assert!(second.starts_with("HTTP/1.1 200 OK\r\n"), "{}", second);
+        listening.close().unwrap();
+    }
 }
@@ -0,0 +1,402 @@
+//! Proactive content negotiation.
+//!
+//! A handler that can produce several representations of a resource
+//! describes them with `Available`, and `negotiate` picks the one the
+//! request prefers, following
+//! [RFC7231 Section 5.3](https://tools.ietf.org/html/rfc7231#section-5.3).
+//!
+//! ```
+//! use hyper::header::{Headers, Accept, AcceptEncoding, qitem};
+//! use hyper::header::Encoding::Gzip;
+//! use hyper::mime::Mime;
+//! use hyper::mime::TopLevel::{Application, Text};
+//! use hyper::mime::SubLevel::{Html, Json};
+//! use hyper::server::negotiate::{negotiate, Available};
+//!
+//! let mut headers = Headers::new();
+//! headers.set(Accept(vec![qitem(Mime(Application, Json, vec![]))]));
+//!
+//! let available = Available {
+//!     types: vec![Mime(Text, Html, vec![]), Mime(Application, Json, vec![])],
+//!     encodings: vec![Gzip],
+//!     ..Available::default()
+//! };
+//!
+//! let chosen = negotiate(&headers, &available).unwrap();
+//! assert_eq!(chosen.content_type, Some(Mime(Application, Json, vec![])));
+//! assert_eq!(chosen.encoding, Some(Gzip));
+//! assert_eq!(chosen.vary.to_string(), "Accept, Accept-Encoding");
+//! ```
+use unicase::UniCase;
+
+use header::{Headers, Accept, AcceptCharset, AcceptEncoding, AcceptLanguage, Charset, Encoding,
+             Quality, QualityItem, Vary};
+use header::parsing::{match_quality, negotiate as choose, sort_by_quality};
+use mime::Mime;
+use mime::TopLevel;
+use mime::SubLevel;
+use LanguageTag;
+
+/// The representations a server is able to produce.
+///
+/// Each list is in order of the server's own preference, which breaks ties
+/// between options the request likes equally. An empty list means the
+/// response doesn't vary along that dimension.
+#[derive(Clone, Debug, Default)]
+pub struct Available {
+    /// The media types available, negotiated with `Accept`.
+    pub types: Vec<Mime>,
+    /// The charsets available, negotiated with `Accept-Charset`.
+    pub charsets: Vec<Charset>,
+    /// The content codings available, negotiated with `Accept-Encoding`.
+    pub encodings: Vec<Encoding>,
+    /// The languages available, negotiated with `Accept-Language`.
+    pub languages: Vec<LanguageTag>,
+}
+
+/// The representation chosen by `negotiate`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Negotiated {
+    /// The chosen media type, if any types were available.
+    pub content_type: Option<Mime>,
+    /// The chosen charset, if any charsets were available.
+    pub charset: Option<Charset>,
+    /// The chosen content coding, if any codings were available.
+    pub encoding: Option<Encoding>,
+    /// The chosen language, if any languages were available.
+    pub language: Option<LanguageTag>,
+    /// The `Vary` header to send with the response, naming every request
+    /// header the choice was made from.
+    pub vary: Vary,
+}
+
+/// Choose the best of the `available` representations for a request.
+///
+/// A dimension with no matching request header accepts any option, and so
+/// gets the server's first preference.
+///
+/// Returns `None` if some dimension has options but the request finds none
+/// of them acceptable. A server can reply `406 Not Acceptable` then, or
+/// disregard the preferences and send its default representation.
+pub fn negotiate(headers: &Headers, available: &Available) -> Option<Negotiated> {
+    let mut vary = Vec::new();
+
+    let content_type = if available.types.is_empty() {
+        None
+    } else {
+        vary.push(UniCase("Accept".to_owned()));
+        let chosen = match headers.get::<Accept>() {
+            Some(accept) => choose(&available.types, |mime| mime_quality(accept, mime)),
+            None => available.types.first(),
+        };
+        match chosen {
+            Some(mime) => Some(mime.clone()),
+            None => return None,
+        }
+    };
+
+    let charset = if available.charsets.is_empty() {
+        None
+    } else {
+        vary.push(UniCase("Accept-Charset".to_owned()));
+        let chosen = match headers.get::<AcceptCharset>() {
+            Some(accept) => choose(&available.charsets, |cs| charset_quality(accept, cs)),
+            None => available.charsets.first(),
+        };
+        match chosen {
+            Some(charset) => Some(charset.clone()),
+            None => return None,
+        }
+    };
+
+    let encoding = if available.encodings.is_empty() {
+        None
+    } else {
+        vary.push(UniCase("Accept-Encoding".to_owned()));
+        let chosen = match headers.get::<AcceptEncoding>() {
+            Some(accept) => accept.negotiate(&available.encodings),
+            None => available.encodings.first().cloned(),
+        };
+        match chosen {
+            Some(encoding) => Some(encoding),
+            None => return None,
+        }
+    };
+
+    let language = if available.languages.is_empty() {
+        None
+    } else {
+        vary.push(UniCase("Accept-Language".to_owned()));
+        let chosen = match headers.get::<AcceptLanguage>() {
+            Some(accept) => choose(&available.languages, |tag| language_quality(accept, tag)),
+            None => available.languages.first(),
+        };
+        match chosen {
+            Some(language) => Some(language.clone()),
+            None => return None,
+        }
+    };
+
+    Some(Negotiated {
+        content_type: content_type,
+        charset: charset,
+        encoding: encoding,
+        language: language,
+        vary: Vary::Items(vary),
+    })
+}
+
+/// The quality of the most specific media range matching `mime`.
+fn mime_quality(ranges: &[QualityItem<Mime>], mime: &Mime) -> Quality {
+    match_quality(ranges, mime, |range, mime| {
+        let Mime(ref top, ref sub, ref params) = *range;
+        match (top, sub) {
+            (&TopLevel::Star, _) => Some(0),
+            (top, &SubLevel::Star) if *top == mime.0 => Some(1),
+            (top, sub) if *top == mime.0 && *sub == mime.1 &&
+                          params.iter().all(|param| mime.2.contains(param)) => Some(2 + params.len()),
+            _ => None,
+        }
+    }).unwrap_or(Quality(0))
+}
+
+/// The quality of `charset`, which may match by name, ignoring case, or by
+/// `*`.
+fn charset_quality(charsets: &[QualityItem<Charset>], charset: &Charset) -> Quality {
+    let name = charset.to_string();
+    match_quality(charsets, charset, |c, _| match *c {
+        Charset::Ext(ref star) if star == "*" => Some(0),
+        ref c if c.to_string().eq_ignore_ascii_case(&name) => Some(1),
+        _ => None,
+    }).unwrap_or(Quality(0))
+}
+
+/// Filter the `available` languages by the ranges of an `Accept-Language`
+/// header, using
+/// [RFC4647 Basic Filtering](https://tools.ietf.org/html/rfc4647#section-3.3.1).
+///
+/// A range matches a tag equal to it, or beginning with it followed by a
+/// `-`, ignoring case: `en` matches `en` and `en-GB`, but not `eng`. The
+/// matches are returned in order of the ranges' qualities, with ranges of
+/// quality zero excluded, and otherwise in the order of `available`.
+///
+/// ```
+/// # extern crate hyper;
+/// # #[macro_use] extern crate language_tags;
+/// use hyper::header::{AcceptLanguage, qitem, QualityItem, Quality};
+/// use hyper::server::negotiate::filter_languages;
+///
+/// # fn main() {
+/// let accept = AcceptLanguage(vec![
+///     QualityItem::new(langtag!(en), Quality(500)),
+///     qitem(langtag!(de)),
+/// ]);
+/// let available = [langtag!(en;;;US), langtag!(fr), langtag!(de;;;CH)];
+/// assert_eq!(filter_languages(&accept, &available),
+///            vec![&langtag!(de;;;CH), &langtag!(en;;;US)]);
+/// # }
+/// ```
+pub fn filter_languages<'a>(ranges: &[QualityItem<LanguageTag>], available: &'a [LanguageTag])
+                            -> Vec<&'a LanguageTag> {
+    let mut matched = Vec::new();
+    for range in sort_by_quality(ranges) {
+        for tag in available {
+            if basic_match(&range.item, tag) && !matched.iter().any(|m| *m == tag) {
+                matched.push(tag);
+            }
+        }
+    }
+    matched
+}
+
+/// Find the single best of the `available` languages for the ranges of an
+/// `Accept-Language` header, using
+/// [RFC4647 Lookup](https://tools.ietf.org/html/rfc4647#section-3.4).
+///
+/// Each range, in order of quality, is progressively truncated until it
+/// equals an available tag: `de-CH-1996` tries `de-CH-1996`, then `de-CH`,
+/// then `de`. Returns `None` if no range finds a tag, in which case a
+/// handler would use its default language.
+///
+/// ```
+/// # extern crate hyper;
+/// # #[macro_use] extern crate language_tags;
+/// use hyper::header::{AcceptLanguage, qitem};
+/// use hyper::server::negotiate::lookup_language;
+///
+/// # fn main() {
+/// let accept = AcceptLanguage(vec![qitem(langtag!(de;;;CH))]);
+/// let available = [langtag!(en), langtag!(de)];
+/// assert_eq!(lookup_language(&accept, &available), Some(&langtag!(de)));
+/// # }
+/// ```
+pub fn lookup_language<'a>(ranges: &[QualityItem<LanguageTag>], available: &'a [LanguageTag])
+                           -> Option<&'a LanguageTag> {
+    for range in sort_by_quality(ranges) {
+        let range = range.item.to_string();
+        let mut subtags: Vec<&str> = range.split('-').collect();
+        while !subtags.is_empty() {
+            let candidate = subtags.join("-");
+            if let Some(tag) = available.iter()
+                    .find(|tag| tag.to_string().eq_ignore_ascii_case(&candidate)) {
+                return Some(tag);
+            }
+            subtags.pop();
+            // never leave a dangling singleton, like the `x` of `x-private`
+            if subtags.last().map_or(false, |s| s.len() == 1) {
+                subtags.pop();
+            }
+        }
+    }
+    None
+}
+
+fn basic_match(range: &LanguageTag, tag: &LanguageTag) -> bool {
+    let range = range.to_string().to_ascii_lowercase();
+    let tag = tag.to_string().to_ascii_lowercase();
+    tag.starts_with(&range) && (tag.len() == range.len() || tag[range.len()..].starts_with('-'))
+}
+
+/// The quality of the most specific language range matching `tag`.
+fn language_quality(ranges: &[QualityItem<LanguageTag>], tag: &LanguageTag) -> Quality {
+    match_quality(ranges, tag, |range, tag| {
+        if basic_match(range, tag) { Some(range.to_string().len()) } else { None }
+    }).unwrap_or(Quality(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Headers, Accept, AcceptCharset, AcceptEncoding, AcceptLanguage, Charset,
+                 Encoding, Quality, QualityItem, Vary, qitem};
+    use mime::Mime;
+    use mime::TopLevel::{Application, Text};
+    use mime::SubLevel::{Html, Json, Plain, Star};
+    use super::{negotiate, filter_languages, lookup_language, Available};
+
+    fn html() -> Mime { Mime(Text, Html, vec![]) }
+    fn json() -> Mime { Mime(Application, Json, vec![]) }
+
+    #[test]
+    fn test_no_preferences() {
+        let available = Available {
+            types: vec![html(), json()],
+            ..Available::default()
+        };
+        let chosen = negotiate(&Headers::new(), &available).unwrap();
+        assert_eq!(chosen.content_type, Some(html()));
+        assert_eq!(chosen.encoding, None);
+        assert_eq!(chosen.vary, Vary::Items(vec!["Accept".parse().unwrap()]));
+    }
+
+    #[test]
+    fn test_accept_quality_and_specificity() {
+        let mut headers = Headers::new();
+        headers.set(Accept(vec![
+            QualityItem::new(Mime(Text, Star, vec![]), Quality(900)),
+            QualityItem::new(html(), Quality(100)),
+            qitem(json()),
+        ]));
+        let available = Available {
+            types: vec![html(), Mime(Text, Plain, vec![]), json()],
+            ..Available::default()
+        };
+        // text/html matches text/* too, but its own entry is more specific
+        assert_eq!(negotiate(&headers, &available).unwrap().content_type, Some(json()));
+
+        headers.set(Accept(vec![QualityItem::new(Mime(Text, Star, vec![]), Quality(900))]));
+        assert_eq!(negotiate(&headers, &available).unwrap().content_type, Some(html()));
+    }
+
+    #[test]
+    fn test_not_acceptable() {
+        let mut headers = Headers::new();
+        headers.set(Accept(vec![qitem(json())]));
+        let available = Available {
+            types: vec![html()],
+            ..Available::default()
+        };
+        assert_eq!(negotiate(&headers, &available), None);
+    }
+
+    #[test]
+    fn test_charset() {
+        let mut headers = Headers::new();
+        headers.set_raw("Accept-Charset", vec![b"iso-8859-5, utf-8;q=0.8".to_vec()]);
+        let available = Available {
+            charsets: vec![Charset::Us_Ascii, Charset::Ext("utf-8".to_owned())],
+            ..Available::default()
+        };
+        let chosen = negotiate(&headers, &available).unwrap();
+        assert_eq!(chosen.charset, Some(Charset::Ext("utf-8".to_owned())));
+        assert_eq!(chosen.vary.to_string(), "Accept-Charset");
+
+        headers.set(AcceptCharset(vec![
+            QualityItem::new(Charset::Ext("*".to_owned()), Quality(100)),
+            QualityItem::new(Charset::Us_Ascii, Quality(0)),
+        ]));
+        assert_eq!(negotiate(&headers, &available).unwrap().charset,
+                   Some(Charset::Ext("utf-8".to_owned())));
+
+        headers.set(AcceptCharset(vec![qitem(Charset::Koi8_R)]));
+        assert_eq!(negotiate(&headers, &available), None);
+    }
+
+    #[test]
+    fn test_encoding() {
+        let mut headers = Headers::new();
+        headers.set(AcceptEncoding(vec![qitem(Encoding::Deflate)]));
+        let available = Available {
+            encodings: vec![Encoding::Gzip, Encoding::Identity],
+            ..Available::default()
+        };
+        assert_eq!(negotiate(&headers, &available).unwrap().encoding, Some(Encoding::Identity));
+
+        headers.set(AcceptEncoding(vec![
+            qitem(Encoding::EncodingExt("*".to_owned())),
+            QualityItem::new(Encoding::Identity, Quality(0)),
+        ]));
+        assert_eq!(negotiate(&headers, &available).unwrap().encoding, Some(Encoding::Gzip));
+    }
+
+    #[test]
+    fn test_language() {
+        let mut headers = Headers::new();
+        headers.set(AcceptLanguage(vec![
+            QualityItem::new(langtag!(en), Quality(500)),
+            qitem(langtag!(de)),
+        ]));
+        let available = Available {
+            languages: vec![langtag!(en;;;GB), langtag!(de;;;AT)],
+            ..Available::default()
+        };
+        let chosen = negotiate(&headers, &available).unwrap();
+        assert_eq!(chosen.language, Some(langtag!(de;;;AT)));
+        assert_eq!(chosen.vary.to_string(), "Accept-Language");
+    }
+
+    #[test]
+    fn test_filter_languages() {
+        let ranges = vec![
+            qitem(langtag!(de;;;DE)),
+            QualityItem::new(langtag!(en), Quality(800)),
+            QualityItem::new(langtag!(fr), Quality(0)),
+        ];
+        let available = [langtag!(fr), langtag!(en;;;GB), langtag!(de), langtag!(en)];
+        assert_eq!(filter_languages(&ranges, &available),
+                   vec![&langtag!(en;;;GB), &langtag!(en)]);
+        assert!(filter_languages(&[], &available).is_empty());
+    }
+
+    #[test]
+    fn test_lookup_language() {
+        let ranges = vec![
+            QualityItem::new(langtag!(fr), Quality(100)),
+            qitem("zh-Hant-CN-x-private1".parse().unwrap()),
+        ];
+        let available = [langtag!(fr), "zh-Hant".parse().unwrap()];
+        assert_eq!(lookup_language(&ranges, &available).map(|t| t.to_string()),
+                   Some("zh-Hant".to_owned()));
+        assert_eq!(lookup_language(&ranges, &available[..1]), Some(&langtag!(fr)));
+        assert_eq!(lookup_language(&ranges[1..], &available[..1]), None);
+    }
+}
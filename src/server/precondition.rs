@@ -0,0 +1,228 @@
+//! Evaluation of conditional requests.
+//!
+//! `evaluate` checks a request's `If-Match`, `If-Unmodified-Since`,
+//! `If-None-Match`, `If-Modified-Since` and `If-Range` headers against the
+//! current state of the target resource, in the order
+//! [RFC7232 Section 6](https://tools.ietf.org/html/rfc7232#section-6)
+//! requires, and says how the request should be answered.
+//!
+//! ```
+//! use hyper::method::Method;
+//! use hyper::header::{Headers, EntityTag, IfNoneMatch};
+//! use hyper::server::precondition::{evaluate, Outcome, Validators};
+//! use hyper::status::StatusCode;
+//!
+//! let etag = EntityTag::strong("v1".to_owned());
+//! let mut headers = Headers::new();
+//! headers.set(IfNoneMatch::Items(vec![etag.clone()]));
+//!
+//! let current = Validators { etag: Some(&etag), last_modified: None };
+//! let outcome = evaluate(&Method::Get, &headers, Some(current));
+//! assert_eq!(outcome, Outcome::NotModified);
+//! assert_eq!(outcome.status(), Some(StatusCode::NotModified));
+//! ```
+use header::{Headers, EntityTag, HttpDate, IfMatch, IfNoneMatch, IfModifiedSince,
+             IfUnmodifiedSince, IfRange, Range};
+use method::Method;
+use status::StatusCode;
+
+/// The validators of the current representation of a resource.
+#[derive(Clone, Copy, Debug)]
+pub struct Validators<'a> {
+    /// The entity-tag that would be sent in an `ETag` header, if any.
+    pub etag: Option<&'a EntityTag>,
+    /// The date that would be sent in a `Last-Modified` header, if any.
+    pub last_modified: Option<HttpDate>,
+}
+
+/// How a conditional request should be answered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// Every condition passed: perform the method as usual.
+    Proceed,
+    /// The `If-Range` condition failed: perform the method, but ignore the
+    /// `Range` header and send the whole representation with `200 OK`.
+    IgnoreRange,
+    /// Reply `304 Not Modified`, without a body.
+    NotModified,
+    /// Reply `412 Precondition Failed`, without performing the method.
+    PreconditionFailed,
+}
+
+impl Outcome {
+    /// The status to reply with instead of performing the method, if any.
+    pub fn status(&self) -> Option<StatusCode> {
+        match *self {
+            Outcome::Proceed | Outcome::IgnoreRange => None,
+            Outcome::NotModified => Some(StatusCode::NotModified),
+            Outcome::PreconditionFailed => Some(StatusCode::PreconditionFailed),
+        }
+    }
+}
+
+/// Evaluate the preconditions of a request.
+///
+/// `current` describes the target resource's current representation, or
+/// is `None` if it has none, as for a `PUT` creating it.
+pub fn evaluate(method: &Method, headers: &Headers, current: Option<Validators>) -> Outcome {
+    let etag = current.and_then(|c| c.etag);
+    let last_modified = current.and_then(|c| c.last_modified);
+    let safe = *method == Method::Get || *method == Method::Head;
+
+    // step 1 and 2: the client's copy must be current
+    if let Some(if_match) = headers.get::<IfMatch>() {
+        let matched = match etag {
+            Some(etag) => if_match.matches(etag),
+            None => *if_match == IfMatch::Any && current.is_some(),
+        };
+        if !matched {
+            return Outcome::PreconditionFailed;
+        }
+    } else if let Some(&IfUnmodifiedSince(ref date)) = headers.get::<IfUnmodifiedSince>() {
+        if last_modified.map_or(false, |lm| secs(&lm) > secs(date)) {
+            return Outcome::PreconditionFailed;
+        }
+    }
+
+    // step 3 and 4: the client's copy must be stale
+    if let Some(if_none_match) = headers.get::<IfNoneMatch>() {
+        let matched = match etag {
+            Some(etag) => if_none_match.matches(etag),
+            None => *if_none_match == IfNoneMatch::Any && current.is_some(),
+        };
+        if matched {
+            return if safe { Outcome::NotModified } else { Outcome::PreconditionFailed };
+        }
+    } else if safe {
+        if let Some(&IfModifiedSince(ref date)) = headers.get::<IfModifiedSince>() {
+            if last_modified.map_or(false, |lm| secs(&lm) <= secs(date)) {
+                return Outcome::NotModified;
+            }
+        }
+    }
+
+    // step 5: only send part of the representation the client already has
+    if *method == Method::Get && headers.has::<Range>() {
+        if let Some(if_range) = headers.get::<IfRange>() {
+            if !if_range.matches(etag, last_modified.as_ref()) {
+                return Outcome::IgnoreRange;
+            }
+        }
+    }
+
+    Outcome::Proceed
+}
+
+// HTTP dates only have a resolution of one second.
+fn secs(date: &HttpDate) -> i64 {
+    date.0.to_timespec().sec
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{self, Duration, Timespec};
+
+    use header::{Headers, EntityTag, HttpDate, IfMatch, IfNoneMatch, IfModifiedSince,
+                 IfUnmodifiedSince, IfRange, Range};
+    use method::Method;
+    use super::{evaluate, Outcome, Validators};
+
+    fn date(secs: i64) -> HttpDate {
+        HttpDate(time::at_utc(Timespec::new(secs, 0)))
+    }
+
+    fn tag(s: &str) -> EntityTag {
+        EntityTag::strong(s.to_owned())
+    }
+
+    #[test]
+    fn test_no_conditions() {
+        assert_eq!(evaluate(&Method::Get, &Headers::new(), None), Outcome::Proceed);
+    }
+
+    #[test]
+    fn test_if_match() {
+        let current = tag("a");
+        let current = Some(Validators { etag: Some(&current), last_modified: None });
+        let mut headers = Headers::new();
+
+        headers.set(IfMatch::Items(vec![tag("b"), tag("a")]));
+        assert_eq!(evaluate(&Method::Put, &headers, current), Outcome::Proceed);
+
+        headers.set(IfMatch::Items(vec![EntityTag::weak("a".to_owned())]));
+        assert_eq!(evaluate(&Method::Put, &headers, current), Outcome::PreconditionFailed);
+
+        headers.set(IfMatch::Any);
+        assert_eq!(evaluate(&Method::Put, &headers, current), Outcome::Proceed);
+        assert_eq!(evaluate(&Method::Put, &headers, None), Outcome::PreconditionFailed);
+    }
+
+    #[test]
+    fn test_if_match_takes_precedence() {
+        let current = tag("a");
+        let current = Some(Validators { etag: Some(&current), last_modified: Some(date(200)) });
+        let mut headers = Headers::new();
+        headers.set(IfMatch::Items(vec![tag("a")]));
+        headers.set(IfUnmodifiedSince(date(100)));
+        assert_eq!(evaluate(&Method::Put, &headers, current), Outcome::Proceed);
+
+        headers.remove::<IfMatch>();
+        assert_eq!(evaluate(&Method::Put, &headers, current), Outcome::PreconditionFailed);
+    }
+
+    #[test]
+    fn test_if_none_match() {
+        let current = tag("a");
+        let current = Some(Validators { etag: Some(&current), last_modified: Some(date(100)) });
+        let mut headers = Headers::new();
+        headers.set(IfNoneMatch::Items(vec![EntityTag::weak("a".to_owned())]));
+        // a stale If-Modified-Since is ignored once If-None-Match is present
+        headers.set(IfModifiedSince(date(50)));
+
+        assert_eq!(evaluate(&Method::Get, &headers, current), Outcome::NotModified);
+        assert_eq!(evaluate(&Method::Head, &headers, current), Outcome::NotModified);
+        assert_eq!(evaluate(&Method::Post, &headers, current), Outcome::PreconditionFailed);
+
+        headers.set(IfNoneMatch::Items(vec![tag("b")]));
+        assert_eq!(evaluate(&Method::Get, &headers, current), Outcome::Proceed);
+
+        headers.set(IfNoneMatch::Any);
+        assert_eq!(evaluate(&Method::Put, &headers, None), Outcome::Proceed);
+    }
+
+    #[test]
+    fn test_if_modified_since() {
+        let modified = date(100) + Duration::milliseconds(500);
+        let current = Some(Validators { etag: None, last_modified: Some(modified) });
+        let mut headers = Headers::new();
+
+        headers.set(IfModifiedSince(date(100)));
+        assert_eq!(evaluate(&Method::Get, &headers, current), Outcome::NotModified);
+        assert_eq!(evaluate(&Method::Post, &headers, current), Outcome::Proceed);
+
+        headers.set(IfModifiedSince(date(99)));
+        assert_eq!(evaluate(&Method::Get, &headers, current), Outcome::Proceed);
+    }
+
+    #[test]
+    fn test_if_range() {
+        let current = tag("a");
+        let current = Some(Validators { etag: Some(&current), last_modified: Some(date(100)) });
+        let mut headers = Headers::new();
+        headers.set(IfRange::EntityTag(tag("b")));
+        // without a Range, If-Range means nothing
+        assert_eq!(evaluate(&Method::Get, &headers, current), Outcome::Proceed);
+
+        headers.set(Range::bytes(0, 10));
+        assert_eq!(evaluate(&Method::Get, &headers, current), Outcome::IgnoreRange);
+
+        headers.set(IfRange::EntityTag(tag("a")));
+        assert_eq!(evaluate(&Method::Get, &headers, current), Outcome::Proceed);
+
+        headers.set(IfRange::Date(date(100)));
+        assert_eq!(evaluate(&Method::Get, &headers, current), Outcome::Proceed);
+
+        headers.set(IfRange::Date(date(101)));
+        assert_eq!(evaluate(&Method::Get, &headers, current), Outcome::IgnoreRange);
+    }
+}
@@ -5,14 +5,20 @@
 use std::io::{self, Read};
 use std::net::SocketAddr;
 
+use url::form_urlencoded;
+
 use buffer::BufReader;
+use extensions::Extensions;
+use metrics::Tag;
 use net::NetworkStream;
 use version::{HttpVersion};
 use method::Method::{self, Get, Head};
-use header::{Headers, ContentLength, TransferEncoding};
-use http::h1::{self, Incoming, HttpReader};
+use header::{Headers, ContentLength, ContentType, TransferEncoding};
+use http::h1::{self, Incoming, HeadLimits, HttpReader};
 use http::h1::HttpReader::{SizedReader, ChunkedReader, EmptyReader};
 use uri::RequestUri;
+use mime::{Mime, TopLevel, SubLevel};
+use Error;
 
 /// A request bundles several parts of an incoming `NetworkStream`, given to a `Handler`.
 pub struct Request<'a, 'b: 'a> {
@@ -26,17 +32,26 @@ pub struct Request<'a, 'b: 'a> {
     pub uri: RequestUri,
     /// The version of HTTP for this request.
     pub version: HttpVersion,
-    body: HttpReader<&'a mut BufReader<&'b mut NetworkStream>>
+    /// Values attached to this request by the code handling it.
+    pub extensions: Extensions,
+    body: HttpReader<&'a mut BufReader<&'b mut NetworkStream>>,
+    tag: Option<Tag>,
 }
 
 
 impl<'a, 'b: 'a> Request<'a, 'b> {
     /// Create a new Request, reading the StartLine and Headers so they are
     /// immediately useful.
-    pub fn new(mut stream: &'a mut BufReader<&'b mut NetworkStream>, addr: SocketAddr)
+    pub fn new(stream: &'a mut BufReader<&'b mut NetworkStream>, addr: SocketAddr)
         -> ::Result<Request<'a, 'b>> {
+        Request::with_limits(stream, addr, &HeadLimits::default())
+    }
 
-        let Incoming { version, subject: (method, uri), headers } = try!(h1::parse_request(stream));
+    /// Like `new`, but failing with `TooLarge` for a head beyond `limits`.
+    pub fn with_limits(mut stream: &'a mut BufReader<&'b mut NetworkStream>, addr: SocketAddr,
+                       limits: &HeadLimits) -> ::Result<Request<'a, 'b>> {
+        let Incoming { version, subject: (method, uri), headers } =
+            try!(h1::parse_request_with(stream, limits));
         debug!("Request Line: {:?} {:?} {:?}", method, uri, version);
         debug!("{:?}", headers);
 
@@ -49,7 +64,7 @@ impl<'a, 'b: 'a> Request<'a, 'b> {
             }
         } else if headers.has::<TransferEncoding>() {
             todo!("check for Transfer-Encoding: chunked");
-            ChunkedReader(stream, None)
+            ChunkedReader(stream, None, None, *limits)
         } else {
             EmptyReader(stream)
         };
@@ -60,10 +75,67 @@ impl<'a, 'b: 'a> Request<'a, 'b> {
             uri: uri,
             headers: headers,
             version: version,
-            body: body
+            extensions: Extensions::new(),
+            body: body,
+            tag: None,
         })
     }
 
+    /// Identifies the connection this request came on, and its position on
+    /// it, as the `Server`'s `Instrument` and `Metrics` are told.
+    ///
+    /// This is `None` for a request that wasn't read by a `Server`.
+    #[inline]
+    pub fn tag(&self) -> Option<Tag> {
+        self.tag
+    }
+
+    #[doc(hidden)]
+    pub fn set_tag(&mut self, tag: Tag) {
+        self.tag = Some(tag);
+    }
+
+    /// The trailer fields sent after a chunked body, once the body has been
+    /// read to its end.
+    #[inline]
+    pub fn trailers(&self) -> Option<&Headers> {
+        self.body.trailers()
+    }
+
+    /// Reads an `application/x-www-form-urlencoded` body, such as an HTML
+    /// form sends, into its names and values, in order.
+    ///
+    /// Fails with `Error::Header` for a body of another `Content-Type`, and
+    /// with `Error::TooLarge` for one longer than `limit` bytes.
+    ///
+    /// ```
+    /// use hyper::server::{Request, Response};
+    ///
+    /// fn handle(mut req: Request, mut res: Response) {
+    ///     let form = match req.read_form(64 * 1024) {
+    ///         Ok(form) => form,
+    ///         Err(_) => {
+    ///             *res.status_mut() = hyper::BadRequest;
+    ///             return;
+    ///         }
+    ///     };
+    ///     let name = form.iter().find(|pair| pair.0 == "name").map_or("stranger", |pair| &pair.1[..]);
+    ///     res.send(format!("Hello, {}!", name).as_bytes()).unwrap();
+    /// }
+    /// ```
+    pub fn read_form(&mut self, limit: u64) -> ::Result<Vec<(String, String)>> {
+        match self.headers.get() {
+            Some(&ContentType(Mime(TopLevel::Application, SubLevel::WwwFormUrlEncoded, _))) => (),
+            _ => return Err(Error::Header),
+        }
+        let mut body = Vec::new();
+        try!(self.take(limit + 1).read_to_end(&mut body));
+        if body.len() as u64 > limit {
+            return Err(Error::TooLarge);
+        }
+        Ok(form_urlencoded::parse(&body))
+    }
+
     /// Deconstruct a Request into its constituent parts.
     #[inline]
     pub fn deconstruct(self) -> (SocketAddr, Method, Headers,
@@ -266,4 +338,35 @@ mod tests {
         assert_eq!(read_to_string(req).unwrap(), "1".to_owned());
     }
 
+    #[test]
+    fn test_read_form() {
+        fn read_form(content_type: &str, limit: u64) -> ::Result<Vec<(String, String)>> {
+            let input = format!("POST / HTTP/1.1\r\n\
+                                 Host: example.domain\r\n\
+                                 Content-Type: {}\r\n\
+                                 Content-Length: 34\r\n\
+                                 \r\n\
+                                 name=J%C3%BCrgen+M&a%26b=1+%2B+1&c", content_type);
+            let mut mock = MockStream::with_input(input.as_bytes());
+            let mock: &mut NetworkStream = &mut mock;
+            let mut stream = BufReader::new(mock);
+            let mut req = Request::new(&mut stream, sock("127.0.0.1:80")).unwrap();
+            req.read_form(limit)
+        }
+
+        assert_eq!(read_form("application/x-www-form-urlencoded", 34).unwrap(), vec![
+            ("name".to_owned(), "J\u{fc}rgen M".to_owned()),
+            ("a&b".to_owned(), "1 + 1".to_owned()),
+            ("c".to_owned(), "".to_owned()),
+        ]);
+        assert_eq!(read_form("application/x-www-form-urlencoded; charset=utf-8", 1024).unwrap().len(), 3);
+        match read_form("application/x-www-form-urlencoded", 33) {
+            Err(::Error::TooLarge) => (),
+            other => panic!("expected TooLarge, got {:?}", other),
+        }
+        match read_form("text/plain", 1024) {
+            Err(::Error::Header) => (),
+            other => panic!("expected Header, got {:?}", other),
+        }
+    }
 }
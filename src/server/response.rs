@@ -3,18 +3,27 @@
 //! These are responses sent by a `hyper::Server` to clients, after
 //! receiving a request.
 use std::any::{Any, TypeId};
+use std::cell::{Cell, RefCell};
 use std::marker::PhantomData;
 use std::mem;
-use std::io::{self, Write};
+use std::io::{self, Read, Seek, Write};
 use std::ptr;
+use std::time::Instant;
 
-use time::now_utc;
+use unicase::UniCase;
 
+use clock::{Clock, SystemClock};
+#[cfg(feature = "flate2")]
+use compress::{DeflateEncoder, GzipEncoder};
+#[cfg(feature = "brotli")]
+use compress::BrotliEncoder;
+use extensions::Extensions;
 use header;
 use http::h1::{CR, LF, LINE_ENDING, HttpWriter};
 use http::h1::HttpWriter::{ThroughWriter, ChunkedWriter, SizedWriter};
 use status;
 use net::{Fresh, Streaming};
+use server::byteranges::ByteRangesBody;
 use version;
 
 
@@ -35,6 +44,16 @@ pub struct Response<'a, W: Any = Fresh> {
     status: status::StatusCode,
     // The outgoing headers on this response.
     headers: &'a mut header::Headers,
+    // Where to record the status once the head is written, for the Server.
+    status_written: Option<&'a Cell<Option<status::StatusCode>>>,
+    // The clock to take the `Date` header from, if not the system's.
+    clock: Option<&'a Clock>,
+    // Values attached to this response by the code handling it.
+    extensions: Extensions,
+    // When writing must be done by, and where to record that it wasn't.
+    deadline: Option<(Instant, &'a Cell<bool>)>,
+    // What compresses the body, if the response is to be compressed.
+    encoder: Option<Encoder>,
 
     _writing: PhantomData<W>
 }
@@ -48,6 +67,31 @@ impl<'a, W: Any> Response<'a, W> {
     #[inline]
     pub fn headers(&self) -> &header::Headers { &*self.headers }
 
+    /// The values attached to this response.
+    #[inline]
+    pub fn extensions(&self) -> &Extensions { &self.extensions }
+
+    /// Get a mutable reference to the values attached to this response.
+    #[inline]
+    pub fn extensions_mut(&mut self) -> &mut Extensions { &mut self.extensions }
+
+    /// When this response must be written by, if the `Server` has a
+    /// request deadline.
+    ///
+    /// Writing after it fails with a `TimedOut` error.
+    #[inline]
+    pub fn deadline(&self) -> Option<Instant> { self.deadline.map(|(deadline, _)| deadline) }
+
+    fn check_deadline(&self) -> io::Result<()> {
+        match self.deadline {
+            Some((deadline, exceeded)) if Instant::now() >= deadline => {
+                exceeded.set(true);
+                Err(io::Error::new(io::ErrorKind::TimedOut, "response deadline exceeded"))
+            },
+            _ => Ok(()),
+        }
+    }
+
     /// Construct a Response from its constituent parts.
     #[inline]
     pub fn construct(version: version::HttpVersion,
@@ -59,6 +103,11 @@ impl<'a, W: Any> Response<'a, W> {
             version: version,
             body: body,
             headers: headers,
+            status_written: None,
+            clock: None,
+            extensions: Extensions::new(),
+            deadline: None,
+            encoder: None,
             _writing: PhantomData,
         }
     }
@@ -74,21 +123,36 @@ impl<'a, W: Any> Response<'a, W> {
                 self.status,
                 ptr::read(&self.headers)
             );
+            drop(ptr::read(&self.extensions));
+            drop(ptr::read(&self.encoder));
             mem::forget(self);
             parts
         }
     }
 
     fn write_head(&mut self) -> io::Result<Body> {
+        try!(self.check_deadline());
         debug!("writing head: {:?} {:?}", self.version, self.status);
+        if let Some(cell) = self.status_written {
+            cell.set(Some(self.status));
+        }
         try!(write!(&mut self.body, "{} {}{}{}", self.version, self.status,
             CR as char, LF as char));
 
         if !self.headers.has::<header::Date>() {
-            self.headers.set(header::Date(header::HttpDate(now_utc())));
+            let date = match self.clock {
+                Some(clock) => clock.http_date(),
+                None => SystemClock.http_date(),
+            };
+            self.headers.set(header::Date(date));
         }
 
 
+        // the compressed length isn't known until it has been written
+        if self.encoder.is_some() {
+            self.headers.remove::<header::ContentLength>();
+        }
+
         let mut body_type = Body::Chunked;
 
         if let Some(cl) = self.headers.get::<header::ContentLength>() {
@@ -114,11 +178,19 @@ impl<'a, W: Any> Response<'a, W> {
 
 
         debug!("headers [\n{:?}]", self.headers);
-        try!(write!(&mut self.body, "{}", self.headers));
+        try!(self.headers.write_to(&mut self.body));
         try!(write!(&mut self.body, "{}", LINE_ENDING));
 
         Ok(body_type)
     }
+
+    // Writes the end of the compressed body, if it is compressed.
+    fn finish_encoding(&mut self) -> io::Result<()> {
+        match self.encoder.take() {
+            Some(encoder) => self.body.write_all(&try!(encoder.finish())),
+            None => Ok(()),
+        }
+    }
 }
 
 impl<'a> Response<'a, Fresh> {
@@ -131,10 +203,89 @@ impl<'a> Response<'a, Fresh> {
             version: version::HttpVersion::Http11,
             headers: headers,
             body: ThroughWriter(stream),
+            status_written: None,
+            clock: None,
+            extensions: Extensions::new(),
+            deadline: None,
+            encoder: None,
             _writing: PhantomData,
         }
     }
 
+    #[doc(hidden)]
+    pub fn report_status_to(&mut self, cell: &'a Cell<Option<status::StatusCode>>) {
+        self.status_written = Some(cell);
+    }
+
+    #[doc(hidden)]
+    pub fn use_clock(&mut self, clock: &'a Clock) {
+        self.clock = Some(clock);
+    }
+
+    #[doc(hidden)]
+    pub fn set_deadline(&mut self, deadline: Instant, exceeded: &'a Cell<bool>) {
+        self.deadline = Some((deadline, exceeded));
+    }
+
+    /// Compress the body with whichever of `gzip` and `deflate`, with the
+    /// `flate2` feature, and `br`, with the `brotli` feature, the `request`
+    /// headers accept best, returning the coding chosen.
+    ///
+    /// A compressed response is sent chunked, with its `Content-Encoding`,
+    /// and any `Content-Length` it is given is left out. The `Vary` header
+    /// gains `Accept-Encoding` either way. A request without an
+    /// `Accept-Encoding`, or preferring `identity`, is answered with the body
+    /// as it is written, as is a response that already has a
+    /// `Content-Encoding`, or a status without a body, which should be set
+    /// first.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use hyper::server::{Request, Response};
+    ///
+    /// fn handler(req: Request, mut res: Response) {
+    ///     res.compress(&req.headers);
+    ///     res.send(b"Hello World!").unwrap();
+    /// }
+    /// ```
+    pub fn compress(&mut self, request: &header::Headers) -> Option<header::Encoding> {
+        let bodiless = match self.status {
+            status::StatusCode::NoContent | status::StatusCode::NotModified => true,
+            status => status.is_informational(),
+        };
+        if bodiless || self.headers.has::<header::ContentEncoding>() {
+            return None;
+        }
+
+        let vary = match self.headers.get::<header::Vary>() {
+            Some(&header::Vary::Any) => header::Vary::Any,
+            Some(&header::Vary::Items(ref items)) => {
+                let mut items = items.clone();
+                if !items.iter().any(|item| *item == UniCase("Accept-Encoding".to_owned())) {
+                    items.push(UniCase("Accept-Encoding".to_owned()));
+                }
+                header::Vary::Items(items)
+            },
+            None => header::Vary::Items(vec![UniCase("Accept-Encoding".to_owned())]),
+        };
+        self.headers.set(vary);
+
+        let chosen = request.get::<header::AcceptEncoding>().and_then(|accept| {
+            accept.negotiate(&codings())
+        });
+        self.encoder = match chosen {
+            Some(ref coding) => Encoder::new(coding),
+            None => None,
+        };
+        if self.encoder.is_none() {
+            return None;
+        }
+        debug!("compressing body with {:?}", chosen);
+        self.headers.set(header::ContentEncoding(chosen.iter().cloned().collect()));
+        chosen
+    }
+
     /// Writes the body and ends the response.
     ///
     /// This is a shortcut method for when you have a response with a fixed
@@ -170,10 +321,79 @@ impl<'a> Response<'a, Fresh> {
         stream.end()
     }
 
+    /// Writes a body read from `body`, and ends the response.
+    ///
+    /// If `len` is known, the response has that `Content-Length`, and it is
+    /// an error for `body` to end before `len` bytes. Any bytes after those
+    /// are not read. Otherwise, the response is sent chunked until `body`
+    /// ends.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hyper::server::Response;
+    /// use std::io;
+    /// fn handler(res: Response) {
+    ///     let zeroes = io::repeat(0);
+    ///     res.send_reader(zeroes, Some(1024)).unwrap();
+    /// }
+    /// ```
+    pub fn send_reader<R: Read>(mut self, body: R, len: Option<u64>) -> io::Result<()> {
+        match len {
+            Some(len) => self.headers.set(header::ContentLength(len)),
+            None => { self.headers.remove::<header::ContentLength>(); },
+        }
+        let mut stream = try!(self.start());
+        let copied = match len {
+            Some(len) => try!(copy(&mut body.take(len), &mut stream)),
+            None => try!(copy(&mut { body }, &mut stream)),
+        };
+        if len.map_or(false, |len| copied < len) {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof,
+                                      "body ended before its Content-Length"));
+        }
+        stream.end()
+    }
+
+    /// Writes a `multipart/byteranges` body, and ends the response.
+    ///
+    /// The status is set to `206 Partial Content`, and the `Content-Type`
+    /// and `Content-Length` are set from `body`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hyper::server::Response;
+    /// use std::io::Cursor;
+    /// use hyper::mime::Mime;
+    /// use hyper::mime::TopLevel::Text;
+    /// use hyper::mime::SubLevel::Plain;
+    /// use hyper::server::byteranges::ByteRangesBody;
+    ///
+    /// fn handler(res: Response) {
+    ///     let source = Cursor::new(b"hello, world".to_vec());
+    ///     let body = ByteRangesBody::new(source, Mime(Text, Plain, vec![]), 12,
+    ///                                    vec![(0, 4), (7, 11)]);
+    ///     res.send_byte_ranges(body).unwrap();
+    /// }
+    /// ```
+    pub fn send_byte_ranges<R: Read + Seek>(mut self, mut body: ByteRangesBody<R>)
+                                            -> io::Result<()> {
+        self.status = status::StatusCode::PartialContent;
+        self.headers.set(header::ContentType(body.content_type()));
+        self.headers.set(header::ContentLength(body.content_length()));
+        let mut stream = try!(self.start());
+        try!(body.write_to(&mut stream));
+        stream.end()
+    }
+
     /// Consume this Response<Fresh>, writing the Headers and Status and
     /// creating a Response<Streaming>
     pub fn start(mut self) -> io::Result<Response<'a, Streaming>> {
         let body_type = try!(self.write_head());
+        let extensions = mem::replace(&mut self.extensions, Extensions::new());
+        let encoder = self.encoder.take();
+        let deadline = self.deadline;
         let (version, body, status, headers) = self.deconstruct();
         let stream = match body_type {
             Body::Chunked => ChunkedWriter(body.into_inner()),
@@ -186,6 +406,11 @@ impl<'a> Response<'a, Fresh> {
             body: stream,
             status: status,
             headers: headers,
+            status_written: None,
+            clock: None,
+            extensions: extensions,
+            deadline: deadline,
+            encoder: encoder,
             _writing: PhantomData,
         })
     }
@@ -202,27 +427,172 @@ impl<'a> Response<'a, Fresh> {
 impl<'a> Response<'a, Streaming> {
     /// Flushes all writing of a response to the client.
     #[inline]
-    pub fn end(self) -> io::Result<()> {
+    pub fn end(mut self) -> io::Result<()> {
         trace!("ending");
+        try!(self.check_deadline());
+        try!(self.finish_encoding());
         let (_, body, _, _) = self.deconstruct();
         try!(body.end());
         Ok(())
     }
+
+    /// Flushes all writing of a chunked response, sending `trailers` after
+    /// its body, such as a checksum computed while it was written.
+    ///
+    /// A client only has to accept trailers if it sent `TE: trailers`, and
+    /// the `Trailer` header should name them. A response whose length was
+    /// set with `Content-Length` can't have trailers, and fails with
+    /// `InvalidInput`, as do trailers with a field such as `Content-Length`
+    /// that frames or routes the message.
+    pub fn end_with_trailers(mut self, trailers: &header::Headers) -> io::Result<()> {
+        trace!("ending with trailers");
+        try!(self.check_deadline());
+        try!(self.finish_encoding());
+        let (_, body, _, _) = self.deconstruct();
+        try!(body.end_with_trailers(trailers));
+        Ok(())
+    }
 }
 
 impl<'a> Write for Response<'a, Streaming> {
     #[inline]
     fn write(&mut self, msg: &[u8]) -> io::Result<usize> {
         debug!("write {:?} bytes", msg.len());
-        self.body.write(msg)
+        try!(self.check_deadline());
+        match self.encoder {
+            Some(ref mut encoder) => {
+                try!(encoder.write_all(msg));
+                try!(self.body.write_all(encoder.output()));
+                encoder.output().clear();
+                Ok(msg.len())
+            },
+            None => self.body.write(msg),
+        }
     }
 
     #[inline]
     fn flush(&mut self) -> io::Result<()> {
+        try!(self.check_deadline());
+        if let Some(ref mut encoder) = self.encoder {
+            try!(encoder.flush());
+            try!(self.body.write_all(encoder.output()));
+            encoder.output().clear();
+        }
         self.body.flush()
     }
 }
 
+// The codings `compress` chooses from, in order of preference.
+fn codings() -> Vec<header::Encoding> {
+    let mut codings = Vec::new();
+    if cfg!(feature = "brotli") {
+        codings.push(header::Encoding::Brotli);
+    }
+    if cfg!(feature = "flate2") {
+        codings.push(header::Encoding::Gzip);
+        codings.push(header::Encoding::Deflate);
+    }
+    codings.push(header::Encoding::Identity);
+    codings
+}
+
+// Compresses what is written to it into a buffer, which is then written to
+// the body.
+#[derive(Debug)]
+enum Encoder {
+    #[cfg(feature = "flate2")]
+    Gzip(GzipEncoder<Vec<u8>>),
+    #[cfg(feature = "flate2")]
+    Deflate(DeflateEncoder<Vec<u8>>),
+    #[cfg(feature = "brotli")]
+    Brotli(BrotliEncoder<Vec<u8>>),
+}
+
+impl Encoder {
+    fn new(coding: &header::Encoding) -> Option<Encoder> {
+        match *coding {
+            #[cfg(feature = "flate2")]
+            header::Encoding::Gzip => Some(Encoder::Gzip(GzipEncoder::new(Vec::new()))),
+            #[cfg(feature = "flate2")]
+            header::Encoding::Deflate => Some(Encoder::Deflate(DeflateEncoder::new(Vec::new()))),
+            #[cfg(feature = "brotli")]
+            header::Encoding::Brotli => Some(Encoder::Brotli(BrotliEncoder::new(Vec::new()))),
+            _ => None,
+        }
+    }
+
+    // What has been compressed so far, to be written to the body.
+    fn output(&mut self) -> &mut Vec<u8> {
+        match *self {
+            #[cfg(feature = "flate2")]
+            Encoder::Gzip(ref mut encoder) => encoder.get_mut(),
+            #[cfg(feature = "flate2")]
+            Encoder::Deflate(ref mut encoder) => encoder.get_mut(),
+            #[cfg(feature = "brotli")]
+            Encoder::Brotli(ref mut encoder) => encoder.get_mut(),
+        }
+    }
+
+    fn finish(self) -> io::Result<Vec<u8>> {
+        match self {
+            #[cfg(feature = "flate2")]
+            Encoder::Gzip(encoder) => encoder.finish(),
+            #[cfg(feature = "flate2")]
+            Encoder::Deflate(encoder) => encoder.finish(),
+            #[cfg(feature = "brotli")]
+            Encoder::Brotli(encoder) => encoder.finish(),
+        }
+    }
+}
+
+impl Write for Encoder {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match *self {
+            #[cfg(feature = "flate2")]
+            Encoder::Gzip(ref mut encoder) => encoder.write(buf),
+            #[cfg(feature = "flate2")]
+            Encoder::Deflate(ref mut encoder) => encoder.write(buf),
+            #[cfg(feature = "brotli")]
+            Encoder::Brotli(ref mut encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match *self {
+            #[cfg(feature = "flate2")]
+            Encoder::Gzip(ref mut encoder) => encoder.flush(),
+            #[cfg(feature = "flate2")]
+            Encoder::Deflate(ref mut encoder) => encoder.flush(),
+            #[cfg(feature = "brotli")]
+            Encoder::Brotli(ref mut encoder) => encoder.flush(),
+        }
+    }
+}
+
+// Each server thread keeps one buffer to copy bodies through, rather than
+// allocating one for every response.
+thread_local!(static COPY_BUF: RefCell<Vec<u8>> = RefCell::new(Vec::new()));
+
+const COPY_BUF_SIZE: usize = 16 * 1024;
+
+fn copy<R: Read, W: Write>(r: &mut R, w: &mut W) -> io::Result<u64> {
+    COPY_BUF.with(|buf| {
+        let mut buf = buf.borrow_mut();
+        buf.resize(COPY_BUF_SIZE, 0);
+        let mut copied = 0;
+        loop {
+            let n = match r.read(&mut buf) {
+                Ok(0) => return Ok(copied),
+                Ok(n) => n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            try!(w.write_all(&buf[..n]));
+            copied += n as u64;
+        }
+    })
+}
+
 #[derive(PartialEq)]
 enum Body {
     Chunked,
@@ -231,6 +601,11 @@ enum Body {
 
 impl<'a, T: Any> Drop for Response<'a, T> {
     fn drop(&mut self) {
+        // ending the body late would pass off what was written as all of it
+        if let Err(e) = self.check_deadline() {
+            debug!("error dropping request: {:?}", e);
+            return;
+        }
         if TypeId::of::<T>() == TypeId::of::<Fresh>() {
             let mut body = match self.write_head() {
                 Ok(Body::Chunked) => ChunkedWriter(self.body.get_mut()),
@@ -240,14 +615,20 @@ impl<'a, T: Any> Drop for Response<'a, T> {
                     return;
                 }
             };
-            end(&mut body);
+            end(&mut body, self.encoder.take());
         } else {
-            end(&mut self.body);
+            end(&mut self.body, self.encoder.take());
         };
 
 
         #[inline]
-        fn end<W: Write>(w: &mut W) {
+        fn end<W: Write>(w: &mut W, encoder: Option<Encoder>) {
+            if let Some(encoder) = encoder {
+                if let Err(e) = encoder.finish().and_then(|end| w.write_all(&end)) {
+                    debug!("error dropping request: {:?}", e);
+                    return;
+                }
+            }
             match w.write(&[]) {
                 Ok(_) => match w.flush() {
                     Ok(_) => debug!("drop successful"),
@@ -298,6 +679,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_raw_bytes_written() {
+        let mut incoming = Headers::new();
+        incoming.set_raw("X-Name", vec![b"caf\xe9".to_vec(), b"b".to_vec()]);
+        let mut headers = Headers::new();
+        assert!(incoming.forward("x-name", &mut headers));
+        let mut stream = MockStream::new();
+        {
+            let res = Response::new(&mut stream, &mut headers);
+            res.start().unwrap().end().unwrap();
+        }
+        let line = b"\r\nX-Name: caf\xe9, b\r\n";
+        assert!(stream.write.windows(line.len()).any(|w| w == line));
+
+        // a field without values still has its line
+        let mut buf = Vec::new();
+        headers.set_raw("X-Empty", vec![]);
+        headers.write_to(&mut buf).unwrap();
+        assert!(buf.ends_with(b"\r\nX-Empty: \r\n"));
+    }
+
+    #[test]
+    fn test_extensions_kept_on_start() {
+        let mut headers = Headers::new();
+        let mut stream = MockStream::new();
+        let mut res = Response::new(&mut stream, &mut headers);
+        res.extensions_mut().insert(7u32);
+        let res = res.start().unwrap();
+        assert_eq!(res.extensions().get::<u32>(), Some(&7));
+        res.end().unwrap();
+    }
+
     #[test]
     fn test_streaming_end() {
         let mut headers = Headers::new();
@@ -361,4 +774,168 @@ mod tests {
             "" // empty zero body
         }
     }
+
+    #[test]
+    fn test_send_byte_ranges() {
+        use std::io::Cursor;
+        use header::{ContentLength, ContentType};
+        use mime::Mime;
+        use mime::TopLevel::Text;
+        use mime::SubLevel::Plain;
+        use server::byteranges::ByteRangesBody;
+
+        let mut headers = Headers::new();
+        let mut stream = MockStream::new();
+        let body = ByteRangesBody::new(Cursor::new(b"0123456789".to_vec()),
+                                       Mime(Text, Plain, vec![]), 10, vec![(0, 1), (8, 9)]);
+        let (content_type, len) = (body.content_type(), body.content_length());
+        Response::new(&mut stream, &mut headers).send_byte_ranges(body).unwrap();
+        assert_eq!(headers.get(), Some(&ContentType(content_type)));
+        assert_eq!(headers.get(), Some(&ContentLength(len)));
+
+        let out = String::from_utf8(stream.write).unwrap();
+        assert!(out.starts_with("HTTP/1.1 206 Partial Content\r\n"));
+        let body = &out[out.find("\r\n\r\n").unwrap() + 4..];
+        assert_eq!(body.len() as u64, len);
+        assert!(body.contains("Content-Range: bytes 8-9/10\r\n\r\n89\r\n"));
+    }
+
+    #[test]
+    fn test_send_reader() {
+        use std::io;
+        use header::ContentLength;
+
+        let mut headers = Headers::new();
+        let mut stream = MockStream::new();
+        Response::new(&mut stream, &mut headers).send_reader(&b"foobar"[..], Some(3)).unwrap();
+        assert_eq!(headers.get(), Some(&ContentLength(3)));
+        lines! { stream =
+            "HTTP/1.1 200 OK",
+            _content_length,
+            _date,
+            "",
+            "foo"
+        }
+
+        let mut headers = Headers::new();
+        let mut stream = MockStream::new();
+        Response::new(&mut stream, &mut headers).send_reader(&b"foobar"[..], None).unwrap();
+        lines! { stream =
+            "HTTP/1.1 200 OK",
+            _date,
+            _transfer_encoding,
+            "",
+            "6",
+            "foobar",
+            "0",
+            ""
+        }
+
+        let mut headers = Headers::new();
+        let mut stream = MockStream::new();
+        let short = Response::new(&mut stream, &mut headers).send_reader(&b"foo"[..], Some(6));
+        assert_eq!(short.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    // The body of a chunked response written to `stream`.
+    #[cfg(any(feature = "flate2", feature = "brotli"))]
+    fn chunked_body(stream: MockStream) -> Vec<u8> {
+        use std::io::Read;
+        use http::h1::HeadLimits;
+        use http::h1::HttpReader::ChunkedReader;
+
+        let out = stream.write;
+        let start = out.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+        let mut body = Vec::new();
+        ChunkedReader(&out[start..], None, None, HeadLimits::default()).read_to_end(&mut body).unwrap();
+        body
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn test_compress() {
+        use std::io::Read;
+        use compress::{DeflateDecoder, GzipDecoder};
+        use header::{AcceptEncoding, ContentEncoding, ContentLength, Encoding, Quality, QualityItem,
+                     Vary, qitem};
+        use status::StatusCode;
+        use unicase::UniCase;
+
+        let mut request = Headers::new();
+        request.set(AcceptEncoding(vec![
+            QualityItem::new(Encoding::Gzip, Quality(500)),
+            qitem(Encoding::Deflate),
+        ]));
+        let mut headers = Headers::new();
+        let mut stream = MockStream::new();
+        {
+            let mut res = Response::new(&mut stream, &mut headers);
+            assert_eq!(res.compress(&request), Some(Encoding::Deflate));
+            res.send(b"Hello, World! Hello, World!").unwrap();
+        }
+        assert_eq!(headers.get(), Some(&ContentEncoding(vec![Encoding::Deflate])));
+        assert_eq!(headers.get(), Some(&Vary::Items(vec![UniCase("Accept-Encoding".to_owned())])));
+        assert_eq!(headers.get::<ContentLength>(), None);
+        let mut decoded = Vec::new();
+        DeflateDecoder::new(&chunked_body(stream)[..]).read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, b"Hello, World! Hello, World!");
+
+        // a response dropped without a body still ends the compressed stream
+        request.set(AcceptEncoding(vec![qitem(Encoding::Gzip)]));
+        let mut headers = Headers::new();
+        let mut stream = MockStream::new();
+        {
+            let mut res = Response::new(&mut stream, &mut headers);
+            assert_eq!(res.compress(&request), Some(Encoding::Gzip));
+        }
+        let mut decoded = Vec::new();
+        GzipDecoder::new(&chunked_body(stream)[..]).read_to_end(&mut decoded).unwrap();
+        assert!(decoded.is_empty());
+
+        // nothing is compressed for a request that doesn't ask, nor without a body
+        let mut headers = Headers::new();
+        let mut stream = MockStream::new();
+        {
+            let mut res = Response::new(&mut stream, &mut headers);
+            res.headers_mut().set(Vary::Items(vec![UniCase("Accept".to_owned())]));
+            assert_eq!(res.compress(&Headers::new()), None);
+            res.send(b"foo").unwrap();
+        }
+        assert_eq!(headers.get::<ContentEncoding>(), None);
+        assert_eq!(headers.get(), Some(&Vary::Items(vec![UniCase("Accept".to_owned()),
+                                                         UniCase("Accept-Encoding".to_owned())])));
+        let mut headers = Headers::new();
+        let mut stream = MockStream::new();
+        let mut res = Response::new(&mut stream, &mut headers);
+        *res.status_mut() = StatusCode::NotModified;
+        assert_eq!(res.compress(&request), None);
+    }
+
+    #[cfg(feature = "brotli")]
+    #[test]
+    fn test_compress_brotli() {
+        use std::io::{Read, Write};
+        use compress::BrotliDecoder;
+        use header::{AcceptEncoding, Encoding, Quality, QualityItem, qitem};
+
+        let mut request = Headers::new();
+        request.set(AcceptEncoding(vec![
+            QualityItem::new(Encoding::Gzip, Quality(500)),
+            qitem(Encoding::Brotli),
+        ]));
+        let mut headers = Headers::new();
+        let mut stream = MockStream::new();
+        {
+            let mut res = Response::new(&mut stream, &mut headers);
+            assert_eq!(res.compress(&request), Some(Encoding::Brotli));
+            let mut res = res.start().unwrap();
+            res.write_all(b"event: one\n\n").unwrap();
+            res.flush().unwrap();
+            res.write_all(b"event: two\n\n").unwrap();
+            res.end().unwrap();
+        }
+        let mut decoded = Vec::new();
+        BrotliDecoder::new(&chunked_body(stream)[..]).read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, b"event: one\n\nevent: two\n\n");
+    }
 }
@@ -1,4 +1,5 @@
 //! HTTP RequestUris
+use std::fmt;
 use std::str::FromStr;
 use url::Url;
 use url::ParseError as UrlError;
@@ -72,6 +73,17 @@ impl FromStr for RequestUri {
     }
 }
 
+impl fmt::Display for RequestUri {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RequestUri::AbsolutePath(ref path) => f.write_str(path),
+            RequestUri::AbsoluteUri(ref url) => write!(f, "{}", url),
+            RequestUri::Authority(ref authority) => f.write_str(authority),
+            RequestUri::Star => f.write_str("*"),
+        }
+    }
+}
+
 #[test]
 fn test_uri_fromstr() {
     fn read(s: &str, result: RequestUri) {
@@ -83,3 +95,10 @@ fn test_uri_fromstr() {
     read("hyper.rs", RequestUri::Authority("hyper.rs".to_owned()));
     read("/", RequestUri::AbsolutePath("/".to_owned()));
 }
+
+#[test]
+fn test_uri_display() {
+    for s in &["*", "http://hyper.rs/", "hyper.rs:443", "/where?q=now"] {
+        assert_eq!(s.parse::<RequestUri>().unwrap().to_string(), *s);
+    }
+}
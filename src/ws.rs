@@ -0,0 +1,567 @@
+//! WebSocket framing.
+//!
+//! Once a connection has been upgraded to the WebSocket protocol, both ends
+//! exchange messages split into frames, as
+//! [RFC 6455 Section 5](https://tools.ietf.org/html/rfc6455#section-5)
+//! describes. A `WebSocket` wraps the upgraded stream, sending and receiving
+//! whole `Message`s: it masks what a client sends, reassembles fragmented
+//! messages, answers pings, and takes part in the closing handshake.
+//!
+//! ```
+//! use std::io::Cursor;
+//! use hyper::ws::{Frame, Message, Role, WebSocket};
+//!
+//! // what a client would send: a masked text frame
+//! let mut input = Vec::new();
+//! Frame::text("Hello").write_to(&mut input, Some([1, 2, 3, 4])).unwrap();
+//!
+//! let mut ws = WebSocket::new(Cursor::new(input), Role::Server);
+//! assert_eq!(ws.recv().unwrap(), Message::Text("Hello".to_owned()));
+//! ```
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Read, Write};
+use std::str;
+
+/// The most bytes a message received by a `WebSocket` may have, by default.
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+// control frames can't have a longer payload, nor be fragmented
+const MAX_CONTROL_PAYLOAD: usize = 125;
+
+/// The kind of a frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Opcode {
+    /// A further fragment of a message.
+    Continuation,
+    /// The first frame of a UTF-8 text message.
+    Text,
+    /// The first frame of a binary message.
+    Binary,
+    /// A request to close the connection.
+    Close,
+    /// A ping, to be answered with a pong.
+    Ping,
+    /// The answer to a ping.
+    Pong,
+}
+
+impl Opcode {
+    fn from_u8(n: u8) -> Option<Opcode> {
+        Some(match n {
+            0x0 => Opcode::Continuation,
+            0x1 => Opcode::Text,
+            0x2 => Opcode::Binary,
+            0x8 => Opcode::Close,
+            0x9 => Opcode::Ping,
+            0xA => Opcode::Pong,
+            _ => return None,
+        })
+    }
+
+    fn to_u8(&self) -> u8 {
+        match *self {
+            Opcode::Continuation => 0x0,
+            Opcode::Text => 0x1,
+            Opcode::Binary => 0x2,
+            Opcode::Close => 0x8,
+            Opcode::Ping => 0x9,
+            Opcode::Pong => 0xA,
+        }
+    }
+
+    /// Whether this is the opcode of a control frame, which may come between
+    /// the fragments of a message.
+    pub fn is_control(&self) -> bool {
+        match *self {
+            Opcode::Close | Opcode::Ping | Opcode::Pong => true,
+            _ => false,
+        }
+    }
+}
+
+/// A single WebSocket frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    /// Whether this is the last frame of its message.
+    pub fin: bool,
+    /// The kind of this frame.
+    pub opcode: Opcode,
+    /// The payload, unmasked.
+    pub payload: Vec<u8>,
+}
+
+impl Frame {
+    /// A whole text message in one frame.
+    pub fn text<S: Into<String>>(text: S) -> Frame {
+        Frame { fin: true, opcode: Opcode::Text, payload: text.into().into_bytes() }
+    }
+
+    /// A whole binary message in one frame.
+    pub fn binary<B: Into<Vec<u8>>>(data: B) -> Frame {
+        Frame { fin: true, opcode: Opcode::Binary, payload: data.into() }
+    }
+
+    /// Read a frame from `r`, returning it and whether it was masked.
+    ///
+    /// A frame with a payload longer than `max_payload` is an
+    /// `InvalidData` error, as is one that breaks the framing rules.
+    pub fn read_from<R: Read>(r: &mut R, max_payload: usize) -> io::Result<(Frame, bool)> {
+        let mut head = [0; 2];
+        try!(r.read_exact(&mut head));
+        if head[0] & 0x70 != 0 {
+            return Err(invalid("reserved bits set without an extension"));
+        }
+        let fin = head[0] & 0x80 != 0;
+        let opcode = match Opcode::from_u8(head[0] & 0x0F) {
+            Some(opcode) => opcode,
+            None => return Err(invalid("unknown opcode")),
+        };
+        let masked = head[1] & 0x80 != 0;
+        let len = match head[1] & 0x7F {
+            126 => {
+                let mut len = [0; 2];
+                try!(r.read_exact(&mut len));
+                (len[0] as u64) << 8 | len[1] as u64
+            },
+            127 => {
+                let mut len = [0; 8];
+                try!(r.read_exact(&mut len));
+                len.iter().fold(0, |n, &b| n << 8 | b as u64)
+            },
+            len => len as u64,
+        };
+        if opcode.is_control() && (!fin || len > MAX_CONTROL_PAYLOAD as u64) {
+            return Err(invalid("control frame fragmented or too long"));
+        }
+        if len > max_payload as u64 {
+            return Err(invalid("frame is too large"));
+        }
+
+        let mut mask = [0; 4];
+        if masked {
+            try!(r.read_exact(&mut mask));
+        }
+        let mut payload = vec![0; len as usize];
+        try!(r.read_exact(&mut payload));
+        if masked {
+            apply_mask(&mut payload, mask);
+        }
+        Ok((Frame { fin: fin, opcode: opcode, payload: payload }, masked))
+    }
+
+    /// Write this frame to `w`, masked with `mask` if there is one.
+    ///
+    /// Frames sent by a client must be masked, and those sent by a server
+    /// must not be.
+    pub fn write_to<W: Write>(&self, w: &mut W, mask: Option<[u8; 4]>) -> io::Result<()> {
+        let mut head = Vec::with_capacity(14);
+        head.push((if self.fin { 0x80 } else { 0 }) | self.opcode.to_u8());
+        let mask_bit = if mask.is_some() { 0x80 } else { 0 };
+        let len = self.payload.len();
+        if len < 126 {
+            head.push(mask_bit | len as u8);
+        } else if len <= 0xFFFF {
+            head.push(mask_bit | 126);
+            head.extend_from_slice(&[(len >> 8) as u8, len as u8]);
+        } else {
+            head.push(mask_bit | 127);
+            let len = len as u64;
+            head.extend((0..8).rev().map(|i| (len >> (i * 8)) as u8));
+        }
+
+        match mask {
+            Some(mask) => {
+                head.extend_from_slice(&mask);
+                let mut payload = self.payload.clone();
+                apply_mask(&mut payload, mask);
+                try!(w.write_all(&head));
+                w.write_all(&payload)
+            },
+            None => {
+                try!(w.write_all(&head));
+                w.write_all(&self.payload)
+            }
+        }
+    }
+}
+
+fn apply_mask(payload: &mut [u8], mask: [u8; 4]) {
+    for (i, b) in payload.iter_mut().enumerate() {
+        *b ^= mask[i % 4];
+    }
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_owned())
+}
+
+/// The reason given for closing a connection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CloseCode {
+    /// 1000, the purpose of the connection has been fulfilled.
+    Normal,
+    /// 1001, the endpoint is going away, such as a server shutting down.
+    GoingAway,
+    /// 1002, the other endpoint broke the protocol.
+    ProtocolError,
+    /// 1003, a message was of a type that can't be accepted.
+    Unsupported,
+    /// 1007, a message's data was inconsistent with its type, such as a text
+    /// message that wasn't UTF-8.
+    InvalidData,
+    /// 1008, a message violated the endpoint's policy.
+    PolicyViolation,
+    /// 1009, a message was too big to process.
+    TooBig,
+    /// 1010, the server didn't negotiate an extension the client needs.
+    MissingExtension,
+    /// 1011, the server met an unexpected condition.
+    InternalError,
+    /// Any other code.
+    Other(u16),
+}
+
+impl CloseCode {
+    /// The code as a number.
+    pub fn to_u16(&self) -> u16 {
+        match *self {
+            CloseCode::Normal => 1000,
+            CloseCode::GoingAway => 1001,
+            CloseCode::ProtocolError => 1002,
+            CloseCode::Unsupported => 1003,
+            CloseCode::InvalidData => 1007,
+            CloseCode::PolicyViolation => 1008,
+            CloseCode::TooBig => 1009,
+            CloseCode::MissingExtension => 1010,
+            CloseCode::InternalError => 1011,
+            CloseCode::Other(n) => n,
+        }
+    }
+
+    /// The code for a number.
+    pub fn from_u16(n: u16) -> CloseCode {
+        match n {
+            1000 => CloseCode::Normal,
+            1001 => CloseCode::GoingAway,
+            1002 => CloseCode::ProtocolError,
+            1003 => CloseCode::Unsupported,
+            1007 => CloseCode::InvalidData,
+            1008 => CloseCode::PolicyViolation,
+            1009 => CloseCode::TooBig,
+            1010 => CloseCode::MissingExtension,
+            1011 => CloseCode::InternalError,
+            n => CloseCode::Other(n),
+        }
+    }
+}
+
+impl fmt::Display for CloseCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.to_u16(), f)
+    }
+}
+
+/// A whole WebSocket message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Message {
+    /// A text message.
+    Text(String),
+    /// A binary message.
+    Binary(Vec<u8>),
+    /// A ping, with its application data.
+    Ping(Vec<u8>),
+    /// A pong, with its application data.
+    Pong(Vec<u8>),
+    /// A close, with its code and reason, if any.
+    Close(Option<(CloseCode, String)>),
+}
+
+impl Message {
+    fn into_frame(self) -> Frame {
+        let (opcode, payload) = match self {
+            Message::Text(text) => (Opcode::Text, text.into_bytes()),
+            Message::Binary(data) => (Opcode::Binary, data),
+            Message::Ping(data) => (Opcode::Ping, data),
+            Message::Pong(data) => (Opcode::Pong, data),
+            Message::Close(None) => (Opcode::Close, Vec::new()),
+            Message::Close(Some((code, reason))) => {
+                let code = code.to_u16();
+                let mut payload = vec![(code >> 8) as u8, code as u8];
+                payload.extend_from_slice(reason.as_bytes());
+                (Opcode::Close, payload)
+            },
+        };
+        Frame { fin: true, opcode: opcode, payload: payload }
+    }
+}
+
+/// Which end of a connection a `WebSocket` is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    /// The end that sent the upgrade request, which masks its frames.
+    Client,
+    /// The end that accepted the upgrade.
+    Server,
+}
+
+/// Messages sent and received over an upgraded stream.
+#[derive(Debug)]
+pub struct WebSocket<S> {
+    stream: S,
+    role: Role,
+    max_message_size: usize,
+    // the opcode and data of a message whose fragments are being received
+    partial: Option<(Opcode, Vec<u8>)>,
+    close_sent: bool,
+    close_received: bool,
+}
+
+impl<S: Read + Write> WebSocket<S> {
+    /// Use the upgraded `stream` as the `role` end of a WebSocket.
+    pub fn new(stream: S, role: Role) -> WebSocket<S> {
+        WebSocket {
+            stream: stream,
+            role: role,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            partial: None,
+            close_sent: false,
+            close_received: false,
+        }
+    }
+
+    /// Set the most bytes a received message may have.
+    ///
+    /// Defaults to `DEFAULT_MAX_MESSAGE_SIZE`.
+    pub fn set_max_message_size(&mut self, max: usize) {
+        self.max_message_size = max;
+    }
+
+    /// Get a reference to the underlying stream.
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    /// Get a mutable reference to the underlying stream.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    /// Get the underlying stream back.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+
+    /// Whether a close has been both sent and received, after which the
+    /// underlying stream should be closed.
+    pub fn is_closed(&self) -> bool {
+        self.close_sent && self.close_received
+    }
+
+    /// Send a message, in a single frame.
+    pub fn send(&mut self, message: Message) -> io::Result<()> {
+        self.send_frame(&message.into_frame())
+    }
+
+    /// Send a single frame, such as a fragment of a message.
+    ///
+    /// A client's frames are masked with a fresh key each.
+    pub fn send_frame(&mut self, frame: &Frame) -> io::Result<()> {
+        if self.close_sent {
+            return Err(io::Error::new(io::ErrorKind::NotConnected, "close already sent"));
+        }
+        let mask = match self.role {
+            Role::Client => Some(mask_key()),
+            Role::Server => None,
+        };
+        try!(frame.write_to(&mut self.stream, mask));
+        try!(self.stream.flush());
+        if frame.opcode == Opcode::Close {
+            self.close_sent = true;
+        }
+        Ok(())
+    }
+
+    /// Receive the next message, waiting for all of its fragments.
+    ///
+    /// A ping is answered with a pong before it is returned. A close is
+    /// answered with the same close code, unless one was already sent.
+    /// Violations of the protocol are `InvalidData` errors, after which the
+    /// connection should be closed with `CloseCode::ProtocolError`.
+    pub fn recv(&mut self) -> io::Result<Message> {
+        loop {
+            let (frame, masked) = try!(Frame::read_from(&mut self.stream, self.max_message_size));
+            if masked != (self.role == Role::Server) {
+                return Err(invalid(match self.role {
+                    Role::Server => "client frame was not masked",
+                    Role::Client => "server frame was masked",
+                }));
+            }
+
+            match frame.opcode {
+                Opcode::Ping => {
+                    if !self.close_sent {
+                        try!(self.send(Message::Pong(frame.payload.clone())));
+                    }
+                    return Ok(Message::Ping(frame.payload));
+                },
+                Opcode::Pong => return Ok(Message::Pong(frame.payload)),
+                Opcode::Close => {
+                    self.close_received = true;
+                    let close = try!(parse_close(&frame.payload));
+                    if !self.close_sent {
+                        let reply = close.as_ref().map(|&(code, _)| (code, String::new()));
+                        try!(self.send(Message::Close(reply)));
+                    }
+                    return Ok(Message::Close(close));
+                },
+                Opcode::Text | Opcode::Binary => {
+                    if self.partial.is_some() {
+                        return Err(invalid("new message before the last one finished"));
+                    }
+                    if frame.fin {
+                        return message(frame.opcode, frame.payload);
+                    }
+                    self.partial = Some((frame.opcode, frame.payload));
+                },
+                Opcode::Continuation => {
+                    let (opcode, mut data) = match self.partial.take() {
+                        Some(partial) => partial,
+                        None => return Err(invalid("continuation of no message")),
+                    };
+                    if data.len() + frame.payload.len() > self.max_message_size {
+                        return Err(invalid("message is too large"));
+                    }
+                    data.extend_from_slice(&frame.payload);
+                    if frame.fin {
+                        return message(opcode, data);
+                    }
+                    self.partial = Some((opcode, data));
+                },
+            }
+        }
+    }
+}
+
+fn message(opcode: Opcode, data: Vec<u8>) -> io::Result<Message> {
+    if opcode == Opcode::Text {
+        String::from_utf8(data).map(Message::Text).map_err(|_| invalid("text message is not UTF-8"))
+    } else {
+        Ok(Message::Binary(data))
+    }
+}
+
+fn parse_close(payload: &[u8]) -> io::Result<Option<(CloseCode, String)>> {
+    match payload.len() {
+        0 => Ok(None),
+        1 => Err(invalid("close payload of 1 byte")),
+        _ => {
+            let code = (payload[0] as u16) << 8 | payload[1] as u16;
+            let reason = try!(str::from_utf8(&payload[2..])
+                              .map_err(|_| invalid("close reason is not UTF-8")));
+            Ok(Some((CloseCode::from_u16(code), reason.to_owned())))
+        }
+    }
+}
+
+// Masking keys need only be unpredictable to scripts in the browser, so the
+// randomly keyed std hasher is enough.
+fn mask_key() -> [u8; 4] {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u8(0);
+    let n = hasher.finish();
+    [(n >> 24) as u8, (n >> 16) as u8, (n >> 8) as u8, n as u8]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Cursor};
+
+    use mock::MockStream;
+    use super::{CloseCode, Frame, Message, Opcode, Role, WebSocket};
+
+    #[test]
+    fn test_frame_lengths() {
+        for &len in &[0, 125, 126, 0xFFFF, 0x10000] {
+            let frame = Frame::binary(vec![7; len]);
+            let mut buf = Vec::new();
+            frame.write_to(&mut buf, Some([9, 8, 7, 6])).unwrap();
+            let (read, masked) = Frame::read_from(&mut Cursor::new(buf), 1 << 20).unwrap();
+            assert!(masked);
+            assert_eq!(read, frame);
+        }
+    }
+
+    #[test]
+    fn test_rfc_examples() {
+        // RFC 6455 Section 5.7
+        let (frame, masked) = Frame::read_from(&mut &b"\x81\x05\x48\x65\x6c\x6c\x6f"[..], 125)
+            .unwrap();
+        assert_eq!((frame, masked), (Frame::text("Hello"), false));
+        let (frame, masked) = Frame::read_from(
+            &mut &b"\x81\x85\x37\xfa\x21\x3d\x7f\x9f\x4d\x51\x58"[..], 125).unwrap();
+        assert_eq!((frame, masked), (Frame::text("Hello"), true));
+
+        let mut buf = Vec::new();
+        Frame { fin: false, opcode: Opcode::Text, payload: b"Hel".to_vec() }
+            .write_to(&mut buf, None).unwrap();
+        assert_eq!(buf, b"\x01\x03\x48\x65\x6c");
+    }
+
+    #[test]
+    fn test_recv_fragments_and_control_frames() {
+        let mut input = Vec::new();
+        let mask = Some([1, 2, 3, 4]);
+        Frame { fin: false, opcode: Opcode::Text, payload: b"Hel".to_vec() }
+            .write_to(&mut input, mask).unwrap();
+        Frame { fin: true, opcode: Opcode::Ping, payload: b"?".to_vec() }
+            .write_to(&mut input, mask).unwrap();
+        Frame { fin: true, opcode: Opcode::Continuation, payload: b"lo".to_vec() }
+            .write_to(&mut input, mask).unwrap();
+        Frame { fin: true, opcode: Opcode::Close, payload: b"\x03\xe8bye".to_vec() }
+            .write_to(&mut input, mask).unwrap();
+
+        let mut ws = WebSocket::new(MockStream::with_input(&input), Role::Server);
+        assert_eq!(ws.recv().unwrap(), Message::Ping(b"?".to_vec()));
+        assert_eq!(ws.recv().unwrap(), Message::Text("Hello".to_owned()));
+        assert_eq!(ws.recv().unwrap(),
+                   Message::Close(Some((CloseCode::Normal, "bye".to_owned()))));
+        assert!(ws.is_closed());
+
+        // answered with an unmasked pong, then close
+        assert_eq!(ws.get_ref().write, b"\x8a\x01?\x88\x02\x03\xe8");
+    }
+
+    #[test]
+    fn test_recv_protocol_errors() {
+        fn recv(input: &[u8], role: Role) -> io::Result<Message> {
+            WebSocket::new(MockStream::with_input(input), role).recv()
+        }
+
+        // an unmasked frame from a client
+        assert!(recv(b"\x81\x05Hello", Role::Server).is_err());
+        // a masked frame from a server
+        assert!(recv(b"\x81\x85\x37\xfa\x21\x3d\x7f\x9f\x4d\x51\x58", Role::Client).is_err());
+        // a fragmented ping
+        assert!(recv(b"\x09\x00", Role::Client).is_err());
+        // a continuation of nothing
+        assert!(recv(b"\x80\x00", Role::Client).is_err());
+        // text that isn't UTF-8
+        assert!(recv(b"\x81\x01\xff", Role::Client).is_err());
+    }
+
+    #[test]
+    fn test_client_send_is_masked() {
+        let mut ws = WebSocket::new(MockStream::new(), Role::Client);
+        ws.send(Message::Binary(vec![1, 2, 3])).unwrap();
+        let written = ws.get_ref().write.clone();
+        assert_eq!(&written[..2], b"\x82\x83");
+        let (frame, masked) = Frame::read_from(&mut &written[..], 125).unwrap();
+        assert!(masked);
+        assert_eq!(frame, Frame::binary(vec![1, 2, 3]));
+
+        ws.send(Message::Close(None)).unwrap();
+        assert!(ws.send(Message::Text("late".to_owned())).is_err());
+    }
+}